@@ -1,3 +1,12 @@
+pub mod scroll_screenshot_auto_scroll_service;
 pub mod scroll_screenshot_capture_service;
+pub mod scroll_screenshot_feature;
+pub mod scroll_screenshot_frame_store;
 pub mod scroll_screenshot_image_service;
+pub mod scroll_screenshot_metadata;
+pub mod scroll_screenshot_page_export;
 pub mod scroll_screenshot_service;
+pub mod scroll_screenshot_session;
+pub mod scroll_screenshot_stitch;
+#[cfg(test)]
+pub mod scroll_screenshot_synthetic;
@@ -0,0 +1,208 @@
+use image::DynamicImage;
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::scroll_screenshot_image_service::ScrollFrameSignature;
+use crate::scroll_screenshot_service::ScrollImageList;
+
+/// 默认每次滚动的滚轮格数
+pub const DEFAULT_AUTO_SCROLL_STEP: i32 = 3;
+/// 滚动后检查画面是否稳定的间隔
+pub const AUTO_SCROLL_SETTLE_INTERVAL: Duration = Duration::from_millis(64);
+/// 等待画面稳定的最长时间，超时后直接使用最后一次截图
+pub const AUTO_SCROLL_SETTLE_TIMEOUT: Duration = Duration::from_millis(1536);
+/// 自动滚动最多拼接的帧数，防止无限滚动的页面一直运行
+pub const AUTO_SCROLL_MAX_FRAME_COUNT: usize = 1024;
+/// 连续多少次没有新内容时认为到达页面末端
+const AUTO_SCROLL_MAX_NO_CHANGE_COUNT: usize = 2;
+/// 两次截图的平均差异小于该值时认为画面稳定
+const SETTLE_MAX_MEAN_DIFF: f32 = 0.5;
+
+#[derive(PartialEq, Serialize, Debug, Clone, Copy)]
+pub enum ScrollAutoScrollStopReason {
+    /// 滚动后没有新的内容
+    EndOfPage,
+    /// 用户取消
+    Cancelled,
+    /// 达到最大帧数
+    MaxFrameCount,
+}
+
+/**
+ * 记录自动滚动的状态，同一时间只允许一个自动滚动任务
+ */
+pub struct ScrollScreenshotAutoScrollService {
+    cancelled: Arc<AtomicBool>,
+    running: bool,
+}
+
+impl ScrollScreenshotAutoScrollService {
+    pub fn new() -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            running: false,
+        }
+    }
+
+    /// 开始自动滚动，返回取消标记，任务中需要定期检查
+    pub fn start(&mut self) -> Result<Arc<AtomicBool>, String> {
+        if self.running {
+            return Err(String::from(
+                "[ScrollScreenshotAutoScrollService::start] Auto scroll is already running",
+            ));
+        }
+
+        self.running = true;
+        self.cancelled = Arc::new(AtomicBool::new(false));
+        Ok(self.cancelled.clone())
+    }
+
+    pub fn finish(&mut self) {
+        self.running = false;
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+/**
+ * 比较滚动后连续两次截图，判断页面是否已经停止滚动（平滑滚动、懒加载等）
+ */
+pub struct ScrollSettleDetector {
+    last_signature: Option<ScrollFrameSignature>,
+}
+
+impl ScrollSettleDetector {
+    pub fn new() -> Self {
+        Self {
+            last_signature: None,
+        }
+    }
+
+    /// 每次滚动后需要重置，避免和滚动前的画面比较
+    pub fn reset(&mut self) {
+        self.last_signature = None;
+    }
+
+    /// 加入一次截图，和上一次截图基本一致时返回 true
+    pub fn push(&mut self, frame: &DynamicImage) -> bool {
+        let signature = ScrollFrameSignature::new(frame);
+        let is_settled = match &self.last_signature {
+            Some(last_signature) => signature.mean_diff(last_signature) < SETTLE_MAX_MEAN_DIFF,
+            None => false,
+        };
+
+        self.last_signature = Some(signature);
+        is_settled
+    }
+}
+
+/**
+ * 根据每一步的拼接结果判断是否需要停止自动滚动
+ */
+pub struct ScrollAutoScrollProgress {
+    pub frame_count: usize,
+    no_change_count: usize,
+    max_frame_count: usize,
+}
+
+impl ScrollAutoScrollProgress {
+    pub fn new(max_frame_count: usize) -> Self {
+        Self {
+            frame_count: 0,
+            no_change_count: 0,
+            max_frame_count,
+        }
+    }
+
+    /// 传入 ScrollScreenshotService::handle_image 的结果，返回 true 表示拼接了新的内容
+    pub fn has_new_content(
+        handle_result: &(
+            Option<(i32, Option<ScrollImageList>)>,
+            bool,
+            ScrollImageList,
+        ),
+    ) -> bool {
+        matches!(handle_result, (Some((_, Some(_))), false, _))
+    }
+
+    /// 记录一步的结果，需要停止时返回停止原因
+    ///
+    /// 偶尔一次没有匹配（如画面还在变化）不会停止，连续多次没有新内容时认为到达页面末端
+    pub fn push(&mut self, has_new_content: bool) -> Option<ScrollAutoScrollStopReason> {
+        if has_new_content {
+            self.frame_count += 1;
+            self.no_change_count = 0;
+        } else {
+            self.no_change_count += 1;
+        }
+
+        if self.no_change_count >= AUTO_SCROLL_MAX_NO_CHANGE_COUNT {
+            Some(ScrollAutoScrollStopReason::EndOfPage)
+        } else if self.frame_count >= self.max_frame_count {
+            Some(ScrollAutoScrollStopReason::MaxFrameCount)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scroll_screenshot_service::{
+        ScrollDirection, ScrollScreenshotParams, ScrollScreenshotService,
+    };
+    use crate::scroll_screenshot_synthetic::generate_source_image;
+
+    #[test]
+    fn test_auto_scroll_stops_at_end_of_page() {
+        let source = DynamicImage::ImageRgb8(generate_source_image(200, 700, 13));
+
+        let mut service = ScrollScreenshotService::new();
+        service.init_with_params(
+            ScrollDirection::Vertical,
+            ScrollScreenshotParams {
+                min_sample_size: 200,
+                max_sample_size: 200,
+                ..ScrollScreenshotParams::default_for_frame(ScrollDirection::Vertical, 200, 200)
+            },
+        );
+        service.handle_image(source.crop_imm(0, 0, 200, 200), ScrollImageList::Bottom);
+
+        // 模拟页面滚动，滚动到底部后画面不再变化
+        let mut settle_detector = ScrollSettleDetector::new();
+        let mut progress = ScrollAutoScrollProgress::new(AUTO_SCROLL_MAX_FRAME_COUNT);
+        let mut position = 0;
+        let mut step_count = 0;
+        let stop_reason = loop {
+            position = (position + 90).min(500);
+            step_count += 1;
+
+            settle_detector.reset();
+            let frame = source.crop_imm(0, position, 200, 200);
+            assert!(!settle_detector.push(&frame));
+            assert!(settle_detector.push(&frame));
+
+            let handle_result = service.handle_image(frame, ScrollImageList::Bottom);
+            if let Some(stop_reason) =
+                progress.push(ScrollAutoScrollProgress::has_new_content(&handle_result))
+            {
+                break stop_reason;
+            }
+
+            assert!(step_count < 16);
+        };
+
+        assert_eq!(stop_reason, ScrollAutoScrollStopReason::EndOfPage);
+        assert_eq!(progress.frame_count, 6);
+        assert_eq!(service.export().unwrap().to_rgb8(), source.to_rgb8());
+    }
+}
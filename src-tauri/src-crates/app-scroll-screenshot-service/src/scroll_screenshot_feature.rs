@@ -0,0 +1,683 @@
+use hora::core::ann_index::ANNIndex;
+use hora::core::metrics::Metric;
+use hora::index::{hnsw_idx::HNSWIndex, hnsw_params::HNSWParams};
+use image::GrayImage;
+use imageproc::corners;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::scroll_screenshot_service::{ScrollDirection, ScrollOffset};
+
+/// 浮点描述符匹配时允许的最大欧氏距离
+const PATCH_MAX_DESCRIPTOR_DISTANCE: f32 = 0.1;
+/// FAST12 检测到的角点超过该数量时使用 FAST12，否则使用 FAST9
+const FAST12_MIN_CORNER_COUNT: usize = 200;
+/// 二进制描述符的位数
+const BRIEF_DESCRIPTOR_BITS: usize = 256;
+/// 二进制描述符匹配时允许的最大汉明距离
+const BRIEF_MAX_HAMMING_DISTANCE: u32 = 24;
+/// 行（列）中最亮和最暗像素的差小于该值时视为空白行，不作为特征
+const LINE_HASH_MIN_CONTRAST: u8 = 16;
+
+/// 二进制描述符
+pub type ScrollBinaryDescriptor = [u64; BRIEF_DESCRIPTOR_BITS / 64];
+
+/// 内置的特征提取和匹配方式
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub enum ScrollFeatureBackend {
+    /// FAST 角点、块均值描述符、HNSW 近邻搜索
+    #[default]
+    FastPatch = 0,
+    /// FAST 角点、BRIEF 二进制描述符、汉明距离匹配
+    FastBrief = 1,
+    /// 不检测角点，每一行（列）作为一个特征按哈希精确匹配，适合文字较多的页面
+    ///
+    /// 只能估计滚动方向上的偏移，自由方向下使用 FastPatch
+    LineHash = 2,
+}
+
+/// 一帧中所有特征的描述符，顺序和特征点一致
+#[derive(Debug, Clone)]
+pub enum ScrollDescriptors {
+    /// 浮点描述符，按欧氏距离匹配
+    Float(Vec<Vec<f32>>),
+    /// 二进制描述符，按汉明距离匹配
+    Binary(Vec<ScrollBinaryDescriptor>),
+    /// 哈希值，只匹配完全相同的值
+    Hash(Vec<u64>),
+}
+
+impl ScrollDescriptors {
+    pub fn len(&self) -> usize {
+        match self {
+            ScrollDescriptors::Float(descriptors) => descriptors.len(),
+            ScrollDescriptors::Binary(descriptors) => descriptors.len(),
+            ScrollDescriptors::Hash(descriptors) => descriptors.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// 当前帧的一个特征在索引帧中的最近邻
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollFeatureMatch {
+    /// 索引帧中特征的下标
+    pub index: usize,
+    /// 描述符距离是否在阈值内，超出阈值的匹配只用于判断画面是否变化
+    pub is_within_distance: bool,
+}
+
+/// 特征点检测
+pub trait ScrollFeatureDetector: Send + Sync {
+    /// 检测灰度图（缩放后）中的特征点
+    fn detect(&mut self, image: &GrayImage) -> Vec<ScrollOffset>;
+
+    /// 重新开始拼接时清除检测过程中记录的状态
+    fn reset(&mut self) {}
+}
+
+/// 特征描述
+pub trait ScrollFeatureDescriptor: Send + Sync {
+    /// 计算特征点的描述符，顺序和特征点一致
+    fn describe(&self, image: &GrayImage, corners: &[ScrollOffset]) -> ScrollDescriptors;
+}
+
+/// 特征匹配
+pub trait ScrollFeatureMatcher: Send + Sync {
+    /// 为索引帧的描述符建立索引
+    fn build_index(&self, descriptors: ScrollDescriptors) -> Arc<dyn ScrollFeatureIndex>;
+}
+
+/// 索引帧的特征索引
+pub trait ScrollFeatureIndex: Send + Sync + Debug {
+    /// 查找每个描述符在索引中的最近邻，结果和描述符一一对应
+    fn nearest(&self, descriptors: &ScrollDescriptors) -> Vec<Option<ScrollFeatureMatch>>;
+}
+
+/**
+ * 特征点检测、描述和匹配的组合
+ * 拼接流程只依赖这三个 trait，可以替换其中任意一个来对比效果
+ */
+pub struct ScrollFeaturePipeline {
+    pub detector: Box<dyn ScrollFeatureDetector>,
+    pub descriptor: Box<dyn ScrollFeatureDescriptor>,
+    pub matcher: Box<dyn ScrollFeatureMatcher>,
+}
+
+impl ScrollFeaturePipeline {
+    pub fn new(
+        detector: Box<dyn ScrollFeatureDetector>,
+        descriptor: Box<dyn ScrollFeatureDescriptor>,
+        matcher: Box<dyn ScrollFeatureMatcher>,
+    ) -> Self {
+        Self {
+            detector,
+            descriptor,
+            matcher,
+        }
+    }
+
+    /// 创建内置的组合
+    pub fn from_backend(
+        backend: ScrollFeatureBackend,
+        direction: ScrollDirection,
+        corner_threshold: u8,
+        descriptor_patch_size: usize,
+    ) -> Self {
+        match backend {
+            ScrollFeatureBackend::LineHash if direction != ScrollDirection::Free => {
+                let is_vertical = direction == ScrollDirection::Vertical;
+                Self::new(
+                    Box::new(ScrollLineDetector::new(is_vertical)),
+                    Box::new(ScrollLineHashDescriptor::new(is_vertical)),
+                    Box::new(ScrollHashMatcher),
+                )
+            }
+            ScrollFeatureBackend::FastBrief => Self::new(
+                Box::new(ScrollFastDetector::new(corner_threshold)),
+                Box::new(ScrollBriefDescriptor::new(descriptor_patch_size)),
+                Box::new(ScrollHammingMatcher::new(BRIEF_MAX_HAMMING_DISTANCE)),
+            ),
+            _ => {
+                let descriptor = ScrollPatchDescriptor::new(descriptor_patch_size);
+                let dimension = descriptor.dimension();
+                Self::new(
+                    Box::new(ScrollFastDetector::new(corner_threshold)),
+                    Box::new(descriptor),
+                    Box::new(ScrollHnswMatcher::new(dimension)),
+                )
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.detector.reset();
+    }
+}
+
+/**
+ * FAST 角点检测
+ * 首帧角点足够多时使用 FAST12，否则使用 FAST9，之后的帧使用相同的算法
+ */
+pub struct ScrollFastDetector {
+    threshold: u8,
+    /// 是否启用 fast12 算法进行角点检测
+    enable_fast12: Option<bool>,
+}
+
+impl ScrollFastDetector {
+    pub fn new(threshold: u8) -> Self {
+        Self {
+            threshold,
+            enable_fast12: None,
+        }
+    }
+}
+
+impl ScrollFeatureDetector for ScrollFastDetector {
+    fn detect(&mut self, image: &GrayImage) -> Vec<ScrollOffset> {
+        let corners = match self.enable_fast12 {
+            Some(true) => corners::corners_fast12(image, self.threshold),
+            Some(false) => corners::corners_fast9(image, self.threshold),
+            None => {
+                let fast12_corners = corners::corners_fast12(image, self.threshold);
+
+                if fast12_corners.len() > FAST12_MIN_CORNER_COUNT {
+                    self.enable_fast12 = Some(true);
+                    fast12_corners
+                } else {
+                    self.enable_fast12 = Some(false);
+                    corners::corners_fast9(image, self.threshold)
+                }
+            }
+        };
+
+        corners
+            .iter()
+            .map(|corner| ScrollOffset::new(corner.x as i32, corner.y as i32))
+            .collect()
+    }
+
+    fn reset(&mut self) {
+        self.enable_fast12 = None;
+    }
+}
+
+/**
+ * 块均值描述符
+ * 以特征点为中心隔行隔列采样，分别计算每行和每列的平均灰度
+ */
+pub struct ScrollPatchDescriptor {
+    patch_size: usize,
+}
+
+impl ScrollPatchDescriptor {
+    pub fn new(patch_size: usize) -> Self {
+        Self { patch_size }
+    }
+
+    /// 描述符的维度
+    pub fn dimension(&self) -> usize {
+        self.patch_size & !1
+    }
+
+    fn compute_descriptor(&self, img: &GrayImage, corner: &ScrollOffset) -> Vec<f32> {
+        let descriptor_size = self.patch_size;
+        let mut descriptor = Vec::with_capacity(self.dimension());
+        let half_size = descriptor_size as i32 / 2;
+
+        let width = img.width() as i32;
+        let height = img.height() as i32;
+
+        let mut push_mean = |is_row: bool, line: usize| {
+            let mut sum = 0.0;
+            let mut valid_pixels = 0;
+
+            for i in 0..(descriptor_size / 2) {
+                let (row, col) = if is_row { (line, i) } else { (i, line) };
+                let x = corner.x + (-half_size + col as i32 * 2);
+                let y = corner.y + (-half_size + row as i32 * 2);
+
+                if x >= 0 && x < width && y >= 0 && y < height {
+                    sum += img.get_pixel(x as u32, y as u32)[0] as f32 / 255.0;
+                    valid_pixels += 1;
+                }
+            }
+
+            descriptor.push(if valid_pixels > 0 {
+                sum / valid_pixels as f32
+            } else {
+                0.0
+            });
+        };
+
+        // 计算行特征
+        for row in 0..(descriptor_size / 2) {
+            push_mean(true, row);
+        }
+
+        // 计算列特征
+        for col in 0..(descriptor_size / 2) {
+            push_mean(false, col);
+        }
+
+        descriptor
+    }
+}
+
+impl ScrollFeatureDescriptor for ScrollPatchDescriptor {
+    fn describe(&self, image: &GrayImage, corners: &[ScrollOffset]) -> ScrollDescriptors {
+        ScrollDescriptors::Float(
+            corners
+                .par_iter()
+                .map(|corner| self.compute_descriptor(image, corner))
+                .collect(),
+        )
+    }
+}
+
+/**
+ * BRIEF 二进制描述符
+ * 在特征点周围按固定的随机点对比较灰度，每个点对得到一位
+ */
+pub struct ScrollBriefDescriptor {
+    /// 点对相对特征点的偏移 (x1, y1, x2, y2)
+    pairs: Vec<(i32, i32, i32, i32)>,
+}
+
+impl ScrollBriefDescriptor {
+    pub fn new(patch_size: usize) -> Self {
+        let half_size = (patch_size as i32 / 2).max(1);
+
+        // 固定种子的 xorshift，保证每次生成的点对一致
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next_offset = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % (half_size as u64 * 2 + 1)) as i32 - half_size
+        };
+
+        let pairs = (0..BRIEF_DESCRIPTOR_BITS)
+            .map(|_| (next_offset(), next_offset(), next_offset(), next_offset()))
+            .collect();
+
+        Self { pairs }
+    }
+
+    fn compute_descriptor(&self, img: &GrayImage, corner: &ScrollOffset) -> ScrollBinaryDescriptor {
+        let max_x = img.width() as i32 - 1;
+        let max_y = img.height() as i32 - 1;
+        // 超出图片的点取边缘像素
+        let get_pixel = |dx: i32, dy: i32| {
+            let x = (corner.x + dx).clamp(0, max_x);
+            let y = (corner.y + dy).clamp(0, max_y);
+            img.get_pixel(x as u32, y as u32)[0]
+        };
+
+        let mut descriptor = [0u64; BRIEF_DESCRIPTOR_BITS / 64];
+        for (i, (x1, y1, x2, y2)) in self.pairs.iter().enumerate() {
+            if get_pixel(*x1, *y1) < get_pixel(*x2, *y2) {
+                descriptor[i / 64] |= 1u64 << (i % 64);
+            }
+        }
+
+        descriptor
+    }
+}
+
+impl ScrollFeatureDescriptor for ScrollBriefDescriptor {
+    fn describe(&self, image: &GrayImage, corners: &[ScrollOffset]) -> ScrollDescriptors {
+        ScrollDescriptors::Binary(
+            corners
+                .par_iter()
+                .map(|corner| self.compute_descriptor(image, corner))
+                .collect(),
+        )
+    }
+}
+
+/// 计算行（列）的哈希，空白行返回 None
+fn get_line_hash(image: &GrayImage, is_vertical: bool, line: u32) -> Option<u64> {
+    let line_length = if is_vertical {
+        image.width()
+    } else {
+        image.height()
+    };
+
+    // FNV-1a
+    let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+    let mut min_value = u8::MAX;
+    let mut max_value = u8::MIN;
+    for i in 0..line_length {
+        let (x, y) = if is_vertical { (i, line) } else { (line, i) };
+        let value = image.get_pixel(x, y)[0];
+        min_value = min_value.min(value);
+        max_value = max_value.max(value);
+        hash ^= value as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+    }
+
+    if max_value.saturating_sub(min_value) < LINE_HASH_MIN_CONTRAST {
+        return None;
+    }
+
+    Some(hash)
+}
+
+/**
+ * 将滚动方向上的每一行（列）作为特征点
+ * 空白行和帧内重复的行无法确定位置，不作为特征点
+ */
+pub struct ScrollLineDetector {
+    is_vertical: bool,
+}
+
+impl ScrollLineDetector {
+    pub fn new(is_vertical: bool) -> Self {
+        Self { is_vertical }
+    }
+}
+
+impl ScrollFeatureDetector for ScrollLineDetector {
+    fn detect(&mut self, image: &GrayImage) -> Vec<ScrollOffset> {
+        let line_count = if self.is_vertical {
+            image.height()
+        } else {
+            image.width()
+        };
+
+        let line_hashes: Vec<Option<u64>> = (0..line_count)
+            .into_par_iter()
+            .map(|line| get_line_hash(image, self.is_vertical, line))
+            .collect();
+
+        let mut hash_counts: HashMap<u64, usize> = HashMap::new();
+        for hash in line_hashes.iter().flatten() {
+            *hash_counts.entry(*hash).or_insert(0) += 1;
+        }
+
+        line_hashes
+            .iter()
+            .enumerate()
+            .filter(|(_, hash)| matches!(hash, Some(hash) if hash_counts[hash] == 1))
+            .map(|(line, _)| {
+                if self.is_vertical {
+                    ScrollOffset::new(0, line as i32)
+                } else {
+                    ScrollOffset::new(line as i32, 0)
+                }
+            })
+            .collect()
+    }
+}
+
+/// 特征点所在行（列）的哈希
+pub struct ScrollLineHashDescriptor {
+    is_vertical: bool,
+}
+
+impl ScrollLineHashDescriptor {
+    pub fn new(is_vertical: bool) -> Self {
+        Self { is_vertical }
+    }
+}
+
+impl ScrollFeatureDescriptor for ScrollLineHashDescriptor {
+    fn describe(&self, image: &GrayImage, corners: &[ScrollOffset]) -> ScrollDescriptors {
+        ScrollDescriptors::Hash(
+            corners
+                .par_iter()
+                .map(|corner| {
+                    let line = if self.is_vertical { corner.y } else { corner.x };
+                    get_line_hash(image, self.is_vertical, line as u32).unwrap_or(0)
+                })
+                .collect(),
+        )
+    }
+}
+
+/// HNSW 近邻搜索，用于浮点描述符
+pub struct ScrollHnswMatcher {
+    dimension: usize,
+}
+
+impl ScrollHnswMatcher {
+    pub fn new(dimension: usize) -> Self {
+        Self { dimension }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScrollHnswIndex {
+    ann_index: HNSWIndex<f32, usize>,
+    descriptors: Vec<Vec<f32>>,
+}
+
+fn euclidean_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (x - y).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+impl ScrollFeatureMatcher for ScrollHnswMatcher {
+    fn build_index(&self, descriptors: ScrollDescriptors) -> Arc<dyn ScrollFeatureIndex> {
+        let mut index_params = HNSWParams::<f32>::default();
+        index_params.ef_search = 24;
+        index_params.ef_build = 12;
+
+        let descriptors = match descriptors {
+            ScrollDescriptors::Float(descriptors) => descriptors,
+            _ => vec![],
+        };
+
+        let mut ann_index = HNSWIndex::new(self.dimension, &index_params);
+        if !descriptors.is_empty() {
+            descriptors.iter().enumerate().for_each(|(i, descriptor)| {
+                ann_index.add(descriptor, i).unwrap();
+            });
+            ann_index.build(Metric::Euclidean).unwrap();
+        }
+
+        Arc::new(ScrollHnswIndex {
+            ann_index,
+            descriptors,
+        })
+    }
+}
+
+impl ScrollFeatureIndex for ScrollHnswIndex {
+    fn nearest(&self, descriptors: &ScrollDescriptors) -> Vec<Option<ScrollFeatureMatch>> {
+        let descriptors = match descriptors {
+            ScrollDescriptors::Float(descriptors) if !self.descriptors.is_empty() => descriptors,
+            _ => return vec![None; descriptors.len()],
+        };
+
+        descriptors
+            .par_iter()
+            .map(|descriptor| {
+                let index = *self.ann_index.search(descriptor, 1).first()?;
+                let distance = euclidean_distance(&self.descriptors[index], descriptor);
+
+                Some(ScrollFeatureMatch {
+                    index,
+                    is_within_distance: distance < PATCH_MAX_DESCRIPTOR_DISTANCE,
+                })
+            })
+            .collect()
+    }
+}
+
+/// 暴力搜索汉明距离最小的二进制描述符
+pub struct ScrollHammingMatcher {
+    max_distance: u32,
+}
+
+impl ScrollHammingMatcher {
+    pub fn new(max_distance: u32) -> Self {
+        Self { max_distance }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScrollHammingIndex {
+    descriptors: Vec<ScrollBinaryDescriptor>,
+    max_distance: u32,
+}
+
+impl ScrollFeatureMatcher for ScrollHammingMatcher {
+    fn build_index(&self, descriptors: ScrollDescriptors) -> Arc<dyn ScrollFeatureIndex> {
+        Arc::new(ScrollHammingIndex {
+            descriptors: match descriptors {
+                ScrollDescriptors::Binary(descriptors) => descriptors,
+                _ => vec![],
+            },
+            max_distance: self.max_distance,
+        })
+    }
+}
+
+impl ScrollFeatureIndex for ScrollHammingIndex {
+    fn nearest(&self, descriptors: &ScrollDescriptors) -> Vec<Option<ScrollFeatureMatch>> {
+        let descriptors = match descriptors {
+            ScrollDescriptors::Binary(descriptors) => descriptors,
+            _ => return vec![None; descriptors.len()],
+        };
+
+        descriptors
+            .par_iter()
+            .map(|descriptor| {
+                let (index, distance) = self
+                    .descriptors
+                    .iter()
+                    .enumerate()
+                    .map(|(index, index_descriptor)| {
+                        let distance = index_descriptor
+                            .iter()
+                            .zip(descriptor.iter())
+                            .map(|(a, b)| (a ^ b).count_ones())
+                            .sum::<u32>();
+                        (index, distance)
+                    })
+                    .min_by_key(|(_, distance)| *distance)?;
+
+                Some(ScrollFeatureMatch {
+                    index,
+                    is_within_distance: distance <= self.max_distance,
+                })
+            })
+            .collect()
+    }
+}
+
+/// 按哈希值精确匹配
+pub struct ScrollHashMatcher;
+
+#[derive(Debug)]
+pub struct ScrollHashIndex {
+    /// 哈希值到特征下标，重复的哈希值无法确定位置，不加入索引
+    hash_indices: HashMap<u64, usize>,
+}
+
+impl ScrollFeatureMatcher for ScrollHashMatcher {
+    fn build_index(&self, descriptors: ScrollDescriptors) -> Arc<dyn ScrollFeatureIndex> {
+        let hashes = match descriptors {
+            ScrollDescriptors::Hash(hashes) => hashes,
+            _ => vec![],
+        };
+
+        let mut hash_indices: HashMap<u64, Option<usize>> = HashMap::new();
+        for (index, hash) in hashes.into_iter().enumerate() {
+            hash_indices
+                .entry(hash)
+                .and_modify(|value| *value = None)
+                .or_insert(Some(index));
+        }
+
+        Arc::new(ScrollHashIndex {
+            hash_indices: hash_indices
+                .into_iter()
+                .filter_map(|(hash, index)| Some((hash, index?)))
+                .collect(),
+        })
+    }
+}
+
+impl ScrollFeatureIndex for ScrollHashIndex {
+    fn nearest(&self, descriptors: &ScrollDescriptors) -> Vec<Option<ScrollFeatureMatch>> {
+        let hashes = match descriptors {
+            ScrollDescriptors::Hash(hashes) => hashes,
+            _ => return vec![None; descriptors.len()],
+        };
+
+        hashes
+            .iter()
+            .map(|hash| {
+                self.hash_indices.get(hash).map(|index| ScrollFeatureMatch {
+                    index: *index,
+                    is_within_distance: true,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scroll_screenshot_service::ScrollScreenshotParams;
+    use crate::scroll_screenshot_stitch::{StitchFrameStatus, stitch_frames};
+    use crate::scroll_screenshot_synthetic::{SyntheticFixture, compare_images};
+    use image::DynamicImage;
+
+    #[test]
+    fn test_feature_backends_on_same_input() {
+        for direction in [ScrollDirection::Vertical, ScrollDirection::Horizontal] {
+            let fixture = SyntheticFixture::new(direction, 200, 200, &[60, 45, 80, 30], 41);
+
+            for backend in [
+                ScrollFeatureBackend::FastPatch,
+                ScrollFeatureBackend::FastBrief,
+                ScrollFeatureBackend::LineHash,
+            ] {
+                // 使用前端的默认参数，匹配前会缩放到默认的采样尺寸
+                let result = stitch_frames(
+                    fixture.frame_images(),
+                    direction,
+                    ScrollScreenshotParams {
+                        feature_backend: backend,
+                        ..ScrollScreenshotParams::default_for_frame(direction, 200, 200)
+                    },
+                );
+
+                assert!(
+                    result
+                        .frames
+                        .iter()
+                        .all(|frame| frame.status == StitchFrameStatus::Stitched),
+                    "{:?} {:?} failed to stitch",
+                    direction,
+                    backend
+                );
+
+                let expected = DynamicImage::ImageRgb8(fixture.source.clone());
+                let report = compare_images(result.image.as_ref().unwrap(), &expected, 0).unwrap();
+                assert!(
+                    report.is_match(),
+                    "{:?} {:?} mismatch: {}",
+                    direction,
+                    backend,
+                    report
+                );
+            }
+        }
+    }
+}
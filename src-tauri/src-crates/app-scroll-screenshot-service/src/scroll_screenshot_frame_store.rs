@@ -0,0 +1,518 @@
+use image::DynamicImage;
+use snow_shot_app_utils::pixel_format::PixelFormat;
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::scroll_screenshot_metadata::ScrollFrameMetadata;
+use crate::scroll_screenshot_service::{ScrollImage, ScrollImageList};
+
+/// 默认的帧内存预算
+pub const DEFAULT_FRAME_MEMORY_BUDGET: usize = 1024 * 1024 * 1024;
+
+/// 区分同一进程中的多个帧存储
+static FRAME_STORE_ID: AtomicUsize = AtomicUsize::new(0);
+
+enum ScrollFrameData {
+    /// 在内存中，格式与帧存储的像素格式一致
+    Memory(DynamicImage),
+    /// 已写入磁盘，按行存储的原始像素数据
+    Disk(PathBuf),
+}
+
+struct ScrollFrame {
+    width: u32,
+    height: u32,
+    overlay_size: i32,
+    metadata: ScrollFrameMetadata,
+    /// 推入的顺序，超出预算时优先写出最早推入的帧
+    sequence: usize,
+    data: ScrollFrameData,
+}
+
+impl ScrollFrame {
+    fn byte_size(&self, pixel_format: PixelFormat) -> usize {
+        self.width as usize * self.height as usize * pixel_format.bytes_per_pixel()
+    }
+}
+
+/// 帧存储中的一帧
+pub struct ScrollFrameRef<'a> {
+    pub image: &'a DynamicImage,
+    pub overlay_size: i32,
+}
+
+/// 拼接帧的存储
+///
+/// 内存占用超过预算时，把较早推入的帧按行写入磁盘，导出时按需读取部分行。
+/// 每个图片列表的最后一帧始终保留在内存中，用于生成缩略图
+pub struct ScrollFrameStore {
+    top_frames: Vec<ScrollFrame>,
+    bottom_frames: Vec<ScrollFrame>,
+    /// 由存储为空时推入的帧决定，之后推入的帧都会转换为该格式
+    pixel_format: PixelFormat,
+    memory_budget: usize,
+    memory_usage: usize,
+    next_sequence: usize,
+    /// 写入磁盘的目录，首次写出时创建
+    spill_directory: PathBuf,
+}
+
+impl ScrollFrameStore {
+    pub fn new(memory_budget: usize) -> Self {
+        let spill_directory = std::env::temp_dir().join(format!(
+            "snow-shot-scroll-screenshot-{}-{}",
+            std::process::id(),
+            FRAME_STORE_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        Self {
+            top_frames: vec![],
+            bottom_frames: vec![],
+            pixel_format: PixelFormat::default(),
+            memory_budget,
+            memory_usage: 0,
+            next_sequence: 0,
+            spill_directory,
+        }
+    }
+
+    fn frames(&self, image_list: ScrollImageList) -> &Vec<ScrollFrame> {
+        match image_list {
+            ScrollImageList::Top => &self.top_frames,
+            ScrollImageList::Bottom => &self.bottom_frames,
+        }
+    }
+
+    fn frames_mut(&mut self, image_list: ScrollImageList) -> &mut Vec<ScrollFrame> {
+        match image_list {
+            ScrollImageList::Top => &mut self.top_frames,
+            ScrollImageList::Bottom => &mut self.bottom_frames,
+        }
+    }
+
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.pixel_format
+    }
+
+    pub fn memory_budget(&self) -> usize {
+        self.memory_budget
+    }
+
+    /// 修改内存预算，超出预算的帧会立即写入磁盘
+    pub fn set_memory_budget(&mut self, memory_budget: usize) -> Result<(), String> {
+        self.memory_budget = memory_budget;
+        self.enforce_memory_budget()
+    }
+
+    /// 内存中的帧占用的字节数
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage
+    }
+
+    /// 已写入磁盘的帧数量
+    pub fn spilled_count(&self) -> usize {
+        self.top_frames
+            .iter()
+            .chain(self.bottom_frames.iter())
+            .filter(|frame| matches!(frame.data, ScrollFrameData::Disk(_)))
+            .count()
+    }
+
+    pub fn len(&self, image_list: ScrollImageList) -> usize {
+        self.frames(image_list).len()
+    }
+
+    pub fn is_empty(&self, image_list: ScrollImageList) -> bool {
+        self.frames(image_list).is_empty()
+    }
+
+    /// 两个图片列表的帧总数
+    pub fn total_len(&self) -> usize {
+        self.top_frames.len() + self.bottom_frames.len()
+    }
+
+    pub fn frame_size(&self, image_list: ScrollImageList, index: usize) -> (u32, u32) {
+        let frame = &self.frames(image_list)[index];
+        (frame.width, frame.height)
+    }
+
+    pub fn overlay_size(&self, image_list: ScrollImageList, index: usize) -> i32 {
+        self.frames(image_list)[index].overlay_size
+    }
+
+    pub fn metadata(&self, image_list: ScrollImageList, index: usize) -> &ScrollFrameMetadata {
+        &self.frames(image_list)[index].metadata
+    }
+
+    /// 帧推入的顺序，清空存储前不会重复，可以用来判断帧是否变化
+    pub fn sequence(&self, image_list: ScrollImageList, index: usize) -> usize {
+        self.frames(image_list)[index].sequence
+    }
+
+    /// 下一个推入的帧的顺序
+    pub fn next_sequence(&self) -> usize {
+        self.next_sequence
+    }
+
+    pub fn push(
+        &mut self,
+        image_list: ScrollImageList,
+        scroll_image: ScrollImage,
+    ) -> Result<(), String> {
+        if self.total_len() == 0 {
+            self.pixel_format = PixelFormat::from_image(&scroll_image.image);
+        }
+        let image = self.pixel_format.convert(scroll_image.image);
+
+        let frame = ScrollFrame {
+            width: image.width(),
+            height: image.height(),
+            overlay_size: scroll_image.overlay_size,
+            metadata: scroll_image.metadata,
+            sequence: self.next_sequence,
+            data: ScrollFrameData::Memory(image),
+        };
+        self.next_sequence += 1;
+        self.memory_usage += frame.byte_size(self.pixel_format);
+        self.frames_mut(image_list).push(frame);
+
+        self.enforce_memory_budget()
+    }
+
+    /// 取出图片列表的最后一帧，新的最后一帧会被读回内存
+    ///
+    /// 从磁盘读取失败时返回错误，帧保留在存储中
+    pub fn pop(&mut self, image_list: ScrollImageList) -> Result<Option<ScrollImage>, String> {
+        let frame_count = self.len(image_list);
+        if frame_count == 0 {
+            return Ok(None);
+        }
+
+        self.load_frame(image_list, frame_count - 1)?;
+        let frame = match self.frames_mut(image_list).pop() {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let load_result = match frame_count {
+            1 => Ok(()),
+            _ => self.load_frame(image_list, frame_count - 2),
+        };
+        if let Err(e) = load_result {
+            self.frames_mut(image_list).push(frame);
+            return Err(e);
+        }
+
+        let overlay_size = frame.overlay_size;
+        let metadata = frame.metadata.clone();
+        let image = self.take_frame_image(frame)?;
+
+        Ok(Some(ScrollImage {
+            image,
+            overlay_size,
+            metadata,
+        }))
+    }
+
+    pub fn last(&self, image_list: ScrollImageList) -> Option<ScrollFrameRef<'_>> {
+        let frame = self.frames(image_list).last()?;
+        match &frame.data {
+            ScrollFrameData::Memory(image) => Some(ScrollFrameRef {
+                image,
+                overlay_size: frame.overlay_size,
+            }),
+            ScrollFrameData::Disk(_) => None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.top_frames.clear();
+        self.bottom_frames.clear();
+        self.memory_usage = 0;
+        self.next_sequence = 0;
+
+        if self.spill_directory.exists() {
+            let _ = fs::remove_dir_all(&self.spill_directory);
+        }
+    }
+
+    /// 读取一帧中连续的若干行，返回按照像素格式存储的数据
+    pub fn read_rows(
+        &self,
+        image_list: ScrollImageList,
+        index: usize,
+        start_row: u32,
+        row_count: u32,
+    ) -> Result<Cow<'_, [u8]>, String> {
+        let frame = &self.frames(image_list)[index];
+        if start_row + row_count > frame.height {
+            return Err(format!(
+                "[ScrollFrameStore::read_rows] Rows out of range: {}..{} of {}",
+                start_row,
+                start_row + row_count,
+                frame.height
+            ));
+        }
+
+        let row_size = frame.width as usize * self.pixel_format.bytes_per_pixel();
+        let start = start_row as usize * row_size;
+        let size = row_count as usize * row_size;
+
+        match &frame.data {
+            ScrollFrameData::Memory(image) => {
+                Ok(Cow::Borrowed(&image.as_bytes()[start..start + size]))
+            }
+            ScrollFrameData::Disk(path) => {
+                let mut file = File::open(path).map_err(|e| {
+                    format!(
+                        "[ScrollFrameStore::read_rows] Failed to open {}: {}",
+                        path.display(),
+                        e
+                    )
+                })?;
+                file.seek(SeekFrom::Start(start as u64)).map_err(|e| {
+                    format!(
+                        "[ScrollFrameStore::read_rows] Failed to seek {}: {}",
+                        path.display(),
+                        e
+                    )
+                })?;
+
+                let mut rows = vec![0; size];
+                file.read_exact(&mut rows).map_err(|e| {
+                    format!(
+                        "[ScrollFrameStore::read_rows] Failed to read {}: {}",
+                        path.display(),
+                        e
+                    )
+                })?;
+
+                Ok(Cow::Owned(rows))
+            }
+        }
+    }
+
+    /// 读取完整的一帧
+    pub fn read_image(
+        &self,
+        image_list: ScrollImageList,
+        index: usize,
+    ) -> Result<Cow<'_, DynamicImage>, String> {
+        let frame = &self.frames(image_list)[index];
+        match &frame.data {
+            ScrollFrameData::Memory(image) => Ok(Cow::Borrowed(image)),
+            ScrollFrameData::Disk(_) => {
+                let rows = self.read_rows(image_list, index, 0, frame.height)?;
+                match self.pixel_format.image_from_bytes(
+                    frame.width,
+                    frame.height,
+                    rows.into_owned(),
+                ) {
+                    Some(image) => Ok(Cow::Owned(image)),
+                    None => Err(format!(
+                        "[ScrollFrameStore::read_image] Invalid frame data: {}",
+                        index
+                    )),
+                }
+            }
+        }
+    }
+
+    fn take_frame_image(&mut self, frame: ScrollFrame) -> Result<DynamicImage, String> {
+        let byte_size = frame.byte_size(self.pixel_format);
+        match frame.data {
+            ScrollFrameData::Memory(image) => {
+                self.memory_usage -= byte_size;
+                Ok(image)
+            }
+            ScrollFrameData::Disk(path) => {
+                let data = fs::read(&path).map_err(|e| {
+                    format!(
+                        "[ScrollFrameStore::take_frame_image] Failed to read {}: {}",
+                        path.display(),
+                        e
+                    )
+                })?;
+                let _ = fs::remove_file(&path);
+
+                match self
+                    .pixel_format
+                    .image_from_bytes(frame.width, frame.height, data)
+                {
+                    Some(image) => Ok(image),
+                    None => Err(format!(
+                        "[ScrollFrameStore::take_frame_image] Invalid frame data: {}",
+                        path.display()
+                    )),
+                }
+            }
+        }
+    }
+
+    /// 把写入磁盘的帧读回内存
+    fn load_frame(&mut self, image_list: ScrollImageList, index: usize) -> Result<(), String> {
+        let frame = &self.frames(image_list)[index];
+        let path = match &frame.data {
+            ScrollFrameData::Memory(_) => return Ok(()),
+            ScrollFrameData::Disk(path) => path.clone(),
+        };
+
+        let image = self.read_image(image_list, index)?.into_owned();
+        let _ = fs::remove_file(&path);
+
+        let pixel_format = self.pixel_format;
+        let frame = &mut self.frames_mut(image_list)[index];
+        frame.data = ScrollFrameData::Memory(image);
+        self.memory_usage += frame.byte_size(pixel_format);
+
+        self.enforce_memory_budget()
+    }
+
+    /// 内存占用超过预算时，把最早推入的帧写入磁盘
+    fn enforce_memory_budget(&mut self) -> Result<(), String> {
+        while self.memory_usage > self.memory_budget {
+            let top_last_index = self.top_frames.len().checked_sub(1);
+            let bottom_last_index = self.bottom_frames.len().checked_sub(1);
+
+            let candidate = self
+                .top_frames
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| Some(*index) != top_last_index)
+                .map(|(index, frame)| (ScrollImageList::Top, index, frame))
+                .chain(
+                    self.bottom_frames
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, _)| Some(*index) != bottom_last_index)
+                        .map(|(index, frame)| (ScrollImageList::Bottom, index, frame)),
+                )
+                .filter(|(_, _, frame)| matches!(frame.data, ScrollFrameData::Memory(_)))
+                .min_by_key(|(_, _, frame)| frame.sequence)
+                .map(|(image_list, index, _)| (image_list, index));
+
+            match candidate {
+                Some((image_list, index)) => self.spill_frame(image_list, index)?,
+                // 只剩下各列表的最后一帧
+                None => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn spill_frame(&mut self, image_list: ScrollImageList, index: usize) -> Result<(), String> {
+        if !self.spill_directory.exists() {
+            fs::create_dir_all(&self.spill_directory).map_err(|e| {
+                format!(
+                    "[ScrollFrameStore::spill_frame] Failed to create directory {}: {}",
+                    self.spill_directory.display(),
+                    e
+                )
+            })?;
+        }
+
+        let spill_directory = self.spill_directory.clone();
+        let pixel_format = self.pixel_format;
+        let frame = &mut self.frames_mut(image_list)[index];
+        let image = match &frame.data {
+            ScrollFrameData::Memory(image) => image,
+            ScrollFrameData::Disk(_) => return Ok(()),
+        };
+
+        let path = spill_directory.join(format!("{}.raw", frame.sequence));
+        let mut file = File::create(&path).map_err(|e| {
+            format!(
+                "[ScrollFrameStore::spill_frame] Failed to create {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+        file.write_all(image.as_bytes()).map_err(|e| {
+            format!(
+                "[ScrollFrameStore::spill_frame] Failed to write {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        frame.data = ScrollFrameData::Disk(path);
+        let byte_size = frame.byte_size(pixel_format);
+        self.memory_usage -= byte_size;
+
+        Ok(())
+    }
+}
+
+impl Drop for ScrollFrameStore {
+    fn drop(&mut self) {
+        if self.spill_directory.exists() {
+            let _ = fs::remove_dir_all(&self.spill_directory);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scroll_screenshot_synthetic::generate_source_image;
+
+    fn scroll_image(seed: u64) -> ScrollImage {
+        ScrollImage {
+            image: DynamicImage::ImageRgb8(generate_source_image(64, 48, seed)),
+            overlay_size: seed as i32,
+            metadata: ScrollFrameMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_spill_and_read_back() {
+        let frame_size = 64 * 48 * PixelFormat::Rgb8.bytes_per_pixel();
+        let mut store = ScrollFrameStore::new(frame_size * 2);
+
+        for seed in 0..4 {
+            store
+                .push(ScrollImageList::Bottom, scroll_image(seed))
+                .unwrap();
+        }
+        store.push(ScrollImageList::Top, scroll_image(4)).unwrap();
+
+        assert!(store.memory_usage() <= frame_size * 2);
+        assert_eq!(store.spilled_count(), 3);
+        assert!(store.last(ScrollImageList::Bottom).is_some());
+        assert!(store.last(ScrollImageList::Top).is_some());
+
+        for seed in 0..4 {
+            let expected = scroll_image(seed).image;
+            let image = store
+                .read_image(ScrollImageList::Bottom, seed as usize)
+                .unwrap();
+            assert_eq!(image.as_bytes(), expected.as_bytes());
+
+            let rows = store
+                .read_rows(ScrollImageList::Bottom, seed as usize, 10, 5)
+                .unwrap();
+            assert_eq!(
+                rows.as_ref(),
+                &expected.as_bytes()[10 * 64 * 3..15 * 64 * 3]
+            );
+        }
+
+        // 取出后，新的最后一帧读回内存
+        let popped = store.pop(ScrollImageList::Bottom).unwrap().unwrap();
+        assert_eq!(popped.overlay_size, 3);
+        let last = store.last(ScrollImageList::Bottom).unwrap();
+        assert_eq!(last.overlay_size, 2);
+        assert_eq!(last.image.as_bytes(), scroll_image(2).image.as_bytes());
+        assert!(store.memory_usage() <= frame_size * 2);
+
+        let spill_directory = store.spill_directory.clone();
+        assert!(spill_directory.exists());
+        store.clear();
+        assert!(!spill_directory.exists());
+        assert_eq!(store.total_len(), 0);
+    }
+}
@@ -1,11 +1,162 @@
-use image::DynamicImage;
+use image::{DynamicImage, GrayImage, imageops::FilterType};
+use serde::Serialize;
 use std::collections::VecDeque;
+use std::time::Duration;
 
+use crate::scroll_screenshot_metadata::ScrollFrameCaptureInfo;
 use crate::scroll_screenshot_service::ScrollImageList;
 
+/// 帧签名的尺寸，将帧缩小为固定尺寸的灰度图
+const FRAME_SIGNATURE_SIZE: u32 = 64;
+/// 签名中像素差异超过该值时认为发生了变化
+const FRAME_SIGNATURE_PIXEL_TOLERANCE: u8 = 8;
+/// 平均差异小于该值时认为是重复帧
+const DUPLICATE_MAX_MEAN_DIFF: f32 = 0.5;
+/// 变化区域占比小于该值时可能是局部动画（光标闪烁、加载动画等）
+const ANIMATION_MAX_CHANGED_AREA_RATIO: f32 = 0.25;
+/// 变化的像素中能被上一帧平移后解释的比例达到该值时认为是滚动
+const SCROLL_MIN_SHIFTED_MATCH_RATIO: f32 = 0.6;
+/// 比较平移后的签名时的最大平移距离
+const FRAME_SIGNATURE_MAX_SHIFT: i32 = (FRAME_SIGNATURE_SIZE / 2) as i32;
+/// 变化率的平滑系数，越大越依赖最近的帧
+const CHANGE_RATE_SMOOTHING: f32 = 0.5;
+/// 平均差异达到该值时变化率为 1
+const CHANGE_RATE_MAX_MEAN_DIFF: f32 = 32.0;
+/// 画面变化最快时的截图间隔
+pub const MIN_CAPTURE_INTERVAL: Duration = Duration::from_millis(64);
+/// 画面静止时的截图间隔
+pub const MAX_CAPTURE_INTERVAL: Duration = Duration::from_millis(256);
+
 pub struct ScrollScreenshotImage {
     pub image: DynamicImage,
     pub direction: ScrollImageList,
+    pub capture_info: Option<ScrollFrameCaptureInfo>,
+}
+
+/// 帧的缩略灰度图，用于在拼接前快速比较两帧
+#[derive(Clone)]
+pub struct ScrollFrameSignature {
+    image: GrayImage,
+}
+
+impl ScrollFrameSignature {
+    pub fn new(image: &DynamicImage) -> Self {
+        Self {
+            image: image
+                .resize_exact(
+                    FRAME_SIGNATURE_SIZE,
+                    FRAME_SIGNATURE_SIZE,
+                    FilterType::Triangle,
+                )
+                .to_luma8(),
+        }
+    }
+
+    /// 平均每个像素的差异
+    pub fn mean_diff(&self, other: &Self) -> f32 {
+        let diff_sum = self
+            .image
+            .as_raw()
+            .iter()
+            .zip(other.image.as_raw().iter())
+            .map(|(a, b)| a.abs_diff(*b) as u64)
+            .sum::<u64>();
+
+        diff_sum as f32 / self.image.as_raw().len() as f32
+    }
+
+    /// 变化区域的外接矩形占整帧的比例
+    pub fn changed_area_ratio(&self, other: &Self) -> f32 {
+        let mut changed_rect: Option<(u32, u32, u32, u32)> = None;
+        for (x, y, pixel) in self.image.enumerate_pixels() {
+            if pixel[0].abs_diff(other.image.get_pixel(x, y)[0]) <= FRAME_SIGNATURE_PIXEL_TOLERANCE
+            {
+                continue;
+            }
+
+            changed_rect = Some(match changed_rect {
+                Some((min_x, min_y, max_x, max_y)) => {
+                    (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                }
+                None => (x, y, x, y),
+            });
+        }
+
+        match changed_rect {
+            Some((min_x, min_y, max_x, max_y)) => {
+                ((max_x - min_x + 1) * (max_y - min_y + 1)) as f32
+                    / (FRAME_SIGNATURE_SIZE * FRAME_SIGNATURE_SIZE) as f32
+            }
+            None => 0.0,
+        }
+    }
+
+    /// 变化的像素中，能被 other 沿水平或垂直方向平移后解释的最大比例
+    ///
+    /// 滚动的内容在上一帧中平移后可以找到，局部动画则不行，即使只有很窄的区域在滚动也能区分。
+    /// 缩小后的签名中滚动距离通常不是整数，所以像素值落在相邻两个平移位置之间时也认为匹配
+    pub fn shifted_match_ratio(&self, other: &Self) -> f32 {
+        let changed_pixels = self
+            .image
+            .enumerate_pixels()
+            .filter(|(x, y, pixel)| {
+                pixel[0].abs_diff(other.image.get_pixel(*x, *y)[0])
+                    > FRAME_SIGNATURE_PIXEL_TOLERANCE
+            })
+            .map(|(x, y, pixel)| (x as i32, y as i32, pixel[0]))
+            .collect::<Vec<_>>();
+        if changed_pixels.is_empty() {
+            return 0.0;
+        }
+
+        let size = FRAME_SIGNATURE_SIZE as i32;
+        let other_pixel = |x: i32, y: i32| -> Option<u8> {
+            if x < 0 || y < 0 || x >= size || y >= size {
+                return None;
+            }
+
+            Some(other.image.get_pixel(x as u32, y as u32)[0])
+        };
+
+        let mut best_match_count = 0;
+        for (axis_x, axis_y) in [(1, 0), (0, 1)] {
+            for shift in
+                (-FRAME_SIGNATURE_MAX_SHIFT..=FRAME_SIGNATURE_MAX_SHIFT).filter(|shift| *shift != 0)
+            {
+                // 平移距离在 shift 和远离 0 的下一个整数之间
+                let next_shift = shift + shift.signum();
+                let match_count = changed_pixels
+                    .iter()
+                    .filter(|(x, y, value)| {
+                        let near = other_pixel(x - shift * axis_x, y - shift * axis_y);
+                        let far = other_pixel(x - next_shift * axis_x, y - next_shift * axis_y);
+                        let (min_value, max_value) = match (near, far) {
+                            (Some(near), Some(far)) => (near.min(far), near.max(far)),
+                            (Some(value), None) | (None, Some(value)) => (value, value),
+                            (None, None) => return false,
+                        };
+
+                        *value >= min_value.saturating_sub(FRAME_SIGNATURE_PIXEL_TOLERANCE)
+                            && *value <= max_value.saturating_add(FRAME_SIGNATURE_PIXEL_TOLERANCE)
+                    })
+                    .count();
+                best_match_count = best_match_count.max(match_count);
+            }
+        }
+
+        best_match_count as f32 / changed_pixels.len() as f32
+    }
+}
+
+/// 新截取的帧相对上一次加入队列的帧的变化
+#[derive(PartialEq, Serialize, Debug, Clone, Copy)]
+pub enum ScrollFrameChange {
+    /// 和上一帧基本一致
+    Duplicate,
+    /// 只有局部区域变化，且变化不能由画面平移解释
+    Animation,
+    /// 画面发生了滚动，需要拼接
+    Scroll,
 }
 
 /**
@@ -14,21 +165,72 @@ pub struct ScrollScreenshotImage {
  */
 pub struct ScrollScreenshotImageService {
     image_queue: VecDeque<ScrollScreenshotImage>,
+    /// 最后一次加入队列的帧
+    last_queued_signature: Option<ScrollFrameSignature>,
+    /// 最后一次截取的帧，包括被过滤的帧
+    last_captured_signature: Option<ScrollFrameSignature>,
+    /// 相邻两次截图的变化率，范围为 0 到 1
+    change_rate: f32,
 }
 
 impl ScrollScreenshotImageService {
     pub fn new() -> Self {
         Self {
             image_queue: VecDeque::new(),
+            last_queued_signature: None,
+            last_captured_signature: None,
+            change_rate: 0.0,
         }
     }
 
     /**
      * 将截图添加到待处理队列尾部
+     *
+     * 和上一次加入队列的帧相比没有滚动时（重复帧、局部动画）不会加入队列，返回帧的变化类型
      */
-    pub fn push_image(&mut self, image: DynamicImage, direction: ScrollImageList) {
-        self.image_queue
-            .push_back(ScrollScreenshotImage { image, direction });
+    pub fn push_image(
+        &mut self,
+        image: DynamicImage,
+        direction: ScrollImageList,
+        capture_info: Option<ScrollFrameCaptureInfo>,
+    ) -> ScrollFrameChange {
+        let signature = ScrollFrameSignature::new(&image);
+
+        if let Some(last_captured_signature) = &self.last_captured_signature {
+            let current_change_rate =
+                (signature.mean_diff(last_captured_signature) / CHANGE_RATE_MAX_MEAN_DIFF).min(1.0);
+            self.change_rate = self.change_rate * (1.0 - CHANGE_RATE_SMOOTHING)
+                + current_change_rate * CHANGE_RATE_SMOOTHING;
+        }
+
+        let frame_change = match &self.last_queued_signature {
+            Some(last_queued_signature) => {
+                if signature.mean_diff(last_queued_signature) < DUPLICATE_MAX_MEAN_DIFF {
+                    ScrollFrameChange::Duplicate
+                } else if signature.changed_area_ratio(last_queued_signature)
+                    < ANIMATION_MAX_CHANGED_AREA_RATIO
+                    && signature.shifted_match_ratio(last_queued_signature)
+                        < SCROLL_MIN_SHIFTED_MATCH_RATIO
+                {
+                    ScrollFrameChange::Animation
+                } else {
+                    ScrollFrameChange::Scroll
+                }
+            }
+            None => ScrollFrameChange::Scroll,
+        };
+
+        if frame_change == ScrollFrameChange::Scroll {
+            self.image_queue.push_back(ScrollScreenshotImage {
+                image,
+                direction,
+                capture_info,
+            });
+            self.last_queued_signature = Some(signature.clone());
+        }
+        self.last_captured_signature = Some(signature);
+
+        frame_change
     }
 
     /**
@@ -46,7 +248,105 @@ impl ScrollScreenshotImageService {
         self.image_queue.len()
     }
 
+    /// 最近截图的变化率，0 表示画面静止，1 表示画面剧烈变化
+    pub fn change_rate(&self) -> f32 {
+        self.change_rate
+    }
+
+    /// 根据变化率计算下一次截图的间隔，画面变化越快间隔越短
+    pub fn capture_interval(&self) -> Duration {
+        MAX_CAPTURE_INTERVAL
+            - (MAX_CAPTURE_INTERVAL - MIN_CAPTURE_INTERVAL).mul_f32(self.change_rate)
+    }
+
     pub fn clear(&mut self) {
         self.image_queue.clear();
+        self.last_queued_signature = None;
+        self.last_captured_signature = None;
+        self.change_rate = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scroll_screenshot_synthetic::generate_source_image;
+
+    #[test]
+    fn test_push_image_filters_frames() {
+        let source = DynamicImage::ImageRgb8(generate_source_image(200, 400, 5));
+        let mut image_service = ScrollScreenshotImageService::new();
+
+        let frame = source.crop_imm(0, 0, 200, 200);
+        assert_eq!(
+            image_service.push_image(frame.clone(), ScrollImageList::Bottom, None),
+            ScrollFrameChange::Scroll
+        );
+        assert_eq!(
+            image_service.push_image(frame.clone(), ScrollImageList::Bottom, None),
+            ScrollFrameChange::Duplicate
+        );
+        assert_eq!(image_service.change_rate(), 0.0);
+        assert_eq!(image_service.capture_interval(), MAX_CAPTURE_INTERVAL);
+
+        // 局部区域变化
+        let mut animation_frame = frame.to_rgb8();
+        for y in 80..110 {
+            for x in 80..110 {
+                animation_frame.put_pixel(x, y, image::Rgb([255, 0, 0]));
+            }
+        }
+        assert_eq!(
+            image_service.push_image(
+                DynamicImage::ImageRgb8(animation_frame),
+                ScrollImageList::Bottom,
+                None
+            ),
+            ScrollFrameChange::Animation
+        );
+
+        assert_eq!(
+            image_service.push_image(
+                source.crop_imm(0, 60, 200, 200),
+                ScrollImageList::Bottom,
+                None
+            ),
+            ScrollFrameChange::Scroll
+        );
+        assert!(image_service.change_rate() > 0.0);
+        assert!(image_service.capture_interval() < MAX_CAPTURE_INTERVAL);
+        assert_eq!(image_service.image_count(), 2);
+    }
+
+    /// 只有很窄的一列内容在滚动，其余区域保持不变
+    #[test]
+    fn test_push_image_narrow_column_scroll() {
+        let background = generate_source_image(400, 400, 7);
+        let column = generate_source_image(40, 600, 8);
+        let column_frame = |offset: u32| {
+            let mut frame = background.clone();
+            image::imageops::replace(
+                &mut frame,
+                &image::imageops::crop_imm(&column, 0, offset, 40, 400).to_image(),
+                180,
+                0,
+            );
+            DynamicImage::ImageRgb8(frame)
+        };
+
+        let mut image_service = ScrollScreenshotImageService::new();
+        assert_eq!(
+            image_service.push_image(column_frame(0), ScrollImageList::Bottom, None),
+            ScrollFrameChange::Scroll
+        );
+        for offset in [45, 90, 150, 200] {
+            assert_eq!(
+                image_service.push_image(column_frame(offset), ScrollImageList::Bottom, None),
+                ScrollFrameChange::Scroll,
+                "offset: {}",
+                offset
+            );
+        }
+        assert_eq!(image_service.image_count(), 5);
     }
 }
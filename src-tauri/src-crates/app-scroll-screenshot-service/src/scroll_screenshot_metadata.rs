@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+use snow_shot_app_shared::ElementRect;
+use snow_shot_app_utils::image_metadata::add_png_text_chunk;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::scroll_screenshot_page_export::create_file;
+use crate::scroll_screenshot_service::{
+    ScrollDirection, ScrollImageList, ScrollOffset, ScrollStickyBands,
+};
+
+/// 元数据的版本，格式不兼容时递增
+pub const SCROLL_EXPORT_METADATA_VERSION: u32 = 1;
+/// 元数据嵌入 PNG 时使用的 iTXt 块关键字
+pub const SCROLL_EXPORT_METADATA_PNG_KEYWORD: &str = "SnowShotScrollMetadata";
+
+/// 截图时的信息，由截图命令提供
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScrollFrameCaptureInfo {
+    /// 截图时间（Unix 时间戳，毫秒）
+    pub timestamp: u64,
+    /// 截图所在的显示器名称，跨越多个显示器时为重叠面积最大的显示器
+    pub monitor_name: Option<String>,
+    /// 截图区域
+    pub crop_region: ElementRect,
+}
+
+/// 拼接帧的附加信息，和帧一起保存
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ScrollFrameMetadata {
+    /// 没有通过截图命令拼接的帧（如测试）为 None
+    pub capture_info: Option<ScrollFrameCaptureInfo>,
+    /// 拼接时匹配的置信度，首帧为 1
+    pub match_confidence: f32,
+}
+
+/// 导出图片在滚动方向上的一段范围
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ScrollExportBand {
+    pub start: u32,
+    pub size: u32,
+}
+
+/// 导出图片中的一帧
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScrollExportFrameInfo {
+    pub image_list: ScrollImageList,
+    /// 在图片列表中的序号
+    pub index: usize,
+    pub capture_info: Option<ScrollFrameCaptureInfo>,
+    /// 帧左上角在导出图片中的位置
+    pub offset: ScrollOffset,
+    /// 帧去除固定区域和重复区域后的宽度
+    pub width: u32,
+    /// 帧去除固定区域和重复区域后的高度
+    pub height: u32,
+    /// 和相邻帧重叠的尺寸
+    pub overlay_size: i32,
+    pub match_confidence: f32,
+    /// 导出图片中来自该帧的范围，自由方向下后拼接的帧直接覆盖，不记录范围
+    pub bands: Vec<ScrollExportBand>,
+}
+
+/// 导出图片的元数据，帧按照在导出图片中的位置排序
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScrollExportMetadata {
+    pub version: u32,
+    pub direction: ScrollDirection,
+    pub width: u32,
+    pub height: u32,
+    pub sticky_bands: Option<ScrollStickyBands>,
+    pub frames: Vec<ScrollExportFrameInfo>,
+}
+
+impl ScrollExportMetadata {
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self)
+            .map_err(|e| format!("[ScrollExportMetadata::to_json] Failed to serialize: {}", e))
+    }
+}
+
+/// 元数据文件的路径，在图片文件名后追加 .json
+pub fn get_metadata_file_path(file_path: &Path) -> PathBuf {
+    let mut metadata_file_path = OsString::from(file_path.as_os_str());
+    metadata_file_path.push(".json");
+    PathBuf::from(metadata_file_path)
+}
+
+/// 将元数据写入图片旁的 JSON 文件
+pub fn write_metadata_file(
+    metadata: &ScrollExportMetadata,
+    file_path: &Path,
+) -> Result<(), String> {
+    let mut writer = create_file(&get_metadata_file_path(file_path))?;
+
+    serde_json::to_writer_pretty(&mut writer, metadata)
+        .map_err(|e| format!("[write_metadata_file] Failed to write metadata: {}", e))?;
+
+    writer
+        .flush()
+        .map_err(|e| format!("[write_metadata_file] Failed to flush: {}", e))
+}
+
+/// 将元数据以文本块写入已编码的 PNG
+pub fn embed_png_metadata(
+    png_data: Vec<u8>,
+    metadata: &ScrollExportMetadata,
+) -> Result<Vec<u8>, String> {
+    add_png_text_chunk(
+        png_data,
+        SCROLL_EXPORT_METADATA_PNG_KEYWORD,
+        &metadata.to_json()?,
+    )
+}
+
+/// 将元数据以 iTXt 块写入 PNG，需要在写入文件头前调用
+pub fn add_png_metadata<W: Write>(
+    encoder: &mut png::Encoder<'_, W>,
+    metadata: &ScrollExportMetadata,
+) -> Result<(), String> {
+    encoder
+        .add_itxt_chunk(
+            SCROLL_EXPORT_METADATA_PNG_KEYWORD.to_string(),
+            metadata.to_json()?,
+        )
+        .map_err(|e| format!("[add_png_metadata] Failed to add text chunk: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scroll_screenshot_service::ScrollScreenshotService;
+    use crate::scroll_screenshot_synthetic::SyntheticFixture;
+    use snow_shot_app_utils::image_encoder::encode_image_for_file;
+
+    #[test]
+    fn test_export_metadata_matches_export() {
+        let fixture =
+            SyntheticFixture::new(ScrollDirection::Vertical, 200, 240, &[60, 50, 70, 40], 46);
+
+        let mut service = ScrollScreenshotService::new();
+        service.init_with_params(ScrollDirection::Vertical, fixture.params);
+        for (index, frame) in fixture.frames.iter().enumerate() {
+            service.handle_image_with_capture_info(
+                frame.image.clone(),
+                ScrollImageList::Bottom,
+                Some(ScrollFrameCaptureInfo {
+                    timestamp: index as u64,
+                    monitor_name: None,
+                    crop_region: ElementRect {
+                        min_x: 0,
+                        min_y: 0,
+                        max_x: 200,
+                        max_y: 240,
+                    },
+                }),
+            );
+        }
+
+        let metadata = service.get_export_metadata().unwrap();
+        let export_image = service.export().unwrap().to_rgb8();
+        assert_eq!(metadata.frames.len(), service.frame_store.total_len());
+        assert_eq!(metadata.height, export_image.height());
+
+        // 每一段都来自对应的帧，并且所有段连续覆盖整个导出图片
+        let mut next_start = 0;
+        let mut last_timestamp = None;
+        for frame_info in metadata.frames.iter() {
+            // 向下滚动时，帧的位置和截图顺序一致
+            let timestamp = frame_info.capture_info.as_ref().unwrap().timestamp;
+            assert!(last_timestamp < Some(timestamp));
+            last_timestamp = Some(timestamp);
+
+            let frame_image = service
+                .frame_store
+                .read_image(frame_info.image_list, frame_info.index)
+                .unwrap()
+                .to_rgb8();
+            for band in frame_info.bands.iter() {
+                assert_eq!(band.start, next_start);
+                next_start += band.size;
+
+                let frame_start = (band.start as i32 - frame_info.offset.y) as u32;
+                for y in 0..band.size {
+                    for x in 0..metadata.width {
+                        assert_eq!(
+                            export_image.get_pixel(x, band.start + y),
+                            frame_image.get_pixel(x, frame_start + y)
+                        );
+                    }
+                }
+            }
+        }
+        assert_eq!(next_start, metadata.height);
+
+        // 嵌入 PNG 的元数据和 JSON 文件一致，流式写出和编码后写入的结果相同
+        let mut png_data = Vec::new();
+        service
+            .export_png_with_metadata(&mut png_data, Some(&metadata))
+            .unwrap();
+        let encoded_png_data = embed_png_metadata(
+            encode_image_for_file(
+                &service.export().unwrap(),
+                Path::new("export.png"),
+                None,
+                None,
+            )
+            .unwrap(),
+            &metadata,
+        )
+        .unwrap();
+        for png_data in [png_data, encoded_png_data] {
+            let png_reader = png::Decoder::new(png_data.as_slice()).read_info().unwrap();
+            let info = png_reader.info();
+            let text = info
+                .utf8_text
+                .iter()
+                .find(|chunk| chunk.keyword == SCROLL_EXPORT_METADATA_PNG_KEYWORD)
+                .map(|chunk| chunk.get_text().unwrap())
+                .or_else(|| {
+                    info.uncompressed_latin1_text
+                        .iter()
+                        .find(|chunk| chunk.keyword == SCROLL_EXPORT_METADATA_PNG_KEYWORD)
+                        .map(|chunk| chunk.text.clone())
+                })
+                .unwrap();
+            let embedded_metadata: ScrollExportMetadata = serde_json::from_str(&text).unwrap();
+            assert_eq!(embedded_metadata, metadata);
+        }
+    }
+}
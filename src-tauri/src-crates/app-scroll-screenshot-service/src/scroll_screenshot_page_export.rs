@@ -0,0 +1,641 @@
+use flate2::{Compression, write::ZlibEncoder};
+use image::{DynamicImage, GenericImageView};
+use serde::{Deserialize, Serialize};
+use snow_shot_app_utils::image_encoder::{encode_image, get_image_encoder_for_file};
+use snow_shot_app_utils::image_format::ImageFileFormat;
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use crate::scroll_screenshot_service::{ScrollDirection, ScrollScreenshotService};
+
+/// PDF 中一个像素对应的点数（按照 96 DPI 换算）
+const PDF_POINTS_PER_PIXEL: f32 = 0.75;
+
+/// 分页导出的输出格式
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum ScrollPageExportFormat {
+    /// 多页 PDF，每页一张图片
+    Pdf,
+    /// 按序号命名的图片文件
+    ImageSequence,
+    /// 将分页图片打包为 ZIP
+    Zip,
+}
+
+/// 分页导出的参数
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollPageExportOptions {
+    pub format: ScrollPageExportFormat,
+    /// 每页在滚动方向上的尺寸，自由方向为高度
+    pub page_size: u32,
+    /// 相邻两页重叠的尺寸，需要小于 page_size
+    pub overlap: u32,
+}
+
+/// 单页在完整图片中的位置
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScrollPageInfo {
+    /// 页序号，从 0 开始
+    pub index: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// 单页对应的文件名，PDF 中的页没有文件名
+    pub file_name: Option<String>,
+}
+
+/// 分页导出的清单，记录每页在完整图片中的偏移
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScrollPageManifest {
+    pub direction: ScrollDirection,
+    /// 完整图片的宽度
+    pub width: u32,
+    /// 完整图片的高度
+    pub height: u32,
+    pub page_size: u32,
+    pub overlap: u32,
+    pub pages: Vec<ScrollPageInfo>,
+}
+
+/// 计算每页在滚动方向上的起始位置和尺寸
+pub fn get_page_ranges(total_size: u32, page_size: u32, overlap: u32) -> Vec<(u32, u32)> {
+    let mut page_ranges = vec![];
+    if total_size == 0 || page_size == 0 || overlap >= page_size {
+        return page_ranges;
+    }
+
+    let mut start = 0;
+    loop {
+        let size = page_size.min(total_size - start);
+        page_ranges.push((start, size));
+
+        if start + size >= total_size {
+            break;
+        }
+
+        start += page_size - overlap;
+    }
+
+    page_ranges
+}
+
+/// 按页序号生成文件名，序号从 1 开始并补齐位数
+fn get_page_file_name(file_stem: &str, index: usize, page_count: usize, extension: &str) -> String {
+    let digit_count = page_count.to_string().len().max(3);
+
+    format!(
+        "{}_{:0width$}.{}",
+        file_stem,
+        index + 1,
+        extension,
+        width = digit_count
+    )
+}
+
+fn write_manifest<W: Write>(manifest: &ScrollPageManifest, mut writer: W) -> Result<(), String> {
+    serde_json::to_writer_pretty(&mut writer, manifest)
+        .map_err(|e| format!("[write_manifest] Failed to write manifest: {}", e))?;
+
+    writer
+        .flush()
+        .map_err(|e| format!("[write_manifest] Failed to flush: {}", e))
+}
+
+/// 创建文件及其所在的目录
+pub fn create_file(file_path: &Path) -> Result<BufWriter<File>, String> {
+    if let Some(parent_dir) = file_path.parent() {
+        std::fs::create_dir_all(parent_dir).map_err(|e| {
+            format!(
+                "[create_file] Failed to create directory {}: {}",
+                parent_dir.display(),
+                e
+            )
+        })?;
+    }
+
+    match File::create(file_path) {
+        Ok(file) => Ok(BufWriter::new(file)),
+        Err(e) => Err(format!(
+            "[create_file] Failed to create file {}: {}",
+            file_path.display(),
+            e
+        )),
+    }
+}
+
+/// 依次写入 PDF 对象，最后写入页面树和交叉引用表
+///
+/// 对象编号：1 为目录，2 为页面树，第 i 页占用 3 + 3i（页面）、4 + 3i（内容）、5 + 3i（图片）
+pub struct ScrollPdfWriter<W: Write> {
+    writer: W,
+    position: usize,
+    object_positions: Vec<usize>,
+    page_count: usize,
+}
+
+impl<W: Write> ScrollPdfWriter<W> {
+    pub fn new(writer: W) -> Result<Self, String> {
+        let mut pdf_writer = Self {
+            writer,
+            position: 0,
+            object_positions: vec![],
+            page_count: 0,
+        };
+
+        pdf_writer.write_bytes(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n")?;
+        pdf_writer.write_object(1, b"<< /Type /Catalog /Pages 2 0 R >>")?;
+
+        Ok(pdf_writer)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.writer
+            .write_all(bytes)
+            .map_err(|e| format!("[ScrollPdfWriter::write_bytes] Failed to write: {}", e))?;
+        self.position += bytes.len();
+
+        Ok(())
+    }
+
+    fn begin_object(&mut self, object_id: usize) -> Result<(), String> {
+        if self.object_positions.len() < object_id {
+            self.object_positions.resize(object_id, 0);
+        }
+        self.object_positions[object_id - 1] = self.position;
+
+        self.write_bytes(format!("{} 0 obj\n", object_id).as_bytes())
+    }
+
+    fn write_object(&mut self, object_id: usize, content: &[u8]) -> Result<(), String> {
+        self.begin_object(object_id)?;
+        self.write_bytes(content)?;
+        self.write_bytes(b"\nendobj\n")
+    }
+
+    fn write_stream_object(
+        &mut self,
+        object_id: usize,
+        dictionary: &str,
+        data: &[u8],
+    ) -> Result<(), String> {
+        self.begin_object(object_id)?;
+        self.write_bytes(
+            format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).as_bytes(),
+        )?;
+        self.write_bytes(data)?;
+        self.write_bytes(b"\nendstream\nendobj\n")
+    }
+
+    /// 写入一页，页面尺寸与图片尺寸一致
+    pub fn add_page(&mut self, image: &DynamicImage) -> Result<(), String> {
+        let (width, height) = image.dimensions();
+        let page_object_id = 3 + self.page_count * 3;
+        let content_object_id = page_object_id + 1;
+        let image_object_id = page_object_id + 2;
+
+        let page_width = width as f32 * PDF_POINTS_PER_PIXEL;
+        let page_height = height as f32 * PDF_POINTS_PER_PIXEL;
+
+        self.write_object(
+            page_object_id,
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                page_width, page_height, image_object_id, content_object_id
+            )
+            .as_bytes(),
+        )?;
+
+        let content = format!(
+            "q {:.2} 0 0 {:.2} 0 0 cm /Im0 Do Q",
+            page_width, page_height
+        );
+        self.write_stream_object(content_object_id, "", content.as_bytes())?;
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+        if let Err(e) = encoder.write_all(image.to_rgb8().as_raw()) {
+            return Err(format!(
+                "[ScrollPdfWriter::add_page] Failed to compress image: {}",
+                e
+            ));
+        }
+        let image_data = encoder.finish().map_err(|e| {
+            format!(
+                "[ScrollPdfWriter::add_page] Failed to compress image: {}",
+                e
+            )
+        })?;
+        self.write_stream_object(
+            image_object_id,
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /FlateDecode",
+                width, height
+            ),
+            &image_data,
+        )?;
+
+        self.page_count += 1;
+
+        Ok(())
+    }
+
+    /// 写入页面树和交叉引用表，返回内部的 writer
+    pub fn finish(mut self) -> Result<W, String> {
+        let kids = (0..self.page_count)
+            .map(|index| format!("{} 0 R", 3 + index * 3))
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.write_object(
+            2,
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids, self.page_count
+            )
+            .as_bytes(),
+        )?;
+
+        let xref_position = self.position;
+        let mut xref = format!(
+            "xref\n0 {}\n0000000000 65535 f \n",
+            self.object_positions.len() + 1
+        );
+        for object_position in self.object_positions.iter() {
+            xref.push_str(&format!("{:010} 00000 n \n", object_position));
+        }
+        xref.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.object_positions.len() + 1,
+            xref_position
+        ));
+        self.write_bytes(xref.as_bytes())?;
+
+        self.writer
+            .flush()
+            .map_err(|e| format!("[ScrollPdfWriter::finish] Failed to flush: {}", e))?;
+
+        Ok(self.writer)
+    }
+}
+
+/// 将拼接结果写入多页 PDF，清单写入同名的 json 文件
+pub fn export_pages_to_pdf(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    page_size: u32,
+    overlap: u32,
+    file_path: &Path,
+) -> Result<ScrollPageManifest, String> {
+    let mut pdf_writer = ScrollPdfWriter::new(create_file(file_path)?)?;
+
+    let manifest = scroll_screenshot_service
+        .export_pages(page_size, overlap, |_, image| pdf_writer.add_page(&image))?;
+
+    pdf_writer.finish()?;
+    write_manifest(&manifest, create_file(&file_path.with_extension("json"))?)?;
+
+    Ok(manifest)
+}
+
+/// 将每页写入 file_path 所在目录，文件名为 {file_stem}_{序号}.{扩展名}，清单写入 {file_stem}.json
+pub fn export_pages_to_image_sequence(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    page_size: u32,
+    overlap: u32,
+    file_path: &Path,
+) -> Result<ScrollPageManifest, String> {
+    // 按照格式表选择扩展名对应的编码器
+    let encoder = get_image_encoder_for_file(file_path, None)?;
+    let extension = encoder.extension();
+    let file_stem = file_path
+        .file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let directory = file_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let page_count = scroll_screenshot_service.get_page_count(page_size, overlap);
+    let manifest = scroll_screenshot_service.export_pages(page_size, overlap, |page, image| {
+        let file_name = get_page_file_name(&file_stem, page.index, page_count, extension);
+
+        let page_data = encode_image(&image, &encoder)?;
+        let mut writer = create_file(&directory.join(&file_name))?;
+        writer
+            .write_all(&page_data)
+            .map_err(|e| format!("[export_pages_to_image_sequence] Failed to write: {}", e))?;
+        writer
+            .flush()
+            .map_err(|e| format!("[export_pages_to_image_sequence] Failed to flush: {}", e))?;
+
+        page.file_name = Some(file_name);
+
+        Ok(())
+    })?;
+
+    write_manifest(
+        &manifest,
+        create_file(&directory.join(format!("{}.json", file_stem)))?,
+    )?;
+
+    Ok(manifest)
+}
+
+/// 将每页编码为 PNG 并和清单一起打包为 ZIP
+pub fn export_pages_to_zip<W: Write + Seek>(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    page_size: u32,
+    overlap: u32,
+    writer: W,
+) -> Result<ScrollPageManifest, String> {
+    let mut zip_writer = ZipWriter::new(writer);
+    // PNG 已经是压缩过的数据，直接存储
+    let file_options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    let page_count = scroll_screenshot_service.get_page_count(page_size, overlap);
+    let manifest = scroll_screenshot_service.export_pages(page_size, overlap, |page, image| {
+        let file_name = get_page_file_name("page", page.index, page_count, "png");
+
+        let page_data = encode_image(&image, &ImageFileFormat::Png.default_encoder())?;
+
+        zip_writer
+            .start_file(file_name.as_str(), file_options)
+            .map_err(|e| format!("[export_pages_to_zip] Failed to start page: {}", e))?;
+        zip_writer
+            .write_all(&page_data)
+            .map_err(|e| format!("[export_pages_to_zip] Failed to write page: {}", e))?;
+
+        page.file_name = Some(file_name);
+
+        Ok(())
+    })?;
+
+    zip_writer
+        .start_file("manifest.json", file_options)
+        .map_err(|e| format!("[export_pages_to_zip] Failed to write manifest: {}", e))?;
+    write_manifest(&manifest, &mut zip_writer)?;
+
+    zip_writer
+        .finish()
+        .map_err(|e| format!("[export_pages_to_zip] Failed to finish: {}", e))?;
+
+    Ok(manifest)
+}
+
+/// 按照 options 分页导出到 file_path
+pub fn export_pages_to_file(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    options: ScrollPageExportOptions,
+    file_path: &Path,
+) -> Result<ScrollPageManifest, String> {
+    match options.format {
+        ScrollPageExportFormat::Pdf => export_pages_to_pdf(
+            scroll_screenshot_service,
+            options.page_size,
+            options.overlap,
+            file_path,
+        ),
+        ScrollPageExportFormat::ImageSequence => export_pages_to_image_sequence(
+            scroll_screenshot_service,
+            options.page_size,
+            options.overlap,
+            file_path,
+        ),
+        ScrollPageExportFormat::Zip => export_pages_to_zip(
+            scroll_screenshot_service,
+            options.page_size,
+            options.overlap,
+            create_file(file_path)?,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scroll_screenshot_synthetic::SyntheticFixture;
+    use std::io::{Cursor, Read};
+
+    #[test]
+    fn test_get_page_ranges() {
+        assert_eq!(
+            get_page_ranges(100, 40, 0),
+            vec![(0, 40), (40, 40), (80, 20)]
+        );
+        assert_eq!(
+            get_page_ranges(100, 40, 10),
+            vec![(0, 40), (30, 40), (60, 40)]
+        );
+        assert_eq!(get_page_ranges(30, 40, 10), vec![(0, 30)]);
+        assert!(get_page_ranges(100, 40, 40).is_empty());
+    }
+
+    #[test]
+    fn test_export_pages_to_zip() {
+        for direction in [ScrollDirection::Vertical, ScrollDirection::Horizontal] {
+            let fixture = SyntheticFixture::new(direction, 200, 200, &[60, 50, 70, 40], 45);
+            let mut service = fixture.stitch_service(fixture.params);
+            let full_image = service.export().unwrap().to_rgb8();
+
+            let mut zip_data = Cursor::new(Vec::new());
+            let manifest = export_pages_to_zip(&mut service, 150, 20, &mut zip_data).unwrap();
+            assert_eq!(manifest.pages.len(), 4);
+
+            let mut archive = zip::ZipArchive::new(zip_data).unwrap();
+            for page in manifest.pages.iter() {
+                let mut page_data = vec![];
+                archive
+                    .by_name(page.file_name.as_ref().unwrap())
+                    .unwrap()
+                    .read_to_end(&mut page_data)
+                    .unwrap();
+                let page_image = image::load_from_memory(&page_data).unwrap().to_rgb8();
+
+                let expected_image =
+                    image::imageops::crop_imm(&full_image, page.x, page.y, page.width, page.height)
+                        .to_image();
+                assert_eq!(page_image, expected_image);
+            }
+
+            let mut manifest_data = vec![];
+            archive
+                .by_name("manifest.json")
+                .unwrap()
+                .read_to_end(&mut manifest_data)
+                .unwrap();
+            assert_eq!(
+                serde_json::from_slice::<ScrollPageManifest>(&manifest_data).unwrap(),
+                manifest
+            );
+        }
+    }
+
+    #[test]
+    fn test_export_pages_to_image_sequence() {
+        let fixture = SyntheticFixture::new(ScrollDirection::Vertical, 200, 200, &[60, 50], 47);
+        let mut service = fixture.stitch_service(fixture.params);
+        let full_image = service.export().unwrap().to_rgb8();
+
+        let directory = std::env::temp_dir().join(format!(
+            "snow-shot-scroll-page-export-{}",
+            std::process::id()
+        ));
+
+        // 扩展名不在格式表中时不导出
+        assert!(
+            export_pages_to_image_sequence(&mut service, 150, 20, &directory.join("page.xyz"))
+                .is_err()
+        );
+
+        let manifest =
+            export_pages_to_image_sequence(&mut service, 150, 20, &directory.join("page.qoi"))
+                .unwrap();
+        for page in manifest.pages.iter() {
+            let file_name = page.file_name.as_ref().unwrap();
+            assert!(file_name.ends_with(".qoi"));
+
+            let page_image = image::open(directory.join(file_name)).unwrap().to_rgb8();
+            let expected_image =
+                image::imageops::crop_imm(&full_image, page.x, page.y, page.width, page.height)
+                    .to_image();
+            assert_eq!(page_image, expected_image);
+        }
+        assert!(directory.join("page.json").exists());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_export_pages_to_pdf() {
+        let mut pdf_writer = ScrollPdfWriter::new(Vec::new()).unwrap();
+        pdf_writer
+            .add_page(&DynamicImage::new_rgb8(40, 30))
+            .unwrap();
+        pdf_writer
+            .add_page(&DynamicImage::new_rgb8(40, 10))
+            .unwrap();
+        let pdf_data = pdf_writer.finish().unwrap();
+
+        let find_last = |pattern: &[u8]| {
+            pdf_data
+                .windows(pattern.len())
+                .rposition(|window| window == pattern)
+                .unwrap()
+        };
+
+        assert!(pdf_data.starts_with(b"%PDF-1.4"));
+        find_last(b"/Kids [3 0 R 6 0 R] /Count 2");
+
+        // 交叉引用表中记录的偏移需要指向对应的对象
+        let xref_position: usize =
+            String::from_utf8_lossy(&pdf_data[find_last(b"startxref\n") + 10..])
+                .lines()
+                .next()
+                .unwrap()
+                .parse()
+                .unwrap();
+        let xref = String::from_utf8_lossy(&pdf_data[xref_position..]).to_string();
+        assert!(xref.starts_with("xref\n0 9\n"));
+        for (index, line) in xref.lines().skip(3).take(8).enumerate() {
+            let object_position: usize = line[..10].parse().unwrap();
+            assert!(
+                pdf_data[object_position..].starts_with(format!("{} 0 obj", index + 1).as_bytes())
+            );
+        }
+    }
+    /// 从交叉引用表中读取各个对象的偏移，下标为对象编号减一
+    fn read_object_positions(pdf_data: &[u8]) -> Vec<usize> {
+        let startxref_position = pdf_data
+            .windows(b"startxref\n".len())
+            .rposition(|window| window == b"startxref\n")
+            .unwrap();
+        let xref_position: usize = String::from_utf8_lossy(&pdf_data[startxref_position + 10..])
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        String::from_utf8_lossy(&pdf_data[xref_position..])
+            .lines()
+            .skip(3)
+            .take_while(|line| line.ends_with(" n "))
+            .map(|line| line[..10].parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_export_pages_to_file_pdf() {
+        for direction in [ScrollDirection::Vertical, ScrollDirection::Horizontal] {
+            let fixture = SyntheticFixture::new(direction, 200, 200, &[60, 50, 70], 49);
+            let mut service = fixture.stitch_service(fixture.params);
+            let full_image = service.export().unwrap().to_rgb8();
+
+            let directory = std::env::temp_dir().join(format!(
+                "snow-shot-scroll-page-export-pdf-{}-{:?}",
+                std::process::id(),
+                direction
+            ));
+            let file_path = directory.join("pages.pdf");
+
+            let manifest = export_pages_to_file(
+                &mut service,
+                ScrollPageExportOptions {
+                    format: ScrollPageExportFormat::Pdf,
+                    page_size: 150,
+                    overlap: 20,
+                },
+                &file_path,
+            )
+            .unwrap();
+            assert_eq!(manifest.pages.len(), 3);
+
+            let pdf_data = std::fs::read(&file_path).unwrap();
+            let object_positions = read_object_positions(&pdf_data);
+            assert_eq!(object_positions.len(), 2 + manifest.pages.len() * 3);
+            let read_object = |object_id: usize| &pdf_data[object_positions[object_id - 1]..];
+
+            assert!(
+                String::from_utf8_lossy(read_object(2)).starts_with(&format!(
+                    "2 0 obj\n<< /Type /Pages /Kids [3 0 R 6 0 R 9 0 R] /Count {} >>",
+                    manifest.pages.len()
+                ))
+            );
+
+            // 每页的图片对象中保存的像素需要与完整图片对应的区域一致
+            for page in manifest.pages.iter() {
+                assert!(page.file_name.is_none());
+
+                let image_object = read_object(5 + page.index * 3);
+                let stream_position = image_object
+                    .windows(b"stream\n".len())
+                    .position(|window| window == b"stream\n")
+                    .unwrap()
+                    + b"stream\n".len();
+                assert!(
+                    String::from_utf8_lossy(&image_object[..stream_position])
+                        .contains(&format!("/Width {} /Height {}", page.width, page.height))
+                );
+
+                let mut page_data = vec![];
+                flate2::read::ZlibDecoder::new(&image_object[stream_position..])
+                    .read_to_end(&mut page_data)
+                    .unwrap();
+
+                let expected_image =
+                    image::imageops::crop_imm(&full_image, page.x, page.y, page.width, page.height)
+                        .to_image();
+                assert_eq!(page_data, expected_image.into_raw());
+            }
+
+            let manifest_data = std::fs::read(directory.join("pages.json")).unwrap();
+            assert_eq!(
+                serde_json::from_slice::<ScrollPageManifest>(&manifest_data).unwrap(),
+                manifest
+            );
+
+            std::fs::remove_dir_all(&directory).unwrap();
+        }
+    }
+}
@@ -1,20 +1,36 @@
+use fast_image_resize::{FilterType, ResizeAlg, ResizeOptions};
 use fast_image_resize::{PixelType, Resizer, images::Image};
-use fast_image_resize::{ResizeAlg, ResizeOptions};
-use hora::core::ann_index::ANNIndex;
-use hora::core::metrics::Metric;
-use hora::index::{hnsw_idx::HNSWIndex, hnsw_params::HNSWParams};
 use image::{DynamicImage, GenericImageView, GrayImage};
-use imageproc::corners;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use snow_shot_app_shared::ElementRect;
+use snow_shot_app_utils::image_encoder::{PngCompressionLevel, PngEncoderOptions, PngFilter};
+use snow_shot_app_utils::pixel_format::PixelFormat;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::scroll_screenshot_feature::{
+    ScrollDescriptors, ScrollFeatureBackend, ScrollFeatureIndex, ScrollFeaturePipeline,
+};
+use crate::scroll_screenshot_frame_store::{DEFAULT_FRAME_MEMORY_BUDGET, ScrollFrameStore};
+use crate::scroll_screenshot_metadata::{
+    SCROLL_EXPORT_METADATA_VERSION, ScrollExportBand, ScrollExportFrameInfo, ScrollExportMetadata,
+    ScrollFrameCaptureInfo, ScrollFrameMetadata, add_png_metadata,
+};
+use crate::scroll_screenshot_page_export::{ScrollPageInfo, ScrollPageManifest, get_page_ranges};
+use crate::scroll_screenshot_session::ScrollSessionAutosave;
+
 #[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum ScrollDirection {
     /// 垂直滚动
     Vertical = 0,
     /// 水平滚动
     Horizontal = 1,
+    /// 自由方向（同时在水平和垂直方向上平移）
+    Free = 2,
 }
 
 #[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
@@ -25,7 +41,7 @@ pub enum ScrollImageList {
     Bottom = 1,
 }
 
-#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct ScrollOffset {
     pub x: i32,
     pub y: i32,
@@ -59,22 +75,26 @@ impl CropRegion {
 #[derive(Debug)]
 pub struct ScrollIndex {
     pub position: i32,
-    pub ann_index: HNSWIndex<f32, usize>,
     pub corners: Vec<ScrollOffset>,
-    pub descriptors: Vec<Vec<f32>>,
+    /// 特征点的索引，没有特征点时为 None
+    pub feature_index: Option<Arc<dyn ScrollFeatureIndex>>,
+    /// 索引帧缩放后的灰度图，用于校验候选偏移
+    pub gray_image: Option<GrayImage>,
+    /// 自由方向下，索引帧在画布上的位置
+    pub free_position: ScrollOffset,
+    /// 自由方向下，索引帧的原尺寸灰度图，用于修正缩放带来的误差
+    pub free_gray_image: Option<GrayImage>,
 }
 
 impl ScrollIndex {
-    pub fn new(dimension: usize) -> Self {
-        let mut index_params = HNSWParams::<f32>::default();
-        index_params.ef_search = 24;
-        index_params.ef_build = 12;
-
+    pub fn new() -> Self {
         Self {
             position: 0,
-            ann_index: HNSWIndex::new(dimension, &index_params),
             corners: vec![],
-            descriptors: vec![],
+            feature_index: None,
+            gray_image: None,
+            free_position: ScrollOffset::new(0, 0),
+            free_gray_image: None,
         }
     }
 }
@@ -82,13 +102,280 @@ impl ScrollIndex {
 pub struct ScrollImage {
     pub image: image::DynamicImage,
     pub overlay_size: i32,
+    pub metadata: ScrollFrameMetadata,
+}
+
+/// 固定区域（标题栏、底栏）在滚动方向上的尺寸
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct ScrollStickyBands {
+    /// 顶部（左侧）固定区域尺寸
+    pub start_size: u32,
+    /// 底部（右侧）固定区域尺寸
+    pub end_size: u32,
+}
+
+impl ScrollStickyBands {
+    pub fn is_empty(&self) -> bool {
+        self.start_size == 0 && self.end_size == 0
+    }
+}
+
+/// 固定区域检测时，允许的像素差异
+const STICKY_BAND_PIXEL_TOLERANCE: u8 = 8;
+/// 小于该尺寸的固定区域忽略
+const STICKY_BAND_MIN_SIZE: u32 = 4;
+/// 连续多少次滚动都检测到固定区域时才应用，避免空白边距被误判为固定区域
+const STICKY_BAND_CONFIRM_COUNT: usize = 2;
+/// 超过该帧数仍无法确定固定区域时，不再检测
+const STICKY_BAND_MAX_DETECTION_FRAMES: usize = 6;
+/// 自由方向下，修正偏移时的最大搜索半径
+const FREE_OFFSET_MAX_REFINE_RADIUS: i32 = 8;
+/// 自由方向下特征点匹配失败时搜索平移量，两帧在每个方向上至少重叠该比例
+const FREE_OFFSET_SEARCH_MIN_OVERLAP_RATIO: f32 = 0.35;
+/// 超过该比例的特征点落在已拼接区域内时，认为画面没有变化
+const MATCH_ORIGIN_RATIO: f32 = 0.72;
+/// 偏移与候选偏移相差不超过该值时，计为候选偏移的内点
+const MATCH_INLIER_TOLERANCE: i32 = 1;
+/// 参与像素校验的候选偏移数量
+const MATCH_MAX_CANDIDATES: usize = 4;
+/// 像素校验时，重叠区域平均灰度差超过该值视为不匹配
+const MATCH_MAX_MEAN_DIFF: f32 = 32.0;
+/// 低于该置信度的匹配结果直接丢弃
+const MATCH_MIN_CONFIDENCE: f32 = 0.05;
+/// 按行导出时，每次填充的行数
+const EXPORT_STRIP_ROW_COUNT: usize = 64;
+
+/// 导出图片中一段内容的来源
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScrollExportSource {
+    Frame(ScrollImageList, usize),
+    StickyStart,
+    StickyEnd,
+}
+
+/// 导出图片在滚动方向上的一段内容
+#[derive(Debug, Clone, Copy)]
+struct ScrollExportSegment {
+    source: ScrollExportSource,
+    /// 在来源中的起始位置
+    source_start: u32,
+    /// 在导出图片中的起始位置
+    target_start: u32,
+    size: u32,
+}
+
+/// 接缝两侧按权重混合的一段内容，越靠后 second 的权重越高
+#[derive(Debug, Clone, Copy)]
+struct ScrollExportBlend {
+    /// 接缝前（上或左）的帧
+    first: ScrollExportSource,
+    first_source_start: u32,
+    /// 接缝后（下或右）的帧
+    second: ScrollExportSource,
+    second_source_start: u32,
+    target_start: u32,
+    size: u32,
+    /// 在完整混合区域中的偏移，分页导出时混合区域可能被截断
+    blend_offset: u32,
+    /// 完整混合区域的尺寸
+    blend_size: u32,
+}
+
+impl ScrollExportBlend {
+    /// 第 index 行（列）中 second 的权重
+    fn get_weight(&self, index: u32) -> f32 {
+        ((self.blend_offset + index) as f32 + 0.5) / self.blend_size as f32
+    }
+}
+
+/// 导出图片的布局，分段和混合区域都按照滚动方向上的位置记录
+#[derive(Debug, Clone)]
+struct ScrollExportLayout {
+    width: usize,
+    height: usize,
+    segments: Vec<ScrollExportSegment>,
+    blends: Vec<ScrollExportBlend>,
+}
+
+impl ScrollExportLayout {
+    /// 截取滚动方向上 [start, start + size) 的部分，位置转换为截取后的坐标
+    fn clip(&self, is_vertical: bool, start: u32, size: u32) -> Self {
+        let end = start + size;
+        let clip_range = |target_start: u32, target_size: u32| {
+            let clip_start = target_start.max(start);
+            let clip_end = (target_start + target_size).min(end);
+
+            if clip_start < clip_end {
+                Some((
+                    clip_start - target_start,
+                    clip_start - start,
+                    clip_end - clip_start,
+                ))
+            } else {
+                None
+            }
+        };
+
+        let segments = self
+            .segments
+            .iter()
+            .filter_map(|segment| {
+                let (offset, target_start, size) = clip_range(segment.target_start, segment.size)?;
+
+                Some(ScrollExportSegment {
+                    source: segment.source,
+                    source_start: segment.source_start + offset,
+                    target_start,
+                    size,
+                })
+            })
+            .collect();
+
+        let blends = self
+            .blends
+            .iter()
+            .filter_map(|blend| {
+                let (offset, target_start, size) = clip_range(blend.target_start, blend.size)?;
+
+                Some(ScrollExportBlend {
+                    first: blend.first,
+                    first_source_start: blend.first_source_start + offset,
+                    second: blend.second,
+                    second_source_start: blend.second_source_start + offset,
+                    target_start,
+                    size,
+                    blend_offset: blend.blend_offset + offset,
+                    blend_size: blend.blend_size,
+                })
+            })
+            .collect();
+
+        let (width, height) = if is_vertical {
+            (self.width, size as usize)
+        } else {
+            (size as usize, self.height)
+        };
+
+        Self {
+            width,
+            height,
+            segments,
+            blends,
+        }
+    }
+}
+
+/// 导出时相邻两帧重叠区域的处理方式
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub enum ScrollSeamMode {
+    /// 后绘制的帧直接覆盖重叠区域
+    #[default]
+    Overlay = 0,
+    /// 在重叠区域中选择两帧差异最小的行（列）作为接缝
+    MinDifference = 1,
+}
+
+/// 拼接状态，索引通过 Arc 共享，记录历史时不需要复制
+#[derive(Debug, Clone)]
+struct ScrollStitchState {
+    top_image_size: i32,
+    top_image_index_size: i32,
+    bottom_image_size: i32,
+    bottom_image_index_size: i32,
+    top_image_ann_index: Arc<ScrollIndex>,
+    bottom_image_ann_index: Arc<ScrollIndex>,
+    free_canvas_rect: ElementRect,
+}
+
+/// 固定区域确定前拼接的帧，确定后去除固定区域重新拼接
+#[derive(Debug, Clone)]
+pub(crate) struct ScrollStickyDetectionFrame {
+    pub image: DynamicImage,
+    pub capture_info: Option<ScrollFrameCaptureInfo>,
+    pub image_list: ScrollImageList,
+    /// 和上一帧对比检测到的固定区域，首帧和无法确定时为 None
+    pub sticky_bands: Option<ScrollStickyBands>,
+}
+
+/// 一帧的拼接记录
+#[derive(Debug)]
+struct ScrollHistoryEntry {
+    /// 帧被拼接到的图片列表
+    image_list: ScrollImageList,
+    /// 拼接前的状态
+    before_state: ScrollStitchState,
+    /// 拼接后的状态
+    after_state: ScrollStitchState,
+    /// 自由方向下帧在画布上的位置
+    free_image_position: Option<ScrollOffset>,
+    /// 检测固定区域时撤销的帧对应的检测帧，重做时放回
+    sticky_detection_frame: Option<ScrollStickyDetectionFrame>,
+}
+
+/// 滚动截图的初始化参数，对应 scroll_screenshot_init 的参数
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ScrollScreenshotParams {
+    /// 采样率
+    pub sample_rate: f32,
+    /// 最小采样尺寸
+    pub min_sample_size: u32,
+    /// 最大采样尺寸
+    pub max_sample_size: u32,
+    /// 特征点阈值
+    pub corner_threshold: u8,
+    /// 描述符块大小
+    pub descriptor_patch_size: usize,
+    /// 最小变化量（高于该值才会建立索引）
+    pub min_size_delta: i32,
+    /// 是否尝试回滚
+    pub try_rollback: bool,
+    /// 是否检测固定的标题栏和底栏
+    pub detect_sticky_bands: bool,
+    /// 帧占用的内存上限（字节），超出后较早的帧会写入磁盘
+    pub memory_budget: usize,
+    /// 重叠区域的接缝处理方式
+    pub seam_mode: ScrollSeamMode,
+    /// 接缝两侧混合的尺寸，为 0 时不混合
+    pub seam_feather_size: u32,
+    /// 特征提取和匹配方式
+    pub feature_backend: ScrollFeatureBackend,
+}
+
+impl ScrollScreenshotParams {
+    /// 与前端默认设置一致的参数
+    ///
+    /// min_size_delta 取滚动方向边长的 80%
+    pub fn default_for_frame(
+        direction: ScrollDirection,
+        image_width: u32,
+        image_height: u32,
+    ) -> Self {
+        let scroll_side_size = if direction == ScrollDirection::Vertical {
+            image_height
+        } else {
+            image_width
+        };
+
+        Self {
+            sample_rate: 1.0,
+            min_sample_size: 128,
+            max_sample_size: 128,
+            corner_threshold: 24,
+            descriptor_patch_size: 28,
+            min_size_delta: (scroll_side_size as f32 * 0.8).ceil() as i32,
+            try_rollback: true,
+            detect_sticky_bands: false,
+            memory_budget: DEFAULT_FRAME_MEMORY_BUDGET,
+            seam_mode: ScrollSeamMode::Overlay,
+            seam_feather_size: 0,
+            feature_backend: ScrollFeatureBackend::FastPatch,
+        }
+    }
 }
 
 pub struct ScrollScreenshotService {
-    /// 滚动截图列表（上或左）
-    pub top_image_list: Vec<ScrollImage>,
-    /// 滚动截图列表（下或右）
-    pub bottom_image_list: Vec<ScrollImage>,
+    /// 滚动截图列表（上或左、下或右），超出内存预算的帧会写入磁盘
+    pub frame_store: ScrollFrameStore,
     /// 当前方向
     pub current_direction: ScrollDirection,
     /// 图片宽度
@@ -112,9 +399,9 @@ pub struct ScrollScreenshotService {
     /// 描述符块大小
     pub descriptor_patch_size: usize,
     /// 特征点索引（上或右）
-    pub top_image_ann_index: ScrollIndex,
+    pub top_image_ann_index: Arc<ScrollIndex>,
     /// 特征点索引（下或左）
-    pub bottom_image_ann_index: ScrollIndex,
+    pub bottom_image_ann_index: Arc<ScrollIndex>,
     /// 最小变化量（高于该值才会建立索引）
     pub min_size_delta: i32,
     /// 缩放的图片宽度
@@ -123,8 +410,10 @@ pub struct ScrollScreenshotService {
     pub image_dst_height: u32,
     /// 滚动方向的图片尺寸
     pub image_scroll_side_size: i32,
-    /// 是否启用 fast12 算法进行角点检测
-    pub enable_corner_fast12: Option<bool>,
+    /// 特征提取和匹配方式
+    pub feature_backend: ScrollFeatureBackend,
+    /// 特征点检测、描述和匹配的实现
+    feature_pipeline: ScrollFeaturePipeline,
     /// 是否尝试回滚
     pub try_rollback: bool,
     /// 采样率
@@ -133,84 +422,38 @@ pub struct ScrollScreenshotService {
     pub min_sample_size: u32,
     /// 最大采样尺寸
     pub max_sample_size: u32,
+    /// 是否检测固定的标题栏和底栏
+    pub detect_sticky_bands: bool,
+    /// 重叠区域的接缝处理方式，只用于垂直和水平方向
+    pub seam_mode: ScrollSeamMode,
+    /// 接缝两侧混合的尺寸
+    pub seam_feather_size: u32,
+    /// 检测到的固定区域，None 表示还未确定
+    pub sticky_bands: Option<ScrollStickyBands>,
+    /// 固定区域确定前拼接的帧
+    pub(crate) sticky_detection_frames: Vec<ScrollStickyDetectionFrame>,
+    /// 顶部（左侧）固定区域的图像
+    pub(crate) sticky_start_image: Option<DynamicImage>,
+    /// 底部（右侧）固定区域的图像
+    pub(crate) sticky_end_image: Option<DynamicImage>,
+    /// 最近一次匹配的置信度，范围为 0 到 1
+    pub match_confidence: f32,
+    /// 已拼接帧的历史记录，用于撤销
+    history: Vec<ScrollHistoryEntry>,
+    /// 被撤销的帧，用于重做
+    redo_history: Vec<(ScrollHistoryEntry, ScrollImage)>,
+    /// 自由方向下，下图片列表中每一帧在画布上的位置
+    pub free_image_positions: Vec<ScrollOffset>,
+    /// 自由方向下，能容纳所有帧的画布区域（以首帧左上角为原点）
+    pub free_canvas_rect: ElementRect,
+    /// 截图过程中自动保存会话的记录
+    pub(crate) session_autosave: ScrollSessionAutosave,
 }
 
 impl ScrollScreenshotService {
-    fn get_descriptor_size(&self) -> usize {
-        self.descriptor_patch_size & !1
-    }
-
-    fn compute_descriptor(&self, img: &image::GrayImage, corner: &ScrollOffset) -> Vec<f32> {
-        let descriptor_size = self.descriptor_patch_size;
-        let mut descriptor = Vec::with_capacity(self.get_descriptor_size());
-        let half_size = descriptor_size as i32 / 2;
-
-        let corner_x = corner.x;
-        let corner_y = corner.y;
-        let width = img.width() as i32;
-        let height = img.height() as i32;
-
-        // 计算行特征
-        for row in 0..(descriptor_size / 2) {
-            let y = corner_y + (-half_size + row as i32 * 2);
-            let mut sum = 0.0;
-            let mut valid_pixels = 0;
-
-            for col in 0..(descriptor_size / 2) {
-                let x = corner_x + (-half_size + col as i32 * 2);
-
-                if x >= 0 && x < width && y >= 0 && y < height {
-                    let pixel = unsafe { img.unsafe_get_pixel(x as u32, y as u32) };
-                    sum += pixel[0] as f32 / 255.0;
-                    valid_pixels += 1;
-                }
-            }
-
-            descriptor.push(if valid_pixels > 0 {
-                sum / valid_pixels as f32
-            } else {
-                0.0
-            });
-        }
-
-        // 计算列特征
-        for col in 0..(descriptor_size / 2) {
-            let x = corner_x + (-half_size + col as i32 * 2);
-            let mut sum = 0.0;
-            let mut valid_pixels = 0;
-
-            for row in 0..(descriptor_size / 2) {
-                let y = corner_y + (-half_size + row as i32 * 2);
-
-                if x >= 0 && x < width && y >= 0 && y < height {
-                    let pixel = unsafe { img.unsafe_get_pixel(x as u32, y as u32) };
-                    sum += pixel[0] as f32 / 255.0;
-                    valid_pixels += 1;
-                }
-            }
-
-            descriptor.push(if valid_pixels > 0 {
-                sum / valid_pixels as f32
-            } else {
-                0.0
-            });
-        }
-
-        descriptor
-    }
-
-    fn euclidean_distance(a: &[f32], b: &[f32]) -> f32 {
-        a.iter()
-            .zip(b.iter())
-            .map(|(x, y)| (x - y).powi(2))
-            .sum::<f32>()
-            .sqrt()
-    }
-
     pub fn new() -> Self {
         Self {
-            top_image_list: vec![],
-            bottom_image_list: vec![],
+            frame_store: ScrollFrameStore::new(DEFAULT_FRAME_MEMORY_BUDGET),
             current_direction: ScrollDirection::Vertical,
             image_width: 0,
             image_height: 0,
@@ -226,53 +469,253 @@ impl ScrollScreenshotService {
             image_dst_width: 0,
             image_dst_height: 0,
             image_scroll_side_size: 0,
-            top_image_ann_index: ScrollIndex::new(0),
-            bottom_image_ann_index: ScrollIndex::new(0),
-            enable_corner_fast12: None,
+            top_image_ann_index: Arc::new(ScrollIndex::new()),
+            bottom_image_ann_index: Arc::new(ScrollIndex::new()),
+            feature_backend: ScrollFeatureBackend::FastPatch,
+            feature_pipeline: ScrollFeaturePipeline::from_backend(
+                ScrollFeatureBackend::FastPatch,
+                ScrollDirection::Vertical,
+                64,
+                9,
+            ),
             try_rollback: false,
             sample_rate: 0.0,
             min_sample_size: 0,
             max_sample_size: 0,
+            detect_sticky_bands: false,
+            seam_mode: ScrollSeamMode::Overlay,
+            seam_feather_size: 0,
+            sticky_bands: None,
+            sticky_detection_frames: vec![],
+            sticky_start_image: None,
+            sticky_end_image: None,
+            match_confidence: 0.0,
+            history: vec![],
+            redo_history: vec![],
+            free_image_positions: vec![],
+            free_canvas_rect: ElementRect {
+                min_x: 0,
+                min_y: 0,
+                max_x: 0,
+                max_y: 0,
+            },
+            session_autosave: ScrollSessionAutosave::default(),
+        }
+    }
+
+    fn capture_stitch_state(&self) -> ScrollStitchState {
+        ScrollStitchState {
+            top_image_size: self.top_image_size,
+            top_image_index_size: self.top_image_index_size,
+            bottom_image_size: self.bottom_image_size,
+            bottom_image_index_size: self.bottom_image_index_size,
+            top_image_ann_index: Arc::clone(&self.top_image_ann_index),
+            bottom_image_ann_index: Arc::clone(&self.bottom_image_ann_index),
+            free_canvas_rect: self.free_canvas_rect,
+        }
+    }
+
+    fn restore_stitch_state(&mut self, state: ScrollStitchState) {
+        self.top_image_size = state.top_image_size;
+        self.top_image_index_size = state.top_image_index_size;
+        self.bottom_image_size = state.bottom_image_size;
+        self.bottom_image_index_size = state.bottom_image_index_size;
+        self.top_image_ann_index = state.top_image_ann_index;
+        self.bottom_image_ann_index = state.bottom_image_ann_index;
+        self.free_canvas_rect = state.free_canvas_rect;
+    }
+
+    /// 记录刚拼接的帧，新的拼接会使重做记录失效
+    fn push_history(&mut self, image_list: ScrollImageList, before_state: ScrollStitchState) {
+        let free_image_position = if self.current_direction == ScrollDirection::Free {
+            self.free_image_positions.last().copied()
+        } else {
+            None
+        };
+
+        self.history.push(ScrollHistoryEntry {
+            image_list,
+            before_state,
+            after_state: self.capture_stitch_state(),
+            free_image_position,
+            sticky_detection_frame: None,
+        });
+        self.redo_history.clear();
+    }
+
+    fn clear_history(&mut self) {
+        self.history.clear();
+        self.redo_history.clear();
+    }
+
+    /// 是否有可以撤销的帧，首帧不能撤销
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_history.is_empty()
+    }
+
+    /// 撤销最近拼接的一帧，返回该帧所在的图片列表
+    ///
+    /// 从磁盘读取帧失败时返回错误，撤销记录保持不变
+    pub fn undo(&mut self) -> Result<Option<ScrollImageList>, String> {
+        let image_list = match self.history.last() {
+            Some(entry) => entry.image_list,
+            None => return Ok(None),
+        };
+
+        // 每条撤销记录都对应帧存储中的一帧
+        let scroll_image = match self.frame_store.pop(image_list)? {
+            Some(scroll_image) => scroll_image,
+            None => {
+                return Err(String::from(
+                    "[undo] Frame store does not match the history",
+                ));
+            }
+        };
+        let mut entry = match self.history.pop() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        if entry.free_image_position.is_some() {
+            self.free_image_positions.pop();
+        }
+
+        // 检测固定区域时，检测帧和已拼接的帧一一对应，同时移出撤销的帧对应的检测帧
+        if self.sticky_detection_frames.len() == self.frame_store.total_len() + 1 {
+            entry.sticky_detection_frame = self.sticky_detection_frames.pop();
+        }
+
+        self.restore_stitch_state(entry.before_state.clone());
+        self.redo_history.push((entry, scroll_image));
+
+        Ok(Some(image_list))
+    }
+
+    /// 重新拼接最近撤销的一帧，返回该帧所在的图片列表
+    ///
+    /// 写入磁盘失败时返回错误，重做记录保持不变
+    pub fn redo(&mut self) -> Result<Option<ScrollImageList>, String> {
+        let (mut entry, scroll_image) = match self.redo_history.pop() {
+            Some(redo_entry) => redo_entry,
+            None => return Ok(None),
+        };
+
+        if let Err(e) = self.frame_store.push(entry.image_list, scroll_image) {
+            // 推入的帧仍在内存中，取回后放回重做记录
+            if let Some(scroll_image) = self.frame_store.pop(entry.image_list)? {
+                self.redo_history.push((entry, scroll_image));
+            }
+            return Err(format!("[redo] Failed to push frame: {}", e));
+        }
+
+        if let Some(free_image_position) = entry.free_image_position {
+            self.free_image_positions.push(free_image_position);
+        }
+        if let Some(sticky_detection_frame) = entry.sticky_detection_frame.take() {
+            self.sticky_detection_frames.push(sticky_detection_frame);
         }
+
+        self.restore_stitch_state(entry.after_state.clone());
+        let image_list = entry.image_list;
+        self.history.push(entry);
+
+        Ok(Some(image_list))
     }
 
     pub fn clear(&mut self) {
-        self.top_image_list.clear();
-        self.bottom_image_list.clear();
-        self.top_image_ann_index = ScrollIndex::new(0);
-        self.bottom_image_ann_index = ScrollIndex::new(0);
+        self.frame_store.clear();
+        self.clear_history();
+        self.top_image_ann_index = Arc::new(ScrollIndex::new());
+        self.bottom_image_ann_index = Arc::new(ScrollIndex::new());
+        self.clear_sticky_bands();
+        self.clear_free_canvas();
+        self.match_confidence = 0.0;
+        self.session_autosave = ScrollSessionAutosave::default();
     }
 
-    pub fn init(
-        &mut self,
-        direction: ScrollDirection,
-        sample_rate: f32,
-        min_sample_size: u32,
-        max_sample_size: u32,
-        corner_threshold: u8,
-        descriptor_patch_size: usize,
-        min_size_delta: i32,
-        try_rollback: bool,
-    ) {
-        self.top_image_list.clear();
-        self.bottom_image_list.clear();
+    fn clear_free_canvas(&mut self) {
+        self.free_image_positions.clear();
+        self.free_canvas_rect = ElementRect {
+            min_x: 0,
+            min_y: 0,
+            max_x: 0,
+            max_y: 0,
+        };
+    }
+
+    fn clear_sticky_bands(&mut self) {
+        self.sticky_bands = None;
+        self.sticky_detection_frames.clear();
+        self.sticky_start_image = None;
+        self.sticky_end_image = None;
+    }
+
+    pub fn init_with_params(&mut self, direction: ScrollDirection, params: ScrollScreenshotParams) {
+        self.frame_store.clear();
+        // 写入磁盘失败时，帧保留在内存中
+        if let Err(e) = self.frame_store.set_memory_budget(params.memory_budget) {
+            log::warn!("[init_with_params] Failed to spill frames to disk: {}", e);
+        }
         self.current_direction = direction;
         self.image_width = 0;
         self.image_height = 0;
         self.top_image_size = 0;
         self.bottom_image_size = 0;
-        self.corner_threshold = corner_threshold;
-        self.descriptor_patch_size = descriptor_patch_size;
-        self.min_size_delta = min_size_delta;
+        self.corner_threshold = params.corner_threshold;
+        self.descriptor_patch_size = params.descriptor_patch_size;
+        self.min_size_delta = params.min_size_delta;
         self.top_image_index_size = 0;
         self.bottom_image_index_size = 0;
-        self.top_image_ann_index = ScrollIndex::new(self.get_descriptor_size());
-        self.bottom_image_ann_index = ScrollIndex::new(self.get_descriptor_size());
-        self.try_rollback = try_rollback;
-        self.enable_corner_fast12 = None;
-        self.sample_rate = sample_rate;
-        self.min_sample_size = min_sample_size;
-        self.max_sample_size = max_sample_size;
+        self.top_image_ann_index = Arc::new(ScrollIndex::new());
+        self.bottom_image_ann_index = Arc::new(ScrollIndex::new());
+        self.try_rollback = params.try_rollback;
+        self.feature_backend = params.feature_backend;
+        self.feature_pipeline = ScrollFeaturePipeline::from_backend(
+            params.feature_backend,
+            direction,
+            params.corner_threshold,
+            params.descriptor_patch_size,
+        );
+        self.sample_rate = params.sample_rate;
+        self.min_sample_size = params.min_sample_size;
+        self.max_sample_size = params.max_sample_size;
+        self.detect_sticky_bands = params.detect_sticky_bands;
+        self.seam_mode = params.seam_mode;
+        self.seam_feather_size = params.seam_feather_size;
+        self.clear_history();
+        self.clear_sticky_bands();
+        self.clear_free_canvas();
+        self.match_confidence = 0.0;
+        self.session_autosave = ScrollSessionAutosave::default();
+    }
+
+    /// 当前的初始化参数
+    pub fn params(&self) -> ScrollScreenshotParams {
+        ScrollScreenshotParams {
+            sample_rate: self.sample_rate,
+            min_sample_size: self.min_sample_size,
+            max_sample_size: self.max_sample_size,
+            corner_threshold: self.corner_threshold,
+            descriptor_patch_size: self.descriptor_patch_size,
+            min_size_delta: self.min_size_delta,
+            try_rollback: self.try_rollback,
+            detect_sticky_bands: self.detect_sticky_bands,
+            memory_budget: self.frame_store.memory_budget(),
+            seam_mode: self.seam_mode,
+            seam_feather_size: self.seam_feather_size,
+            feature_backend: self.feature_backend,
+        }
+    }
+
+    /// 替换特征点检测、描述和匹配的实现，用于对比不同的实现
+    ///
+    /// init 时会按照参数重新创建，需要在 init 之后调用
+    pub fn set_feature_pipeline(&mut self, feature_pipeline: ScrollFeaturePipeline) {
+        self.feature_pipeline = feature_pipeline;
     }
 
     pub fn init_image_size(&mut self, image_width: u32, image_height: u32) {
@@ -282,8 +725,11 @@ impl ScrollScreenshotService {
         let image_scale_side_size;
         if self.current_direction == ScrollDirection::Vertical {
             image_scale_side_size = image_width as f32;
-        } else {
+        } else if self.current_direction == ScrollDirection::Horizontal {
             image_scale_side_size = image_height as f32;
+        } else {
+            // 自由方向两个方向都需要缩放，按长边计算
+            image_scale_side_size = image_width.max(image_height) as f32;
         }
 
         let target_side_size = (image_scale_side_size * self.sample_rate)
@@ -295,9 +741,12 @@ impl ScrollScreenshotService {
         if self.current_direction == ScrollDirection::Vertical {
             self.image_dst_width = (image_width as f32 * self.image_scale) as u32;
             self.image_dst_height = image_height;
-        } else {
+        } else if self.current_direction == ScrollDirection::Horizontal {
             self.image_dst_width = image_width;
             self.image_dst_height = (image_height as f32 * self.image_scale) as u32;
+        } else {
+            self.image_dst_width = ((image_width as f32 * self.image_scale) as u32).max(1);
+            self.image_dst_height = ((image_height as f32 * self.image_scale) as u32).max(1);
         }
 
         self.image_scroll_side_size = if self.current_direction == ScrollDirection::Vertical {
@@ -307,15 +756,245 @@ impl ScrollScreenshotService {
         };
     }
 
+    /// 对比首帧和当前帧，检测首尾没有变化的区域
+    ///
+    /// 没有发生滚动或无法确定时返回 None
+    fn detect_sticky_bands_from_frames(
+        &self,
+        previous_frame: &DynamicImage,
+        current_frame: &DynamicImage,
+    ) -> Option<ScrollStickyBands> {
+        if previous_frame.dimensions() != current_frame.dimensions() {
+            return None;
+        }
+
+        let previous_frame = previous_frame.to_luma8();
+        let current_frame = current_frame.to_luma8();
+        let (image_width, image_height) = previous_frame.dimensions();
+
+        let is_vertical = self.current_direction == ScrollDirection::Vertical;
+        let (scroll_side_size, line_length) = if is_vertical {
+            (image_height, image_width)
+        } else {
+            (image_width, image_height)
+        };
+
+        let is_line_unchanged = |line: u32| {
+            (0..line_length).all(|i| {
+                let (x, y) = if is_vertical { (i, line) } else { (line, i) };
+                previous_frame.get_pixel(x, y)[0].abs_diff(current_frame.get_pixel(x, y)[0])
+                    <= STICKY_BAND_PIXEL_TOLERANCE
+            })
+        };
+
+        let start_size = (0..scroll_side_size)
+            .take_while(|line| is_line_unchanged(*line))
+            .count() as u32;
+
+        // 没有发生滚动
+        if start_size == scroll_side_size {
+            return None;
+        }
+
+        let end_size = (0..scroll_side_size)
+            .rev()
+            .take_while(|line| is_line_unchanged(*line))
+            .count() as u32;
+
+        // 固定区域过大时，更可能是滚动量太小，等待下一帧
+        let max_band_size = scroll_side_size / 3;
+        if start_size > max_band_size || end_size > max_band_size {
+            return None;
+        }
+
+        Some(ScrollStickyBands {
+            start_size,
+            end_size,
+        })
+    }
+
+    /// 和上一个拼接的帧对比检测固定区域
+    ///
+    /// 连续多次滚动都检测到时，取各次结果的交集并应用。返回当前帧的检测结果，
+    /// 需要在当前帧拼接后和帧一起记录
+    fn update_sticky_bands(&mut self, image: &DynamicImage) -> Option<ScrollStickyBands> {
+        // 记录的帧和已拼接的帧不一致时无法重新拼接
+        if self.sticky_detection_frames.len() >= STICKY_BAND_MAX_DETECTION_FRAMES
+            || self.sticky_detection_frames.len() != self.frame_store.total_len()
+        {
+            self.apply_sticky_bands(ScrollStickyBands::default());
+            return None;
+        }
+
+        let previous_frame = self.sticky_detection_frames.last()?;
+        let sticky_bands = self.detect_sticky_bands_from_frames(&previous_frame.image, image)?;
+
+        let detected_bands = self
+            .sticky_detection_frames
+            .iter()
+            .filter_map(|frame| frame.sticky_bands)
+            .chain(std::iter::once(sticky_bands))
+            .collect::<Vec<ScrollStickyBands>>();
+        if detected_bands.len() < STICKY_BAND_CONFIRM_COUNT {
+            return Some(sticky_bands);
+        }
+
+        let first_frame = self.sticky_detection_frames[0].image.to_luma8();
+        let scroll_side_size = if self.current_direction == ScrollDirection::Vertical {
+            first_frame.height()
+        } else {
+            first_frame.width()
+        };
+        let confirm_band_size = |size: u32, start: u32| {
+            // 纯色的区域无法区分是固定区域还是空白的内容
+            if size < STICKY_BAND_MIN_SIZE || self.is_uniform_band(&first_frame, start, size) {
+                0
+            } else {
+                size
+            }
+        };
+
+        let start_size = detected_bands
+            .iter()
+            .map(|bands| bands.start_size)
+            .min()
+            .unwrap_or(0);
+        let end_size = detected_bands
+            .iter()
+            .map(|bands| bands.end_size)
+            .min()
+            .unwrap_or(0);
+        self.apply_sticky_bands(ScrollStickyBands {
+            start_size: confirm_band_size(start_size, 0),
+            end_size: confirm_band_size(end_size, scroll_side_size - end_size),
+        });
+
+        None
+    }
+
+    /// 区域内的像素是否全部相同
+    fn is_uniform_band(&self, image: &GrayImage, start: u32, size: u32) -> bool {
+        let (image_width, image_height) = image.dimensions();
+        let (x, y, width, height) = if self.current_direction == ScrollDirection::Vertical {
+            (0, start, image_width, size)
+        } else {
+            (start, 0, size, image_height)
+        };
+
+        let first_pixel = image.get_pixel(x, y)[0];
+        (y..y + height).all(|y| (x..x + width).all(|x| image.get_pixel(x, y)[0] == first_pixel))
+    }
+
+    /// 应用检测到的固定区域
+    ///
+    /// 固定区域在拼接了几帧后才能确定，此时用去除固定区域后的帧重新开始拼接
+    fn apply_sticky_bands(&mut self, sticky_bands: ScrollStickyBands) {
+        let detection_frames = std::mem::take(&mut self.sticky_detection_frames);
+        self.sticky_bands = Some(sticky_bands);
+
+        if sticky_bands.is_empty() {
+            return;
+        }
+
+        let first_frame = match detection_frames.first() {
+            Some(first_frame) => &first_frame.image,
+            None => return,
+        };
+        self.set_sticky_bands(sticky_bands, first_frame);
+
+        self.frame_store.clear();
+        self.clear_history();
+        self.image_width = 0;
+        self.image_height = 0;
+        self.top_image_size = 0;
+        self.bottom_image_size = 0;
+        self.top_image_index_size = 0;
+        self.bottom_image_index_size = 0;
+        self.top_image_ann_index = Arc::new(ScrollIndex::new());
+        self.bottom_image_ann_index = Arc::new(ScrollIndex::new());
+        self.feature_pipeline.reset();
+
+        for frame in detection_frames {
+            self.handle_image_with_capture_info(frame.image, frame.image_list, frame.capture_info);
+        }
+    }
+
+    /// 使用已知的固定区域，之后拼接的帧都会去除固定区域
+    ///
+    /// 固定区域的图像从 first_frame 中截取，需要在拼接前调用
+    pub fn set_sticky_bands(
+        &mut self,
+        sticky_bands: ScrollStickyBands,
+        first_frame: &DynamicImage,
+    ) {
+        self.sticky_bands = Some(sticky_bands);
+        self.sticky_detection_frames.clear();
+
+        // 固定区域和之后推入的帧使用相同的像素格式
+        let first_frame = PixelFormat::from_image(first_frame).convert_ref(first_frame);
+        let (image_width, image_height) = first_frame.dimensions();
+        if self.current_direction == ScrollDirection::Vertical {
+            self.sticky_start_image =
+                Some(first_frame.crop_imm(0, 0, image_width, sticky_bands.start_size));
+            self.sticky_end_image = Some(first_frame.crop_imm(
+                0,
+                image_height - sticky_bands.end_size,
+                image_width,
+                sticky_bands.end_size,
+            ));
+        } else {
+            self.sticky_start_image =
+                Some(first_frame.crop_imm(0, 0, sticky_bands.start_size, image_height));
+            self.sticky_end_image = Some(first_frame.crop_imm(
+                image_width - sticky_bands.end_size,
+                0,
+                sticky_bands.end_size,
+                image_height,
+            ));
+        }
+    }
+
+    /// 去除图片的固定区域
+    fn crop_sticky_bands(&self, image: DynamicImage) -> DynamicImage {
+        let sticky_bands = match self.sticky_bands {
+            Some(sticky_bands) if !sticky_bands.is_empty() => sticky_bands,
+            _ => return image,
+        };
+
+        let (image_width, image_height) = image.dimensions();
+        let band_size = sticky_bands.start_size + sticky_bands.end_size;
+
+        if self.current_direction == ScrollDirection::Vertical {
+            if image_height <= band_size {
+                return image;
+            }
+
+            image.crop_imm(
+                0,
+                sticky_bands.start_size,
+                image_width,
+                image_height - band_size,
+            )
+        } else {
+            if image_width <= band_size {
+                return image;
+            }
+
+            image.crop_imm(
+                sticky_bands.start_size,
+                0,
+                image_width - band_size,
+                image_height,
+            )
+        }
+    }
+
     fn get_descriptors(
         &self,
         image: &image::ImageBuffer<image::Luma<u8>, Vec<u8>>,
         corners: &[ScrollOffset],
-    ) -> Vec<Vec<f32>> {
-        corners
-            .par_iter()
-            .map(|corner| self.compute_descriptor(image, corner))
-            .collect()
+    ) -> ScrollDescriptors {
+        self.feature_pipeline.descriptor.describe(image, corners)
     }
 
     fn get_gray_image(&mut self, image: &DynamicImage) -> GrayImage {
@@ -339,11 +1018,18 @@ impl ScrollScreenshotService {
 
         let mut dst_image = Image::new(self.image_dst_width, self.image_dst_height, PixelType::U8);
 
+        // 自由方向的平移在缩放后通常不是整数像素，最近邻缩放会让相同内容采样到不同的像素，
+        // 按区域平均缩放后特征点的描述子保持稳定
+        let resize_alg = if self.current_direction == ScrollDirection::Free {
+            ResizeAlg::Convolution(FilterType::Box)
+        } else {
+            ResizeAlg::Nearest
+        };
         self.image_resizer
             .resize(
                 &src_image,
                 &mut dst_image,
-                &ResizeOptions::new().resize_alg(ResizeAlg::Nearest),
+                &ResizeOptions::new().resize_alg(resize_alg),
             )
             .unwrap();
 
@@ -374,7 +1060,7 @@ impl ScrollScreenshotService {
             }
         } else {
             let start_position = image_width - delta_size.abs() as u32;
-            if start_position > 0 {
+            if delta_size > 0 {
                 region = CropRegion::new(
                     start_position,
                     0,
@@ -390,33 +1076,8 @@ impl ScrollScreenshotService {
     }
 
     fn get_corners(&mut self, image: &image::GrayImage) -> Vec<ScrollOffset> {
-        let corners;
-        if self.enable_corner_fast12.is_none() {
-            let fast12_corners = corners::corners_fast12(image, self.corner_threshold);
-
-            if fast12_corners.len() > 200 {
-                corners = fast12_corners;
-                self.enable_corner_fast12 = Some(true);
-            } else {
-                corners = corners::corners_fast9(image, self.corner_threshold);
-                self.enable_corner_fast12 = Some(false);
-            }
-        } else {
-            if self.enable_corner_fast12.unwrap() {
-                corners = corners::corners_fast12(image, self.corner_threshold);
-            } else {
-                corners = corners::corners_fast9(image, self.corner_threshold);
-            }
-        }
-
-        corners
-            .iter()
-            .map(|corner| ScrollOffset {
-                x: corner.x as i32,
-                y: corner.y as i32,
-            })
-            .collect()
-    }
+        self.feature_pipeline.detector.detect(image)
+    }
 
     fn build_index(
         &mut self,
@@ -425,25 +1086,8 @@ impl ScrollScreenshotService {
         edge_position: i32,
         index_edge_position_distance: i32,
     ) {
-        let mut new_scroll_index = ScrollIndex::new(self.get_descriptor_size());
-
-        new_scroll_index.descriptors = self.get_descriptors(&gray_image, &image_corners);
-
-        new_scroll_index.corners = image_corners.to_vec();
-
-        let mut index_params = HNSWParams::<f32>::default();
-        index_params.ef_search = 32;
-        index_params.ef_build = 16;
-
-        new_scroll_index
-            .descriptors
-            .iter()
-            .enumerate()
-            .for_each(|(i, descriptor)| {
-                new_scroll_index.ann_index.add(descriptor, i).unwrap();
-            });
-
-        new_scroll_index.ann_index.build(Metric::Euclidean).unwrap();
+        let descriptors = self.get_descriptors(&gray_image, image_corners);
+        let mut new_scroll_index = self.create_scroll_index(image_corners.to_vec(), descriptors);
 
         let index_position = if edge_position > 0 {
             self.bottom_image_index_size - index_edge_position_distance
@@ -452,11 +1096,12 @@ impl ScrollScreenshotService {
         };
 
         new_scroll_index.position = index_position;
+        new_scroll_index.gray_image = Some(gray_image);
 
         if edge_position > 0 {
-            self.bottom_image_ann_index = new_scroll_index;
+            self.bottom_image_ann_index = Arc::new(new_scroll_index);
         } else {
-            self.top_image_ann_index = new_scroll_index;
+            self.top_image_ann_index = Arc::new(new_scroll_index);
         }
     }
 
@@ -467,6 +1112,7 @@ impl ScrollScreenshotService {
         image_corners: Vec<ScrollOffset>,
         edge_position: i32,
         delta_size: i32,
+        metadata: ScrollFrameMetadata,
     ) -> (ScrollImage, i32) {
         let mut index_delta_size = 0;
 
@@ -507,6 +1153,7 @@ impl ScrollScreenshotService {
                     crop_region.height,
                 ),
                 overlay_size: image_overlay_size,
+                metadata,
             },
             index_delta_size,
         )
@@ -520,6 +1167,7 @@ impl ScrollScreenshotService {
         index_position: i32,
         origin_position: ScrollOffset,
         new_position: ScrollOffset,
+        metadata: ScrollFrameMetadata,
     ) -> (i32, Option<ScrollImageList>) {
         let position_offset = if self.current_direction == ScrollDirection::Vertical {
             ScrollOffset {
@@ -564,17 +1212,31 @@ impl ScrollScreenshotService {
                 return (edge_position, None); // 没有新增区域或变化太小
             };
 
-        let (cropped_image, index_delta_size) =
-            self.add_index(image, gray_image, image_corners, edge_position, delta_size);
+        let (cropped_image, index_delta_size) = self.add_index(
+            image,
+            gray_image,
+            image_corners,
+            edge_position,
+            delta_size,
+            metadata,
+        );
+
+        // 写入磁盘失败时，帧保留在内存中
+        let image_list = if is_bottom {
+            ScrollImageList::Bottom
+        } else {
+            ScrollImageList::Top
+        };
+        if let Err(e) = self.frame_store.push(image_list, cropped_image) {
+            log::warn!("[push_image] Failed to spill frames to disk: {}", e);
+        }
 
         if is_bottom {
-            self.bottom_image_list.push(cropped_image);
             self.bottom_image_size += delta_size;
             self.bottom_image_index_size += index_delta_size;
 
             (edge_position, Some(ScrollImageList::Bottom))
         } else {
-            self.top_image_list.push(cropped_image);
             self.top_image_size -= delta_size;
             self.top_image_index_size += index_delta_size;
 
@@ -582,13 +1244,70 @@ impl ScrollScreenshotService {
         }
     }
 
+    /// 校验候选偏移，返回重叠区域的相似度，范围为 0 到 1
+    ///
+    /// diff 为同一内容在当前帧中的位置减去在索引帧中的位置
+    fn verify_offset(
+        &self,
+        index_gray_image: &GrayImage,
+        gray_image: &GrayImage,
+        diff: i32,
+    ) -> f32 {
+        const SAMPLE_STEP: usize = 2;
+
+        if index_gray_image.dimensions() != gray_image.dimensions() {
+            return 0.0;
+        }
+
+        let (width, height) = gray_image.dimensions();
+        let (width, height) = (width as i32, height as i32);
+        let (dx, dy) = if self.current_direction == ScrollDirection::Vertical {
+            (0, diff)
+        } else {
+            (diff, 0)
+        };
+
+        let min_x = (-dx).max(0);
+        let max_x = (width - dx).min(width);
+        let min_y = (-dy).max(0);
+        let max_y = (height - dy).min(height);
+        if max_x <= min_x || max_y <= min_y {
+            return 0.0;
+        }
+
+        let mut diff_sum = 0u64;
+        let mut sample_count = 0u64;
+        for y in (min_y..max_y).step_by(SAMPLE_STEP) {
+            for x in (min_x..max_x).step_by(SAMPLE_STEP) {
+                let index_pixel = index_gray_image.get_pixel(x as u32, y as u32)[0];
+                let pixel = gray_image.get_pixel((x + dx) as u32, (y + dy) as u32)[0];
+                diff_sum += index_pixel.abs_diff(pixel) as u64;
+                sample_count += 1;
+            }
+        }
+
+        if sample_count == 0 {
+            return 0.0;
+        }
+
+        let mean_diff = diff_sum as f32 / sample_count as f32;
+        1.0 - (mean_diff / MATCH_MAX_MEAN_DIFF).min(1.0)
+    }
+
+    /// 估计当前帧相对索引帧的偏移
+    ///
+    /// 每个候选偏移按照内点数量和重叠区域的像素差异打分，得分最高的作为结果。
+    /// 置信度由最佳候选的相似度和它领先第二名的幅度决定，重复内容（如相同的列表项）会得到较低的置信度
+    ///
+    /// 返回值为 (索引, 索引特征点下标, 当前帧特征点下标, 置信度)，以及是否未发生变化
     pub fn get_offsets<'a>(
         &self,
         index: &'a ScrollIndex,
-        image_descriptors: &[Vec<f32>],
+        image_descriptors: &ScrollDescriptors,
         image_corners: &[ScrollOffset],
+        gray_image: &GrayImage,
         scroll_image_list: ScrollImageList,
-    ) -> (Option<(&'a ScrollIndex, usize, usize)>, bool) {
+    ) -> (Option<(&'a ScrollIndex, usize, usize, f32)>, bool) {
         let image_scroll_side_size = if self.current_direction == ScrollDirection::Vertical {
             self.image_height as i32
         } else {
@@ -600,19 +1319,20 @@ impl ScrollScreenshotService {
             (self.top_image_size + 1) + index.position
         };
 
+        let feature_index = match &index.feature_index {
+            Some(feature_index) => feature_index,
+            None => return (None, false),
+        };
+
         let min_diff_count = AtomicUsize::new(0);
 
-        let offsets: Vec<(i32, &'a ScrollIndex, usize, usize)> = image_descriptors
+        let offsets: Vec<(i32, usize, usize)> = feature_index
+            .nearest(image_descriptors)
             .par_iter()
             .enumerate()
-            .filter_map(|(i, descriptor)| {
-                let search_result = index.ann_index.search(descriptor, 1);
-                if search_result.is_empty() {
-                    return None;
-                }
-
-                let idx1 = search_result[0];
-                let dist = Self::euclidean_distance(&index.descriptors[idx1], descriptor);
+            .filter_map(|(i, feature_match)| {
+                let feature_match = (*feature_match)?;
+                let idx1 = feature_match.index;
 
                 let point1 = &index.corners[idx1];
                 let point2 = &image_corners[i];
@@ -643,15 +1363,17 @@ impl ScrollScreenshotService {
                     return None;
                 }
 
-                if dist < 0.1 {
-                    Some((diff, index, idx1, i))
+                if feature_match.is_within_distance {
+                    Some((diff, idx1, i))
                 } else {
                     None
                 }
             })
             .collect();
 
-        if min_diff_count.load(Ordering::Relaxed) > (image_corners.len() as f32 * 0.72) as usize {
+        if min_diff_count.load(Ordering::Relaxed)
+            > (image_corners.len() as f32 * MATCH_ORIGIN_RATIO) as usize
+        {
             return (None, true);
         }
 
@@ -659,70 +1381,466 @@ impl ScrollScreenshotService {
             return (None, false);
         }
 
-        // 寻找频率最高的偏移作为主要偏移模式
-        let mut offset_counts: std::collections::HashMap<i32, (i32, &ScrollIndex, usize, usize)> =
+        // 每个偏移作为一个候选，记录一组对应的特征点
+        let mut offset_counts: std::collections::HashMap<i32, (i32, usize, usize)> =
             std::collections::HashMap::new();
-        for (offset, scroll_index, origin_position_index, new_position_index) in offsets {
-            if let Some(value) = offset_counts.get_mut(&offset) {
+        for (offset, origin_position_index, new_position_index) in &offsets {
+            if let Some(value) = offset_counts.get_mut(offset) {
                 value.0 += 1;
             } else {
-                offset_counts.insert(
-                    offset,
-                    (1, scroll_index, origin_position_index, new_position_index),
-                );
+                offset_counts.insert(*offset, (1, *origin_position_index, *new_position_index));
             }
         }
 
-        // let mut sorted_offsets: Vec<_> = offset_counts.iter().collect();
-        // sorted_offsets.sort_by_key(|(_, (count, _, _, _))| -count);
-        // println!(
-        //     "sorted_offsets: {:?}",
-        //     sorted_offsets[..10.min(sorted_offsets.len())]
-        //         .iter()
-        //         .map(|(offset, (count, _, _, _))| (offset, count))
-        //         .collect::<Vec<_>>()
-        // );
-
-        let mut max_count = 0;
-        let mut second_max_count = 0;
-        let mut max_offset = None;
+        // 统计每个候选偏移的内点数量（允许 MATCH_INLIER_TOLERANCE 的误差）
+        let mut candidates: Vec<(i32, i32, i32, usize, usize)> = offset_counts
+            .iter()
+            .map(|(offset, (count, origin_idx, new_idx))| {
+                let inlier_count = offsets
+                    .iter()
+                    .filter(|(diff, _, _)| (diff - offset).abs() <= MATCH_INLIER_TOLERANCE)
+                    .count() as i32;
+                (*offset, inlier_count, *count, *origin_idx, *new_idx)
+            })
+            .collect();
+        // 内点数量相同时，优先精确匹配数量多的偏移，再优先偏移量小的
+        candidates.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then(b.2.cmp(&a.2))
+                .then(a.0.abs().cmp(&b.0.abs()))
+        });
+
+        // 相近的偏移只保留得票最高的一个
+        let mut selected_candidates: Vec<(i32, i32, i32, usize, usize)> = vec![];
+        for candidate in candidates {
+            if selected_candidates.len() >= MATCH_MAX_CANDIDATES {
+                break;
+            }
 
-        for (_, (count, scroll_index, origin_idx, new_idx)) in &offset_counts {
-            if *count > max_count {
-                second_max_count = max_count;
-                max_count = *count;
-                max_offset = Some((scroll_index, origin_idx, new_idx));
-            } else if *count > second_max_count {
-                second_max_count = *count;
+            if selected_candidates
+                .iter()
+                .any(|selected| (selected.0 - candidate.0).abs() <= MATCH_INLIER_TOLERANCE)
+            {
+                continue;
             }
+
+            selected_candidates.push(candidate);
         }
 
-        let max_offset = match max_offset {
-            Some(offset) => offset,
+        let min_inlier_count = (image_corners.len() as i32 / 10).max(1);
+
+        // 在重叠区域校验候选偏移，得分为内点数量乘以相似度
+        let mut scored_candidates: Vec<(f32, f32, usize, usize)> = selected_candidates
+            .into_iter()
+            .filter(|(_, inlier_count, _, _, _)| *inlier_count >= min_inlier_count)
+            .map(|(offset, inlier_count, _, origin_idx, new_idx)| {
+                let similarity = match &index.gray_image {
+                    Some(index_gray_image) => {
+                        self.verify_offset(index_gray_image, gray_image, offset)
+                    }
+                    None => 1.0,
+                };
+
+                (
+                    inlier_count as f32 * similarity,
+                    similarity,
+                    origin_idx,
+                    new_idx,
+                )
+            })
+            .collect();
+        scored_candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let (best_score, best_similarity, origin_idx, new_idx) = match scored_candidates.first() {
+            Some(candidate) => *candidate,
             None => return (None, false),
         };
 
-        if max_count < (image_corners.len() as i32 / 10) {
+        if best_score <= 0.0 {
             return (None, false);
         }
 
-        if max_count < second_max_count * 2 {
+        let second_score = scored_candidates
+            .get(1)
+            .map(|candidate| candidate.0)
+            .unwrap_or(0.0);
+        let confidence = (best_similarity * (1.0 - second_score / best_score)).clamp(0.0, 1.0);
+
+        if confidence < MATCH_MIN_CONFIDENCE {
             return (None, false);
         }
 
-        let (dominant_scroll_index, dominant_origin_position_index, dominant_new_position_index) =
-            max_offset;
+        (Some((index, origin_idx, new_idx, confidence)), false)
+    }
+
+    fn create_scroll_index(
+        &self,
+        corners: Vec<ScrollOffset>,
+        descriptors: ScrollDescriptors,
+    ) -> ScrollIndex {
+        let mut scroll_index = ScrollIndex::new();
+        scroll_index.corners = corners;
+        scroll_index.feature_index = Some(self.feature_pipeline.matcher.build_index(descriptors));
+
+        scroll_index
+    }
+
+    /// 从索引帧的灰度图重新建立索引，用于恢复会话
+    pub(crate) fn create_index_from_gray_image(&mut self, gray_image: GrayImage) -> ScrollIndex {
+        let corners = self.get_corners(&gray_image);
+        let descriptors = self.get_descriptors(&gray_image, &corners);
+        let mut scroll_index = self.create_scroll_index(corners, descriptors);
+        scroll_index.gray_image = Some(gray_image);
+
+        scroll_index
+    }
+
+    /// 自由方向下，通过特征点匹配估计新帧相对索引帧的平移量（缩放后的坐标）
+    fn get_free_offset(
+        &self,
+        index: &ScrollIndex,
+        image_descriptors: &ScrollDescriptors,
+        image_corners: &[ScrollOffset],
+    ) -> Option<(ScrollOffset, f32)> {
+        if index.corners.is_empty() {
+            return None;
+        }
+        let feature_index = index.feature_index.as_ref()?;
+
+        let offsets: Vec<ScrollOffset> = feature_index
+            .nearest(image_descriptors)
+            .par_iter()
+            .enumerate()
+            .filter_map(|(i, feature_match)| {
+                let feature_match = (*feature_match)?;
+                if !feature_match.is_within_distance {
+                    return None;
+                }
+
+                let point1 = &index.corners[feature_match.index];
+                let point2 = &image_corners[i];
+
+                Some(ScrollOffset::new(point1.x - point2.x, point1.y - point2.y))
+            })
+            .collect();
+
+        // 寻找频率最高的平移量
+        let mut offset_counts: HashMap<ScrollOffset, i32> = HashMap::new();
+        for offset in offsets {
+            *offset_counts.entry(offset).or_insert(0) += 1;
+        }
+
+        // 缩放后的偏移可能落在相邻的两个像素上，统计时合并相邻的偏移
+        let get_cluster_count = |offset: &ScrollOffset| -> i32 {
+            let mut count = 0;
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    count += offset_counts
+                        .get(&ScrollOffset::new(offset.x + dx, offset.y + dy))
+                        .copied()
+                        .unwrap_or(0);
+                }
+            }
+            count
+        };
+
+        // 相邻的偏移合并后数量相同，优先选择自身数量最多的，避免结果受 HashMap 遍历顺序影响
+        let (max_offset, max_count) = offset_counts
+            .iter()
+            .map(|(offset, count)| (*offset, get_cluster_count(offset), *count))
+            .max_by_key(|(offset, cluster_count, count)| {
+                (*cluster_count, *count, -offset.y, -offset.x)
+            })
+            .map(|(offset, cluster_count, _)| (offset, cluster_count))?;
+        let second_max_count = offset_counts
+            .keys()
+            .filter(|offset| {
+                (offset.x - max_offset.x).abs() > 2 || (offset.y - max_offset.y).abs() > 2
+            })
+            .map(get_cluster_count)
+            .max()
+            .unwrap_or(0);
+
+        if max_count < (image_corners.len() as i32 / 10) || max_count < second_max_count * 2 {
+            return None;
+        }
+
+        // 置信度由最佳偏移领先第二名的幅度决定
+        let confidence = 1.0 - second_max_count as f32 / max_count as f32;
+
+        Some((max_offset, confidence))
+    }
+
+    /// 在原尺寸灰度图上搜索差异最小的平移量，修正缩放带来的误差
+    ///
+    /// offset 为当前帧左上角在上一帧中的位置
+    fn refine_free_offset(
+        previous_gray_image: &GrayImage,
+        current_gray_image: &GrayImage,
+        offset: ScrollOffset,
+        radius: i32,
+    ) -> ScrollOffset {
+        let mut best_offset = offset;
+        let mut best_diff = f32::MAX;
+        for dy in (offset.y - radius)..=(offset.y + radius) {
+            for dx in (offset.x - radius)..=(offset.x + radius) {
+                let diff =
+                    get_free_offset_mean_diff(previous_gray_image, current_gray_image, dx, dy, 4);
+                if let Some(diff) = diff.filter(|diff| *diff < best_diff) {
+                    best_diff = diff;
+                    best_offset = ScrollOffset::new(dx, dy);
+                }
+            }
+        }
+
+        best_offset
+    }
+
+    /// 特征点匹配失败时，在缩放后的灰度图上逐个平移量比较像素差异
+    ///
+    /// 缩放后的平移通常不是整数像素，特征点的描述子会发生变化，但按区域平均缩放后像素差异仍在真实平移附近最小，
+    /// 返回的偏移和置信度与 get_free_offset 相同，需要再由 refine_free_offset 在原尺寸上修正
+    fn search_free_offset(
+        previous_gray_image: &GrayImage,
+        current_gray_image: &GrayImage,
+    ) -> Option<(ScrollOffset, f32)> {
+        if previous_gray_image.dimensions() != current_gray_image.dimensions() {
+            return None;
+        }
+
+        let width = previous_gray_image.width() as i32;
+        let height = previous_gray_image.height() as i32;
+        let max_dx = (width as f32 * (1.0 - FREE_OFFSET_SEARCH_MIN_OVERLAP_RATIO)) as i32;
+        let max_dy = (height as f32 * (1.0 - FREE_OFFSET_SEARCH_MIN_OVERLAP_RATIO)) as i32;
+
+        let diffs = (-max_dy..=max_dy)
+            .into_par_iter()
+            .flat_map_iter(|dy| {
+                (-max_dx..=max_dx).filter_map(move |dx| {
+                    if dx == 0 && dy == 0 {
+                        return None;
+                    }
+
+                    get_free_offset_mean_diff(previous_gray_image, current_gray_image, dx, dy, 2)
+                        .map(|diff| (ScrollOffset::new(dx, dy), diff))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let (best_offset, best_diff) = diffs
+            .iter()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .copied()?;
+        let second_diff = diffs
+            .iter()
+            .filter(|(offset, _)| {
+                (offset.x - best_offset.x).abs() > 2 || (offset.y - best_offset.y).abs() > 2
+            })
+            .map(|(_, diff)| *diff)
+            .min_by(|a, b| a.total_cmp(b))?;
+
+        // 和 get_free_offset 一样，最佳偏移需要明显优于其他偏移
+        if second_diff <= 0.0 || best_diff * 2.0 > second_diff {
+            return None;
+        }
+
+        Some((best_offset, 1.0 - best_diff / second_diff))
+    }
+
+    /// 自由方向的拼接，帧可以同时在水平和垂直方向上平移
+    fn handle_image_free(
+        &mut self,
+        image: DynamicImage,
+        capture_info: Option<ScrollFrameCaptureInfo>,
+    ) -> (
+        Option<(i32, Option<ScrollImageList>)>,
+        bool,
+        ScrollImageList,
+    ) {
+        let image_width = image.width();
+        let image_height = image.height();
+
+        if self.image_width == 0 || self.image_height == 0 {
+            self.init_image_size(image_width, image_height);
+        } else if image_width != self.image_width || image_height != self.image_height {
+            return (None, false, ScrollImageList::Bottom);
+        }
+
+        let gray_image = self.get_gray_image(&image);
+        let image_corners = self.get_corners(&gray_image);
+        if image_corners.is_empty() {
+            return (None, false, ScrollImageList::Bottom);
+        }
+
+        let image_descriptors = self.get_descriptors(&gray_image, &image_corners);
+        let free_gray_image = if self.image_scale < 1.0 {
+            Some(image.to_luma8())
+        } else {
+            None
+        };
+
+        let (position, confidence) = if self.frame_store.is_empty(ScrollImageList::Bottom) {
+            (ScrollOffset::new(0, 0), 1.0)
+        } else {
+            // 优先和最近的帧匹配，失败时和首帧匹配
+            let mut matched = self
+                .get_free_offset(
+                    &self.bottom_image_ann_index,
+                    &image_descriptors,
+                    &image_corners,
+                )
+                .map(|(offset, confidence)| (offset, confidence, &self.bottom_image_ann_index));
+            if matched.is_none() && self.try_rollback {
+                matched = self
+                    .get_free_offset(
+                        &self.top_image_ann_index,
+                        &image_descriptors,
+                        &image_corners,
+                    )
+                    .map(|(offset, confidence)| (offset, confidence, &self.top_image_ann_index));
+            }
+
+            // 缩放后特征点可能无法匹配，直接比较缩放后的灰度图
+            if matched.is_none() && self.image_scale < 1.0 {
+                matched = [&self.bottom_image_ann_index, &self.top_image_ann_index]
+                    .into_iter()
+                    .take(if self.try_rollback { 2 } else { 1 })
+                    .find_map(|index| {
+                        let previous_gray_image = index.gray_image.as_ref()?;
+                        Self::search_free_offset(previous_gray_image, &gray_image)
+                            .map(|(offset, confidence)| (offset, confidence, index))
+                    });
+            }
+
+            let (offset, confidence, index) = match matched {
+                Some(matched) => matched,
+                None => {
+                    self.match_confidence = 0.0;
+                    return (None, false, ScrollImageList::Bottom);
+                }
+            };
+
+            // 转换为原尺寸的偏移
+            let mut offset = ScrollOffset::new(
+                (offset.x as f32 / self.image_scale).round() as i32,
+                (offset.y as f32 / self.image_scale).round() as i32,
+            );
+
+            if let (Some(previous_gray_image), Some(current_gray_image)) =
+                (&index.free_gray_image, &free_gray_image)
+            {
+                let radius = ((1.0 / self.image_scale).ceil() as i32)
+                    .clamp(1, FREE_OFFSET_MAX_REFINE_RADIUS);
+                offset = Self::refine_free_offset(
+                    previous_gray_image,
+                    current_gray_image,
+                    offset,
+                    radius,
+                );
+            }
+
+            if offset.x == 0 && offset.y == 0 {
+                return (None, true, ScrollImageList::Bottom);
+            }
+
+            (
+                ScrollOffset::new(
+                    index.free_position.x + offset.x,
+                    index.free_position.y + offset.y,
+                ),
+                confidence,
+            )
+        };
+        self.match_confidence = confidence;
+
+        // 相同位置的帧不再重复拼接
+        if !self.free_image_positions.is_empty() && self.free_image_positions.contains(&position) {
+            return (None, true, ScrollImageList::Bottom);
+        }
+
+        let before_state = if self.frame_store.is_empty(ScrollImageList::Bottom) {
+            None
+        } else {
+            Some(self.capture_stitch_state())
+        };
+
+        let mut scroll_index = self.create_scroll_index(image_corners, image_descriptors);
+        scroll_index.gray_image = Some(gray_image);
+        scroll_index.free_position = position;
+        scroll_index.free_gray_image = free_gray_image;
+
+        let frame_rect = ElementRect {
+            min_x: position.x,
+            min_y: position.y,
+            max_x: position.x + image_width as i32,
+            max_y: position.y + image_height as i32,
+        };
+        if self.frame_store.is_empty(ScrollImageList::Bottom) {
+            self.free_canvas_rect = frame_rect;
+        } else {
+            self.free_canvas_rect = ElementRect {
+                min_x: self.free_canvas_rect.min_x.min(frame_rect.min_x),
+                min_y: self.free_canvas_rect.min_y.min(frame_rect.min_y),
+                max_x: self.free_canvas_rect.max_x.max(frame_rect.max_x),
+                max_y: self.free_canvas_rect.max_y.max(frame_rect.max_y),
+            };
+        }
+
+        // 首帧的索引作为回滚时的匹配目标
+        if self.frame_store.is_empty(ScrollImageList::Bottom) {
+            let mut top_scroll_index = ScrollIndex::new();
+            top_scroll_index.corners = scroll_index.corners.clone();
+            top_scroll_index.feature_index = scroll_index.feature_index.clone();
+            top_scroll_index.gray_image = scroll_index.gray_image.clone();
+            top_scroll_index.free_gray_image = scroll_index.free_gray_image.clone();
+            self.top_image_ann_index = Arc::new(top_scroll_index);
+        }
+        self.bottom_image_ann_index = Arc::new(scroll_index);
+
+        // 写入磁盘失败时，帧保留在内存中
+        if let Err(e) = self.frame_store.push(
+            ScrollImageList::Bottom,
+            ScrollImage {
+                image,
+                overlay_size: 0,
+                metadata: ScrollFrameMetadata {
+                    capture_info,
+                    match_confidence: confidence,
+                },
+            },
+        ) {
+            log::warn!("[handle_image_free] Failed to spill frames to disk: {}", e);
+        }
+        self.free_image_positions.push(position);
+
+        // 画布相对首帧在垂直方向上的扩展尺寸
+        self.top_image_size = -self.free_canvas_rect.min_y;
+        self.bottom_image_size = self.free_canvas_rect.max_y;
+
+        if let Some(before_state) = before_state {
+            self.push_history(ScrollImageList::Bottom, before_state);
+        }
 
         (
-            Some((
-                dominant_scroll_index,
-                *dominant_origin_position_index,
-                *dominant_new_position_index,
-            )),
+            Some((0, Some(ScrollImageList::Bottom))),
             false,
+            ScrollImageList::Bottom,
         )
     }
 
+    /// 首帧左上角在导出图片中的位置
+    ///
+    /// 裁剪固定区域后内容整体后移了固定区域的尺寸，所以首帧（含固定区域）的起始位置就是上图片列表的尺寸
+    pub fn export_origin(&self) -> ScrollOffset {
+        match self.current_direction {
+            ScrollDirection::Vertical => ScrollOffset::new(0, self.top_image_size),
+            ScrollDirection::Horizontal => ScrollOffset::new(self.top_image_size, 0),
+            ScrollDirection::Free => {
+                ScrollOffset::new(-self.free_canvas_rect.min_x, -self.free_canvas_rect.min_y)
+            }
+        }
+    }
+
     pub fn handle_image(
         &mut self,
         image: DynamicImage,
@@ -732,6 +1850,34 @@ impl ScrollScreenshotService {
         bool,
         ScrollImageList,
     ) {
+        self.handle_image_with_capture_info(image, scroll_image_list, None)
+    }
+
+    /// 和 handle_image 相同，截图信息会和拼接的帧一起保存，用于导出元数据
+    pub fn handle_image_with_capture_info(
+        &mut self,
+        image: DynamicImage,
+        scroll_image_list: ScrollImageList,
+        capture_info: Option<ScrollFrameCaptureInfo>,
+    ) -> (
+        Option<(i32, Option<ScrollImageList>)>,
+        bool,
+        ScrollImageList,
+    ) {
+        if self.current_direction == ScrollDirection::Free {
+            return self.handle_image_free(image, capture_info);
+        }
+
+        // 固定区域确定前，和上一个拼接的帧对比
+        let detected_sticky_bands = if self.detect_sticky_bands && self.sticky_bands.is_none() {
+            self.update_sticky_bands(&image)
+        } else {
+            None
+        };
+        let is_detecting_sticky_bands = self.detect_sticky_bands && self.sticky_bands.is_none();
+
+        let image = self.crop_sticky_bands(image);
+
         let image_width = image.width();
         let image_height = image.height();
 
@@ -754,7 +1900,19 @@ impl ScrollScreenshotService {
 
         let image_descriptors = self.get_descriptors(&gray_image, &image_corners);
 
-        if self.top_image_list.is_empty() && self.bottom_image_list.is_empty() {
+        if self.frame_store.total_len() == 0 {
+            if is_detecting_sticky_bands {
+                self.sticky_detection_frames.clear();
+                self.sticky_detection_frames
+                    .push(ScrollStickyDetectionFrame {
+                        image: image.clone(),
+                        capture_info: capture_info.clone(),
+                        image_list: ScrollImageList::Bottom,
+                        sticky_bands: None,
+                    });
+            }
+
+            let top_gray_image = gray_image.clone();
             let bottom_image = self.push_image(
                 image,
                 gray_image,
@@ -762,28 +1920,18 @@ impl ScrollScreenshotService {
                 0,
                 ScrollOffset { x: 0, y: 0 },
                 ScrollOffset { x: 0, y: 0 },
+                ScrollFrameMetadata {
+                    capture_info,
+                    match_confidence: 1.0,
+                },
             );
 
-            let mut new_top_image_ann_index = ScrollIndex::new(self.get_descriptor_size());
-            new_top_image_ann_index.descriptors = image_descriptors;
-            new_top_image_ann_index.corners = image_corners;
-            new_top_image_ann_index
-                .descriptors
-                .iter()
-                .enumerate()
-                .for_each(|(i, descriptor)| {
-                    new_top_image_ann_index
-                        .ann_index
-                        .add(descriptor, i)
-                        .unwrap();
-                });
+            let mut new_top_image_ann_index =
+                self.create_scroll_index(image_corners, image_descriptors);
+            new_top_image_ann_index.gray_image = Some(top_gray_image);
 
-            new_top_image_ann_index
-                .ann_index
-                .build(Metric::Euclidean)
-                .unwrap();
-
-            self.top_image_ann_index = new_top_image_ann_index;
+            self.top_image_ann_index = Arc::new(new_top_image_ann_index);
+            self.match_confidence = 1.0;
 
             return (Some(bottom_image), false, ScrollImageList::Bottom);
         }
@@ -806,6 +1954,7 @@ impl ScrollScreenshotService {
             first_index,
             &image_descriptors,
             &image_corners,
+            &gray_image,
             scroll_image_list,
         );
 
@@ -833,6 +1982,7 @@ impl ScrollScreenshotService {
                 second_index,
                 &image_descriptors,
                 &image_corners,
+                &gray_image,
                 second_scroll_image_list,
             );
 
@@ -846,147 +1996,1259 @@ impl ScrollScreenshotService {
         }
 
         if offsets.is_none() {
+            self.match_confidence = 0.0;
             return (None, false, result_scroll_image_list);
         }
 
-        let (dominant_scroll_index, dominant_origin_position_index, dominant_new_position_index) =
-            match offsets {
-                Some(offsets) => offsets,
-                None => return (None, false, scroll_image_list),
-            };
+        let (
+            dominant_scroll_index,
+            dominant_origin_position_index,
+            dominant_new_position_index,
+            confidence,
+        ) = match offsets {
+            Some(offsets) => offsets,
+            None => return (None, false, scroll_image_list),
+        };
 
         let origin_position = dominant_scroll_index.corners[dominant_origin_position_index];
         let new_position = image_corners[dominant_new_position_index];
+        let index_position = dominant_scroll_index.position;
+        self.match_confidence = confidence;
 
         // 将偏移的图片推到列表中
-        (
-            Some(self.push_image(
-                image,
-                gray_image,
-                image_corners,
-                dominant_scroll_index.position,
-                origin_position,
-                new_position,
-            )),
-            false,
-            result_scroll_image_list,
-        )
+        let before_state = self.capture_stitch_state();
+        let detection_frame = if is_detecting_sticky_bands {
+            Some((image.clone(), capture_info.clone()))
+        } else {
+            None
+        };
+        let push_result = self.push_image(
+            image,
+            gray_image,
+            image_corners,
+            index_position,
+            origin_position,
+            new_position,
+            ScrollFrameMetadata {
+                capture_info,
+                match_confidence: confidence,
+            },
+        );
+        if let Some(image_list) = push_result.1 {
+            self.push_history(image_list, before_state);
+
+            if let Some((image, capture_info)) = detection_frame {
+                self.sticky_detection_frames
+                    .push(ScrollStickyDetectionFrame {
+                        image,
+                        capture_info,
+                        image_list,
+                        sticky_bands: detected_sticky_bands,
+                    });
+            }
+        }
+
+        (Some(push_result), false, result_scroll_image_list)
     }
 
-    pub fn export(&mut self) -> Option<image::DynamicImage> {
-        if self.top_image_list.is_empty() && self.bottom_image_list.is_empty() {
-            return None;
+    /// 导出自由方向的拼接结果，没有帧覆盖的区域保持透明
+    ///
+    /// 画布带有透明通道，16 位的帧导出为 16 位 RGBA
+    fn export_free(&self) -> Result<image::DynamicImage, String> {
+        if self.frame_store.is_empty(ScrollImageList::Bottom) {
+            return Err(String::from("[export_free] No image to export"));
         }
 
-        // 计算最终图片尺寸
-        let (total_width, total_height) = if self.current_direction == ScrollDirection::Vertical {
-            (
-                self.image_width as usize,
-                (self.top_image_size + self.bottom_image_size) as usize,
-            )
+        let canvas_rect = self.free_canvas_rect;
+        let canvas_width = (canvas_rect.max_x - canvas_rect.min_x) as u32;
+        let canvas_height = (canvas_rect.max_y - canvas_rect.min_y) as u32;
+        let mut canvas_image = if self.frame_store.pixel_format() == PixelFormat::Rgb16 {
+            image::DynamicImage::ImageRgba16(image::ImageBuffer::new(canvas_width, canvas_height))
         } else {
-            (
-                (self.top_image_size + self.bottom_image_size) as usize,
-                self.image_height as usize,
-            )
+            image::DynamicImage::ImageRgba8(image::RgbaImage::new(canvas_width, canvas_height))
         };
 
-        const RGB_CHANNEL_COUNT: usize = 3;
+        // 后拼接的帧覆盖先拼接的帧
+        for (index, position) in self.free_image_positions.iter().enumerate() {
+            let image = self
+                .frame_store
+                .read_image(ScrollImageList::Bottom, index)?;
+
+            let x = (position.x - canvas_rect.min_x) as i64;
+            let y = (position.y - canvas_rect.min_y) as i64;
+            match &mut canvas_image {
+                image::DynamicImage::ImageRgba16(canvas_image) => {
+                    image::imageops::replace(canvas_image, &image.to_rgba16(), x, y);
+                }
+                image::DynamicImage::ImageRgba8(canvas_image) => {
+                    image::imageops::replace(canvas_image, &image.to_rgba8(), x, y);
+                }
+                _ => unreachable!(),
+            }
+        }
 
-        // 创建最终大小的图片
-        let mut final_image = unsafe {
-            let mut vec = Vec::with_capacity(total_width * total_height * RGB_CHANNEL_COUNT);
-            vec.set_len(total_width * total_height * RGB_CHANNEL_COUNT);
-            vec
+        Ok(canvas_image)
+    }
+
+    /// 计算导出图片中每一段内容的来源，后绘制的帧覆盖先绘制的帧
+    ///
+    /// 接缝模式为 MinDifference 时，相邻两帧在重叠区域中差异最小的位置切换
+    fn get_export_layout(&self) -> Option<ScrollExportLayout> {
+        let (total_width, total_height) = self.get_export_size()?;
+        let total_size = self.get_page_side_size((total_width, total_height)) as i32;
+
+        let get_scroll_side_size = |(width, height): (u32, u32)| -> i32 {
+            if self.current_direction == ScrollDirection::Vertical {
+                height as i32
+            } else {
+                width as i32
+            }
         };
 
-        // 当前位置偏移量
-        let mut offset_x: i32 = 0;
-        let mut offset_y: i32 = 0;
+        let mut draw_list = self.get_frame_draw_list();
 
-        // top 会覆盖 bottom，优先从 bottom 开始
-        if self.current_direction == ScrollDirection::Vertical {
-            // 垂直方向，从顶部开始
-            offset_y = self.top_image_size as i32;
+        let blends = if self.seam_mode == ScrollSeamMode::MinDifference {
+            self.apply_export_seams(&mut draw_list)
         } else {
-            // 水平方向，从左侧开始
-            offset_x = self.top_image_size as i32;
+            vec![]
+        };
+
+        if let Some(sticky_start_image) = &self.sticky_start_image {
+            draw_list.push((
+                ScrollExportSource::StickyStart,
+                0,
+                0,
+                get_scroll_side_size(sticky_start_image.dimensions()),
+            ));
+        }
+
+        if let Some(sticky_end_image) = &self.sticky_end_image {
+            let size = get_scroll_side_size(sticky_end_image.dimensions());
+            draw_list.push((ScrollExportSource::StickyEnd, 0, total_size - size, size));
+        }
+
+        // 从最后绘制的开始，只保留没有被覆盖的部分
+        let mut covered_ranges: Vec<(i32, i32)> = vec![];
+        let mut segments = vec![];
+        for (source, source_start, start, size) in draw_list.into_iter().rev() {
+            let mut visible_ranges = vec![(start.max(0), (start + size).min(total_size))];
+            for (covered_start, covered_end) in covered_ranges.iter() {
+                visible_ranges = visible_ranges
+                    .into_iter()
+                    .flat_map(|(visible_start, visible_end)| {
+                        [
+                            (visible_start, visible_end.min(*covered_start)),
+                            (visible_start.max(*covered_end), visible_end),
+                        ]
+                    })
+                    .filter(|(visible_start, visible_end)| visible_start < visible_end)
+                    .collect();
+            }
+
+            for (visible_start, visible_end) in visible_ranges {
+                segments.push(ScrollExportSegment {
+                    source,
+                    source_start: (source_start + visible_start - start) as u32,
+                    target_start: visible_start as u32,
+                    size: (visible_end - visible_start) as u32,
+                });
+                covered_ranges.push((visible_start, visible_end));
+            }
         }
 
-        for scroll_image in self.bottom_image_list.iter() {
-            let img = &scroll_image.image;
-            let overlay_size = scroll_image.overlay_size;
+        segments.sort_by_key(|segment| segment.target_start);
+
+        Some(ScrollExportLayout {
+            width: total_width as usize,
+            height: total_height as usize,
+            segments,
+            blends,
+        })
+    }
+
+    /// 导出图片中每一帧的位置、来源区域和拼接信息
+    pub fn get_export_metadata(&self) -> Option<ScrollExportMetadata> {
+        let (width, height) = self.get_export_size()?;
+
+        let get_frame_info = |image_list: ScrollImageList, index: usize, offset: ScrollOffset| {
+            let (frame_width, frame_height) = self.frame_store.frame_size(image_list, index);
+            let metadata = self.frame_store.metadata(image_list, index);
+
+            ScrollExportFrameInfo {
+                image_list,
+                index,
+                capture_info: metadata.capture_info.clone(),
+                offset,
+                width: frame_width,
+                height: frame_height,
+                overlay_size: self.frame_store.overlay_size(image_list, index),
+                match_confidence: metadata.match_confidence,
+                bands: vec![],
+            }
+        };
 
+        let frames = if self.current_direction == ScrollDirection::Free {
+            let canvas_rect = self.free_canvas_rect;
+            self.free_image_positions
+                .iter()
+                .enumerate()
+                .map(|(index, position)| {
+                    get_frame_info(
+                        ScrollImageList::Bottom,
+                        index,
+                        ScrollOffset::new(
+                            position.x - canvas_rect.min_x,
+                            position.y - canvas_rect.min_y,
+                        ),
+                    )
+                })
+                .collect()
+        } else {
+            let layout = self.get_export_layout()?;
+
+            let mut frames = self
+                .get_frame_draw_list()
+                .into_iter()
+                .filter_map(|(source, _, start, _)| {
+                    let (image_list, index) = match source {
+                        ScrollExportSource::Frame(image_list, index) => (image_list, index),
+                        _ => return None,
+                    };
+
+                    let offset = if self.current_direction == ScrollDirection::Vertical {
+                        ScrollOffset::new(0, start)
+                    } else {
+                        ScrollOffset::new(start, 0)
+                    };
+
+                    let mut frame_info = get_frame_info(image_list, index, offset);
+                    // 分段按照位置排序，相邻的分段合并为一段
+                    for segment in layout
+                        .segments
+                        .iter()
+                        .filter(|segment| segment.source == source)
+                    {
+                        match frame_info.bands.last_mut() {
+                            Some(band) if band.start + band.size == segment.target_start => {
+                                band.size += segment.size;
+                            }
+                            _ => frame_info.bands.push(ScrollExportBand {
+                                start: segment.target_start,
+                                size: segment.size,
+                            }),
+                        }
+                    }
+
+                    Some((start, frame_info))
+                })
+                .collect::<Vec<_>>();
+
+            frames.sort_by_key(|(start, _)| *start);
+            frames
+                .into_iter()
+                .map(|(_, frame_info)| frame_info)
+                .collect()
+        };
+
+        Some(ScrollExportMetadata {
+            version: SCROLL_EXPORT_METADATA_VERSION,
+            direction: self.current_direction,
+            width,
+            height,
+            sticky_bands: self.sticky_bands,
+            frames,
+        })
+    }
+
+    /// 按照绘制顺序记录每一帧的位置，(来源, 来源中的起始位置, 起始位置, 尺寸)
+    fn get_frame_draw_list(&self) -> Vec<(ScrollExportSource, i32, i32, i32)> {
+        let sticky_bands = self.sticky_bands.unwrap_or_default();
+        let sticky_start_size = sticky_bands.start_size as i32;
+
+        let get_scroll_side_size = |(width, height): (u32, u32)| -> i32 {
             if self.current_direction == ScrollDirection::Vertical {
-                // 垂直拼接
-                snow_shot_app_utils::overlay_image(
-                    &mut final_image,
-                    total_width,
-                    img,
-                    0,
-                    (offset_y - overlay_size) as usize,
-                    RGB_CHANNEL_COUNT,
+                height as i32
+            } else {
+                width as i32
+            }
+        };
+
+        let mut draw_list: Vec<(ScrollExportSource, i32, i32, i32)> =
+            Vec::with_capacity(self.frame_store.total_len() + 2);
+
+        // top 会覆盖 bottom，优先从 bottom 开始
+        let mut offset = self.top_image_size + sticky_start_size;
+        for index in 0..self.frame_store.len(ScrollImageList::Bottom) {
+            let size =
+                get_scroll_side_size(self.frame_store.frame_size(ScrollImageList::Bottom, index));
+            let overlay_size = self
+                .frame_store
+                .overlay_size(ScrollImageList::Bottom, index);
+
+            draw_list.push((
+                ScrollExportSource::Frame(ScrollImageList::Bottom, index),
+                0,
+                offset - overlay_size,
+                size,
+            ));
+            offset += size - overlay_size;
+        }
+
+        // 最先推入的图片优先级最低，所以从尾部开始
+        let mut offset = self.top_image_size + sticky_start_size;
+        for index in 0..self.frame_store.len(ScrollImageList::Top) {
+            let size =
+                get_scroll_side_size(self.frame_store.frame_size(ScrollImageList::Top, index));
+            let actual_size = size + self.frame_store.overlay_size(ScrollImageList::Top, index);
+
+            draw_list.push((
+                ScrollExportSource::Frame(ScrollImageList::Top, index),
+                0,
+                offset - actual_size,
+                size,
+            ));
+            offset -= actual_size;
+        }
+
+        draw_list
+    }
+
+    /// 在相邻两帧的重叠区域中选择接缝，并裁剪两帧的范围使其在接缝处衔接
+    ///
+    /// 返回接缝两侧的混合区域
+    fn apply_export_seams(
+        &self,
+        draw_list: &mut [(ScrollExportSource, i32, i32, i32)],
+    ) -> Vec<ScrollExportBlend> {
+        let mut frame_order = (0..draw_list.len()).collect::<Vec<_>>();
+        frame_order.sort_by_key(|index| draw_list[*index].2);
+
+        let feather_size = self.seam_feather_size as i32;
+        let mut blends = vec![];
+        for order_index in 1..frame_order.len() {
+            let first_index = frame_order[order_index - 1];
+            let second_index = frame_order[order_index];
+            let (first_source, first_source_start, first_start, first_size) =
+                draw_list[first_index];
+            let (second_source, second_source_start, second_start, second_size) =
+                draw_list[second_index];
+
+            // 只处理部分重叠的两帧，被完全覆盖的帧保持原有的覆盖关系
+            let overlap_start = second_start;
+            let overlap_end = first_start + first_size;
+            if overlap_start <= first_start
+                || overlap_start >= overlap_end
+                || second_start + second_size <= overlap_end
+            {
+                continue;
+            }
+
+            let cut = match self.find_export_seam(
+                (first_source, first_source_start - first_start),
+                (second_source, second_source_start - second_start),
+                overlap_start,
+                overlap_end,
+                feather_size,
+            ) {
+                Ok(cut) => cut,
+                Err(_) => continue,
+            };
+
+            draw_list[first_index].3 = cut - first_start;
+            draw_list[second_index] = (
+                second_source,
+                second_source_start + (cut - second_start),
+                cut,
+                second_size - (cut - second_start),
+            );
+
+            if feather_size > 0 {
+                let blend_start = (cut - feather_size / 2).max(overlap_start);
+                let blend_end = (cut - feather_size / 2 + feather_size).min(overlap_end);
+                if blend_start < blend_end {
+                    blends.push(ScrollExportBlend {
+                        first: first_source,
+                        first_source_start: (first_source_start + blend_start - first_start) as u32,
+                        second: second_source,
+                        second_source_start: (second_source_start + blend_start - second_start)
+                            as u32,
+                        target_start: blend_start as u32,
+                        size: (blend_end - blend_start) as u32,
+                        blend_offset: 0,
+                        blend_size: (blend_end - blend_start) as u32,
+                    });
+                }
+            }
+        }
+
+        blends
+    }
+
+    /// 计算重叠区域中每一行（列）两帧的差异，返回差异最小的接缝位置
+    ///
+    /// first 和 second 为 (来源, 来源位置相对导出位置的偏移)，接缝之前使用 first，之后使用 second
+    fn find_export_seam(
+        &self,
+        (first_source, first_offset): (ScrollExportSource, i32),
+        (second_source, second_offset): (ScrollExportSource, i32),
+        overlap_start: i32,
+        overlap_end: i32,
+        feather_size: i32,
+    ) -> Result<i32, String> {
+        let pixel_format = self.frame_store.pixel_format();
+        let bytes_per_pixel = pixel_format.bytes_per_pixel();
+        let overlap_size = (overlap_end - overlap_start) as usize;
+
+        let mut line_differences = vec![0u64; overlap_size];
+        if self.current_direction == ScrollDirection::Vertical {
+            let first_rows = self.read_export_source_rows(
+                first_source,
+                (overlap_start + first_offset) as u32,
+                overlap_size as u32,
+            )?;
+            let second_rows = self.read_export_source_rows(
+                second_source,
+                (overlap_start + second_offset) as u32,
+                overlap_size as u32,
+            )?;
+
+            let row_size = first_rows.len() / overlap_size;
+            for (line, line_difference) in line_differences.iter_mut().enumerate() {
+                let row = line * row_size..(line + 1) * row_size;
+                *line_difference = get_sample_difference(
+                    pixel_format,
+                    &first_rows[row.clone()],
+                    &second_rows[row],
                 );
+            }
+        } else {
+            let image_height = self.image_height;
+            let first_rows = self.read_export_source_rows(first_source, 0, image_height)?;
+            let second_rows = self.read_export_source_rows(second_source, 0, image_height)?;
+            let first_row_size = first_rows.len() / image_height as usize;
+            let second_row_size = second_rows.len() / image_height as usize;
+
+            for (line, line_difference) in line_differences.iter_mut().enumerate() {
+                let first_column = (overlap_start + first_offset) as usize + line;
+                let second_column = (overlap_start + second_offset) as usize + line;
+
+                for row in 0..image_height as usize {
+                    let first_pixel = row * first_row_size + first_column * bytes_per_pixel;
+                    let second_pixel = row * second_row_size + second_column * bytes_per_pixel;
+                    *line_difference += get_sample_difference(
+                        pixel_format,
+                        &first_rows[first_pixel..first_pixel + bytes_per_pixel],
+                        &second_rows[second_pixel..second_pixel + bytes_per_pixel],
+                    );
+                }
+            }
+        }
+
+        // 接缝位置 cut 表示 cut 之前使用 first，需要两侧的行（列）以及混合区域都没有差异
+        let mut prefix_differences = vec![0u64; overlap_size + 1];
+        for (line, line_difference) in line_differences.iter().enumerate() {
+            prefix_differences[line + 1] = prefix_differences[line] + line_difference;
+        }
+        let get_window_difference = |cut: i32| {
+            let window_start = (cut - feather_size / 2 - 1 - overlap_start).max(0) as usize;
+            let window_end = ((cut - feather_size / 2 + feather_size + 1 - overlap_start) as usize)
+                .min(overlap_size);
+            prefix_differences[window_end] - prefix_differences[window_start.min(window_end)]
+        };
+
+        // 差异相同时选择靠近重叠区域中间的位置
+        let overlap_center = (overlap_start + overlap_end) / 2;
+        let cut = (overlap_start + 1..overlap_end)
+            .min_by_key(|cut| (get_window_difference(*cut), (cut - overlap_center).abs()))
+            .unwrap_or(overlap_start);
+
+        Ok(cut)
+    }
+
+    /// 读取分段来源中连续的若干行
+    fn read_export_source_rows(
+        &self,
+        source: ScrollExportSource,
+        start_row: u32,
+        row_count: u32,
+    ) -> Result<Cow<'_, [u8]>, String> {
+        let sticky_image = match source {
+            ScrollExportSource::Frame(image_list, index) => {
+                return self
+                    .frame_store
+                    .read_rows(image_list, index, start_row, row_count);
+            }
+            ScrollExportSource::StickyStart => self.sticky_start_image.as_ref(),
+            ScrollExportSource::StickyEnd => self.sticky_end_image.as_ref(),
+        };
+
+        let sticky_image = match sticky_image {
+            Some(sticky_image) => sticky_image,
+            None => {
+                return Err(String::from(
+                    "[read_export_source_rows] Sticky band image not found",
+                ));
+            }
+        };
+
+        let row_size =
+            sticky_image.width() as usize * self.frame_store.pixel_format().bytes_per_pixel();
+        Ok(Cow::Borrowed(
+            &sticky_image.as_bytes()
+                [start_row as usize * row_size..(start_row + row_count) as usize * row_size],
+        ))
+    }
+
+    /// 填充导出图片中从 start_row 开始的若干行，没有内容的区域为黑色（带透明通道时为透明）
+    fn fill_export_rows(
+        &self,
+        layout: &ScrollExportLayout,
+        start_row: u32,
+        rows: &mut [u8],
+    ) -> Result<(), String> {
+        let pixel_format = self.frame_store.pixel_format();
+        let bytes_per_pixel = pixel_format.bytes_per_pixel();
+        let row_size = layout.width * bytes_per_pixel;
+        let row_count = (rows.len() / row_size) as u32;
+        let end_row = start_row + row_count;
+
+        rows.fill(0);
+
+        if self.current_direction == ScrollDirection::Vertical {
+            for segment in layout.segments.iter() {
+                let segment_end = segment.target_start + segment.size;
+                if segment_end <= start_row || segment.target_start >= end_row {
+                    continue;
+                }
+
+                let copy_start = segment.target_start.max(start_row);
+                let copy_end = segment_end.min(end_row);
+                let source_rows = self.read_export_source_rows(
+                    segment.source,
+                    segment.source_start + (copy_start - segment.target_start),
+                    copy_end - copy_start,
+                )?;
+
+                let target_offset = (copy_start - start_row) as usize * row_size;
+                rows[target_offset..target_offset + source_rows.len()]
+                    .copy_from_slice(&source_rows);
+            }
+
+            for blend in layout.blends.iter() {
+                let blend_end = blend.target_start + blend.size;
+                if blend_end <= start_row || blend.target_start >= end_row {
+                    continue;
+                }
+
+                let copy_start = blend.target_start.max(start_row);
+                let copy_end = blend_end.min(end_row);
+                let first_rows = self.read_export_source_rows(
+                    blend.first,
+                    blend.first_source_start + (copy_start - blend.target_start),
+                    copy_end - copy_start,
+                )?;
+                let second_rows = self.read_export_source_rows(
+                    blend.second,
+                    blend.second_source_start + (copy_start - blend.target_start),
+                    copy_end - copy_start,
+                )?;
+
+                for row in copy_start..copy_end {
+                    let source_offset = (row - copy_start) as usize * row_size;
+                    let target_offset = (row - start_row) as usize * row_size;
+                    blend_samples(
+                        pixel_format,
+                        &first_rows[source_offset..source_offset + row_size],
+                        &second_rows[source_offset..source_offset + row_size],
+                        blend.get_weight(row - blend.target_start),
+                        &mut rows[target_offset..target_offset + row_size],
+                    );
+                }
+            }
+        } else {
+            for segment in layout.segments.iter() {
+                let source_rows =
+                    self.read_export_source_rows(segment.source, start_row, row_count)?;
+                let source_row_size = source_rows.len() / row_count as usize;
+                let source_offset = segment.source_start as usize * bytes_per_pixel;
+                let target_offset = segment.target_start as usize * bytes_per_pixel;
+                let copy_size = segment.size as usize * bytes_per_pixel;
+
+                for row in 0..row_count as usize {
+                    let source_row = &source_rows[row * source_row_size..];
+                    rows[row * row_size + target_offset
+                        ..row * row_size + target_offset + copy_size]
+                        .copy_from_slice(&source_row[source_offset..source_offset + copy_size]);
+                }
+            }
+
+            for blend in layout.blends.iter() {
+                let first_rows = self.read_export_source_rows(blend.first, start_row, row_count)?;
+                let second_rows =
+                    self.read_export_source_rows(blend.second, start_row, row_count)?;
+                let first_row_size = first_rows.len() / row_count as usize;
+                let second_row_size = second_rows.len() / row_count as usize;
+
+                for row in 0..row_count as usize {
+                    for column in 0..blend.size {
+                        let first_offset = row * first_row_size
+                            + (blend.first_source_start + column) as usize * bytes_per_pixel;
+                        let second_offset = row * second_row_size
+                            + (blend.second_source_start + column) as usize * bytes_per_pixel;
+                        let target_offset = row * row_size
+                            + (blend.target_start + column) as usize * bytes_per_pixel;
+
+                        blend_samples(
+                            pixel_format,
+                            &first_rows[first_offset..first_offset + bytes_per_pixel],
+                            &second_rows[second_offset..second_offset + bytes_per_pixel],
+                            blend.get_weight(column),
+                            &mut rows[target_offset..target_offset + bytes_per_pixel],
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 在内存中构建完整的导出图片，读取写入磁盘的帧失败时返回错误
+    pub fn export(&mut self) -> Result<image::DynamicImage, String> {
+        if self.current_direction == ScrollDirection::Free {
+            return self.export_free();
+        }
+
+        let layout = match self.get_export_layout() {
+            Some(layout) => layout,
+            None => return Err(String::from("[export] No image to export")),
+        };
+        let pixel_format = self.frame_store.pixel_format();
+
+        let mut final_image =
+            vec![0; layout.width * layout.height * pixel_format.bytes_per_pixel()];
+        self.fill_export_rows(&layout, 0, &mut final_image)?;
+
+        match pixel_format.image_from_bytes(layout.width as u32, layout.height as u32, final_image)
+        {
+            Some(image) => Ok(image),
+            None => Err(String::from("[export] Invalid image data")),
+        }
+    }
 
-                offset_y += (img.height() as i32 - overlay_size) as i32;
+    /// 按行写出 PNG，导出时只占用 EXPORT_STRIP_ROW_COUNT 行的内存
+    pub fn export_png<W: Write>(&mut self, writer: W) -> Result<(), String> {
+        self.export_png_with_metadata(writer, None)
+    }
+
+    /// 和 export_png 相同，metadata 不为 None 时以 iTXt 块嵌入 PNG
+    pub fn export_png_with_metadata<W: Write>(
+        &mut self,
+        writer: W,
+        metadata: Option<&ScrollExportMetadata>,
+    ) -> Result<(), String> {
+        self.export_png_with_options(writer, metadata, &PngEncoderOptions::default())
+    }
+
+    /// 和 export_png_with_metadata 相同，按照 options 的压缩等级和过滤方式编码
+    ///
+    /// optimize 需要完整的图片，按行写出时不支持
+    pub fn export_png_with_options<W: Write>(
+        &mut self,
+        writer: W,
+        metadata: Option<&ScrollExportMetadata>,
+        options: &PngEncoderOptions,
+    ) -> Result<(), String> {
+        if options.optimize {
+            return Err(String::from(
+                "[export_png_with_options] Optimized PNG can not be written by rows",
+            ));
+        }
+
+        if self.current_direction == ScrollDirection::Free {
+            let image = self.export_free()?;
+
+            let bit_depth = if image.color() == image::ColorType::Rgba16 {
+                png::BitDepth::Sixteen
             } else {
-                // 水平拼接
-                snow_shot_app_utils::overlay_image(
-                    &mut final_image,
-                    total_width,
-                    img,
-                    (offset_x - overlay_size) as usize,
-                    0,
-                    RGB_CHANNEL_COUNT,
-                );
-                offset_x += (img.width() as i32 - overlay_size) as i32;
+                png::BitDepth::Eight
+            };
+            let mut png_writer = write_png_header(
+                writer,
+                image.width(),
+                image.height(),
+                png::ColorType::Rgba,
+                bit_depth,
+                metadata,
+                options,
+            )?;
+
+            // PNG 的 16 位数据为大端序
+            let data = match image {
+                image::DynamicImage::ImageRgba16(image) => image
+                    .as_raw()
+                    .iter()
+                    .flat_map(|sample| sample.to_be_bytes())
+                    .collect(),
+                image => image.to_rgba8().into_raw(),
+            };
+            png_writer
+                .write_image_data(&data)
+                .map_err(|e| format!("[export_png_with_options] Failed to write image: {}", e))?;
+
+            return png_writer
+                .finish()
+                .map_err(|e| format!("[export_png_with_options] Failed to finish: {}", e));
+        }
+
+        let layout = match self.get_export_layout() {
+            Some(layout) => layout,
+            None => {
+                return Err(String::from("[export_png_with_options] No image to export"));
+            }
+        };
+        let (total_width, total_height) = (layout.width, layout.height);
+
+        let pixel_format = self.frame_store.pixel_format();
+        let (color_type, bit_depth) = match pixel_format {
+            PixelFormat::Rgb8 => (png::ColorType::Rgb, png::BitDepth::Eight),
+            PixelFormat::Rgba8 => (png::ColorType::Rgba, png::BitDepth::Eight),
+            PixelFormat::Rgb16 => (png::ColorType::Rgb, png::BitDepth::Sixteen),
+        };
+        let mut png_writer = write_png_header(
+            writer,
+            total_width as u32,
+            total_height as u32,
+            color_type,
+            bit_depth,
+            metadata,
+            options,
+        )?;
+        let mut stream_writer = png_writer.stream_writer().map_err(|e| {
+            format!(
+                "[export_png_with_options] Failed to create stream writer: {}",
+                e
+            )
+        })?;
+
+        let row_size = total_width * pixel_format.bytes_per_pixel();
+        let mut rows = vec![0; row_size * EXPORT_STRIP_ROW_COUNT.min(total_height)];
+        let mut start_row = 0;
+        while start_row < total_height {
+            let row_count = EXPORT_STRIP_ROW_COUNT.min(total_height - start_row);
+            let rows = &mut rows[..row_count * row_size];
+
+            self.fill_export_rows(&layout, start_row as u32, rows)?;
+
+            // PNG 的 16 位数据为大端序
+            if pixel_format == PixelFormat::Rgb16 {
+                for sample in rows.chunks_exact_mut(2) {
+                    let value = u16::from_ne_bytes([sample[0], sample[1]]);
+                    sample.copy_from_slice(&value.to_be_bytes());
+                }
+            }
+            stream_writer
+                .write_all(rows)
+                .map_err(|e| format!("[export_png_with_options] Failed to write rows: {}", e))?;
+
+            start_row += row_count;
+        }
+
+        stream_writer
+            .finish()
+            .map_err(|e| format!("[export_png_with_options] Failed to finish rows: {}", e))?;
+        png_writer
+            .finish()
+            .map_err(|e| format!("[export_png_with_options] Failed to finish: {}", e))
+    }
+
+    /// 导出图片的尺寸，自由方向为画布尺寸
+    pub fn get_export_size(&self) -> Option<(u32, u32)> {
+        if self.current_direction == ScrollDirection::Free {
+            if self.frame_store.is_empty(ScrollImageList::Bottom) {
+                return None;
             }
+
+            let canvas_rect = self.free_canvas_rect;
+            return Some((
+                (canvas_rect.max_x - canvas_rect.min_x) as u32,
+                (canvas_rect.max_y - canvas_rect.min_y) as u32,
+            ));
         }
 
-        // 最先推入的图片优先级最低，所以从尾部开始
+        if self.frame_store.total_len() == 0 {
+            return None;
+        }
+
+        // 固定区域只保留一份，放在图片首尾
+        let sticky_bands = self.sticky_bands.unwrap_or_default();
+        let total_size = (self.top_image_size + self.bottom_image_size) as u32
+            + sticky_bands.start_size
+            + sticky_bands.end_size;
+
         if self.current_direction == ScrollDirection::Vertical {
-            offset_y = self.top_image_size as i32;
+            Some((self.image_width, total_size))
         } else {
-            offset_x = self.top_image_size as i32;
+            Some((total_size, self.image_height))
         }
+    }
 
-        for scroll_image in self.top_image_list.iter() {
-            let img = &scroll_image.image;
-            let overlay_size = scroll_image.overlay_size;
+    /// 分页方向上的尺寸，水平滚动按宽度分页，其余按高度分页
+    fn get_page_side_size(&self, (width, height): (u32, u32)) -> u32 {
+        if self.current_direction == ScrollDirection::Horizontal {
+            width
+        } else {
+            height
+        }
+    }
 
-            if self.current_direction == ScrollDirection::Vertical {
-                // 垂直拼接
-                let actual_height = img.height() as i32 + overlay_size;
+    pub fn get_page_count(&self, page_size: u32, overlap: u32) -> usize {
+        match self.get_export_size() {
+            Some(export_size) => {
+                get_page_ranges(self.get_page_side_size(export_size), page_size, overlap).len()
+            }
+            None => 0,
+        }
+    }
 
-                snow_shot_app_utils::overlay_image(
-                    &mut final_image,
-                    total_width,
-                    img,
-                    0,
-                    (offset_y - actual_height) as usize,
-                    RGB_CHANNEL_COUNT,
-                );
+    /// 沿滚动方向将拼接结果切分为多页，相邻两页重叠 overlap
+    ///
+    /// 每次只在内存中构建一页，on_page 可以修改页信息（如记录文件名），返回的清单包含修改后的页信息
+    pub fn export_pages<F>(
+        &mut self,
+        page_size: u32,
+        overlap: u32,
+        mut on_page: F,
+    ) -> Result<ScrollPageManifest, String>
+    where
+        F: FnMut(&mut ScrollPageInfo, image::DynamicImage) -> Result<(), String>,
+    {
+        if page_size == 0 || overlap >= page_size {
+            return Err(format!(
+                "[export_pages] Invalid page size {} with overlap {}",
+                page_size, overlap
+            ));
+        }
 
-                offset_y -= actual_height;
+        let (total_width, total_height) = match self.get_export_size() {
+            Some(export_size) => export_size,
+            None => return Err(String::from("[export_pages] No image to export")),
+        };
+        let page_ranges = get_page_ranges(
+            self.get_page_side_size((total_width, total_height)),
+            page_size,
+            overlap,
+        );
+
+        // 自由方向的画布不按行组织，直接在内存中构建完整的图片后裁剪
+        let (free_image, layout) = if self.current_direction == ScrollDirection::Free {
+            (Some(self.export_free()?), None)
+        } else {
+            match self.get_export_layout() {
+                Some(layout) => (None, Some(layout)),
+                None => return Err(String::from("[export_pages] No image to export")),
+            }
+        };
+
+        let mut pages = Vec::with_capacity(page_ranges.len());
+        for (index, (start, size)) in page_ranges.into_iter().enumerate() {
+            let mut page = if self.current_direction == ScrollDirection::Horizontal {
+                ScrollPageInfo {
+                    index,
+                    x: start,
+                    y: 0,
+                    width: size,
+                    height: total_height,
+                    file_name: None,
+                }
             } else {
-                let actual_width = img.width() as i32 + overlay_size;
-
-                // 水平拼接
-                snow_shot_app_utils::overlay_image(
-                    &mut final_image,
-                    total_width,
-                    img,
-                    (offset_x - actual_width) as usize,
-                    0,
-                    RGB_CHANNEL_COUNT,
+                ScrollPageInfo {
+                    index,
+                    x: 0,
+                    y: start,
+                    width: total_width,
+                    height: size,
+                    file_name: None,
+                }
+            };
+
+            let page_image = match (&free_image, &layout) {
+                (Some(free_image), _) => {
+                    free_image.crop_imm(page.x, page.y, page.width, page.height)
+                }
+                (None, Some(layout)) => {
+                    let page_layout = layout.clip(
+                        self.current_direction == ScrollDirection::Vertical,
+                        start,
+                        size,
+                    );
+
+                    let pixel_format = self.frame_store.pixel_format();
+                    let mut page_data =
+                        vec![
+                            0;
+                            page_layout.width * page_layout.height * pixel_format.bytes_per_pixel()
+                        ];
+                    self.fill_export_rows(&page_layout, 0, &mut page_data)?;
+
+                    match pixel_format.image_from_bytes(page.width, page.height, page_data) {
+                        Some(image) => image,
+                        None => {
+                            return Err(format!(
+                                "[export_pages] Invalid image data for page {}",
+                                index
+                            ));
+                        }
+                    }
+                }
+                (None, None) => unreachable!(),
+            };
+
+            on_page(&mut page, page_image)?;
+            pages.push(page);
+        }
+
+        Ok(ScrollPageManifest {
+            direction: self.current_direction,
+            width: total_width,
+            height: total_height,
+            page_size,
+            overlap,
+            pages,
+        })
+    }
+}
+
+/// 写入 PNG 的文件头，之后写入的像素数据需要是大端序
+fn write_png_header<W: Write>(
+    writer: W,
+    width: u32,
+    height: u32,
+    color_type: png::ColorType,
+    bit_depth: png::BitDepth,
+    metadata: Option<&ScrollExportMetadata>,
+    options: &PngEncoderOptions,
+) -> Result<png::Writer<W>, String> {
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(color_type);
+    encoder.set_depth(bit_depth);
+    encoder.set_compression(match options.compression_level {
+        PngCompressionLevel::Fast => png::Compression::Fast,
+        PngCompressionLevel::Default => png::Compression::Default,
+        PngCompressionLevel::Best => png::Compression::Best,
+    });
+    match options.filter {
+        PngFilter::NoFilter => encoder.set_filter(png::FilterType::NoFilter),
+        PngFilter::Sub => encoder.set_filter(png::FilterType::Sub),
+        PngFilter::Up => encoder.set_filter(png::FilterType::Up),
+        PngFilter::Avg => encoder.set_filter(png::FilterType::Avg),
+        PngFilter::Paeth => encoder.set_filter(png::FilterType::Paeth),
+        PngFilter::Adaptive => encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive),
+    }
+
+    if let Some(metadata) = metadata {
+        add_png_metadata(&mut encoder, metadata)?;
+    }
+
+    encoder
+        .write_header()
+        .map_err(|e| format!("[write_png_header] Failed to write header: {}", e))
+}
+
+/// 当前帧左上角位于上一帧 (dx, dy) 时，两帧重叠区域每隔 sample_step 个像素采样的平均差异
+fn get_free_offset_mean_diff(
+    previous_gray_image: &GrayImage,
+    current_gray_image: &GrayImage,
+    dx: i32,
+    dy: i32,
+    sample_step: usize,
+) -> Option<f32> {
+    let width = previous_gray_image.width() as i32;
+    let height = previous_gray_image.height() as i32;
+
+    let min_x = dx.max(0);
+    let max_x = (width + dx).min(width);
+    let min_y = dy.max(0);
+    let max_y = (height + dy).min(height);
+    if max_x <= min_x || max_y <= min_y {
+        return None;
+    }
+
+    let mut diff_sum = 0u64;
+    let mut sample_count = 0u64;
+    for y in (min_y..max_y).step_by(sample_step) {
+        for x in (min_x..max_x).step_by(sample_step) {
+            let previous_pixel = previous_gray_image.get_pixel(x as u32, y as u32)[0];
+            let current_pixel = current_gray_image.get_pixel((x - dx) as u32, (y - dy) as u32)[0];
+            diff_sum += previous_pixel.abs_diff(current_pixel) as u64;
+            sample_count += 1;
+        }
+    }
+
+    if sample_count == 0 {
+        return None;
+    }
+
+    Some(diff_sum as f32 / sample_count as f32)
+}
+
+/// 两段像素数据差异的总和，16 位格式只比较高 8 位
+fn get_sample_difference(pixel_format: PixelFormat, first: &[u8], second: &[u8]) -> u64 {
+    if pixel_format == PixelFormat::Rgb16 {
+        first
+            .chunks_exact(2)
+            .zip(second.chunks_exact(2))
+            .map(|(first, second)| {
+                let first = u16::from_ne_bytes([first[0], first[1]]) >> 8;
+                let second = u16::from_ne_bytes([second[0], second[1]]) >> 8;
+                first.abs_diff(second) as u64
+            })
+            .sum()
+    } else {
+        first
+            .iter()
+            .zip(second.iter())
+            .map(|(first, second)| first.abs_diff(*second) as u64)
+            .sum()
+    }
+}
+
+/// 按照 second 的权重混合两段像素数据
+fn blend_samples(
+    pixel_format: PixelFormat,
+    first: &[u8],
+    second: &[u8],
+    weight: f32,
+    target: &mut [u8],
+) {
+    if pixel_format == PixelFormat::Rgb16 {
+        for ((first, second), target) in first
+            .chunks_exact(2)
+            .zip(second.chunks_exact(2))
+            .zip(target.chunks_exact_mut(2))
+        {
+            let first = u16::from_ne_bytes([first[0], first[1]]) as f32;
+            let second = u16::from_ne_bytes([second[0], second[1]]) as f32;
+            let value = (first + (second - first) * weight).round() as u16;
+            target.copy_from_slice(&value.to_ne_bytes());
+        }
+    } else {
+        for ((first, second), target) in first.iter().zip(second.iter()).zip(target.iter_mut()) {
+            let first = *first as f32;
+            let second = *second as f32;
+            *target = (first + (second - first) * weight).round() as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scroll_screenshot_synthetic::{SyntheticFixture, SyntheticScroll};
+
+    #[test]
+    fn test_undo_redo() {
+        let fixture = SyntheticFixture::new(ScrollDirection::Vertical, 200, 240, &[60, 50, 70], 21);
+
+        let mut service = ScrollScreenshotService::new();
+        service.init_with_params(ScrollDirection::Vertical, fixture.params);
+
+        let mut exported_images = vec![];
+        for frame in fixture.frames.iter() {
+            let (result, _, _) = service.handle_image(frame.image.clone(), ScrollImageList::Bottom);
+            assert!(matches!(result, Some((_, Some(ScrollImageList::Bottom)))));
+            exported_images.push(service.export().unwrap().to_rgb8());
+        }
+
+        // 首帧不能撤销
+        for exported_image in exported_images.iter().rev().skip(1) {
+            assert_eq!(service.undo(), Ok(Some(ScrollImageList::Bottom)));
+            assert_eq!(&service.export().unwrap().to_rgb8(), exported_image);
+        }
+        assert!(!service.can_undo());
+        assert_eq!(service.undo(), Ok(None));
+
+        for exported_image in exported_images.iter().skip(1) {
+            assert_eq!(service.redo(), Ok(Some(ScrollImageList::Bottom)));
+            assert_eq!(&service.export().unwrap().to_rgb8(), exported_image);
+        }
+        assert!(!service.can_redo());
+
+        // 撤销后继续拼接，重做记录失效
+        service.undo().unwrap();
+        let frame = fixture.frames.last().unwrap();
+        service.handle_image(frame.image.clone(), ScrollImageList::Bottom);
+        assert!(!service.can_redo());
+        assert_eq!(
+            &service.export().unwrap().to_rgb8(),
+            exported_images.last().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_undo_redo_while_detecting_sticky_bands() {
+        let fixture = SyntheticFixture::from_scroll(SyntheticScroll {
+            scroll_deltas: vec![60, 50, 70, 40],
+            sticky_header_size: 20,
+            sticky_footer_size: 16,
+            seed: 45,
+            ..SyntheticScroll::new(ScrollDirection::Vertical, 200, 240)
+        });
+        let params = ScrollScreenshotParams {
+            detect_sticky_bands: true,
+            ..fixture.params
+        };
+        let mut expected_service = fixture.stitch_service(params);
+        assert!(
+            expected_service
+                .sticky_bands
+                .is_some_and(|sticky_bands| !sticky_bands.is_empty())
+        );
+
+        let mut service = ScrollScreenshotService::new();
+        service.init_with_params(ScrollDirection::Vertical, params);
+        for (index, frame) in fixture.frames.iter().enumerate() {
+            service.handle_image(frame.image.clone(), ScrollImageList::Bottom);
+
+            // 固定区域确定前撤销再重做，检测帧随之移出和放回，不影响检测
+            if index == 1 {
+                assert!(service.sticky_bands.is_none());
+                assert_eq!(service.undo(), Ok(Some(ScrollImageList::Bottom)));
+                assert!(service.sticky_bands.is_none());
+                assert_eq!(service.sticky_detection_frames.len(), 1);
+                assert_eq!(service.redo(), Ok(Some(ScrollImageList::Bottom)));
+                assert_eq!(service.sticky_detection_frames.len(), 2);
+            }
+        }
+
+        assert_eq!(service.sticky_bands, expected_service.sticky_bands);
+        assert_eq!(
+            service.export().unwrap().to_rgb8(),
+            expected_service.export().unwrap().to_rgb8()
+        );
+    }
+
+    #[test]
+    fn test_export_png_with_spilled_frames() {
+        for direction in [ScrollDirection::Vertical, ScrollDirection::Horizontal] {
+            let fixture = SyntheticFixture::new(direction, 200, 200, &[60, 50, 70, 40], 33);
+            let mut service = fixture.stitch_service(ScrollScreenshotParams {
+                // 只保留一帧在内存中，其余帧写入临时文件
+                memory_budget: 200 * 200 * 3,
+                ..fixture.params
+            });
+            assert!(service.frame_store.spilled_count() > 0);
+
+            let mut buf = Vec::new();
+            service.export_png(&mut buf).unwrap();
+            let png_image = image::load_from_memory(&buf).unwrap().to_rgb8();
+
+            assert_eq!(png_image, service.export().unwrap().to_rgb8());
+            assert_eq!(png_image.dimensions(), fixture.source.dimensions());
+        }
+    }
+
+    #[test]
+    fn test_export_pixel_formats() {
+        let fixture = SyntheticFixture::new(ScrollDirection::Vertical, 200, 200, &[60, 50, 70], 57);
+        let rgb_source = &fixture.source;
+        let (source_width, source_height) = rgb_source.dimensions();
+
+        // 透明度和 16 位的低位都和内容无关，匹配时只使用灰度图，导出时需要原样保留
+        let sources = [
+            DynamicImage::ImageRgba8(image::RgbaImage::from_fn(
+                source_width,
+                source_height,
+                |x, y| {
+                    let pixel = rgb_source.get_pixel(x, y);
+                    image::Rgba([pixel[0], pixel[1], pixel[2], ((x + y * 7) % 256) as u8])
+                },
+            )),
+            DynamicImage::ImageRgb16(image::ImageBuffer::from_fn(
+                source_width,
+                source_height,
+                |x, y| {
+                    let pixel = rgb_source.get_pixel(x, y);
+                    let low_bits = ((x * 3 + y) % 256) as u16;
+                    image::Rgb(pixel.0.map(|channel| ((channel as u16) << 8) | low_bits))
+                },
+            )),
+        ];
+
+        for source in sources {
+            let mut service = ScrollScreenshotService::new();
+            service.init_with_params(
+                ScrollDirection::Vertical,
+                ScrollScreenshotParams {
+                    memory_budget: 200 * 200 * 8,
+                    ..fixture.params
+                },
+            );
+
+            for frame in fixture.frames.iter() {
+                service.handle_image(
+                    source.crop_imm(0, frame.position, 200, 200),
+                    ScrollImageList::Bottom,
                 );
-                offset_x -= actual_width;
             }
+            assert!(service.frame_store.spilled_count() > 0);
+
+            let exported_image = service.export().unwrap();
+            assert_eq!(exported_image, source);
+
+            let mut buf = Vec::new();
+            service.export_png(&mut buf).unwrap();
+            assert_eq!(image::load_from_memory(&buf).unwrap(), source);
         }
+    }
 
-        Some(image::DynamicImage::ImageRgb8(
-            image::RgbImage::from_raw(total_width as u32, total_height as u32, final_image)
-                .unwrap(),
-        ))
+    #[test]
+    fn test_seam_avoids_changed_content() {
+        let fixture = SyntheticFixture::new(ScrollDirection::Vertical, 200, 200, &[40], 71);
+        let source = DynamicImage::ImageRgb8(fixture.source.clone());
+
+        // 第二帧中一小块内容发生变化（如动画），位于拼接时保留的重叠区域中
+        let mut changed_frame = fixture.frames[1].image.to_rgb8();
+        for y in 110..126 {
+            for x in 80..120 {
+                changed_frame.put_pixel(x, y, image::Rgb([255, 0, 255]));
+            }
+        }
+        let frames = [
+            fixture.frames[0].image.clone(),
+            DynamicImage::ImageRgb8(changed_frame),
+        ];
+
+        for (seam_mode, seam_feather_size) in [
+            (ScrollSeamMode::Overlay, 0),
+            (ScrollSeamMode::MinDifference, 0),
+            (ScrollSeamMode::MinDifference, 8),
+        ] {
+            let mut service = ScrollScreenshotService::new();
+            service.init_with_params(
+                ScrollDirection::Vertical,
+                ScrollScreenshotParams {
+                    seam_mode,
+                    seam_feather_size,
+                    ..fixture.params
+                },
+            );
+
+            for frame in frames.iter() {
+                service.handle_image(frame.clone(), ScrollImageList::Bottom);
+            }
+
+            let exported_image = service.export().unwrap();
+            assert_eq!(exported_image.dimensions(), fixture.source.dimensions());
+            if seam_mode == ScrollSeamMode::Overlay {
+                assert_ne!(exported_image, source);
+            } else {
+                assert_eq!(exported_image, source);
+
+                let mut buf = Vec::new();
+                service.export_png(&mut buf).unwrap();
+                assert_eq!(image::load_from_memory(&buf).unwrap(), source);
+            }
+        }
     }
 }
@@ -0,0 +1,858 @@
+use image::{DynamicImage, GrayImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use snow_shot_app_shared::ElementRect;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::scroll_screenshot_metadata::{ScrollFrameCaptureInfo, ScrollFrameMetadata};
+use crate::scroll_screenshot_page_export::create_file;
+use crate::scroll_screenshot_service::{
+    ScrollDirection, ScrollImage, ScrollImageList, ScrollIndex, ScrollOffset,
+    ScrollScreenshotParams, ScrollScreenshotService, ScrollStickyBands, ScrollStickyDetectionFrame,
+};
+
+/// 会话文件的版本，格式不兼容时递增
+pub const SCROLL_SESSION_VERSION: u32 = 2;
+/// 会话清单在 ZIP 中的文件名
+const SESSION_MANIFEST_FILE_NAME: &str = "session.json";
+/// 截图过程中自动保存会话的最小间隔
+pub const SCROLL_SESSION_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(3);
+
+/// 会话中保存的一帧
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScrollSessionFrame {
+    pub file_name: String,
+    pub overlay_size: i32,
+    #[serde(default)]
+    pub metadata: ScrollFrameMetadata,
+}
+
+/// 会话中保存的固定区域确定前拼接的帧
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScrollSessionStickyDetectionFrame {
+    pub file_name: String,
+    #[serde(default)]
+    pub capture_info: Option<ScrollFrameCaptureInfo>,
+    pub image_list: ScrollImageList,
+    pub sticky_bands: Option<ScrollStickyBands>,
+}
+
+/// 会话中保存的索引，特征点在加载时从灰度图重新计算
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScrollSessionIndex {
+    pub position: i32,
+    pub free_position: ScrollOffset,
+    /// 缩放后的灰度图
+    pub gray_image_file_name: Option<String>,
+    /// 自由方向下原尺寸的灰度图
+    pub free_gray_image_file_name: Option<String>,
+}
+
+/// 会话清单，图片以 PNG 格式和清单一起打包为 ZIP
+///
+/// 撤销历史不会保存
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScrollSessionManifest {
+    pub version: u32,
+    pub direction: ScrollDirection,
+    pub params: ScrollScreenshotParams,
+    pub image_width: u32,
+    pub image_height: u32,
+    pub top_image_size: i32,
+    pub top_image_index_size: i32,
+    pub bottom_image_size: i32,
+    pub bottom_image_index_size: i32,
+    pub match_confidence: f32,
+    pub sticky_bands: Option<ScrollStickyBands>,
+    #[serde(default)]
+    pub sticky_detection_frames: Vec<ScrollSessionStickyDetectionFrame>,
+    pub sticky_start_file_name: Option<String>,
+    pub sticky_end_file_name: Option<String>,
+    pub top_frames: Vec<ScrollSessionFrame>,
+    pub bottom_frames: Vec<ScrollSessionFrame>,
+    pub top_index: ScrollSessionIndex,
+    pub bottom_index: ScrollSessionIndex,
+    pub free_image_positions: Vec<ScrollOffset>,
+    pub free_canvas_rect: ElementRect,
+}
+
+/// 加载会话后的概要信息
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct ScrollSessionInfo {
+    pub direction: ScrollDirection,
+    pub frame_count: usize,
+    pub top_frame_count: usize,
+    pub bottom_frame_count: usize,
+    /// 导出图片的宽度，没有帧时为 0
+    pub width: u32,
+    /// 导出图片的高度，没有帧时为 0
+    pub height: u32,
+}
+
+/// 会话条目的写入位置
+trait SessionSink {
+    fn write_entry(&mut self, file_name: &str, data: &[u8]) -> Result<(), String>;
+
+    /// 条目已经写入过时直接复用，返回是否复用
+    fn reuse_entry(&mut self, _file_name: &str) -> bool {
+        false
+    }
+}
+
+/// 会话条目的读取位置
+trait SessionSource {
+    fn read_entry(&mut self, file_name: &str) -> Result<Vec<u8>, String>;
+}
+
+struct ZipSessionSink<W: Write + Seek> {
+    zip_writer: ZipWriter<W>,
+    file_options: SimpleFileOptions,
+}
+
+impl<W: Write + Seek> SessionSink for ZipSessionSink<W> {
+    fn write_entry(&mut self, file_name: &str, data: &[u8]) -> Result<(), String> {
+        self.zip_writer
+            .start_file(file_name, self.file_options)
+            .map_err(|e| {
+                format!(
+                    "[ZipSessionSink::write_entry] Failed to start {}: {}",
+                    file_name, e
+                )
+            })?;
+        self.zip_writer.write_all(data).map_err(|e| {
+            format!(
+                "[ZipSessionSink::write_entry] Failed to write {}: {}",
+                file_name, e
+            )
+        })
+    }
+}
+
+impl<R: Read + Seek> SessionSource for ZipArchive<R> {
+    fn read_entry(&mut self, file_name: &str) -> Result<Vec<u8>, String> {
+        let mut entry = self.by_name(file_name).map_err(|e| {
+            format!(
+                "[ZipArchive::read_entry] Failed to find {}: {}",
+                file_name, e
+            )
+        })?;
+
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data).map_err(|e| {
+            format!(
+                "[ZipArchive::read_entry] Failed to read {}: {}",
+                file_name, e
+            )
+        })?;
+
+        Ok(data)
+    }
+}
+
+/// 以目录保存的会话，每个条目是一个文件
+///
+/// 条目先写入临时文件再替换，写入过程中退出不会破坏已有的条目
+struct DirectorySession {
+    directory: PathBuf,
+    /// 本次保存写入或复用的条目
+    file_names: HashSet<String>,
+}
+
+impl DirectorySession {
+    fn new(directory: &Path) -> Self {
+        Self {
+            directory: directory.to_path_buf(),
+            file_names: HashSet::new(),
+        }
+    }
+
+    /// 删除本次保存没有用到的条目
+    fn remove_unused_entries(&self) -> Result<(), String> {
+        self.remove_unused_entries_in(&self.directory, "")
+    }
+
+    fn remove_unused_entries_in(&self, directory: &Path, prefix: &str) -> Result<(), String> {
+        let entries = std::fs::read_dir(directory).map_err(|e| {
+            format!(
+                "[DirectorySession::remove_unused_entries] Failed to read {}: {}",
+                directory.display(),
+                e
+            )
+        })?;
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let file_name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+            if path.is_dir() {
+                self.remove_unused_entries_in(&path, &format!("{}/", file_name))?;
+            } else if !self.file_names.contains(&file_name) {
+                std::fs::remove_file(&path).map_err(|e| {
+                    format!(
+                        "[DirectorySession::remove_unused_entries] Failed to remove {}: {}",
+                        path.display(),
+                        e
+                    )
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SessionSink for DirectorySession {
+    fn write_entry(&mut self, file_name: &str, data: &[u8]) -> Result<(), String> {
+        let file_path = self.directory.join(file_name);
+        let mut temp_file_path = file_path.as_os_str().to_owned();
+        temp_file_path.push(".tmp");
+        let temp_file_path = PathBuf::from(temp_file_path);
+
+        let mut writer = create_file(&temp_file_path)?;
+        writer
+            .write_all(data)
+            .and_then(|_| writer.flush())
+            .map_err(|e| {
+                format!(
+                    "[DirectorySession::write_entry] Failed to write {}: {}",
+                    file_name, e
+                )
+            })?;
+        drop(writer);
+
+        std::fs::rename(&temp_file_path, &file_path).map_err(|e| {
+            format!(
+                "[DirectorySession::write_entry] Failed to replace {}: {}",
+                file_path.display(),
+                e
+            )
+        })?;
+
+        self.file_names.insert(file_name.to_string());
+        Ok(())
+    }
+
+    fn reuse_entry(&mut self, file_name: &str) -> bool {
+        if !self.directory.join(file_name).is_file() {
+            return false;
+        }
+
+        self.file_names.insert(file_name.to_string());
+        true
+    }
+}
+
+impl SessionSource for DirectorySession {
+    fn read_entry(&mut self, file_name: &str) -> Result<Vec<u8>, String> {
+        let file_path = self.directory.join(file_name);
+        std::fs::read(&file_path).map_err(|e| {
+            format!(
+                "[DirectorySession::read_entry] Failed to read {}: {}",
+                file_path.display(),
+                e
+            )
+        })
+    }
+}
+
+struct SessionWriter<'a, S: SessionSink> {
+    sink: &'a mut S,
+    /// 帧的文件名前缀，帧的内容不会变化，相同的文件名可以复用
+    frame_prefix: String,
+    /// 其他图片的文件名前缀，每次保存都会重新写入
+    entry_prefix: String,
+}
+
+impl<S: SessionSink> SessionWriter<'_, S> {
+    /// 图片以 PNG 保存，保留原始的像素格式
+    fn write_image(&mut self, file_name: String, image: &DynamicImage) -> Result<String, String> {
+        let mut data = Cursor::new(Vec::new());
+        image.write_to(&mut data, ImageFormat::Png).map_err(|e| {
+            format!(
+                "[SessionWriter::write_image] Failed to encode {}: {}",
+                file_name, e
+            )
+        })?;
+
+        self.sink.write_entry(&file_name, data.get_ref())?;
+        Ok(file_name)
+    }
+
+    fn write_optional_image(
+        &mut self,
+        file_name: &str,
+        image: Option<&DynamicImage>,
+    ) -> Result<Option<String>, String> {
+        match image {
+            Some(image) => Ok(Some(
+                self.write_image(format!("{}{}", self.entry_prefix, file_name), image)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn write_sticky_detection_frames(
+        &mut self,
+        frames: &[ScrollStickyDetectionFrame],
+    ) -> Result<Vec<ScrollSessionStickyDetectionFrame>, String> {
+        frames
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| {
+                Ok(ScrollSessionStickyDetectionFrame {
+                    file_name: self.write_image(
+                        format!("sticky_detection/{}{}.png", self.entry_prefix, index),
+                        &frame.image,
+                    )?,
+                    capture_info: frame.capture_info.clone(),
+                    image_list: frame.image_list,
+                    sticky_bands: frame.sticky_bands,
+                })
+            })
+            .collect()
+    }
+
+    fn write_frames(
+        &mut self,
+        scroll_screenshot_service: &ScrollScreenshotService,
+        image_list: ScrollImageList,
+    ) -> Result<Vec<ScrollSessionFrame>, String> {
+        let frame_store = &scroll_screenshot_service.frame_store;
+
+        (0..frame_store.len(image_list))
+            .map(|index| -> Result<ScrollSessionFrame, String> {
+                let file_name = format!(
+                    "frames/{}{}.png",
+                    self.frame_prefix,
+                    frame_store.sequence(image_list, index)
+                );
+                if !self.sink.reuse_entry(&file_name) {
+                    let image = frame_store.read_image(image_list, index)?;
+                    self.write_image(file_name.clone(), &image)?;
+                }
+
+                Ok(ScrollSessionFrame {
+                    file_name,
+                    overlay_size: frame_store.overlay_size(image_list, index),
+                    metadata: frame_store.metadata(image_list, index).clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn write_index(
+        &mut self,
+        scroll_index: &ScrollIndex,
+        name: &str,
+    ) -> Result<ScrollSessionIndex, String> {
+        let to_image = |gray_image: &Option<GrayImage>| {
+            gray_image
+                .as_ref()
+                .map(|gray_image| DynamicImage::ImageLuma8(gray_image.clone()))
+        };
+
+        Ok(ScrollSessionIndex {
+            position: scroll_index.position,
+            free_position: scroll_index.free_position,
+            gray_image_file_name: self.write_optional_image(
+                &format!("index/{}.png", name),
+                to_image(&scroll_index.gray_image).as_ref(),
+            )?,
+            free_gray_image_file_name: self.write_optional_image(
+                &format!("index/{}_free.png", name),
+                to_image(&scroll_index.free_gray_image).as_ref(),
+            )?,
+        })
+    }
+
+    /// 写入所有图片后最后写入清单
+    fn write_session(
+        &mut self,
+        scroll_screenshot_service: &ScrollScreenshotService,
+    ) -> Result<(), String> {
+        let manifest = ScrollSessionManifest {
+            version: SCROLL_SESSION_VERSION,
+            direction: scroll_screenshot_service.current_direction,
+            params: scroll_screenshot_service.params(),
+            image_width: scroll_screenshot_service.image_width,
+            image_height: scroll_screenshot_service.image_height,
+            top_image_size: scroll_screenshot_service.top_image_size,
+            top_image_index_size: scroll_screenshot_service.top_image_index_size,
+            bottom_image_size: scroll_screenshot_service.bottom_image_size,
+            bottom_image_index_size: scroll_screenshot_service.bottom_image_index_size,
+            match_confidence: scroll_screenshot_service.match_confidence,
+            sticky_bands: scroll_screenshot_service.sticky_bands,
+            sticky_detection_frames: self.write_sticky_detection_frames(
+                &scroll_screenshot_service.sticky_detection_frames,
+            )?,
+            sticky_start_file_name: self.write_optional_image(
+                "sticky_start.png",
+                scroll_screenshot_service.sticky_start_image.as_ref(),
+            )?,
+            sticky_end_file_name: self.write_optional_image(
+                "sticky_end.png",
+                scroll_screenshot_service.sticky_end_image.as_ref(),
+            )?,
+            top_frames: self.write_frames(scroll_screenshot_service, ScrollImageList::Top)?,
+            bottom_frames: self.write_frames(scroll_screenshot_service, ScrollImageList::Bottom)?,
+            top_index: self.write_index(&scroll_screenshot_service.top_image_ann_index, "top")?,
+            bottom_index: self
+                .write_index(&scroll_screenshot_service.bottom_image_ann_index, "bottom")?,
+            free_image_positions: scroll_screenshot_service.free_image_positions.clone(),
+            free_canvas_rect: scroll_screenshot_service.free_canvas_rect,
+        };
+
+        let manifest_data = serde_json::to_vec_pretty(&manifest).map_err(|e| {
+            format!(
+                "[SessionWriter::write_session] Failed to serialize manifest: {}",
+                e
+            )
+        })?;
+        self.sink
+            .write_entry(SESSION_MANIFEST_FILE_NAME, &manifest_data)
+    }
+}
+
+/// 保存当前的拼接状态，用于恢复截图或之后重新导出
+pub fn save_session<W: Write + Seek>(
+    scroll_screenshot_service: &ScrollScreenshotService,
+    writer: W,
+) -> Result<(), String> {
+    let mut sink = ZipSessionSink {
+        zip_writer: ZipWriter::new(writer),
+        // PNG 已经是压缩过的数据，直接存储
+        file_options: SimpleFileOptions::default().compression_method(CompressionMethod::Stored),
+    };
+
+    SessionWriter {
+        sink: &mut sink,
+        frame_prefix: String::new(),
+        entry_prefix: String::new(),
+    }
+    .write_session(scroll_screenshot_service)?;
+
+    sink.zip_writer
+        .finish()
+        .map_err(|e| format!("[save_session] Failed to finish: {}", e))?;
+
+    Ok(())
+}
+
+fn read_image<S: SessionSource>(source: &mut S, file_name: &str) -> Result<DynamicImage, String> {
+    let data = source.read_entry(file_name)?;
+    image::load_from_memory_with_format(&data, ImageFormat::Png)
+        .map_err(|e| format!("[read_image] Failed to decode {}: {}", file_name, e))
+}
+
+fn read_optional_image<S: SessionSource>(
+    source: &mut S,
+    file_name: &Option<String>,
+) -> Result<Option<DynamicImage>, String> {
+    match file_name {
+        Some(file_name) => Ok(Some(read_image(source, file_name)?)),
+        None => Ok(None),
+    }
+}
+
+/// 从灰度图重新建立索引
+fn read_index<S: SessionSource>(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    source: &mut S,
+    session_index: &ScrollSessionIndex,
+) -> Result<ScrollIndex, String> {
+    let gray_image = read_optional_image(source, &session_index.gray_image_file_name)?;
+    let mut scroll_index = match gray_image {
+        Some(gray_image) => {
+            scroll_screenshot_service.create_index_from_gray_image(gray_image.to_luma8())
+        }
+        None => ScrollIndex::new(),
+    };
+
+    scroll_index.position = session_index.position;
+    scroll_index.free_position = session_index.free_position;
+    scroll_index.free_gray_image =
+        read_optional_image(source, &session_index.free_gray_image_file_name)?
+            .map(|image| image.to_luma8());
+
+    Ok(scroll_index)
+}
+
+/// 加载会话，替换当前的拼接状态，恢复过程中出错时拼接状态会被清空
+pub fn load_session<R: Read + Seek>(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    reader: R,
+) -> Result<ScrollSessionInfo, String> {
+    let mut archive = ZipArchive::new(reader)
+        .map_err(|e| format!("[load_session] Failed to open session: {}", e))?;
+
+    load_session_from_source(scroll_screenshot_service, &mut archive)
+}
+
+fn load_session_from_source<S: SessionSource>(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    source: &mut S,
+) -> Result<ScrollSessionInfo, String> {
+    let manifest_data = source.read_entry(SESSION_MANIFEST_FILE_NAME)?;
+    let manifest: ScrollSessionManifest = serde_json::from_slice(&manifest_data)
+        .map_err(|e| format!("[load_session] Failed to parse manifest: {}", e))?;
+    if manifest.version != SCROLL_SESSION_VERSION {
+        return Err(format!(
+            "[load_session] Unsupported session version: {}",
+            manifest.version
+        ));
+    }
+
+    scroll_screenshot_service.init_with_params(manifest.direction, manifest.params);
+
+    let result = restore_session(scroll_screenshot_service, source, &manifest);
+    if result.is_err() {
+        scroll_screenshot_service.init_with_params(manifest.direction, manifest.params);
+    }
+    result?;
+
+    let (width, height) = scroll_screenshot_service
+        .get_export_size()
+        .unwrap_or((0, 0));
+
+    Ok(ScrollSessionInfo {
+        direction: manifest.direction,
+        frame_count: scroll_screenshot_service.frame_store.total_len(),
+        top_frame_count: scroll_screenshot_service
+            .frame_store
+            .len(ScrollImageList::Top),
+        bottom_frame_count: scroll_screenshot_service
+            .frame_store
+            .len(ScrollImageList::Bottom),
+        width,
+        height,
+    })
+}
+
+fn restore_session<S: SessionSource>(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    source: &mut S,
+    manifest: &ScrollSessionManifest,
+) -> Result<(), String> {
+    if manifest.image_width != 0 && manifest.image_height != 0 {
+        scroll_screenshot_service.init_image_size(manifest.image_width, manifest.image_height);
+    }
+
+    for (image_list, frames) in [
+        (ScrollImageList::Top, &manifest.top_frames),
+        (ScrollImageList::Bottom, &manifest.bottom_frames),
+    ] {
+        for frame in frames {
+            let image = read_image(source, &frame.file_name)?;
+            scroll_screenshot_service.frame_store.push(
+                image_list,
+                ScrollImage {
+                    image,
+                    overlay_size: frame.overlay_size,
+                    metadata: frame.metadata.clone(),
+                },
+            )?;
+        }
+    }
+
+    scroll_screenshot_service.sticky_detection_frames = manifest
+        .sticky_detection_frames
+        .iter()
+        .map(|frame| {
+            Ok(ScrollStickyDetectionFrame {
+                image: read_image(source, &frame.file_name)?,
+                capture_info: frame.capture_info.clone(),
+                image_list: frame.image_list,
+                sticky_bands: frame.sticky_bands,
+            })
+        })
+        .collect::<Result<Vec<ScrollStickyDetectionFrame>, String>>()?;
+    scroll_screenshot_service.sticky_start_image =
+        read_optional_image(source, &manifest.sticky_start_file_name)?;
+    scroll_screenshot_service.sticky_end_image =
+        read_optional_image(source, &manifest.sticky_end_file_name)?;
+    scroll_screenshot_service.sticky_bands = manifest.sticky_bands;
+
+    // 首次检测特征点时会选择角点算法，和截图时一样先处理上图片列表的索引
+    let top_index = read_index(scroll_screenshot_service, source, &manifest.top_index)?;
+    let bottom_index = read_index(scroll_screenshot_service, source, &manifest.bottom_index)?;
+    scroll_screenshot_service.top_image_ann_index = Arc::new(top_index);
+    scroll_screenshot_service.bottom_image_ann_index = Arc::new(bottom_index);
+
+    scroll_screenshot_service.top_image_size = manifest.top_image_size;
+    scroll_screenshot_service.top_image_index_size = manifest.top_image_index_size;
+    scroll_screenshot_service.bottom_image_size = manifest.bottom_image_size;
+    scroll_screenshot_service.bottom_image_index_size = manifest.bottom_image_index_size;
+    scroll_screenshot_service.match_confidence = manifest.match_confidence;
+    scroll_screenshot_service.free_image_positions = manifest.free_image_positions.clone();
+    scroll_screenshot_service.free_canvas_rect = manifest.free_canvas_rect;
+
+    Ok(())
+}
+
+pub fn save_session_to_file(
+    scroll_screenshot_service: &ScrollScreenshotService,
+    file_path: &Path,
+) -> Result<(), String> {
+    save_session(scroll_screenshot_service, create_file(file_path)?)
+}
+
+pub fn load_session_from_file(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    file_path: &Path,
+) -> Result<ScrollSessionInfo, String> {
+    let file = File::open(file_path).map_err(|e| {
+        format!(
+            "[load_session_from_file] Failed to open {}: {}",
+            file_path.display(),
+            e
+        )
+    })?;
+
+    load_session(scroll_screenshot_service, BufReader::new(file))
+}
+
+/// 自动保存时的拼接状态，用于判断拼接状态是否变化
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct ScrollSessionAutosaveState {
+    frame_count: usize,
+    next_frame_sequence: usize,
+    top_image_size: i32,
+    bottom_image_size: i32,
+}
+
+impl ScrollSessionAutosaveState {
+    fn from_service(scroll_screenshot_service: &ScrollScreenshotService) -> Self {
+        Self {
+            frame_count: scroll_screenshot_service.frame_store.total_len(),
+            next_frame_sequence: scroll_screenshot_service.frame_store.next_sequence(),
+            top_image_size: scroll_screenshot_service.top_image_size,
+            bottom_image_size: scroll_screenshot_service.bottom_image_size,
+        }
+    }
+}
+
+/// 截图过程中的自动保存记录，初始化或清空拼接状态时重置
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScrollSessionAutosave {
+    last_save_time: Option<Instant>,
+    saved_state: ScrollSessionAutosaveState,
+    /// 区分不同截图的帧文件，首次保存时生成
+    session_id: Option<u128>,
+    /// 保存的次数，区分每次保存写入的非帧图片
+    revision: u64,
+}
+
+/// 自动保存会话的目录，程序异常退出后可以从该目录恢复截图
+pub fn get_autosave_session_directory() -> PathBuf {
+    std::env::temp_dir().join("snow-shot-scroll-screenshot-autosave")
+}
+
+/// 拼接状态变化且距离上次保存超过 SCROLL_SESSION_AUTOSAVE_INTERVAL 时保存会话，返回是否保存
+pub fn autosave_session(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    directory: &Path,
+) -> Result<bool, String> {
+    let state = ScrollSessionAutosaveState::from_service(scroll_screenshot_service);
+    let autosave = scroll_screenshot_service.session_autosave;
+    if state.frame_count == 0 || state == autosave.saved_state {
+        return Ok(false);
+    }
+    if autosave
+        .last_save_time
+        .is_some_and(|last_save_time| last_save_time.elapsed() < SCROLL_SESSION_AUTOSAVE_INTERVAL)
+    {
+        return Ok(false);
+    }
+
+    save_autosave_session(scroll_screenshot_service, directory)?;
+    Ok(true)
+}
+
+/// 增量保存会话，已经写入的帧不会重新编码
+///
+/// 清单最后写入，之后才删除上一次保存用到的旧文件，保存过程中退出时仍然可以恢复上一次保存的会话
+fn save_autosave_session(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    directory: &Path,
+) -> Result<(), String> {
+    let autosave = scroll_screenshot_service.session_autosave;
+    let session_id = autosave.session_id.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default()
+    });
+
+    let mut directory_session = DirectorySession::new(directory);
+    SessionWriter {
+        sink: &mut directory_session,
+        frame_prefix: format!("{}_", session_id),
+        entry_prefix: format!("{}_{}_", session_id, autosave.revision),
+    }
+    .write_session(scroll_screenshot_service)?;
+
+    scroll_screenshot_service.session_autosave = ScrollSessionAutosave {
+        last_save_time: Some(Instant::now()),
+        saved_state: ScrollSessionAutosaveState::from_service(scroll_screenshot_service),
+        session_id: Some(session_id),
+        revision: autosave.revision + 1,
+    };
+
+    directory_session.remove_unused_entries()
+}
+
+/// 是否存在自动保存的会话
+pub fn has_autosave_session(directory: &Path) -> bool {
+    directory.join(SESSION_MANIFEST_FILE_NAME).is_file()
+}
+
+/// 加载自动保存的会话，替换当前的拼接状态
+pub fn load_autosave_session(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    directory: &Path,
+) -> Result<ScrollSessionInfo, String> {
+    load_session_from_source(
+        scroll_screenshot_service,
+        &mut DirectorySession::new(directory),
+    )
+}
+
+/// 截图正常结束后删除自动保存的会话
+pub fn remove_autosave_session(directory: &Path) -> Result<(), String> {
+    if !directory.exists() {
+        return Ok(());
+    }
+
+    std::fs::remove_dir_all(directory).map_err(|e| {
+        format!(
+            "[remove_autosave_session] Failed to remove {}: {}",
+            directory.display(),
+            e
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scroll_screenshot_synthetic::{SyntheticFixture, SyntheticScroll};
+
+    #[test]
+    fn test_resume_session() {
+        let mut fixture = SyntheticFixture::from_scroll(SyntheticScroll {
+            scroll_deltas: vec![60, 50, 70, 40],
+            sticky_header_size: 20,
+            sticky_footer_size: 16,
+            seed: 45,
+            ..SyntheticScroll::new(ScrollDirection::Vertical, 200, 240)
+        });
+        let last_frame = fixture.frames.pop().unwrap();
+        let mut service = fixture.stitch_service(ScrollScreenshotParams {
+            detect_sticky_bands: true,
+            ..fixture.params
+        });
+        assert!(service.sticky_bands.is_some());
+
+        let mut session_data = Cursor::new(Vec::new());
+        save_session(&service, &mut session_data).unwrap();
+
+        let mut resumed_service = ScrollScreenshotService::new();
+        session_data.set_position(0);
+        let session_info = load_session(&mut resumed_service, session_data).unwrap();
+        assert_eq!(session_info.frame_count, service.frame_store.total_len());
+        assert_eq!(resumed_service.sticky_bands, service.sticky_bands);
+        assert_eq!(
+            resumed_service.export().unwrap().to_rgb8(),
+            service.export().unwrap().to_rgb8()
+        );
+
+        // 恢复后可以继续拼接
+        for service in [&mut service, &mut resumed_service] {
+            let (result, _, _) =
+                service.handle_image(last_frame.image.clone(), ScrollImageList::Bottom);
+            assert!(matches!(result, Some((_, Some(ScrollImageList::Bottom)))));
+        }
+        assert_eq!(
+            resumed_service.export().unwrap().to_rgb8(),
+            service.export().unwrap().to_rgb8()
+        );
+    }
+
+    #[test]
+    fn test_autosave_session() {
+        let fixture =
+            SyntheticFixture::new(ScrollDirection::Vertical, 200, 240, &[60, 50, 70, 40], 47);
+        let directory = std::env::temp_dir().join(format!(
+            "snow-shot-scroll-screenshot-autosave-test-{}",
+            std::process::id()
+        ));
+        let list_frame_files = || -> HashSet<PathBuf> {
+            std::fs::read_dir(directory.join("frames"))
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .collect()
+        };
+        let frame_count = fixture.frames.len();
+
+        let mut service = ScrollScreenshotService::new();
+        service.init_with_params(ScrollDirection::Vertical, fixture.params);
+        // 没有帧时不保存
+        assert!(!autosave_session(&mut service, &directory).unwrap());
+        assert!(!has_autosave_session(&directory));
+
+        for frame in fixture.frames[..frame_count - 1].iter() {
+            service.handle_image(frame.image.clone(), ScrollImageList::Bottom);
+        }
+        assert!(autosave_session(&mut service, &directory).unwrap());
+        assert!(has_autosave_session(&directory));
+        // 拼接状态没有变化时不重复保存
+        assert!(!autosave_session(&mut service, &directory).unwrap());
+
+        // 距离上次保存不足间隔时，即使有变化也不保存
+        service.handle_image(
+            fixture.frames[frame_count - 1].image.clone(),
+            ScrollImageList::Bottom,
+        );
+        assert!(!autosave_session(&mut service, &directory).unwrap());
+
+        // 已经写入的帧直接复用，只写入新的帧
+        let saved_frame_files = list_frame_files();
+        assert_eq!(saved_frame_files.len(), frame_count - 1);
+        let modified_times = saved_frame_files
+            .iter()
+            .map(|path| (path.clone(), path.metadata().unwrap().modified().unwrap()))
+            .collect::<Vec<_>>();
+        save_autosave_session(&mut service, &directory).unwrap();
+        assert_eq!(list_frame_files().len(), frame_count);
+        for (path, modified_time) in modified_times {
+            assert_eq!(path.metadata().unwrap().modified().unwrap(), modified_time);
+        }
+
+        let mut resumed_service = ScrollScreenshotService::new();
+        let session_info = load_autosave_session(&mut resumed_service, &directory).unwrap();
+        assert_eq!(session_info.bottom_frame_count, frame_count);
+        assert_eq!(
+            resumed_service.export().unwrap().to_rgb8(),
+            service.export().unwrap().to_rgb8()
+        );
+
+        // 撤销的帧在下一次保存后删除
+        service.undo().unwrap();
+        save_autosave_session(&mut service, &directory).unwrap();
+        assert_eq!(list_frame_files(), saved_frame_files);
+        let session_info = load_autosave_session(&mut resumed_service, &directory).unwrap();
+        assert_eq!(session_info.bottom_frame_count, frame_count - 1);
+        assert_eq!(
+            resumed_service.export().unwrap().to_rgb8(),
+            service.export().unwrap().to_rgb8()
+        );
+
+        remove_autosave_session(&directory).unwrap();
+        assert!(!directory.exists());
+        remove_autosave_session(&directory).unwrap();
+    }
+}
@@ -0,0 +1,642 @@
+use image::DynamicImage;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::scroll_screenshot_service::{
+    ScrollDirection, ScrollImageList, ScrollOffset, ScrollScreenshotParams,
+    ScrollScreenshotService, ScrollStickyBands,
+};
+
+#[derive(PartialEq, Serialize, Debug, Clone, Copy)]
+pub enum StitchFrameStatus {
+    /// 有新增区域，已拼接
+    Stitched,
+    /// 匹配成功，但没有新增区域
+    NoNewContent,
+    /// 和已拼接的区域相同
+    Unchanged,
+    /// 未找到匹配
+    NoMatch,
+    /// 尺寸和首帧不一致
+    SizeMismatch,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct StitchFrame {
+    pub status: StitchFrameStatus,
+    /// 帧的左上角在导出图片中的位置，未匹配的帧为 None
+    pub offset: Option<ScrollOffset>,
+    /// 帧被拼接到的图片列表
+    pub image_list: Option<ScrollImageList>,
+    /// 匹配的置信度，未匹配的帧为 None
+    pub confidence: Option<f32>,
+}
+
+pub struct StitchResult {
+    /// 拼接后的图片，没有可用的帧或读取帧失败时为错误
+    pub image: Result<DynamicImage, String>,
+    /// 与输入帧一一对应的处理结果
+    pub frames: Vec<StitchFrame>,
+    /// 检测到的固定区域
+    pub sticky_bands: Option<ScrollStickyBands>,
+}
+
+/// 不依赖截图和 Tauri 的拼接入口，按顺序拼接已经截取好的帧
+///
+/// 帧的尺寸需要一致，尺寸不一致的帧会被跳过。
+/// try_rollback 为 true 时，当前方向匹配失败会再尝试另一个方向
+pub fn stitch_frames<I>(
+    frames: I,
+    direction: ScrollDirection,
+    params: ScrollScreenshotParams,
+) -> StitchResult
+where
+    I: IntoIterator<Item = DynamicImage>,
+{
+    let frames: Vec<DynamicImage> = frames.into_iter().collect();
+    let mut scroll_screenshot_service = ScrollScreenshotService::new();
+    scroll_screenshot_service.init_with_params(direction, params);
+    let mut frame_positions = stitch_frames_with_service(
+        &mut scroll_screenshot_service,
+        frames.iter().cloned(),
+        direction,
+    );
+
+    // 固定区域在多帧之后才能确认，确认前记录的帧位置没有去除固定区域，使用确认的固定区域重新拼接
+    let sticky_bands = scroll_screenshot_service
+        .sticky_bands
+        .filter(|sticky_bands| !sticky_bands.is_empty());
+    if let (Some(sticky_bands), Some(first_frame)) = (sticky_bands, frames.first()) {
+        scroll_screenshot_service = ScrollScreenshotService::new();
+        scroll_screenshot_service.init_with_params(
+            direction,
+            ScrollScreenshotParams {
+                detect_sticky_bands: false,
+                ..params
+            },
+        );
+        scroll_screenshot_service.set_sticky_bands(sticky_bands, first_frame);
+        frame_positions = stitch_frames_with_service(
+            &mut scroll_screenshot_service,
+            frames.iter().cloned(),
+            direction,
+        );
+    }
+
+    let origin = scroll_screenshot_service.export_origin();
+    let frames = frame_positions
+        .into_iter()
+        .map(
+            |(status, frame_position, image_list, confidence)| StitchFrame {
+                status,
+                offset: frame_position.map(|frame_position| {
+                    ScrollOffset::new(frame_position.x + origin.x, frame_position.y + origin.y)
+                }),
+                image_list,
+                confidence,
+            },
+        )
+        .collect();
+
+    StitchResult {
+        image: scroll_screenshot_service.export(),
+        frames,
+        sticky_bands: scroll_screenshot_service.sticky_bands,
+    }
+}
+
+/// 单帧的拼接状态、相对首帧的位置、所在的图片列表和匹配置信度
+type StitchFramePosition = (
+    StitchFrameStatus,
+    Option<ScrollOffset>,
+    Option<ScrollImageList>,
+    Option<f32>,
+);
+
+/// 使用给定的拼接服务按顺序拼接帧，返回每一帧的位置
+fn stitch_frames_with_service(
+    scroll_screenshot_service: &mut ScrollScreenshotService,
+    frames: impl Iterator<Item = DynamicImage>,
+    direction: ScrollDirection,
+) -> Vec<StitchFramePosition> {
+    // 帧的左上角相对首帧的位置
+    let mut frame_positions: Vec<StitchFramePosition> = vec![];
+    let mut scroll_image_list = ScrollImageList::Bottom;
+    // 首帧尺寸，去除固定区域后服务中记录的尺寸会变小，所以单独记录
+    let mut frame_size: Option<(u32, u32)> = None;
+
+    for frame in frames {
+        match frame_size {
+            Some(frame_size) if frame_size != (frame.width(), frame.height()) => {
+                frame_positions.push((StitchFrameStatus::SizeMismatch, None, None, None));
+                continue;
+            }
+            Some(_) => {}
+            None => frame_size = Some((frame.width(), frame.height())),
+        }
+
+        // 没有滚动方向的提示，try_rollback 为 true 时由拼接服务在当前方向匹配失败后尝试另一个方向
+        let (handle_result, is_origin, result_scroll_image_list) =
+            scroll_screenshot_service.handle_image(frame, scroll_image_list);
+
+        if is_origin {
+            frame_positions.push((StitchFrameStatus::Unchanged, None, None, None));
+            continue;
+        }
+
+        let (edge_position, image_list) = match handle_result {
+            Some(result) => result,
+            None => {
+                frame_positions.push((StitchFrameStatus::NoMatch, None, None, None));
+                continue;
+            }
+        };
+
+        let frame_position = if direction == ScrollDirection::Free {
+            match scroll_screenshot_service.free_image_positions.last() {
+                Some(position) => *position,
+                None => {
+                    frame_positions.push((StitchFrameStatus::NoMatch, None, None, None));
+                    continue;
+                }
+            }
+        } else {
+            let image_scroll_side_size = scroll_screenshot_service.image_scroll_side_size;
+            let frame_position = if edge_position >= 0 {
+                edge_position - image_scroll_side_size
+            } else {
+                edge_position
+            };
+
+            if direction == ScrollDirection::Vertical {
+                ScrollOffset::new(0, frame_position)
+            } else {
+                ScrollOffset::new(frame_position, 0)
+            }
+        };
+
+        let confidence = Some(scroll_screenshot_service.match_confidence);
+        match image_list {
+            Some(image_list) => {
+                // 下一帧优先沿着当前方向匹配
+                scroll_image_list = result_scroll_image_list;
+                frame_positions.push((
+                    StitchFrameStatus::Stitched,
+                    Some(frame_position),
+                    Some(image_list),
+                    confidence,
+                ));
+            }
+            None => {
+                frame_positions.push((
+                    StitchFrameStatus::NoNewContent,
+                    Some(frame_position),
+                    None,
+                    confidence,
+                ));
+            }
+        }
+    }
+
+    frame_positions
+}
+
+/// 从磁盘按顺序读取帧并拼接
+pub fn stitch_frame_files<P>(
+    frame_paths: &[P],
+    direction: ScrollDirection,
+    params: ScrollScreenshotParams,
+) -> Result<StitchResult, String>
+where
+    P: AsRef<Path>,
+{
+    let mut frames = Vec::with_capacity(frame_paths.len());
+    for frame_path in frame_paths {
+        let frame_path = frame_path.as_ref();
+        match image::open(frame_path) {
+            Ok(frame) => frames.push(frame),
+            Err(e) => {
+                return Err(format!(
+                    "[stitch_frame_files] Failed to open frame {}: {}",
+                    frame_path.display(),
+                    e
+                ));
+            }
+        }
+    }
+
+    Ok(stitch_frames(frames, direction, params))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::GenericImageView;
+
+    use super::*;
+    use crate::scroll_screenshot_synthetic::{
+        SyntheticFixture, SyntheticScroll, compare_images, generate_source_image, synthetic_params,
+    };
+
+    /// 拼接模拟的帧，检查每一帧的偏移，并和源图片逐像素对比
+    fn assert_stitch(fixture: SyntheticFixture, tolerance: u8) -> StitchResult {
+        let scroll = &fixture.scroll;
+        let source = &fixture.source;
+        let positions = fixture
+            .frames
+            .iter()
+            .map(|frame| frame.position as i32)
+            .collect::<Vec<i32>>();
+
+        let result = stitch_frames(fixture.frame_images(), scroll.direction, fixture.params);
+
+        assert_eq!(result.frames.len(), positions.len());
+
+        let stitched_positions = result
+            .frames
+            .iter()
+            .zip(positions.iter())
+            .filter(|(frame, _)| frame.status == StitchFrameStatus::Stitched)
+            .map(|(_, position)| *position)
+            .collect::<Vec<i32>>();
+        let min_position = *stitched_positions.iter().min().unwrap();
+        let max_position = *stitched_positions.iter().max().unwrap();
+
+        for (index, (frame, position)) in result.frames.iter().zip(positions.iter()).enumerate() {
+            assert_ne!(
+                frame.status,
+                StitchFrameStatus::NoMatch,
+                "frame {} was not matched",
+                index
+            );
+
+            if frame.status == StitchFrameStatus::Stitched {
+                let confidence = frame.confidence.unwrap();
+                assert!(
+                    confidence > 0.5,
+                    "frame {} confidence too low: {}",
+                    index,
+                    confidence
+                );
+            }
+
+            if let Some(offset) = frame.offset {
+                let expected_offset = if scroll.direction == ScrollDirection::Vertical {
+                    ScrollOffset::new(0, position - min_position)
+                } else {
+                    ScrollOffset::new(position - min_position, 0)
+                };
+                assert_eq!(offset, expected_offset, "frame {} offset mismatch", index);
+            }
+        }
+
+        let image = result.image.as_ref().unwrap();
+        let scroll_size = (max_position - min_position) as u32
+            + if scroll.direction == ScrollDirection::Vertical {
+                scroll.frame_height
+            } else {
+                scroll.frame_width
+            };
+        let expected = if scroll.direction == ScrollDirection::Vertical {
+            source.view(0, min_position as u32, scroll.frame_width, scroll_size)
+        } else {
+            source.view(min_position as u32, 0, scroll_size, scroll.frame_height)
+        };
+        let mut expected = expected.to_image();
+
+        // 固定区域只保留一份，位于图片首尾
+        let (header, footer) = scroll.sticky_bars();
+        if let Some(header) = header {
+            image::imageops::replace(&mut expected, &header, 0, 0);
+        }
+        if let Some(footer) = footer {
+            let (footer_x, footer_y) = if scroll.direction == ScrollDirection::Vertical {
+                (0, (expected.height() - footer.height()) as i64)
+            } else {
+                ((expected.width() - footer.width()) as i64, 0)
+            };
+            image::imageops::replace(&mut expected, &footer, footer_x, footer_y);
+        }
+        let expected = DynamicImage::ImageRgb8(expected);
+
+        let report = compare_images(image, &expected, tolerance).unwrap();
+        assert!(report.is_match(), "exported image mismatch: {}", report);
+
+        result
+    }
+
+    #[test]
+    fn test_stitch_vertical_forward() {
+        let result = assert_stitch(
+            SyntheticFixture::new(
+                ScrollDirection::Vertical,
+                200,
+                240,
+                &[60, 45, 80, 30, 70, 100],
+                1,
+            ),
+            0,
+        );
+        assert!(
+            result
+                .frames
+                .iter()
+                .all(|frame| frame.status == StitchFrameStatus::Stitched)
+        );
+    }
+
+    #[test]
+    fn test_stitch_vertical_backward() {
+        let scroll = SyntheticScroll {
+            start_position: 400,
+            scroll_deltas: vec![-50, -70, -60, -90],
+            seed: 2,
+            ..SyntheticScroll::new(ScrollDirection::Vertical, 200, 240)
+        };
+
+        let fixture = SyntheticFixture::from_scroll(scroll);
+        let frame_images = fixture.frame_images();
+        let params = fixture.params;
+
+        let result = assert_stitch(fixture, 0);
+        assert!(
+            result.frames[1..]
+                .iter()
+                .all(|frame| frame.image_list == Some(ScrollImageList::Top))
+        );
+
+        // 不回滚时只沿着下方匹配
+        let result = stitch_frames(
+            frame_images,
+            ScrollDirection::Vertical,
+            ScrollScreenshotParams {
+                try_rollback: false,
+                ..params
+            },
+        );
+        assert!(
+            result.frames[1..]
+                .iter()
+                .all(|frame| frame.image_list != Some(ScrollImageList::Top))
+        );
+    }
+
+    #[test]
+    fn test_stitch_vertical_both_directions() {
+        let scroll = SyntheticScroll {
+            start_position: 200,
+            scroll_deltas: vec![70, 60, -150, -80, -60],
+            seed: 3,
+            ..SyntheticScroll::new(ScrollDirection::Vertical, 200, 240)
+        };
+
+        assert_stitch(SyntheticFixture::from_scroll(scroll), 0);
+    }
+
+    #[test]
+    fn test_stitch_vertical_noise() {
+        let scroll = SyntheticScroll {
+            scroll_deltas: vec![50, 60, 70, 40],
+            noise: 3,
+            seed: 4,
+            ..SyntheticScroll::new(ScrollDirection::Vertical, 200, 240)
+        };
+
+        assert_stitch(SyntheticFixture::from_scroll(scroll), 3);
+    }
+
+    #[test]
+    fn test_stitch_horizontal_forward() {
+        assert_stitch(
+            SyntheticFixture::new(ScrollDirection::Horizontal, 240, 200, &[60, 45, 80, 30], 5),
+            0,
+        );
+    }
+
+    #[test]
+    fn test_stitch_horizontal_backward() {
+        let scroll = SyntheticScroll {
+            start_position: 300,
+            scroll_deltas: vec![-60, -50, -80],
+            seed: 6,
+            ..SyntheticScroll::new(ScrollDirection::Horizontal, 240, 200)
+        };
+
+        assert_stitch(SyntheticFixture::from_scroll(scroll), 0);
+    }
+
+    #[test]
+    fn test_stitch_unchanged_frame() {
+        let result = assert_stitch(
+            SyntheticFixture::new(ScrollDirection::Vertical, 200, 240, &[60, 0, 60], 7),
+            0,
+        );
+        assert_eq!(result.frames[2].status, StitchFrameStatus::Unchanged);
+    }
+
+    #[test]
+    fn test_stitch_sticky_bars() {
+        let scroll = SyntheticScroll {
+            scroll_deltas: vec![50, 50, 70, 40],
+            sticky_header_size: 24,
+            sticky_footer_size: 16,
+            seed: 8,
+            ..SyntheticScroll::new(ScrollDirection::Vertical, 200, 240)
+        };
+        let mut fixture = SyntheticFixture::from_scroll(scroll);
+        fixture.params.detect_sticky_bands = true;
+
+        let result = assert_stitch(fixture, 0);
+        assert_eq!(
+            result.sticky_bands,
+            Some(ScrollStickyBands {
+                start_size: 24,
+                end_size: 16,
+            })
+        );
+    }
+
+    #[test]
+    fn test_stitch_sticky_bars_horizontal() {
+        let scroll = SyntheticScroll {
+            scroll_deltas: vec![60, 50, 40],
+            sticky_header_size: 20,
+            seed: 10,
+            ..SyntheticScroll::new(ScrollDirection::Horizontal, 240, 200)
+        };
+        let mut fixture = SyntheticFixture::from_scroll(scroll);
+        fixture.params.detect_sticky_bands = true;
+
+        let result = assert_stitch(fixture, 0);
+        assert_eq!(
+            result.sticky_bands,
+            Some(ScrollStickyBands {
+                start_size: 20,
+                end_size: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_stitch_without_sticky_bars() {
+        let mut fixture = SyntheticFixture::new(ScrollDirection::Vertical, 200, 240, &[60, 60], 11);
+        fixture.params.detect_sticky_bands = true;
+
+        let result = assert_stitch(fixture, 0);
+        assert_eq!(result.sticky_bands, Some(ScrollStickyBands::default()));
+    }
+
+    #[test]
+    fn test_stitch_blank_margin_is_not_sticky() {
+        // 页面顶部有空白边距，前两帧顶部的空白区域相同，但之后的帧不同
+        let mut fixture =
+            SyntheticFixture::new(ScrollDirection::Vertical, 200, 240, &[40, 60, 50], 14);
+        fixture.params.detect_sticky_bands = true;
+        for y in 0..80 {
+            for x in 0..200 {
+                fixture.source.put_pixel(x, y, image::Rgb([255, 255, 255]));
+            }
+        }
+        fixture.frames = fixture.scroll.slice(&fixture.source);
+
+        let result = assert_stitch(fixture, 0);
+        assert_eq!(result.sticky_bands, Some(ScrollStickyBands::default()));
+    }
+
+    /// 按照给定的位置从源图片中切出帧，自由方向拼接后和源图片对比
+    /// 帧的尺寸为 160x120，sample_size 为 None 时使用默认的采样尺寸
+    fn assert_stitch_free(sample_size: Option<u32>, positions: &[(u32, u32)]) {
+        let (frame_width, frame_height) = (160, 120);
+        let default_params = ScrollScreenshotParams::default_for_frame(
+            ScrollDirection::Free,
+            frame_width,
+            frame_height,
+        );
+        let source = generate_source_image(480, 360, 12);
+        let frames = positions
+            .iter()
+            .map(|(x, y)| {
+                DynamicImage::ImageRgb8(source.view(*x, *y, frame_width, frame_height).to_image())
+            })
+            .collect::<Vec<DynamicImage>>();
+
+        let result = stitch_frames(
+            frames,
+            ScrollDirection::Free,
+            ScrollScreenshotParams {
+                min_sample_size: sample_size.unwrap_or(default_params.min_sample_size),
+                max_sample_size: sample_size.unwrap_or(default_params.max_sample_size),
+                ..default_params
+            },
+        );
+
+        let min_x = positions.iter().map(|(x, _)| *x).min().unwrap();
+        let min_y = positions.iter().map(|(_, y)| *y).min().unwrap();
+        for (index, (frame, (x, y))) in result.frames.iter().zip(positions.iter()).enumerate() {
+            assert_eq!(
+                frame.status,
+                StitchFrameStatus::Stitched,
+                "frame {} was not stitched",
+                index
+            );
+            assert_eq!(
+                frame.offset,
+                Some(ScrollOffset::new((x - min_x) as i32, (y - min_y) as i32)),
+                "frame {} offset mismatch",
+                index
+            );
+        }
+
+        let image = result.image.unwrap().to_rgba8();
+        let max_x = positions.iter().map(|(x, _)| *x).max().unwrap() + frame_width;
+        let max_y = positions.iter().map(|(_, y)| *y).max().unwrap() + frame_height;
+        assert_eq!(image.dimensions(), (max_x - min_x, max_y - min_y));
+
+        // 被帧覆盖的区域和源图片一致，其余区域透明
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let source_x = x + min_x;
+            let source_y = y + min_y;
+            let covered = positions.iter().any(|(frame_x, frame_y)| {
+                (*frame_x..frame_x + frame_width).contains(&source_x)
+                    && (*frame_y..frame_y + frame_height).contains(&source_y)
+            });
+
+            if covered {
+                let source_pixel = source.get_pixel(source_x, source_y);
+                assert_eq!(
+                    pixel.0,
+                    [source_pixel[0], source_pixel[1], source_pixel[2], 255],
+                    "pixel ({}, {}) mismatch",
+                    x,
+                    y
+                );
+            } else {
+                assert_eq!(pixel[3], 0, "pixel ({}, {}) should be transparent", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stitch_free() {
+        assert_stitch_free(
+            Some(160),
+            &[(100, 100), (160, 130), (220, 100), (170, 40), (90, 60)],
+        );
+    }
+
+    #[test]
+    fn test_stitch_free_scaled() {
+        // 缩放后匹配，偏移需要换算回原尺寸，缩放后不是整数像素的平移也能匹配
+        let positions = [(120, 120), (175, 141), (233, 97), (161, 63)];
+        assert_stitch_free(None, &positions);
+        assert_stitch_free(Some(80), &positions);
+    }
+
+    #[test]
+    fn test_stitch_repeated_content() {
+        // 内容按固定周期重复，任何一个周期的偏移都能匹配，不能给出高置信度
+        let tile = generate_source_image(200, 60, 13);
+        let mut source = image::RgbImage::new(200, 600);
+        for y in (0..600).step_by(60) {
+            image::imageops::replace(&mut source, &tile, 0, y as i64);
+        }
+
+        let frames = [0, 20, 40, 60]
+            .iter()
+            .map(|y| DynamicImage::ImageRgb8(source.view(0, *y, 200, 240).to_image()))
+            .collect::<Vec<DynamicImage>>();
+
+        let result = stitch_frames(
+            frames,
+            ScrollDirection::Vertical,
+            synthetic_params(ScrollDirection::Vertical, 200, 240),
+        );
+
+        for (index, frame) in result.frames.iter().enumerate().skip(1) {
+            if let Some(confidence) = frame.confidence {
+                assert!(
+                    confidence < 0.5,
+                    "frame {} confidence too high: {}",
+                    index,
+                    confidence
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_stitch_size_mismatch() {
+        let source = DynamicImage::ImageRgb8(generate_source_image(200, 600, 9));
+        let frames = vec![
+            source.crop_imm(0, 0, 200, 240),
+            source.crop_imm(0, 60, 200, 200),
+        ];
+
+        let result = stitch_frames(
+            frames,
+            ScrollDirection::Vertical,
+            ScrollScreenshotParams::default_for_frame(ScrollDirection::Vertical, 200, 240),
+        );
+
+        assert_eq!(result.frames[1].status, StitchFrameStatus::SizeMismatch);
+    }
+}
@@ -0,0 +1,389 @@
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+use std::fmt;
+
+use crate::scroll_screenshot_service::{
+    ScrollDirection, ScrollImageList, ScrollScreenshotParams, ScrollScreenshotService,
+};
+
+/// 简单的伪随机数生成器（xorshift64*），保证同一个种子生成的图片一致
+pub struct SyntheticRng(u64);
+
+impl SyntheticRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// 生成 [min, max] 范围内的整数
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+
+        min + (self.next_u64() % (max - min + 1) as u64) as i32
+    }
+}
+
+/// 生成一张用于测试的长图
+///
+/// 白色背景上随机分布类似文字的色块，特征点足够多且不重复
+pub fn generate_source_image(width: u32, height: u32, seed: u64) -> RgbImage {
+    let mut rng = SyntheticRng::new(seed);
+    let mut image = RgbImage::from_pixel(width, height, Rgb([255, 255, 255]));
+
+    let block_count = (width as u64 * height as u64 / 160) as usize;
+    for _ in 0..block_count {
+        let block_width = rng.range(2, 18) as u32;
+        let block_height = rng.range(2, 12) as u32;
+        let x = rng.range(0, width as i32 - 1) as u32;
+        let y = rng.range(0, height as i32 - 1) as u32;
+        let color = Rgb([
+            rng.range(0, 160) as u8,
+            rng.range(0, 160) as u8,
+            rng.range(0, 160) as u8,
+        ]);
+
+        for block_y in y..(y + block_height).min(height) {
+            for block_x in x..(x + block_width).min(width) {
+                image.put_pixel(block_x, block_y, color);
+            }
+        }
+    }
+
+    image
+}
+
+/// 滚动截图的模拟参数
+#[derive(Debug, Clone)]
+pub struct SyntheticScroll {
+    pub direction: ScrollDirection,
+    /// 帧宽度
+    pub frame_width: u32,
+    /// 帧高度
+    pub frame_height: u32,
+    /// 首帧在源图片滚动方向上的起始位置
+    pub start_position: u32,
+    /// 每一帧相对上一帧的滚动量，正数为向下（右）滚动
+    pub scroll_deltas: Vec<i32>,
+    /// 每个通道叠加的随机噪声幅度
+    pub noise: u8,
+    /// 固定在帧顶部（左侧）的标题栏尺寸
+    pub sticky_header_size: u32,
+    /// 固定在帧底部（右侧）的底栏尺寸
+    pub sticky_footer_size: u32,
+    pub seed: u64,
+}
+
+#[derive(Clone)]
+pub struct SyntheticFrame {
+    pub image: DynamicImage,
+    /// 帧在源图片滚动方向上的起始位置
+    pub position: u32,
+}
+
+impl SyntheticScroll {
+    pub fn new(direction: ScrollDirection, frame_width: u32, frame_height: u32) -> Self {
+        Self {
+            direction,
+            frame_width,
+            frame_height,
+            start_position: 0,
+            scroll_deltas: vec![],
+            noise: 0,
+            sticky_header_size: 0,
+            sticky_footer_size: 0,
+            seed: 1,
+        }
+    }
+
+    fn scroll_side_size(&self) -> u32 {
+        if self.direction == ScrollDirection::Vertical {
+            self.frame_height
+        } else {
+            self.frame_width
+        }
+    }
+
+    /// 每一帧在源图片滚动方向上的起始位置
+    pub fn frame_positions(&self) -> Vec<u32> {
+        let mut positions = Vec::with_capacity(self.scroll_deltas.len() + 1);
+        let mut position = self.start_position as i32;
+        positions.push(self.start_position);
+
+        for delta in &self.scroll_deltas {
+            position = (position + delta).max(0);
+            positions.push(position as u32);
+        }
+
+        positions
+    }
+
+    /// 能容纳所有帧的源图片尺寸
+    pub fn source_size(&self) -> (u32, u32) {
+        let max_position = self.frame_positions().into_iter().max().unwrap_or(0);
+        let scroll_size = max_position + self.scroll_side_size();
+
+        if self.direction == ScrollDirection::Vertical {
+            (self.frame_width, scroll_size)
+        } else {
+            (scroll_size, self.frame_height)
+        }
+    }
+
+    /// 按照滚动量从源图片中切出每一帧
+    pub fn slice(&self, source: &RgbImage) -> Vec<SyntheticFrame> {
+        let mut rng = SyntheticRng::new(self.seed.wrapping_add(1));
+        let (header, footer) = self.sticky_bars();
+
+        self.frame_positions()
+            .into_iter()
+            .map(|position| {
+                let (x, y) = if self.direction == ScrollDirection::Vertical {
+                    (0, position)
+                } else {
+                    (position, 0)
+                };
+
+                let mut frame = source
+                    .view(x, y, self.frame_width, self.frame_height)
+                    .to_image();
+
+                if let Some(header) = &header {
+                    image::imageops::replace(&mut frame, header, 0, 0);
+                }
+                if let Some(footer) = &footer {
+                    let (footer_x, footer_y) = if self.direction == ScrollDirection::Vertical {
+                        (0, (self.frame_height - footer.height()) as i64)
+                    } else {
+                        ((self.frame_width - footer.width()) as i64, 0)
+                    };
+                    image::imageops::replace(&mut frame, footer, footer_x, footer_y);
+                }
+
+                if self.noise > 0 {
+                    let noise = self.noise as i32;
+                    frame.pixels_mut().for_each(|pixel| {
+                        for channel in pixel.0.iter_mut() {
+                            *channel =
+                                (*channel as i32 + rng.range(-noise, noise)).clamp(0, 255) as u8;
+                        }
+                    });
+                }
+
+                SyntheticFrame {
+                    image: DynamicImage::ImageRgb8(frame),
+                    position,
+                }
+            })
+            .collect()
+    }
+
+    /// 生成固定的标题栏和底栏，内容和源图片无关
+    pub fn sticky_bars(&self) -> (Option<RgbImage>, Option<RgbImage>) {
+        let bar = |size: u32, seed: u64| {
+            if size == 0 {
+                return None;
+            }
+
+            let (width, height) = if self.direction == ScrollDirection::Vertical {
+                (self.frame_width, size)
+            } else {
+                (size, self.frame_height)
+            };
+
+            let mut bar = generate_source_image(width, height, seed);
+            // 加深背景，和源图片区分开
+            bar.pixels_mut().for_each(|pixel| {
+                if pixel.0 == [255, 255, 255] {
+                    pixel.0 = [32, 48, 64];
+                }
+            });
+
+            Some(bar)
+        };
+
+        (
+            bar(self.sticky_header_size, self.seed.wrapping_add(2)),
+            bar(self.sticky_footer_size, self.seed.wrapping_add(3)),
+        )
+    }
+}
+
+/// 采样尺寸和帧尺寸一致的拼接参数，匹配时不缩放
+pub fn synthetic_params(
+    direction: ScrollDirection,
+    frame_width: u32,
+    frame_height: u32,
+) -> ScrollScreenshotParams {
+    let sample_size = if direction == ScrollDirection::Vertical {
+        frame_width
+    } else {
+        frame_height
+    };
+
+    ScrollScreenshotParams {
+        min_sample_size: sample_size,
+        max_sample_size: sample_size,
+        ..ScrollScreenshotParams::default_for_frame(direction, frame_width, frame_height)
+    }
+}
+
+/// 测试共用的模拟数据：源图片、切出的帧和不缩放的拼接参数
+pub struct SyntheticFixture {
+    pub scroll: SyntheticScroll,
+    pub source: RgbImage,
+    pub frames: Vec<SyntheticFrame>,
+    /// 见 synthetic_params
+    pub params: ScrollScreenshotParams,
+}
+
+impl SyntheticFixture {
+    pub fn new(
+        direction: ScrollDirection,
+        frame_width: u32,
+        frame_height: u32,
+        scroll_deltas: &[i32],
+        seed: u64,
+    ) -> Self {
+        let mut scroll = SyntheticScroll::new(direction, frame_width, frame_height);
+        scroll.scroll_deltas = scroll_deltas.to_vec();
+        scroll.seed = seed;
+
+        Self::from_scroll(scroll)
+    }
+
+    pub fn from_scroll(scroll: SyntheticScroll) -> Self {
+        let (source_width, source_height) = scroll.source_size();
+        let source = generate_source_image(source_width, source_height, scroll.seed);
+        let frames = scroll.slice(&source);
+
+        let params = synthetic_params(scroll.direction, scroll.frame_width, scroll.frame_height);
+
+        Self {
+            scroll,
+            source,
+            frames,
+            params,
+        }
+    }
+
+    pub fn frame_images(&self) -> Vec<DynamicImage> {
+        self.frames
+            .iter()
+            .map(|frame| frame.image.clone())
+            .collect()
+    }
+
+    /// 使用给定的参数依次拼接所有帧
+    pub fn stitch_service(&self, params: ScrollScreenshotParams) -> ScrollScreenshotService {
+        let mut service = ScrollScreenshotService::new();
+        service.init_with_params(self.scroll.direction, params);
+        for frame in self.frames.iter() {
+            service.handle_image(frame.image.clone(), ScrollImageList::Bottom);
+        }
+
+        service
+    }
+}
+
+/// 两张图片的逐像素对比结果
+#[derive(Debug, Clone, Copy)]
+pub struct ImageDiffReport {
+    pub width: u32,
+    pub height: u32,
+    /// 允许的最大通道差值
+    pub tolerance: u8,
+    /// 最大通道差值
+    pub max_diff: u8,
+    /// 平均通道差值
+    pub mean_diff: f64,
+    /// 差值超过 tolerance 的像素数量
+    pub mismatched_pixels: usize,
+    /// 第一个超过 tolerance 的像素位置
+    pub first_mismatch: Option<(u32, u32)>,
+}
+
+impl ImageDiffReport {
+    pub fn is_match(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+impl fmt::Display for ImageDiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} tolerance: {} max_diff: {} mean_diff: {:.3} mismatched_pixels: {} ({:.3}%) first_mismatch: {:?}",
+            self.width,
+            self.height,
+            self.tolerance,
+            self.max_diff,
+            self.mean_diff,
+            self.mismatched_pixels,
+            self.mismatched_pixels as f64 * 100.0
+                / (self.width as f64 * self.height as f64).max(1.0),
+            self.first_mismatch,
+        )
+    }
+}
+
+/// 逐像素对比两张图片，尺寸不一致时返回错误
+pub fn compare_images(
+    actual: &DynamicImage,
+    expected: &DynamicImage,
+    tolerance: u8,
+) -> Result<ImageDiffReport, String> {
+    if actual.dimensions() != expected.dimensions() {
+        return Err(format!(
+            "[compare_images] Image size mismatch: actual {:?} expected {:?}",
+            actual.dimensions(),
+            expected.dimensions()
+        ));
+    }
+
+    let actual = actual.to_rgb8();
+    let expected = expected.to_rgb8();
+
+    let mut max_diff = 0u8;
+    let mut diff_sum = 0u64;
+    let mut mismatched_pixels = 0;
+    let mut first_mismatch = None;
+
+    for (x, y, actual_pixel) in actual.enumerate_pixels() {
+        let expected_pixel = expected.get_pixel(x, y);
+
+        let pixel_diff = actual_pixel
+            .0
+            .iter()
+            .zip(expected_pixel.0.iter())
+            .map(|(a, b)| a.abs_diff(*b))
+            .inspect(|diff| diff_sum += *diff as u64)
+            .max()
+            .unwrap_or(0);
+
+        max_diff = max_diff.max(pixel_diff);
+        if pixel_diff > tolerance {
+            mismatched_pixels += 1;
+            if first_mismatch.is_none() {
+                first_mismatch = Some((x, y));
+            }
+        }
+    }
+
+    let (width, height) = actual.dimensions();
+    Ok(ImageDiffReport {
+        width,
+        height,
+        tolerance,
+        max_diff,
+        mean_diff: diff_sum as f64 / (width as f64 * height as f64 * 3.0).max(1.0),
+        mismatched_pixels,
+        first_mismatch,
+    })
+}
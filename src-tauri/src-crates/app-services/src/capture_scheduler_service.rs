@@ -0,0 +1,673 @@
+use chrono::{Datelike, Local, NaiveDateTime, Timelike};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use snow_shot_app_shared::ElementRect;
+use snow_shot_app_utils::image_encoder::ImageEncoder;
+use snow_shot_app_utils::monitor_info::MonitorList;
+use snow_shot_app_utils::save_image_to_file;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::time::Instant;
+
+pub const CAPTURE_SCHEDULER_COUNTDOWN_EMIT_KEY: &str = "capture-scheduler:countdown";
+pub const CAPTURE_SCHEDULER_PROGRESS_EMIT_KEY: &str = "capture-scheduler:progress";
+
+/// 等待时检查暂停和取消的间隔
+const CAPTURE_SCHEDULER_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 定时截图的对象
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CaptureScheduleTarget {
+    /// 截取桌面的区域，物理像素坐标
+    Region { region: ElementRect },
+    /// 截取当前的焦点窗口
+    FocusedWindow,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CaptureScheduleOptions {
+    pub target: CaptureScheduleTarget,
+    /// 第一次截图前的倒计时，秒
+    #[serde(default)]
+    pub delay_seconds: u32,
+    /// 重复截图的间隔，秒，为 0 时只截图一次
+    #[serde(default)]
+    pub interval_seconds: f64,
+    /// 重复截图的总时长，秒，和 max_count 都未指定时一直截图直到取消
+    #[serde(default)]
+    pub duration_seconds: Option<f64>,
+    /// 最多截图的次数
+    #[serde(default)]
+    pub max_count: Option<u32>,
+    /// 保存的文件路径，和截图文件名一样使用 dayjs 格式的时间变量，如 {{YYYY-MM-DD_HH-mm-ss}}
+    pub file_path_template: String,
+    /// 未指定时根据文件扩展名选择格式
+    #[serde(default)]
+    pub encoder: Option<ImageEncoder>,
+    /// 和上一次保存的截图相同时跳过
+    #[serde(default)]
+    pub skip_unchanged: bool,
+    /// 每个颜色通道的平均差异不超过该值时认为相同，0 表示完全相同
+    #[serde(default)]
+    pub unchanged_threshold: f32,
+}
+
+impl CaptureScheduleOptions {
+    /// 截图的总次数，None 表示不限制
+    pub fn frame_count(&self) -> Option<u32> {
+        if self.interval_seconds <= 0.0 {
+            return Some(1);
+        }
+
+        let duration_count = self
+            .duration_seconds
+            .map(|duration| (duration.max(0.0) / self.interval_seconds).floor() as u32 + 1);
+
+        match (duration_count, self.max_count) {
+            (Some(duration_count), Some(max_count)) => Some(duration_count.min(max_count)),
+            (duration_count, max_count) => duration_count.or(max_count),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureScheduleFrameStatus {
+    Saved,
+    /// 和上一次保存的截图相同
+    Skipped,
+    Failed,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CaptureScheduleCountdown {
+    pub remaining_seconds: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CaptureScheduleProgress {
+    /// 第几次截图，从 1 开始
+    pub index: u32,
+    pub total: Option<u32>,
+    pub status: CaptureScheduleFrameStatus,
+    pub file_path: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureScheduleStopReason {
+    Completed,
+    Cancelled,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CaptureScheduleResult {
+    pub stop_reason: CaptureScheduleStopReason,
+    pub saved_count: u32,
+    pub skipped_count: u32,
+    pub failed_count: u32,
+    pub file_paths: Vec<String>,
+}
+
+/// 定时截图任务的暂停和取消标记
+#[derive(Debug, Default)]
+pub struct CaptureScheduleControl {
+    cancelled: AtomicBool,
+    paused: AtomicBool,
+}
+
+impl CaptureScheduleControl {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// 等待到 deadline，暂停的时间顺延，返回暂停的总时长，取消时返回 None
+    pub async fn wait_until(&self, deadline: Instant) -> Option<Duration> {
+        let mut paused_duration = Duration::ZERO;
+        loop {
+            if self.is_cancelled() {
+                return None;
+            }
+
+            if self.is_paused() {
+                let pause_start = Instant::now();
+                tokio::time::sleep(CAPTURE_SCHEDULER_POLL_INTERVAL).await;
+                paused_duration += pause_start.elapsed();
+                continue;
+            }
+
+            let now = Instant::now();
+            let deadline = deadline + paused_duration;
+            if now >= deadline {
+                return Some(paused_duration);
+            }
+
+            tokio::time::sleep((deadline - now).min(CAPTURE_SCHEDULER_POLL_INTERVAL)).await;
+        }
+    }
+}
+
+/// 定时截图的时钟，每次截图的时间点由开始时间和序号计算，截图耗时不会累积
+///
+/// 暂停的时间不计入，恢复后之后的时间点一起顺延
+struct CaptureScheduleClock {
+    start: Instant,
+    paused_duration: Duration,
+}
+
+impl CaptureScheduleClock {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            paused_duration: Duration::ZERO,
+        }
+    }
+
+    /// 开始后经过的时间，不包含暂停的时间
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed().saturating_sub(self.paused_duration)
+    }
+
+    /// 等待到开始后的 offset，已经超过时立即返回，取消时返回 false
+    async fn wait_until(&mut self, control: &CaptureScheduleControl, offset: Duration) -> bool {
+        match control
+            .wait_until(self.start + self.paused_duration + offset)
+            .await
+        {
+            Some(paused_duration) => {
+                self.paused_duration += paused_duration;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/**
+ * 记录定时截图的状态，同一时间只允许一个定时截图任务
+ */
+#[derive(Default)]
+pub struct CaptureSchedulerService {
+    control: Arc<CaptureScheduleControl>,
+    running: bool,
+}
+
+impl CaptureSchedulerService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 开始定时截图，返回任务的控制标记
+    pub fn start(&mut self) -> Result<Arc<CaptureScheduleControl>, String> {
+        if self.running {
+            return Err(String::from(
+                "[CaptureSchedulerService::start] Capture schedule is already running",
+            ));
+        }
+
+        self.running = true;
+        self.control = Arc::new(CaptureScheduleControl::default());
+        Ok(self.control.clone())
+    }
+
+    pub fn finish(&mut self) {
+        self.running = false;
+    }
+
+    pub fn cancel(&self) {
+        self.control.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn pause(&self) {
+        self.control.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.control.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+/// dayjs 格式中使用的字符，和前端生成文件名时的规则一致
+fn is_date_template(content: &str) -> bool {
+    !content.is_empty()
+        && content.chars().all(|c| {
+            matches!(
+                c,
+                'Y' | 'M' | 'D' | 'H' | 'm' | 's' | 'A' | 'a' | '-' | '_' | ':' | '/' | '.'
+            ) || c.is_whitespace()
+        })
+}
+
+/// 按照 dayjs 的格式输出时间，不支持的标记原样输出
+fn format_date_template(template: &str, date_time: &NaiveDateTime) -> String {
+    const MONTH_NAMES: [&str; 12] = [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ];
+
+    let chars = template.chars().collect::<Vec<char>>();
+    let mut result = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        // 和 dayjs 一样，连续的相同字符按照最长的标记匹配
+        let max_len = match c {
+            'Y' | 'M' => 4,
+            'D' | 'H' | 'm' | 's' => 2,
+            _ => 1,
+        };
+        let len = chars[index..]
+            .iter()
+            .take(max_len)
+            .take_while(|token_char| **token_char == c)
+            .count();
+        index += len;
+
+        let month_name = MONTH_NAMES[date_time.month0() as usize];
+        let is_pm = date_time.hour() >= 12;
+        let text = match (c, len) {
+            ('Y', 4) => format!("{:04}", date_time.year()),
+            ('Y', 2) => format!("{:02}", date_time.year().rem_euclid(100)),
+            ('M', 1) => date_time.month().to_string(),
+            ('M', 2) => format!("{:02}", date_time.month()),
+            ('M', 3) => month_name[..3].to_string(),
+            ('M', 4) => month_name.to_string(),
+            ('D', 1) => date_time.day().to_string(),
+            ('D', 2) => format!("{:02}", date_time.day()),
+            ('H', 1) => date_time.hour().to_string(),
+            ('H', 2) => format!("{:02}", date_time.hour()),
+            ('m', 1) => date_time.minute().to_string(),
+            ('m', 2) => format!("{:02}", date_time.minute()),
+            ('s', 1) => date_time.second().to_string(),
+            ('s', 2) => format!("{:02}", date_time.second()),
+            ('A', _) => String::from(if is_pm { "PM" } else { "AM" }),
+            ('a', _) => String::from(if is_pm { "pm" } else { "am" }),
+            _ => c.to_string().repeat(len),
+        };
+        result.push_str(&text);
+    }
+
+    result
+}
+
+/// 替换模板中 {{}} 包裹的时间变量，和前端的 generateImageFileName 一致
+pub fn render_file_name_template(template: &str, date_time: &NaiveDateTime) -> String {
+    let mut result = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start + 2..].find("}}") {
+            Some(end) => start + 2 + end,
+            None => break,
+        };
+
+        result.push_str(&rest[..start]);
+        let content = &rest[start + 2..end];
+        if is_date_template(content) {
+            result.push_str(&format_date_template(content, date_time));
+        } else {
+            result.push_str(&rest[start..end + 2]);
+        }
+        rest = &rest[end + 2..];
+    }
+    result.push_str(rest);
+
+    result
+}
+
+/// 生成截图的保存路径
+///
+/// 模板生成的路径和上一次截图相同时（如模板没有时间变量或一秒内多次截图）在扩展名前添加序号，避免覆盖
+pub fn render_capture_file_path(file_path: &str, index: u32, duplicated: bool) -> PathBuf {
+    let mut file_path = PathBuf::from(file_path);
+    if !duplicated {
+        return file_path;
+    }
+
+    let index_text = format!("{:04}", index);
+    let file_stem = file_path
+        .file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let file_name = match file_path.extension() {
+        Some(extension) => format!(
+            "{}_{}.{}",
+            file_stem,
+            index_text,
+            extension.to_string_lossy()
+        ),
+        None => format!("{}_{}", file_stem, index_text),
+    };
+    file_path.set_file_name(file_name);
+
+    file_path
+}
+
+/// 两张截图每个颜色通道的平均差异不超过 threshold 时认为相同
+pub fn is_image_unchanged(previous: &DynamicImage, current: &DynamicImage, threshold: f32) -> bool {
+    if previous.width() != current.width() || previous.height() != current.height() {
+        return false;
+    }
+
+    let previous = previous.to_rgb8();
+    let current = current.to_rgb8();
+    if threshold <= 0.0 {
+        return previous.as_raw() == current.as_raw();
+    }
+
+    let diff_sum = previous
+        .as_raw()
+        .iter()
+        .zip(current.as_raw().iter())
+        .map(|(a, b)| a.abs_diff(*b) as u64)
+        .sum::<u64>();
+
+    diff_sum as f64 / previous.as_raw().len().max(1) as f64 <= threshold as f64
+}
+
+fn emit_event<S: Serialize + Clone>(app_handle: &AppHandle, event: &str, payload: S) {
+    if let Err(e) = app_handle.emit(event, payload) {
+        log::error!(
+            "[run_capture_schedule] Failed to emit event {}: {}",
+            event,
+            e
+        );
+    }
+}
+
+/// 按照参数执行定时截图，每次截图后发送进度事件，返回时任务已结束
+///
+/// @param capture_focused_window 截取焦点窗口，截图对象为焦点窗口时使用
+pub async fn run_capture_schedule<F>(
+    app_handle: &AppHandle,
+    control: &CaptureScheduleControl,
+    options: &CaptureScheduleOptions,
+    capture_focused_window: F,
+) -> CaptureScheduleResult
+where
+    F: Fn() -> Result<DynamicImage, String>,
+{
+    let mut result = CaptureScheduleResult {
+        stop_reason: CaptureScheduleStopReason::Cancelled,
+        saved_count: 0,
+        skipped_count: 0,
+        failed_count: 0,
+        file_paths: Vec::new(),
+    };
+
+    // 倒计时，每秒发送一次剩余时间
+    let mut countdown_clock = CaptureScheduleClock::new();
+    for remaining_seconds in (1..=options.delay_seconds).rev() {
+        emit_event(
+            app_handle,
+            CAPTURE_SCHEDULER_COUNTDOWN_EMIT_KEY,
+            CaptureScheduleCountdown { remaining_seconds },
+        );
+        let offset = Duration::from_secs((options.delay_seconds - remaining_seconds + 1) as u64);
+        if !countdown_clock.wait_until(control, offset).await {
+            return result;
+        }
+    }
+    emit_event(
+        app_handle,
+        CAPTURE_SCHEDULER_COUNTDOWN_EMIT_KEY,
+        CaptureScheduleCountdown {
+            remaining_seconds: 0,
+        },
+    );
+
+    let total = options.frame_count();
+    let interval = Duration::from_secs_f64(options.interval_seconds.max(0.0));
+    let duration = options
+        .duration_seconds
+        .map(|duration| Duration::from_secs_f64(duration.max(0.0)));
+    let mut clock = CaptureScheduleClock::new();
+    let mut last_saved_image: Option<DynamicImage> = None;
+    let mut last_file_path: Option<String> = None;
+    let mut index = 0;
+    while total.is_none_or(|total| index < total) {
+        // 第 index 次截图的时间点为开始后 index 个间隔，截图耗时超过间隔时立即截图
+        // 倒计时结束后暂停的，等到恢复后再截图
+        if !clock.wait_until(control, interval * index).await {
+            return result;
+        }
+        // 截图耗时导致落后时，不在总时长之后继续截图
+        if index > 0 && duration.is_some_and(|duration| clock.elapsed() > duration) {
+            break;
+        }
+        index += 1;
+
+        let capture_result = match options.target {
+            CaptureScheduleTarget::Region { region } => {
+                MonitorList::get_by_region(region)
+                    .capture_region(region, None)
+                    .await
+            }
+            CaptureScheduleTarget::FocusedWindow => capture_focused_window(),
+        };
+
+        let mut progress = CaptureScheduleProgress {
+            index,
+            total,
+            status: CaptureScheduleFrameStatus::Failed,
+            file_path: None,
+            error: None,
+        };
+
+        match capture_result {
+            Ok(image) => {
+                let unchanged = options.skip_unchanged
+                    && last_saved_image.as_ref().is_some_and(|last_saved_image| {
+                        is_image_unchanged(last_saved_image, &image, options.unchanged_threshold)
+                    });
+
+                if unchanged {
+                    progress.status = CaptureScheduleFrameStatus::Skipped;
+                } else {
+                    let template_file_path = render_file_name_template(
+                        &options.file_path_template,
+                        &Local::now().naive_local(),
+                    );
+                    let file_path = render_capture_file_path(
+                        &template_file_path,
+                        index,
+                        last_file_path.as_ref() == Some(&template_file_path),
+                    );
+                    last_file_path = Some(template_file_path);
+                    progress.file_path = Some(file_path.to_string_lossy().to_string());
+
+                    match save_image_to_file(&image, file_path, options.encoder, None).await {
+                        Ok(_) => {
+                            progress.status = CaptureScheduleFrameStatus::Saved;
+                            last_saved_image = Some(image);
+                        }
+                        Err(e) => progress.error = Some(e),
+                    }
+                }
+            }
+            Err(e) => progress.error = Some(e),
+        }
+
+        match progress.status {
+            CaptureScheduleFrameStatus::Saved => {
+                result.saved_count += 1;
+                result
+                    .file_paths
+                    .push(progress.file_path.clone().unwrap_or_default());
+            }
+            CaptureScheduleFrameStatus::Skipped => result.skipped_count += 1,
+            CaptureScheduleFrameStatus::Failed => {
+                log::warn!(
+                    "[run_capture_schedule] Failed to capture frame {}: {}",
+                    index,
+                    progress.error.as_deref().unwrap_or_default()
+                );
+                result.failed_count += 1;
+            }
+        }
+
+        emit_event(app_handle, CAPTURE_SCHEDULER_PROGRESS_EMIT_KEY, progress);
+    }
+
+    result.stop_reason = CaptureScheduleStopReason::Completed;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use image::{Rgb, RgbImage};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_capture_schedule() {
+        let options = CaptureScheduleOptions {
+            target: CaptureScheduleTarget::FocusedWindow,
+            delay_seconds: 5,
+            interval_seconds: 10.0,
+            duration_seconds: Some(60.0),
+            max_count: None,
+            file_path_template: String::from("/tmp/capture/SnowShot_{{YYYY-MM-DD_HH-mm-ss}}.png"),
+            encoder: None,
+            skip_unchanged: true,
+            unchanged_threshold: 0.0,
+        };
+        // 每 10 秒截图，持续 1 分钟，包含开始时的一次
+        assert_eq!(options.frame_count(), Some(7));
+        assert_eq!(
+            CaptureScheduleOptions {
+                max_count: Some(3),
+                ..options.clone()
+            }
+            .frame_count(),
+            Some(3)
+        );
+        assert_eq!(
+            CaptureScheduleOptions {
+                duration_seconds: None,
+                ..options.clone()
+            }
+            .frame_count(),
+            None
+        );
+        assert_eq!(
+            CaptureScheduleOptions {
+                interval_seconds: 0.0,
+                ..options.clone()
+            }
+            .frame_count(),
+            Some(1)
+        );
+
+        // 和前端一样使用 dayjs 格式的时间变量，其它变量原样保留
+        let date_time = NaiveDate::from_ymd_opt(2025, 3, 7)
+            .unwrap()
+            .and_hms_opt(14, 5, 9)
+            .unwrap();
+        assert_eq!(
+            render_file_name_template(&options.file_path_template, &date_time),
+            "/tmp/capture/SnowShot_2025-03-07_14-05-09.png"
+        );
+        assert_eq!(
+            render_file_name_template("{{YY/M/D H:m:s A}}_{{MMM}}_{{MMMM a}}.png", &date_time),
+            "25/3/7 14:5:9 PM_Mar_March pm.png"
+        );
+        assert_eq!(
+            render_file_name_template("{{index}}_{{YYYYYY}}_{{Y}}_{{YYYY", &date_time),
+            "{{index}}_202525_Y_{{YYYY"
+        );
+
+        assert_eq!(
+            render_capture_file_path("/tmp/capture/SnowShot.png", 12, true),
+            PathBuf::from("/tmp/capture/SnowShot_0012.png")
+        );
+        assert_eq!(
+            render_capture_file_path("/tmp/capture/SnowShot", 2, true),
+            PathBuf::from("/tmp/capture/SnowShot_0002")
+        );
+        assert_eq!(
+            render_capture_file_path("/tmp/capture/SnowShot.png", 1, false),
+            PathBuf::from("/tmp/capture/SnowShot.png")
+        );
+
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, Rgb([100, 100, 100])));
+        let mut changed_image = image.to_rgb8();
+        changed_image.put_pixel(0, 0, Rgb([124, 100, 100]));
+        let changed_image = DynamicImage::ImageRgb8(changed_image);
+        assert!(is_image_unchanged(&image, &image.clone(), 0.0));
+        assert!(!is_image_unchanged(&image, &changed_image, 0.0));
+        // 48 个通道中一个相差 24，平均差异为 0.5
+        assert!(is_image_unchanged(&image, &changed_image, 0.5));
+        assert!(!is_image_unchanged(
+            &image,
+            &DynamicImage::ImageRgb8(RgbImage::new(4, 5)),
+            255.0
+        ));
+
+        // 暂停的时间顺延，取消后立即返回
+        // 时间暂停时 sleep 按照定时器自动推进，暂停期间按 50ms 轮询，120ms 时仍在等待
+        let control = Arc::new(CaptureScheduleControl::default());
+        control.paused.store(true, Ordering::Relaxed);
+        let wait_control = control.clone();
+        let deadline = Instant::now() + Duration::from_millis(10);
+        let wait_handle = tokio::spawn(async move { wait_control.wait_until(deadline).await });
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert!(!wait_handle.is_finished());
+        control.paused.store(false, Ordering::Relaxed);
+        assert_eq!(wait_handle.await.unwrap(), Some(Duration::from_millis(150)));
+
+        control.cancelled.store(true, Ordering::Relaxed);
+        let wait_start = Instant::now();
+        assert!(
+            control
+                .wait_until(Instant::now() + Duration::from_secs(60))
+                .await
+                .is_none()
+        );
+        assert_eq!(wait_start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_capture_schedule_clock() {
+        let control = CaptureScheduleControl::default();
+        let interval = Duration::from_millis(100);
+
+        // 每次截图耗时 60ms，截图的时间点仍然按照间隔，不会累积
+        let mut clock = CaptureScheduleClock::new();
+        for index in 0..4 {
+            assert!(clock.wait_until(&control, interval * index).await);
+            assert_eq!(clock.elapsed(), interval * index);
+            tokio::time::advance(Duration::from_millis(60)).await;
+        }
+        assert_eq!(clock.elapsed(), interval * 3 + Duration::from_millis(60));
+
+        // 截图耗时超过间隔时，已经超过的时间点立即返回
+        let mut clock = CaptureScheduleClock::new();
+        tokio::time::advance(interval * 2).await;
+        let wait_start = Instant::now();
+        assert!(clock.wait_until(&control, interval).await);
+        assert_eq!(wait_start.elapsed(), Duration::ZERO);
+    }
+}
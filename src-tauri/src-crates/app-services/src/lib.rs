@@ -1,3 +1,4 @@
+pub mod capture_scheduler_service;
 pub mod device_event_handler_service;
 pub mod file_cache_service;
 pub mod free_drag_window_service;
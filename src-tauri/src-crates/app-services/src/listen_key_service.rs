@@ -4,7 +4,7 @@ use std::{
 };
 
 use device_query::Keycode;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter, Window};
 
 use crate::device_event_handler_service::DeviceEventHandlerService;
@@ -16,9 +16,18 @@ pub struct ListenKeyService {
     device_event_handler: Arc<Mutex<DeviceEventHandlerService>>,
 }
 
-#[derive(Serialize, Clone)]
+pub const LISTEN_KEY_SERVICE_KEY_DOWN_EMIT_KEY: &str = "listen-key-service:key-down";
+pub const LISTEN_KEY_SERVICE_KEY_UP_EMIT_KEY: &str = "listen-key-service:key-up";
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ListenKeyDownEvent {
-    key: usize,
+    pub key: usize,
+}
+
+impl ListenKeyDownEvent {
+    pub fn is_key(&self, key: Keycode) -> bool {
+        self.key == key as usize
+    }
 }
 
 #[derive(Serialize, Clone)]
@@ -37,6 +46,15 @@ impl ListenKeyService {
     }
 
     pub fn start(&mut self, app_handle: AppHandle, window: Window) -> Result<(), String> {
+        self.start_by_window_label(app_handle, window.label())
+    }
+
+    /// 以指定的标识开始监听，用于不属于某个窗口的监听者（如后台任务）
+    pub fn start_by_window_label(
+        &mut self,
+        app_handle: AppHandle,
+        window_label: &str,
+    ) -> Result<(), String> {
         let mut window_label_set_lock = match self.window_label_set.lock() {
             Ok(guard) => guard,
             Err(err) => {
@@ -46,7 +64,7 @@ impl ListenKeyService {
                 ));
             }
         };
-        window_label_set_lock.insert(window.label().to_owned());
+        window_label_set_lock.insert(window_label.to_owned());
 
         let mut key_down_guard_lock = match self._key_down_guard.lock() {
             Ok(guard) => guard,
@@ -64,7 +82,7 @@ impl ListenKeyService {
             *key_down_guard_lock = Some(Box::new(device_event_handler.on_key_down(
                 move |key: &Keycode| {
                     match key_down_app_handle.emit(
-                        "listen-key-service:key-down",
+                        LISTEN_KEY_SERVICE_KEY_DOWN_EMIT_KEY,
                         ListenKeyDownEvent { key: *key as usize },
                     ) {
                         Ok(_) => {}
@@ -94,7 +112,7 @@ impl ListenKeyService {
             *key_up_guard_lock = Some(Box::new(device_event_handler.on_key_up(
                 move |key: &Keycode| {
                     match key_up_app_handle.emit(
-                        "listen-key-service:key-up",
+                        LISTEN_KEY_SERVICE_KEY_UP_EMIT_KEY,
                         ListenKeyUpEvent { key: *key as usize },
                     ) {
                         Ok(_) => {}
@@ -47,6 +47,8 @@ struct RecordingParams {
     encoder_preset: String,
     video_max_width: i32,
     video_max_height: i32,
+    /// 是否录制鼠标指针
+    draw_cursor: bool,
 }
 
 pub struct VideoRecordService {
@@ -189,6 +191,7 @@ impl VideoRecordService {
         encoder_preset: String,
         video_max_width: i32,
         video_max_height: i32,
+        draw_cursor: bool,
     ) -> Result<()> {
         if self.state == VideoRecordState::Recording {
             return Err(std::io::Error::new(
@@ -214,6 +217,7 @@ impl VideoRecordService {
             encoder_preset,
             video_max_width,
             video_max_height,
+            draw_cursor,
         });
 
         // 重置片段相关状态
@@ -280,6 +284,8 @@ impl VideoRecordService {
                 // 设置录制区域大小
                 .arg("-video_size")
                 .arg(format!("{}x{}", width, height))
+                .arg("-draw_mouse")
+                .arg(if params.draw_cursor { "1" } else { "0" })
                 // 输入源为桌面
                 .arg("-i")
                 .arg("desktop");
@@ -292,7 +298,9 @@ impl VideoRecordService {
                 .arg("-f")
                 .arg("avfoundation")
                 .arg("-framerate")
-                .arg(params.frame_rate.to_string());
+                .arg(params.frame_rate.to_string())
+                .arg("-capture_cursor")
+                .arg(if params.draw_cursor { "1" } else { "0" });
         }
 
         let mut audio_input = String::new();
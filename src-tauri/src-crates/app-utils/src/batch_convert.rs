@@ -0,0 +1,518 @@
+use image::imageops::FilterType;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::image_encoder::{ImageEncoder, encode_image_for_file};
+
+/// 批量转换图片的参数
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchConvertOptions {
+    /// 输入的目录或通配符路径，通配符支持 `*`、`?` 和匹配任意层目录的 `**`，如 `D:/captures/**/*.png`
+    pub input: String,
+    /// 输出目录，保持文件相对输入目录的层级，未指定时输出到源文件所在目录
+    #[serde(default)]
+    pub output_dir: Option<PathBuf>,
+    /// 目标格式及编码参数，输出文件使用该格式的默认扩展名
+    pub encoder: ImageEncoder,
+    /// 图片的最大边长，超过时按比例缩小
+    #[serde(default)]
+    pub max_dimension: Option<u32>,
+    /// 输入为目录时是否包含子目录
+    #[serde(default)]
+    pub recursive: bool,
+    /// 是否覆盖已经存在的文件，不覆盖时跳过
+    #[serde(default)]
+    pub overwrite: bool,
+    /// 只列出转换计划，不解码和写入图片
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 同时转换的图片数量，未指定时使用全局线程池
+    #[serde(default)]
+    pub parallelism: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchConvertStatus {
+    Converted,
+    /// 输出文件已经存在
+    Skipped,
+    Failed,
+    /// 预演模式下将要转换的文件
+    Planned,
+}
+
+/// 单个文件的转换结果
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchConvertItem {
+    pub input_path: PathBuf,
+    pub output_path: PathBuf,
+    pub status: BatchConvertStatus,
+    pub source_width: u32,
+    pub source_height: u32,
+    pub target_width: u32,
+    pub target_height: u32,
+    pub input_file_size: u64,
+    /// 预演、跳过或失败时为 0
+    pub output_file_size: u64,
+    pub error: Option<String>,
+}
+
+/// 每处理完一个文件发送的进度
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchConvertProgress {
+    pub completed: usize,
+    pub total: usize,
+    pub item: BatchConvertItem,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchConvertResult {
+    pub converted: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// 按输入路径排序
+    pub items: Vec<BatchConvertItem>,
+}
+
+/// 批量转换图片格式，每处理完一个文件调用一次 on_progress
+///
+/// 单个文件失败不会中断转换，错误记录在对应的结果中
+pub fn batch_convert_images(
+    options: &BatchConvertOptions,
+    on_progress: impl Fn(BatchConvertProgress) + Sync,
+) -> Result<BatchConvertResult, String> {
+    let (base_dir, input_paths) = collect_input_paths(&options.input, options.recursive)?;
+
+    let output_dir = options.output_dir.as_deref();
+    let mut output_paths = HashSet::with_capacity(input_paths.len());
+    let plan = input_paths
+        .into_iter()
+        .map(|input_path| {
+            let output_path = get_output_path(&input_path, &base_dir, output_dir, &options.encoder);
+            // 同名不同格式的文件会转换到同一个路径，只转换第一个
+            let duplicated = !output_paths.insert(output_path.clone());
+            (input_path, output_path, duplicated)
+        })
+        .collect::<Vec<_>>();
+
+    let total = plan.len();
+    let completed = AtomicUsize::new(0);
+    let convert = || {
+        plan.par_iter()
+            .map(|(input_path, output_path, duplicated)| {
+                let item = convert_image(input_path, output_path, *duplicated, options);
+                on_progress(BatchConvertProgress {
+                    completed: completed.fetch_add(1, Ordering::Relaxed) + 1,
+                    total,
+                    item: item.clone(),
+                });
+                item
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let items = match options.parallelism {
+        Some(parallelism) => rayon::ThreadPoolBuilder::new()
+            .num_threads(parallelism.max(1))
+            .build()
+            .map_err(|e| format!("[batch_convert_images] Failed to build thread pool: {}", e))?
+            .install(convert),
+        None => convert(),
+    };
+
+    let count = |status| items.iter().filter(|item| item.status == status).count();
+    Ok(BatchConvertResult {
+        converted: count(BatchConvertStatus::Converted),
+        skipped: count(BatchConvertStatus::Skipped),
+        failed: count(BatchConvertStatus::Failed),
+        items,
+    })
+}
+
+fn convert_image(
+    input_path: &Path,
+    output_path: &Path,
+    duplicated: bool,
+    options: &BatchConvertOptions,
+) -> BatchConvertItem {
+    let mut item = BatchConvertItem {
+        input_path: input_path.to_path_buf(),
+        output_path: output_path.to_path_buf(),
+        status: BatchConvertStatus::Failed,
+        source_width: 0,
+        source_height: 0,
+        target_width: 0,
+        target_height: 0,
+        input_file_size: fs::metadata(input_path).map_or(0, |metadata| metadata.len()),
+        output_file_size: 0,
+        error: None,
+    };
+
+    if duplicated {
+        item.error = Some(format!(
+            "[convert_image] Output path is used by another file: {}",
+            output_path.display()
+        ));
+        return item;
+    }
+
+    // 覆盖源文件也需要显式开启覆盖
+    if !options.overwrite && output_path.exists() {
+        item.status = BatchConvertStatus::Skipped;
+        return item;
+    }
+
+    let (source_width, source_height) = match image::image_dimensions(input_path) {
+        Ok(size) => size,
+        Err(e) => {
+            item.error = Some(format!("[convert_image] Failed to read image size: {}", e));
+            return item;
+        }
+    };
+    let (target_width, target_height) =
+        get_target_size(source_width, source_height, options.max_dimension);
+    item.source_width = source_width;
+    item.source_height = source_height;
+    item.target_width = target_width;
+    item.target_height = target_height;
+
+    if options.dry_run {
+        item.status = BatchConvertStatus::Planned;
+        return item;
+    }
+
+    let result = image::open(input_path)
+        .map_err(|e| format!("[convert_image] Failed to open image: {}", e))
+        .and_then(|image| {
+            let image = if (target_width, target_height) == (source_width, source_height) {
+                image
+            } else {
+                image.resize_exact(target_width, target_height, FilterType::Lanczos3)
+            };
+
+            encode_image_for_file(&image, output_path, Some(options.encoder), None)
+        })
+        .and_then(|image_buffer| {
+            if let Some(parent_dir) = output_path.parent() {
+                fs::create_dir_all(parent_dir)
+                    .map_err(|e| format!("[convert_image] Failed to create directory: {}", e))?;
+            }
+
+            fs::write(output_path, &image_buffer)
+                .map_err(|e| format!("[convert_image] Failed to write image: {}", e))?;
+
+            Ok(image_buffer.len() as u64)
+        });
+
+    match result {
+        Ok(output_file_size) => {
+            item.status = BatchConvertStatus::Converted;
+            item.output_file_size = output_file_size;
+        }
+        Err(e) => {
+            log::warn!("{} {}", e, input_path.display());
+            item.error = Some(e);
+        }
+    }
+
+    item
+}
+
+/// 按比例缩小到不超过最大边长
+fn get_target_size(width: u32, height: u32, max_dimension: Option<u32>) -> (u32, u32) {
+    let max_dimension = match max_dimension {
+        Some(max_dimension) if max_dimension > 0 && width.max(height) > max_dimension => {
+            max_dimension
+        }
+        _ => return (width, height),
+    };
+
+    let scale = max_dimension as f64 / width.max(height) as f64;
+    (
+        ((width as f64 * scale).round() as u32).clamp(1, max_dimension),
+        ((height as f64 * scale).round() as u32).clamp(1, max_dimension),
+    )
+}
+
+fn get_output_path(
+    input_path: &Path,
+    base_dir: &Path,
+    output_dir: Option<&Path>,
+    encoder: &ImageEncoder,
+) -> PathBuf {
+    let output_path = match output_dir {
+        Some(output_dir) => output_dir.join(input_path.strip_prefix(base_dir).unwrap_or(
+            // 不会发生，输入文件都在基础目录中
+            Path::new(input_path.file_name().unwrap_or_default()),
+        )),
+        None => input_path.to_path_buf(),
+    };
+
+    output_path.with_extension(encoder.extension())
+}
+
+/// 收集输入的图片文件，返回通配符之前的基础目录和排序后的文件列表
+///
+/// 只保留 image 可以解码的格式
+fn collect_input_paths(input: &str, recursive: bool) -> Result<(PathBuf, Vec<PathBuf>), String> {
+    let input_path = Path::new(input);
+
+    let (base_dir, patterns) = if input_path.is_dir() {
+        let patterns = if recursive {
+            vec![String::from("**"), String::from("*")]
+        } else {
+            vec![String::from("*")]
+        };
+        (input_path.to_path_buf(), patterns)
+    } else {
+        let mut base_dir = PathBuf::new();
+        let mut patterns = Vec::new();
+        for component in input_path.components() {
+            match component {
+                Component::Normal(name) if !patterns.is_empty() || is_pattern(name) => {
+                    match name.to_str() {
+                        Some(name) => patterns.push(name.to_string()),
+                        None => {
+                            return Err(format!(
+                                "[collect_input_paths] Invalid input path: {}",
+                                input
+                            ));
+                        }
+                    }
+                }
+                _ if !patterns.is_empty() => {
+                    return Err(format!(
+                        "[collect_input_paths] Invalid input path: {}",
+                        input
+                    ));
+                }
+                component => base_dir.push(component),
+            }
+        }
+
+        if patterns.is_empty() {
+            return Err(format!(
+                "[collect_input_paths] Input is neither a directory nor a pattern: {}",
+                input
+            ));
+        }
+
+        (base_dir, patterns)
+    };
+
+    if !base_dir.is_dir() {
+        return Err(format!(
+            "[collect_input_paths] Directory does not exist: {}",
+            base_dir.display()
+        ));
+    }
+
+    let patterns = patterns.iter().map(String::as_str).collect::<Vec<_>>();
+    let mut input_paths = Vec::new();
+    collect_matched_paths(&base_dir, &patterns, &mut input_paths);
+
+    input_paths.retain(|path| {
+        image::ImageFormat::from_path(path).is_ok_and(|image_format| image_format.reading_enabled())
+    });
+    input_paths.sort();
+    // `**` 可能多次匹配同一个文件
+    input_paths.dedup();
+
+    Ok((base_dir, input_paths))
+}
+
+fn is_pattern(name: &std::ffi::OsStr) -> bool {
+    name.to_str().is_some_and(|name| name.contains(['*', '?']))
+}
+
+fn collect_matched_paths(dir: &Path, patterns: &[&str], input_paths: &mut Vec<PathBuf>) {
+    let Some((&pattern, rest_patterns)) = patterns.split_first() else {
+        return;
+    };
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!(
+                "[collect_matched_paths] Failed to read directory {}: {}",
+                dir.display(),
+                e
+            );
+            return;
+        }
+    };
+
+    if pattern == "**" {
+        // 匹配零层目录
+        collect_matched_paths(dir, rest_patterns, input_paths);
+    }
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if pattern == "**" {
+            if file_type.is_dir() {
+                collect_matched_paths(&path, patterns, input_paths);
+            }
+            continue;
+        }
+
+        let matched = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| wildcard_match(pattern, name));
+        if !matched {
+            continue;
+        }
+
+        if rest_patterns.is_empty() {
+            if file_type.is_file() {
+                input_paths.push(path);
+            }
+        } else if file_type.is_dir() {
+            collect_matched_paths(&path, rest_patterns, input_paths);
+        }
+    }
+}
+
+/// 匹配文件名，`*` 匹配任意个字符，`?` 匹配一个字符，不区分 ASCII 大小写
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+
+    let (mut pattern_index, mut name_index) = (0, 0);
+    // 最近一个 `*` 的位置及其匹配到的名称位置，失配时回退
+    let mut star: Option<(usize, usize)> = None;
+    while name_index < name.len() {
+        match pattern.get(pattern_index) {
+            Some('*') => {
+                star = Some((pattern_index, name_index));
+                pattern_index += 1;
+            }
+            Some(&c) if c == '?' || c.eq_ignore_ascii_case(&name[name_index]) => {
+                pattern_index += 1;
+                name_index += 1;
+            }
+            _ => match star {
+                Some((star_pattern_index, star_name_index)) => {
+                    pattern_index = star_pattern_index + 1;
+                    name_index = star_name_index + 1;
+                    star = Some((star_pattern_index, star_name_index + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[pattern_index..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_encoder::PngEncoderOptions;
+    use image::{GenericImageView, Rgba, RgbaImage};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_batch_convert_images() {
+        assert!(wildcard_match("*.png", "capture.PNG"));
+        assert!(wildcard_match("cap?ure*-*.png", "capture-2-1.png"));
+        assert!(!wildcard_match("*.png", "capture.png.bak"));
+        assert_eq!(get_target_size(400, 100, Some(200)), (200, 50));
+        assert_eq!(get_target_size(400, 1, Some(200)), (200, 1));
+        assert_eq!(get_target_size(100, 50, Some(200)), (100, 50));
+
+        let dir =
+            std::env::temp_dir().join(format!("snow-shot-batch-convert-{}", std::process::id()));
+        let input_dir = dir.join("input");
+        let output_dir = dir.join("output");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(input_dir.join("nested")).unwrap();
+
+        let image = RgbaImage::from_fn(300, 120, |x, y| {
+            Rgba([(x % 256) as u8, (y * 2) as u8, 60, 255])
+        });
+        for path in ["a.png", "b.bmp", "nested/c.png"] {
+            image.save(input_dir.join(path)).unwrap();
+        }
+        fs::write(input_dir.join("notes.txt"), "not an image").unwrap();
+
+        let mut options = BatchConvertOptions {
+            input: input_dir
+                .join("**")
+                .join("*.png")
+                .to_string_lossy()
+                .into_owned(),
+            output_dir: Some(output_dir.clone()),
+            encoder: ImageEncoder::Png(PngEncoderOptions::default()),
+            max_dimension: Some(150),
+            recursive: false,
+            overwrite: false,
+            dry_run: true,
+            parallelism: Some(2),
+        };
+
+        // 预演不写入文件
+        let progress_list = Mutex::new(Vec::new());
+        let result = batch_convert_images(&options, |progress| {
+            progress_list.lock().unwrap().push(progress);
+        })
+        .unwrap();
+        assert_eq!(result.items.len(), 2);
+        assert!(
+            result
+                .items
+                .iter()
+                .all(|item| item.status == BatchConvertStatus::Planned)
+        );
+        assert_eq!(
+            result.items[1].output_path,
+            output_dir.join("nested").join("c.png")
+        );
+        assert_eq!(
+            (result.items[0].target_width, result.items[0].target_height),
+            (150, 60)
+        );
+        assert!(!output_dir.exists());
+        let mut completed_list = progress_list
+            .into_inner()
+            .unwrap()
+            .iter()
+            .map(|progress| (progress.completed, progress.total))
+            .collect::<Vec<_>>();
+        completed_list.sort();
+        assert_eq!(completed_list, vec![(1, 2), (2, 2)]);
+
+        // 目录输入包含子目录，转换后按最大边长缩小
+        options.input = input_dir.to_string_lossy().into_owned();
+        options.recursive = true;
+        options.dry_run = false;
+        options.encoder = ImageEncoder::Bmp;
+        let result = batch_convert_images(&options, |_| {}).unwrap();
+        assert_eq!((result.converted, result.failed), (3, 0));
+        assert_eq!(result.items[1].output_path, output_dir.join("b.bmp"));
+        let converted_image = image::open(output_dir.join("nested").join("c.bmp")).unwrap();
+        assert_eq!(converted_image.dimensions(), (150, 60));
+
+        // 已经存在的文件默认跳过，a.png 和 a.bmp 输出到同一路径时只转换一个
+        image.save(input_dir.join("a.bmp")).unwrap();
+        options.recursive = false;
+        let result = batch_convert_images(&options, |_| {}).unwrap();
+        assert_eq!((result.skipped, result.failed), (2, 1));
+
+        options.overwrite = true;
+        let result = batch_convert_images(&options, |_| {}).unwrap();
+        assert_eq!((result.converted, result.failed), (2, 1));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,227 @@
+use image::RgbImage;
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+use rayon::slice::{ParallelSlice, ParallelSliceMut};
+
+#[cfg(target_os = "linux")]
+pub mod x11;
+
+/// Linux 下截取屏幕使用的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureBackend {
+    /// 直接从 X 服务器读取，优先使用 XShm，不支持时使用 XGetImage
+    X11,
+    /// Wayland 下无法直接读取屏幕，通过 xcap 使用 xdg-desktop-portal 或 PipeWire 截图
+    Portal,
+}
+
+impl CaptureBackend {
+    /// 根据当前会话的环境变量选择截图方式
+    pub fn detect() -> Self {
+        Self::from_session(
+            std::env::var("XDG_SESSION_TYPE").ok().as_deref(),
+            std::env::var("WAYLAND_DISPLAY").ok().as_deref(),
+            std::env::var("DISPLAY").ok().as_deref(),
+        )
+    }
+
+    /// Wayland 会话中 XWayland 只能读取到 X 客户端的窗口，所以即使存在 DISPLAY 也使用 Portal
+    pub fn from_session(
+        session_type: Option<&str>,
+        wayland_display: Option<&str>,
+        display: Option<&str>,
+    ) -> Self {
+        let is_set = |value: Option<&str>| value.is_some_and(|value| !value.is_empty());
+
+        match session_type {
+            Some(session_type) if session_type.eq_ignore_ascii_case("wayland") => {
+                CaptureBackend::Portal
+            }
+            Some(session_type) if session_type.eq_ignore_ascii_case("x11") => CaptureBackend::X11,
+            _ if is_set(wayland_display) => CaptureBackend::Portal,
+            _ if is_set(display) => CaptureBackend::X11,
+            _ => CaptureBackend::Portal,
+        }
+    }
+}
+
+/// X11 ZPixmap 图像的像素格式
+#[derive(Debug, Clone, Copy)]
+pub struct ZPixmapFormat {
+    pub bits_per_pixel: u8,
+    /// 每行的字节数，包含行尾的填充
+    pub stride: usize,
+    pub big_endian: bool,
+    pub red_mask: u32,
+    pub green_mask: u32,
+    pub blue_mask: u32,
+}
+
+/// 将 ZPixmap 图像转为 RGB，目前只支持 32 位的像素（24 位和 32 位色深）
+pub fn zpixmap_to_rgb(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    format: &ZPixmapFormat,
+) -> Result<RgbImage, String> {
+    if format.bits_per_pixel != 32 {
+        return Err(format!(
+            "[zpixmap_to_rgb] Unsupported bits per pixel: {}",
+            format.bits_per_pixel
+        ));
+    }
+
+    let (width, height) = (width as usize, height as usize);
+    if format.stride < width * 4 || data.len() < format.stride * height {
+        return Err(format!(
+            "[zpixmap_to_rgb] Invalid image data, stride: {}, size: {}",
+            format.stride,
+            data.len()
+        ));
+    }
+
+    let channel_masks = [format.red_mask, format.green_mask, format.blue_mask];
+    if channel_masks.contains(&0) {
+        return Err(String::from("[zpixmap_to_rgb] Invalid color mask"));
+    }
+    // 通道值右移后的最大值，不是 8 位的通道缩放到 0-255
+    let channel_shifts = channel_masks.map(|mask| mask.trailing_zeros());
+    let channel_max_values = channel_masks.map(|mask| mask >> mask.trailing_zeros());
+
+    let mut pixels = vec![0u8; width * height * 3];
+    if width == 0 {
+        return Ok(RgbImage::from_raw(0, height as u32, pixels).unwrap());
+    }
+
+    pixels
+        .par_chunks_exact_mut(width * 3)
+        .zip(data.par_chunks(format.stride))
+        .for_each(|(row, data_row)| {
+            for (pixel, data_pixel) in row
+                .chunks_exact_mut(3)
+                .zip(data_row[..width * 4].chunks_exact(4))
+            {
+                let data_pixel = [data_pixel[0], data_pixel[1], data_pixel[2], data_pixel[3]];
+                let value = if format.big_endian {
+                    u32::from_be_bytes(data_pixel)
+                } else {
+                    u32::from_le_bytes(data_pixel)
+                };
+
+                for channel in 0..3 {
+                    let channel_value = (value & channel_masks[channel]) >> channel_shifts[channel];
+                    let max_value = channel_max_values[channel];
+                    pixel[channel] = if max_value == 255 {
+                        channel_value as u8
+                    } else {
+                        ((channel_value as u64 * 255 + max_value as u64 / 2) / max_value as u64)
+                            as u8
+                    };
+                }
+            }
+        });
+
+    Ok(RgbImage::from_raw(width as u32, height as u32, pixels).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_backend_from_session() {
+        assert_eq!(
+            CaptureBackend::from_session(Some("wayland"), Some("wayland-0"), Some(":0")),
+            CaptureBackend::Portal
+        );
+        assert_eq!(
+            CaptureBackend::from_session(Some("x11"), None, Some(":0")),
+            CaptureBackend::X11
+        );
+        // Xvfb 等没有会话类型的环境
+        assert_eq!(
+            CaptureBackend::from_session(None, None, Some(":99")),
+            CaptureBackend::X11
+        );
+        assert_eq!(
+            CaptureBackend::from_session(Some("tty"), Some(""), None),
+            CaptureBackend::Portal
+        );
+    }
+
+    #[test]
+    fn test_zpixmap_to_rgb() {
+        // BGRX，每行末尾填充 4 字节
+        let data = [
+            30, 20, 10, 0, 60, 50, 40, 0, 0, 0, 0, 0, //
+            255, 128, 0, 0, 0, 0, 255, 0, 0, 0, 0, 0,
+        ];
+        let format = ZPixmapFormat {
+            bits_per_pixel: 32,
+            stride: 12,
+            big_endian: false,
+            red_mask: 0xff0000,
+            green_mask: 0xff00,
+            blue_mask: 0xff,
+        };
+        let image = zpixmap_to_rgb(&data, 2, 2, &format).unwrap();
+        assert_eq!(
+            image.as_raw(),
+            &[10, 20, 30, 40, 50, 60, 0, 128, 255, 255, 0, 0]
+        );
+
+        // 不是 8 位的颜色通道
+        let data = [0x00, 0x04, 0, 0];
+        let format = ZPixmapFormat {
+            bits_per_pixel: 32,
+            stride: 4,
+            big_endian: false,
+            red_mask: 0xf800,
+            green_mask: 0x07e0,
+            blue_mask: 0x001f,
+        };
+        let image = zpixmap_to_rgb(&data, 1, 1, &format).unwrap();
+        assert_eq!(image.as_raw(), &[0, 130, 0]);
+    }
+
+    #[test]
+    fn test_zpixmap_to_rgb_invalid_data() {
+        let data = [0x00, 0x04, 0, 0];
+        let format = ZPixmapFormat {
+            bits_per_pixel: 32,
+            stride: 4,
+            big_endian: false,
+            red_mask: 0xf800,
+            green_mask: 0x07e0,
+            blue_mask: 0x001f,
+        };
+        assert!(zpixmap_to_rgb(&data, 2, 1, &format).is_err());
+        let format = ZPixmapFormat {
+            bits_per_pixel: 16,
+            ..format
+        };
+        assert!(zpixmap_to_rgb(&data, 1, 1, &format).is_err());
+        let format = ZPixmapFormat {
+            bits_per_pixel: 32,
+            red_mask: 0,
+            ..format
+        };
+        assert!(zpixmap_to_rgb(&data, 1, 1, &format).is_err());
+    }
+
+    /// 在 Xvfb 等 X 服务器中运行时截取根窗口，没有 X 服务器时跳过
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_x11_capture_region() {
+        if CaptureBackend::detect() != CaptureBackend::X11 {
+            return;
+        }
+
+        // 连续截图复用缓存的连接，区域变大时重新创建共享内存
+        for (width, height) in [(16, 8), (4, 4), (64, 32)] {
+            let image = x11::capture_region(0, 0, width, height).unwrap();
+            assert_eq!(image.dimensions(), (width, height));
+        }
+
+        assert!(x11::capture_region(0, 0, 0, 8).is_err());
+    }
+}
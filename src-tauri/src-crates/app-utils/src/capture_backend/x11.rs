@@ -0,0 +1,305 @@
+use image::RgbImage;
+use std::sync::Mutex;
+use xcb::{shm, x};
+
+use super::{ZPixmapFormat, zpixmap_to_rgb};
+
+/// 缓存的 X 连接，连续截图（如滚动截图）时复用连接和共享内存
+static X11_CAPTURER: Mutex<Option<X11Capturer>> = Mutex::new(None);
+
+/// 截取根窗口上的区域，坐标为整个 X 屏幕的物理像素
+///
+/// 优先通过 XShm 共享内存读取，X 服务器不支持或读取失败时使用 XGetImage
+pub fn capture_region(x: i32, y: i32, width: u32, height: u32) -> Result<RgbImage, String> {
+    if width == 0 || height == 0 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(format!(
+            "[x11::capture_region] Invalid region size: {}x{}",
+            width, height
+        ));
+    }
+
+    let mut capturer = X11_CAPTURER
+        .lock()
+        .map_err(|_| String::from("[x11::capture_region] Failed to lock X11 capturer"))?;
+
+    if capturer.is_none() {
+        *capturer = Some(X11Capturer::connect()?);
+    }
+    let result = capturer
+        .as_mut()
+        .unwrap()
+        .capture_region(x, y, width, height);
+
+    // 连接断开（如 X 服务器重启）后丢弃缓存，下次截图时重新连接
+    if capturer
+        .as_ref()
+        .is_some_and(|capturer| capturer.connection.has_error().is_err())
+    {
+        *capturer = None;
+    }
+
+    result
+}
+
+/// X 连接及附加到 X 服务器的共享内存
+struct X11Capturer {
+    connection: xcb::Connection,
+    screen_num: usize,
+    /// X 服务器不支持 XShm 或读取失败后不再尝试
+    use_shm: bool,
+    /// 已附加的共享内存，区域更大时重新创建
+    shm: Option<(shm::Seg, ShmSegment)>,
+}
+
+impl X11Capturer {
+    fn connect() -> Result<Self, String> {
+        let (connection, screen_num) =
+            xcb::Connection::connect_with_extensions(None, &[], &[xcb::Extension::Shm]).map_err(
+                |e| {
+                    format!(
+                        "[X11Capturer::connect] Failed to connect to X server: {}",
+                        e
+                    )
+                },
+            )?;
+
+        let use_shm = connection
+            .active_extensions()
+            .any(|extension| extension == xcb::Extension::Shm);
+
+        Ok(Self {
+            connection,
+            screen_num: screen_num as usize,
+            use_shm,
+            shm: None,
+        })
+    }
+
+    fn capture_region(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> Result<RgbImage, String> {
+        let setup = self.connection.get_setup();
+        let screen = match setup.roots().nth(self.screen_num) {
+            Some(screen) => screen,
+            None => {
+                return Err(String::from(
+                    "[X11Capturer::capture_region] Failed to get screen",
+                ));
+            }
+        };
+        let root = screen.root();
+        let format = get_zpixmap_format(setup, screen, width)?;
+
+        if self.use_shm {
+            match self.capture_region_with_shm(root, x, y, width, height, &format) {
+                Ok(image) => return Ok(image),
+                Err(e) => {
+                    log::warn!("{}, fallback to XGetImage", e);
+                    self.release_shm();
+                    self.use_shm = false;
+                }
+            }
+        }
+
+        let cookie = self.connection.send_request(&x::GetImage {
+            format: x::ImageFormat::ZPixmap,
+            drawable: x::Drawable::Window(root),
+            x: x as i16,
+            y: y as i16,
+            width: width as u16,
+            height: height as u16,
+            plane_mask: u32::MAX,
+        });
+        let reply = self
+            .connection
+            .wait_for_reply(cookie)
+            .map_err(|e| format!("[X11Capturer::capture_region] Failed to get image: {}", e))?;
+
+        zpixmap_to_rgb(reply.data(), width, height, &format)
+    }
+
+    fn capture_region_with_shm(
+        &mut self,
+        root: x::Window,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        format: &ZPixmapFormat,
+    ) -> Result<RgbImage, String> {
+        let size = format.stride * height as usize;
+        if self
+            .shm
+            .as_ref()
+            .is_none_or(|(_, segment)| segment.size < size)
+        {
+            self.release_shm();
+            self.shm = Some(self.attach_shm(size)?);
+        }
+        let (shm_seg, segment) = self.shm.as_ref().unwrap();
+
+        let cookie = self.connection.send_request(&shm::GetImage {
+            drawable: x::Drawable::Window(root),
+            x: x as i16,
+            y: y as i16,
+            width: width as u16,
+            height: height as u16,
+            plane_mask: u32::MAX,
+            format: x::ImageFormat::ZPixmap as u8,
+            shmseg: *shm_seg,
+            offset: 0,
+        });
+        if let Err(e) = self.connection.wait_for_reply(cookie) {
+            return Err(format!(
+                "[X11Capturer::capture_region_with_shm] Failed to get image: {}",
+                e
+            ));
+        }
+
+        zpixmap_to_rgb(&segment.data()[..size], width, height, format)
+    }
+
+    fn attach_shm(&self, size: usize) -> Result<(shm::Seg, ShmSegment), String> {
+        let segment = ShmSegment::new(size)?;
+
+        let shm_seg: shm::Seg = self.connection.generate_id();
+        self.connection
+            .send_and_check_request(&shm::Attach {
+                shmseg: shm_seg,
+                shmid: segment.id as u32,
+                read_only: false,
+            })
+            .map_err(|e| {
+                format!(
+                    "[X11Capturer::attach_shm] Failed to attach shared memory: {}",
+                    e
+                )
+            })?;
+
+        Ok((shm_seg, segment))
+    }
+
+    /// 从 X 服务器分离共享内存后释放
+    fn release_shm(&mut self) {
+        let (shm_seg, segment) = match self.shm.take() {
+            Some(shm) => shm,
+            None => return,
+        };
+
+        if let Err(e) = self
+            .connection
+            .send_and_check_request(&shm::Detach { shmseg: shm_seg })
+        {
+            log::warn!(
+                "[X11Capturer::release_shm] Failed to detach shared memory: {}",
+                e
+            );
+        }
+
+        drop(segment);
+    }
+}
+
+impl Drop for X11Capturer {
+    fn drop(&mut self) {
+        self.release_shm();
+    }
+}
+
+/// 根据根窗口的色深和视觉类型确定 ZPixmap 的像素格式
+fn get_zpixmap_format(
+    setup: &x::Setup,
+    screen: &x::Screen,
+    width: u32,
+) -> Result<ZPixmapFormat, String> {
+    let pixmap_format = match setup
+        .pixmap_formats()
+        .iter()
+        .find(|pixmap_format| pixmap_format.depth() == screen.root_depth())
+    {
+        Some(pixmap_format) => pixmap_format,
+        None => {
+            return Err(format!(
+                "[x11::get_zpixmap_format] Unsupported depth: {}",
+                screen.root_depth()
+            ));
+        }
+    };
+
+    let visual = match screen
+        .allowed_depths()
+        .flat_map(|depth| depth.visuals().iter())
+        .find(|visual| visual.visual_id() == screen.root_visual())
+    {
+        Some(visual) => visual,
+        None => {
+            return Err(String::from(
+                "[x11::get_zpixmap_format] Failed to get root visual",
+            ));
+        }
+    };
+
+    // 每行按照 scanline_pad 位对齐
+    let scanline_pad = pixmap_format.scanline_pad().max(8) as usize;
+    let row_bits = width as usize * pixmap_format.bits_per_pixel() as usize;
+
+    Ok(ZPixmapFormat {
+        bits_per_pixel: pixmap_format.bits_per_pixel(),
+        stride: row_bits.div_ceil(scanline_pad) * scanline_pad / 8,
+        big_endian: setup.image_byte_order() == x::ImageOrder::MsbFirst,
+        red_mask: visual.red_mask(),
+        green_mask: visual.green_mask(),
+        blue_mask: visual.blue_mask(),
+    })
+}
+
+/// 附加到 X 服务器的共享内存，释放时分离
+struct ShmSegment {
+    id: i32,
+    addr: *mut libc::c_void,
+    size: usize,
+}
+
+impl ShmSegment {
+    fn new(size: usize) -> Result<Self, String> {
+        let id = unsafe { libc::shmget(libc::IPC_PRIVATE, size, libc::IPC_CREAT | 0o600) };
+        if id < 0 {
+            return Err(format!(
+                "[ShmSegment::new] Failed to create shared memory: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let addr = unsafe { libc::shmat(id, std::ptr::null(), libc::SHM_RDONLY) };
+        if addr as isize == -1 {
+            let error = std::io::Error::last_os_error();
+            unsafe { libc::shmctl(id, libc::IPC_RMID, std::ptr::null_mut()) };
+            return Err(format!(
+                "[ShmSegment::new] Failed to attach shared memory: {}",
+                error
+            ));
+        }
+
+        Ok(Self { id, addr, size })
+    }
+
+    fn data(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.addr as *const u8, self.size) }
+    }
+}
+
+// 共享内存只在持有 X11_CAPTURER 的锁时访问
+unsafe impl Send for ShmSegment {}
+
+impl Drop for ShmSegment {
+    fn drop(&mut self) {
+        unsafe {
+            libc::shmdt(self.addr);
+            libc::shmctl(self.id, libc::IPC_RMID, std::ptr::null_mut());
+        }
+    }
+}
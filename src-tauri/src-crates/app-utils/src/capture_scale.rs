@@ -0,0 +1,362 @@
+use serde::{Deserialize, Serialize};
+use snow_shot_app_shared::ElementRect;
+
+/// 多个显示器拼接时的缩放方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum CaptureScaleMode {
+    /// 保留每个显示器的物理像素，缩放比例不同时内容大小不一致
+    #[default]
+    Native,
+    /// 将每个显示器缩放到相同的缩放比例后拼接，未指定时使用显示器中最大的缩放比例
+    Resample { target_scale_factor: Option<f32> },
+}
+
+/// 单个显示器被截取的区域在输出图像中的位置
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MonitorScaleMapping {
+    /// 显示器在 MonitorList 中的索引
+    pub monitor_index: usize,
+    /// 被截取的区域，桌面的物理像素坐标
+    pub source_rect: ElementRect,
+    /// 在输出图像中的区域
+    pub target_rect: ElementRect,
+    /// 输出图像像素和源区域像素的比例
+    pub scale: f32,
+}
+
+/// 桌面物理像素坐标和输出图像坐标的映射
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CaptureScaleMapping {
+    /// 输出图像的缩放比例，保留物理像素时为 None
+    pub target_scale_factor: Option<f32>,
+    pub width: u32,
+    pub height: u32,
+    pub monitors: Vec<MonitorScaleMapping>,
+}
+
+/// 缩放比例获取失败时为 0，按照 1 处理
+fn effective_scale_factor(scale_factor: f32) -> f32 {
+    if scale_factor > 0.0 {
+        scale_factor
+    } else {
+        1.0
+    }
+}
+
+/// 沿一个坐标轴把物理像素坐标映射到输出图像坐标
+///
+/// 每段区间使用覆盖它的显示器中最大的缩放倍数，并排的显示器不会重叠或留下空隙
+struct AxisMapping {
+    /// 区间的端点和端点映射后的坐标
+    edges: Vec<(i32, f64)>,
+}
+
+impl AxisMapping {
+    fn new(ranges: &[(i32, i32, f64)]) -> Self {
+        let mut edge_list = ranges
+            .iter()
+            .flat_map(|&(min, max, _)| [min, max])
+            .collect::<Vec<_>>();
+        edge_list.sort_unstable();
+        edge_list.dedup();
+
+        let mut edges = Vec::with_capacity(edge_list.len());
+        let mut position = 0.0;
+        for (index, &edge) in edge_list.iter().enumerate() {
+            edges.push((edge, position));
+
+            if let Some(&next_edge) = edge_list.get(index + 1) {
+                // 不被任何显示器覆盖的空隙保持原大小
+                let scale = ranges
+                    .iter()
+                    .filter(|&&(min, max, _)| min <= edge && next_edge <= max)
+                    .map(|&(_, _, scale)| scale)
+                    .fold(None, |result: Option<f64>, scale| {
+                        Some(result.map_or(scale, |result| result.max(scale)))
+                    })
+                    .unwrap_or(1.0);
+                position += (next_edge - edge) as f64 * scale;
+            }
+        }
+
+        Self { edges }
+    }
+
+    fn map(&self, value: i32) -> f64 {
+        self.edges
+            .iter()
+            .find(|(edge, _)| *edge == value)
+            .map_or(0.0, |(_, position)| *position)
+    }
+}
+
+impl CaptureScaleMapping {
+    /// 计算显示器截图拼接后的映射
+    ///
+    /// @param monitors 显示器的物理像素区域和缩放比例，顺序和 MonitorList 一致
+    /// @param region 截取的区域，未指定时截取所有显示器
+    pub fn new(
+        monitors: &[(ElementRect, f32)],
+        region: Option<ElementRect>,
+        scale_mode: CaptureScaleMode,
+    ) -> Self {
+        let overlap_monitors = monitors
+            .iter()
+            .enumerate()
+            .filter(|(_, (rect, _))| region.is_none_or(|region| rect.overlaps(&region)))
+            .map(|(index, &(rect, scale_factor))| {
+                let source_rect = match region {
+                    Some(region) => rect.clip_rect(&region),
+                    None => rect,
+                };
+                (
+                    index,
+                    rect,
+                    source_rect,
+                    effective_scale_factor(scale_factor),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let target_scale_factor = match scale_mode {
+            CaptureScaleMode::Native => {
+                let origin = match region {
+                    Some(region) => region,
+                    None => bounding_box(overlap_monitors.iter().map(|monitor| monitor.1)),
+                };
+
+                return Self {
+                    target_scale_factor: None,
+                    width: (origin.max_x - origin.min_x).max(0) as u32,
+                    height: (origin.max_y - origin.min_y).max(0) as u32,
+                    monitors: overlap_monitors
+                        .iter()
+                        .map(|&(monitor_index, _, source_rect, _)| MonitorScaleMapping {
+                            monitor_index,
+                            source_rect,
+                            target_rect: ElementRect {
+                                min_x: source_rect.min_x - origin.min_x,
+                                min_y: source_rect.min_y - origin.min_y,
+                                max_x: source_rect.max_x - origin.min_x,
+                                max_y: source_rect.max_y - origin.min_y,
+                            },
+                            scale: 1.0,
+                        })
+                        .collect(),
+                };
+            }
+            CaptureScaleMode::Resample {
+                target_scale_factor,
+            } => target_scale_factor
+                .filter(|scale_factor| *scale_factor > 0.0)
+                .unwrap_or_else(|| {
+                    overlap_monitors
+                        .iter()
+                        .map(|monitor| monitor.3)
+                        .fold(1.0, f32::max)
+                }),
+        };
+
+        // 显示器的位置按照所有显示器计算，截取区域时位置也保持不变
+        let monitor_scales = monitors
+            .iter()
+            .map(|&(rect, scale_factor)| {
+                (
+                    rect,
+                    target_scale_factor as f64 / effective_scale_factor(scale_factor) as f64,
+                )
+            })
+            .collect::<Vec<_>>();
+        let axis_x = AxisMapping::new(
+            &monitor_scales
+                .iter()
+                .map(|(rect, scale)| (rect.min_x, rect.max_x, *scale))
+                .collect::<Vec<_>>(),
+        );
+        let axis_y = AxisMapping::new(
+            &monitor_scales
+                .iter()
+                .map(|(rect, scale)| (rect.min_y, rect.max_y, *scale))
+                .collect::<Vec<_>>(),
+        );
+
+        let mut mapping_list = overlap_monitors
+            .iter()
+            .map(|&(monitor_index, rect, source_rect, _)| {
+                let scale = monitor_scales[monitor_index].1;
+                let min_x = (axis_x.map(rect.min_x)
+                    + (source_rect.min_x - rect.min_x) as f64 * scale)
+                    .round() as i32;
+                let min_y = (axis_y.map(rect.min_y)
+                    + (source_rect.min_y - rect.min_y) as f64 * scale)
+                    .round() as i32;
+
+                MonitorScaleMapping {
+                    monitor_index,
+                    source_rect,
+                    target_rect: ElementRect {
+                        min_x,
+                        min_y,
+                        max_x: min_x
+                            + ((source_rect.max_x - source_rect.min_x) as f64 * scale)
+                                .round()
+                                .max(1.0) as i32,
+                        max_y: min_y
+                            + ((source_rect.max_y - source_rect.min_y) as f64 * scale)
+                                .round()
+                                .max(1.0) as i32,
+                    },
+                    scale: scale as f32,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // 输出图像从所有区域的左上角开始
+        let target_box = bounding_box(mapping_list.iter().map(|mapping| mapping.target_rect));
+        for mapping in mapping_list.iter_mut() {
+            mapping.target_rect = ElementRect {
+                min_x: mapping.target_rect.min_x - target_box.min_x,
+                min_y: mapping.target_rect.min_y - target_box.min_y,
+                max_x: mapping.target_rect.max_x - target_box.min_x,
+                max_y: mapping.target_rect.max_y - target_box.min_y,
+            };
+        }
+
+        Self {
+            target_scale_factor: Some(target_scale_factor),
+            width: (target_box.max_x - target_box.min_x) as u32,
+            height: (target_box.max_y - target_box.min_y) as u32,
+            monitors: mapping_list,
+        }
+    }
+
+    /// 获取桌面物理像素坐标所在显示器的映射
+    pub fn find_monitor(&self, x: i32, y: i32) -> Option<&MonitorScaleMapping> {
+        self.monitors
+            .iter()
+            .find(|mapping| contains_point(&mapping.source_rect, x, y))
+    }
+
+    /// 将桌面物理像素坐标转为输出图像坐标，不在截取区域内时返回 None
+    pub fn source_to_target(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        let mapping = self.find_monitor(x, y)?;
+
+        Some((
+            mapping.target_rect.min_x
+                + ((x - mapping.source_rect.min_x) as f32 * mapping.scale) as i32,
+            mapping.target_rect.min_y
+                + ((y - mapping.source_rect.min_y) as f32 * mapping.scale) as i32,
+        ))
+    }
+
+    /// 将输出图像坐标转为桌面物理像素坐标，不在任何显示器中时返回 None
+    pub fn target_to_source(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        let mapping = self
+            .monitors
+            .iter()
+            .find(|mapping| contains_point(&mapping.target_rect, x, y))?;
+
+        Some((
+            mapping.source_rect.min_x
+                + ((x - mapping.target_rect.min_x) as f32 / mapping.scale) as i32,
+            mapping.source_rect.min_y
+                + ((y - mapping.target_rect.min_y) as f32 / mapping.scale) as i32,
+        ))
+    }
+}
+
+fn contains_point(rect: &ElementRect, x: i32, y: i32) -> bool {
+    rect.min_x <= x && x < rect.max_x && rect.min_y <= y && y < rect.max_y
+}
+
+fn bounding_box(rect_list: impl Iterator<Item = ElementRect>) -> ElementRect {
+    rect_list
+        .reduce(|result, rect| ElementRect {
+            min_x: result.min_x.min(rect.min_x),
+            min_y: result.min_y.min(rect.min_y),
+            max_x: result.max_x.max(rect.max_x),
+            max_y: result.max_y.max(rect.max_y),
+        })
+        .unwrap_or(ElementRect {
+            min_x: 0,
+            min_y: 0,
+            max_x: 0,
+            max_y: 0,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(min_x: i32, min_y: i32, max_x: i32, max_y: i32) -> ElementRect {
+        ElementRect {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
+
+    #[test]
+    fn test_capture_scale_mapping() {
+        // 100% 的 1920x1080 显示器右侧是 150% 的 2880x1620 显示器
+        let monitors = [
+            (rect(0, 0, 1920, 1080), 1.0),
+            (rect(1920, 0, 4800, 1620), 1.5),
+        ];
+
+        let mapping = CaptureScaleMapping::new(&monitors, None, CaptureScaleMode::Native);
+        assert_eq!((mapping.width, mapping.height), (4800, 1620));
+        assert_eq!(mapping.monitors[1].target_rect, rect(1920, 0, 4800, 1620));
+
+        // 缩放到 150% 后两个显示器的逻辑大小一致
+        let mapping = CaptureScaleMapping::new(
+            &monitors,
+            None,
+            CaptureScaleMode::Resample {
+                target_scale_factor: None,
+            },
+        );
+        assert_eq!(mapping.target_scale_factor, Some(1.5));
+        assert_eq!((mapping.width, mapping.height), (5760, 1620));
+        assert_eq!(mapping.monitors[0].target_rect, rect(0, 0, 2880, 1620));
+        assert_eq!(mapping.monitors[1].target_rect, rect(2880, 0, 5760, 1620));
+        assert_eq!(mapping.source_to_target(1000, 500), Some((1500, 750)));
+        assert_eq!(mapping.source_to_target(2000, 100), Some((2960, 100)));
+        assert_eq!(mapping.target_to_source(2960, 100), Some((2000, 100)));
+        assert_eq!(mapping.source_to_target(1000, 1200), None);
+
+        // 缩放到 100%，截取跨越两个显示器的区域
+        let mapping = CaptureScaleMapping::new(
+            &monitors,
+            Some(rect(1820, 100, 2120, 400)),
+            CaptureScaleMode::Resample {
+                target_scale_factor: Some(1.0),
+            },
+        );
+        assert_eq!(mapping.monitors[0].source_rect, rect(1820, 100, 1920, 400));
+        assert_eq!(mapping.monitors[1].source_rect, rect(1920, 100, 2120, 400));
+        // 按照逻辑坐标对齐，150% 显示器上 y = 100 的物理像素在逻辑坐标 67 处
+        assert_eq!(mapping.monitors[0].target_rect, rect(0, 33, 100, 333));
+        assert_eq!(mapping.monitors[1].target_rect, rect(100, 0, 233, 200));
+        assert_eq!((mapping.width, mapping.height), (233, 333));
+
+        // 负坐标的显示器在上方
+        let monitors = [
+            (rect(0, 0, 1920, 1080), 1.0),
+            (rect(-320, -2160, 3520, 0), 2.0),
+        ];
+        let mapping = CaptureScaleMapping::new(
+            &monitors,
+            None,
+            CaptureScaleMode::Resample {
+                target_scale_factor: Some(1.0),
+            },
+        );
+        assert_eq!(mapping.monitors[1].target_rect, rect(0, 0, 1920, 1080));
+        assert_eq!(mapping.monitors[0].target_rect, rect(160, 1080, 2080, 2160));
+        assert_eq!((mapping.width, mapping.height), (2080, 2160));
+    }
+}
@@ -0,0 +1,211 @@
+use image::{DynamicImage, Rgb, RgbImage};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use snow_shot_app_shared::ElementRect;
+use xcap::Monitor;
+
+use crate::monitor_info::MonitorInfo;
+
+/// 显示器和截图的来源，MonitorList 通过它获取显示器并截图
+pub trait CaptureSource: Send + Sync {
+    /// 获取所有显示器
+    fn monitors(&self) -> Vec<MonitorInfo>;
+
+    /// 截取显示器，crop_region 为相对显示器的区域，未指定时截取整个显示器
+    fn capture_monitor(
+        &self,
+        monitor: &MonitorInfo,
+        crop_region: Option<ElementRect>,
+        exclude_window: Option<&tauri::Window>,
+    ) -> Option<DynamicImage>;
+
+    /// 需要应用到截图上的 5x5 颜色变换矩阵，如 Windows 放大镜的颜色效果
+    fn color_effect(&self) -> Option<[f32; 25]> {
+        None
+    }
+}
+
+/// 通过 xcap 获取的真实显示器
+pub struct XcapCaptureSource;
+
+impl CaptureSource for XcapCaptureSource {
+    fn monitors(&self) -> Vec<MonitorInfo> {
+        Monitor::all()
+            .unwrap_or_default()
+            .par_iter()
+            .map(MonitorInfo::new)
+            .collect()
+    }
+
+    fn capture_monitor(
+        &self,
+        monitor: &MonitorInfo,
+        crop_region: Option<ElementRect>,
+        exclude_window: Option<&tauri::Window>,
+    ) -> Option<DynamicImage> {
+        crate::capture_target_monitor(monitor.monitor.as_ref()?, crop_region, exclude_window)
+    }
+
+    /**
+     * 获取 Windows 下放大镜的颜色变换举证
+     */
+    fn color_effect(&self) -> Option<[f32; 25]> {
+        #[cfg(not(target_os = "windows"))]
+        {
+            None
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            let init_result = unsafe { windows::Win32::UI::Magnification::MagInitialize() };
+            if !init_result.as_bool() {
+                log::warn!(
+                    "[XcapCaptureSource::color_effect] Failed to initialize magnification library"
+                );
+                return None;
+            }
+
+            let mut current_effect = windows::Win32::UI::Magnification::MAGCOLOREFFECT::default();
+            let get_effect_result = unsafe {
+                windows::Win32::UI::Magnification::MagGetFullscreenColorEffect(&mut current_effect)
+            };
+
+            // 释放 Mag
+            let uninit_result = unsafe { windows::Win32::UI::Magnification::MagUninitialize() };
+            if !uninit_result.as_bool() {
+                log::warn!(
+                    "[XcapCaptureSource::color_effect] Failed to uninitialize magnification library"
+                );
+            }
+
+            if !get_effect_result.as_bool() {
+                log::warn!(
+                    "[XcapCaptureSource::color_effect] Failed to get magnification color effect"
+                );
+                return None;
+            }
+
+            let matrix = current_effect.transform;
+            // 无任何效果的默认矩阵
+            const NORMAL_MATRIX: [f32; 25] = [
+                1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0,
+                0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+            ];
+            // 判断 matrix 是否等于 NORMAL_MATRIX
+            if matrix.eq(&NORMAL_MATRIX) {
+                return None;
+            }
+
+            Some(matrix)
+        }
+    }
+}
+
+/// 伪造的显示器，图像按照显示器的物理像素区域生成
+#[derive(Debug, Clone)]
+pub struct FakeMonitor {
+    pub name: String,
+    pub rect: ElementRect,
+    pub scale_factor: f32,
+    /// 截图失败的显示器
+    pub fail: bool,
+}
+
+/// 不依赖真实显示器的截图来源，每个像素的颜色由它在整个桌面中的坐标决定
+#[derive(Debug, Clone, Default)]
+pub struct FakeCaptureSource {
+    monitors: Vec<FakeMonitor>,
+    color_effect: Option<[f32; 25]>,
+}
+
+impl FakeCaptureSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_monitor(mut self, name: &str, rect: ElementRect, scale_factor: f32) -> Self {
+        self.monitors.push(FakeMonitor {
+            name: name.to_string(),
+            rect,
+            scale_factor,
+            fail: false,
+        });
+        self
+    }
+
+    pub fn with_failed_monitor(mut self, name: &str, rect: ElementRect, scale_factor: f32) -> Self {
+        self.monitors.push(FakeMonitor {
+            name: name.to_string(),
+            rect,
+            scale_factor,
+            fail: true,
+        });
+        self
+    }
+
+    pub fn with_color_effect(mut self, matrix: [f32; 25]) -> Self {
+        self.color_effect = Some(matrix);
+        self
+    }
+
+    /// 桌面坐标 (x, y) 处像素的颜色
+    pub fn pixel(x: i32, y: i32) -> Rgb<u8> {
+        Rgb([
+            x.rem_euclid(256) as u8,
+            y.rem_euclid(256) as u8,
+            (x.div_euclid(256) * 16 + y.div_euclid(256)).rem_euclid(256) as u8,
+        ])
+    }
+}
+
+impl CaptureSource for FakeCaptureSource {
+    fn monitors(&self) -> Vec<MonitorInfo> {
+        self.monitors
+            .iter()
+            .map(|monitor| MonitorInfo {
+                monitor: None,
+                name: monitor.name.clone(),
+                rect: monitor.rect,
+                scale_factor: monitor.scale_factor,
+                rotation: 0.0,
+            })
+            .collect()
+    }
+
+    fn capture_monitor(
+        &self,
+        monitor: &MonitorInfo,
+        crop_region: Option<ElementRect>,
+        _exclude_window: Option<&tauri::Window>,
+    ) -> Option<DynamicImage> {
+        let fake_monitor = self
+            .monitors
+            .iter()
+            .find(|fake_monitor| fake_monitor.name == monitor.name)?;
+        if fake_monitor.fail {
+            return None;
+        }
+
+        let rect = fake_monitor.rect;
+        let crop_region = crop_region.unwrap_or(ElementRect {
+            min_x: 0,
+            min_y: 0,
+            max_x: rect.max_x - rect.min_x,
+            max_y: rect.max_y - rect.min_y,
+        });
+
+        Some(DynamicImage::ImageRgb8(RgbImage::from_fn(
+            (crop_region.max_x - crop_region.min_x) as u32,
+            (crop_region.max_y - crop_region.min_y) as u32,
+            |x, y| {
+                Self::pixel(
+                    rect.min_x + crop_region.min_x + x as i32,
+                    rect.min_y + crop_region.min_y + y as i32,
+                )
+            },
+        )))
+    }
+
+    fn color_effect(&self) -> Option<[f32; 25]> {
+        self.color_effect
+    }
+}
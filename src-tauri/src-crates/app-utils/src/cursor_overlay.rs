@@ -0,0 +1,589 @@
+use image::{DynamicImage, RgbaImage, imageops::FilterType};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::capture_scale::CaptureScaleMapping;
+
+/// 鼠标指针的绘制样式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorStyle {
+    /// 只绘制指针
+    #[default]
+    Plain,
+    /// 在指针下方绘制半透明的圆形高亮
+    Highlight,
+    /// 在指针周围绘制点击圈
+    ClickRing,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct CursorOverlayOptions {
+    pub style: CursorStyle,
+    /// 高亮或点击圈的颜色，RGBA，未指定时使用样式的默认颜色
+    pub color: Option<[u8; 4]>,
+    /// 高亮或点击圈的半径，物理像素
+    pub radius: u32,
+}
+
+impl Default for CursorOverlayOptions {
+    fn default() -> Self {
+        Self {
+            style: CursorStyle::Plain,
+            color: None,
+            radius: 24,
+        }
+    }
+}
+
+impl CursorOverlayOptions {
+    fn color(&self) -> [u8; 4] {
+        match self.color {
+            Some(color) => color,
+            None => match self.style {
+                CursorStyle::ClickRing => [255, 77, 79, 220],
+                _ => [255, 214, 0, 96],
+            },
+        }
+    }
+}
+
+/// 鼠标指针的图像，hotspot 为指针位置在图像中的坐标
+#[derive(Debug, Clone)]
+pub struct CursorImage {
+    pub image: RgbaImage,
+    pub hotspot_x: i32,
+    pub hotspot_y: i32,
+}
+
+/// 默认箭头指针，B 为黑色描边，W 为白色填充
+const DEFAULT_ARROW: [&str; 19] = [
+    "B           ",
+    "BB          ",
+    "BWB         ",
+    "BWWB        ",
+    "BWWWB       ",
+    "BWWWWB      ",
+    "BWWWWWB     ",
+    "BWWWWWWB    ",
+    "BWWWWWWWB   ",
+    "BWWWWWWWWB  ",
+    "BWWWWWWWWWB ",
+    "BWWWWWWBBBBB",
+    "BWWWBWWB    ",
+    "BWWBBWWB    ",
+    "BWB  BWWB   ",
+    "BB   BWWB   ",
+    "B     BWWB  ",
+    "      BWWB  ",
+    "       BB   ",
+];
+
+impl CursorImage {
+    /// 无法获取系统指针时使用的箭头
+    pub fn default_arrow() -> Self {
+        let image = RgbaImage::from_fn(
+            DEFAULT_ARROW[0].len() as u32,
+            DEFAULT_ARROW.len() as u32,
+            |x, y| match DEFAULT_ARROW[y as usize].as_bytes()[x as usize] {
+                b'B' => image::Rgba([0, 0, 0, 255]),
+                b'W' => image::Rgba([255, 255, 255, 255]),
+                _ => image::Rgba([0, 0, 0, 0]),
+            },
+        );
+
+        Self {
+            image,
+            hotspot_x: 0,
+            hotspot_y: 0,
+        }
+    }
+
+    /// 将 XFixes 返回的预乘 alpha 的 ARGB 像素转为指针图像
+    pub fn from_premultiplied_argb(
+        pixels: &[u32],
+        width: u32,
+        height: u32,
+        hotspot_x: i32,
+        hotspot_y: i32,
+    ) -> Result<Self, String> {
+        if pixels.len() < (width * height) as usize {
+            return Err(format!(
+                "[CursorImage::from_premultiplied_argb] Invalid cursor size: {}x{}, pixels: {}",
+                width,
+                height,
+                pixels.len()
+            ));
+        }
+
+        let image = RgbaImage::from_fn(width, height, |x, y| {
+            let pixel = pixels[(y * width + x) as usize];
+            let alpha = (pixel >> 24) as u8;
+            let unpremultiply = |value: u32| {
+                if alpha == 0 {
+                    0
+                } else {
+                    ((value & 0xff) * 255 / alpha as u32).min(255) as u8
+                }
+            };
+
+            image::Rgba([
+                unpremultiply(pixel >> 16),
+                unpremultiply(pixel >> 8),
+                unpremultiply(pixel),
+                alpha,
+            ])
+        });
+
+        Ok(Self {
+            image,
+            hotspot_x,
+            hotspot_y,
+        })
+    }
+
+    /// 按比例缩放指针，热点随之移动
+    pub fn scaled(&self, scale: f32) -> Self {
+        if (scale - 1.0).abs() < f32::EPSILON || scale <= 0.0 {
+            return self.clone();
+        }
+
+        let width = ((self.image.width() as f32 * scale).round() as u32).max(1);
+        let height = ((self.image.height() as f32 * scale).round() as u32).max(1);
+
+        Self {
+            image: image::imageops::resize(&self.image, width, height, FilterType::CatmullRom),
+            hotspot_x: (self.hotspot_x as f32 * scale).round() as i32,
+            hotspot_y: (self.hotspot_y as f32 * scale).round() as i32,
+        }
+    }
+}
+
+/// 获取当前的鼠标指针图像
+///
+/// Linux X11 通过 XFixes 获取，Windows 通过 GetCursorInfo 获取，其它情况使用默认箭头
+pub fn get_cursor_image() -> CursorImage {
+    #[cfg(target_os = "linux")]
+    let result = if crate::capture_backend::CaptureBackend::detect()
+        == crate::capture_backend::CaptureBackend::X11
+    {
+        get_x11_cursor_image()
+    } else {
+        Err(String::from(
+            "[get_cursor_image] Cursor image is not available in Wayland",
+        ))
+    };
+
+    #[cfg(target_os = "windows")]
+    let result = get_windows_cursor_image();
+
+    #[cfg(target_os = "macos")]
+    let result: Result<CursorImage, String> = Err(String::from(
+        "[get_cursor_image] Cursor image is not supported on macOS",
+    ));
+
+    match result {
+        Ok(cursor) => cursor,
+        Err(e) => {
+            log::warn!("{}, fallback to default arrow", e);
+            CursorImage::default_arrow()
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn get_x11_cursor_image() -> Result<CursorImage, String> {
+    use xcb::xfixes;
+
+    let (connection, _) =
+        xcb::Connection::connect_with_extensions(None, &[xcb::Extension::XFixes], &[]).map_err(
+            |e| {
+                format!(
+                    "[get_x11_cursor_image] Failed to connect to X server: {}",
+                    e
+                )
+            },
+        )?;
+
+    // 使用 XFixes 的请求前必须先协商版本
+    let cookie = connection.send_request(&xfixes::QueryVersion {
+        client_major_version: 4,
+        client_minor_version: 0,
+    });
+    connection.wait_for_reply(cookie).map_err(|e| {
+        format!(
+            "[get_x11_cursor_image] Failed to query XFixes version: {}",
+            e
+        )
+    })?;
+
+    let cookie = connection.send_request(&xfixes::GetCursorImage {});
+    let reply = connection
+        .wait_for_reply(cookie)
+        .map_err(|e| format!("[get_x11_cursor_image] Failed to get cursor image: {}", e))?;
+
+    CursorImage::from_premultiplied_argb(
+        reply.cursor_image(),
+        reply.width() as u32,
+        reply.height() as u32,
+        reply.xhot() as i32,
+        reply.yhot() as i32,
+    )
+}
+
+#[cfg(target_os = "windows")]
+fn get_windows_cursor_image() -> Result<CursorImage, String> {
+    use windows::Win32::Graphics::Gdi::{
+        BI_RGB, BITMAP, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, DeleteObject, GetDC,
+        GetDIBits, GetObjectW, HBITMAP, HDC, HGDIOBJ, ReleaseDC,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CURSOR_SHOWING, CURSORINFO, GetCursorInfo, GetIconInfo, HICON, ICONINFO,
+    };
+
+    /// 读取位图的 32 位 BGRA 像素
+    fn get_bitmap_pixels(hdc: HDC, bitmap: HBITMAP) -> Option<(u32, u32, Vec<u8>)> {
+        let mut bitmap_info = BITMAP::default();
+        let size = unsafe {
+            GetObjectW(
+                HGDIOBJ(bitmap.0),
+                std::mem::size_of::<BITMAP>() as i32,
+                Some(&mut bitmap_info as *mut BITMAP as *mut std::ffi::c_void),
+            )
+        };
+        if size == 0 {
+            return None;
+        }
+
+        let (width, height) = (bitmap_info.bmWidth as u32, bitmap_info.bmHeight as u32);
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width as i32,
+                // 负数表示从上到下存储
+                biHeight: -(height as i32),
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        let lines = unsafe {
+            GetDIBits(
+                hdc,
+                bitmap,
+                0,
+                height,
+                Some(pixels.as_mut_ptr() as *mut std::ffi::c_void),
+                &mut info,
+                DIB_RGB_COLORS,
+            )
+        };
+        if lines == 0 {
+            return None;
+        }
+
+        Some((width, height, pixels))
+    }
+
+    let mut cursor_info = CURSORINFO {
+        cbSize: std::mem::size_of::<CURSORINFO>() as u32,
+        ..Default::default()
+    };
+    unsafe { GetCursorInfo(&mut cursor_info) }.map_err(|e| {
+        format!(
+            "[get_windows_cursor_image] Failed to get cursor info: {}",
+            e
+        )
+    })?;
+    if cursor_info.flags != CURSOR_SHOWING {
+        return Err(String::from(
+            "[get_windows_cursor_image] Cursor is not showing",
+        ));
+    }
+
+    let mut icon_info = ICONINFO::default();
+    unsafe { GetIconInfo(HICON(cursor_info.hCursor.0), &mut icon_info) }
+        .map_err(|e| format!("[get_windows_cursor_image] Failed to get icon info: {}", e))?;
+
+    let hdc = unsafe { GetDC(None) };
+    let color_pixels = if icon_info.hbmColor.is_invalid() {
+        None
+    } else {
+        get_bitmap_pixels(hdc, icon_info.hbmColor)
+    };
+    let mask_pixels = get_bitmap_pixels(hdc, icon_info.hbmMask);
+    unsafe {
+        ReleaseDC(None, hdc);
+        if !icon_info.hbmColor.is_invalid() {
+            let _ = DeleteObject(HGDIOBJ(icon_info.hbmColor.0));
+        }
+        let _ = DeleteObject(HGDIOBJ(icon_info.hbmMask.0));
+    }
+
+    let (mask_width, mask_height, mask_pixels) = match mask_pixels {
+        Some(mask_pixels) => mask_pixels,
+        None => {
+            return Err(String::from(
+                "[get_windows_cursor_image] Failed to get cursor mask",
+            ));
+        }
+    };
+
+    let image = match color_pixels {
+        Some((width, height, color_pixels)) => {
+            // 没有 alpha 通道的彩色指针使用 AND 掩码作为透明度
+            let has_alpha = color_pixels.chunks_exact(4).any(|pixel| pixel[3] != 0);
+            RgbaImage::from_fn(width, height, |x, y| {
+                let index = ((y * width + x) * 4) as usize;
+                let alpha = if has_alpha {
+                    color_pixels[index + 3]
+                } else if mask_pixels.get(index).copied().unwrap_or(0) == 0 {
+                    255
+                } else {
+                    0
+                };
+
+                image::Rgba([
+                    color_pixels[index + 2],
+                    color_pixels[index + 1],
+                    color_pixels[index],
+                    alpha,
+                ])
+            })
+        }
+        None => {
+            // 单色指针的掩码上半部分为 AND 掩码，下半部分为 XOR 掩码
+            let height = mask_height / 2;
+            RgbaImage::from_fn(mask_width, height, |x, y| {
+                let and_index = ((y * mask_width + x) * 4) as usize;
+                let xor_index = (((y + height) * mask_width + x) * 4) as usize;
+                let and_bit = mask_pixels[and_index] != 0;
+                let xor_bit = mask_pixels[xor_index] != 0;
+
+                match (and_bit, xor_bit) {
+                    (true, false) => image::Rgba([0, 0, 0, 0]),
+                    (false, false) => image::Rgba([0, 0, 0, 255]),
+                    (false, true) => image::Rgba([255, 255, 255, 255]),
+                    // 反色的像素无法表示，绘制为黑色
+                    (true, true) => image::Rgba([0, 0, 0, 255]),
+                }
+            })
+        }
+    };
+
+    Ok(CursorImage {
+        image,
+        hotspot_x: icon_info.xHotspot as i32,
+        hotspot_y: icon_info.yHotspot as i32,
+    })
+}
+
+/// 按照覆盖率将 RGBA 颜色混合到 RGB 或 RGBA 像素上
+fn blend_pixel(pixel: &mut [u8], color: [u8; 4], coverage: f32) {
+    let alpha = color[3] as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+    if alpha <= 0.0 {
+        return;
+    }
+
+    for channel in 0..3 {
+        pixel[channel] =
+            (pixel[channel] as f32 * (1.0 - alpha) + color[channel] as f32 * alpha).round() as u8;
+    }
+    if pixel.len() == 4 {
+        pixel[3] = (pixel[3] as f32 + (255.0 - pixel[3] as f32) * alpha).round() as u8;
+    }
+}
+
+/// 在截图上绘制鼠标指针
+///
+/// @param position 指针热点在截图中的坐标
+pub fn draw_cursor(
+    image: &mut DynamicImage,
+    cursor: &CursorImage,
+    position: (i32, i32),
+    options: &CursorOverlayOptions,
+) {
+    if !matches!(
+        image,
+        DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_)
+    ) {
+        *image = DynamicImage::ImageRgb8(image.to_rgb8());
+    }
+
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    let (channel_count, pixels): (usize, &mut [u8]) = match image {
+        DynamicImage::ImageRgb8(image) => (3, image),
+        DynamicImage::ImageRgba8(image) => (4, image),
+        _ => unreachable!(),
+    };
+    let mut blend = |x: i32, y: i32, color: [u8; 4], coverage: f32| {
+        if x < 0 || y < 0 || x >= width || y >= height {
+            return;
+        }
+
+        let index = (y as usize * width as usize + x as usize) * channel_count;
+        blend_pixel(&mut pixels[index..index + channel_count], color, coverage);
+    };
+
+    // 高亮和点击圈绘制在指针下方，边缘做抗锯齿
+    let radius = options.radius as f32;
+    let color = options.color();
+    let ring_width = (radius / 6.0).max(2.0);
+    if options.style != CursorStyle::Plain && options.radius > 0 {
+        let bound = options.radius as i32 + 1;
+        for offset_y in -bound..=bound {
+            for offset_x in -bound..=bound {
+                let distance = ((offset_x * offset_x + offset_y * offset_y) as f32).sqrt();
+                let coverage = match options.style {
+                    CursorStyle::Highlight => radius + 0.5 - distance,
+                    CursorStyle::ClickRing => {
+                        ring_width / 2.0 + 0.5 - (distance - (radius - ring_width / 2.0)).abs()
+                    }
+                    CursorStyle::Plain => 0.0,
+                };
+                if coverage > 0.0 {
+                    blend(
+                        position.0 + offset_x,
+                        position.1 + offset_y,
+                        color,
+                        coverage,
+                    );
+                }
+            }
+        }
+    }
+
+    let (origin_x, origin_y) = (position.0 - cursor.hotspot_x, position.1 - cursor.hotspot_y);
+    for (x, y, pixel) in cursor.image.enumerate_pixels() {
+        blend(origin_x + x as i32, origin_y + y as i32, pixel.0, 1.0);
+    }
+}
+
+/// 在截取的显示器图像上绘制鼠标指针，鼠标不在截图中时不绘制
+///
+/// @param image_origin 截图左上角在桌面中的物理像素坐标
+pub fn overlay_cursor(
+    app: &AppHandle,
+    image: &mut DynamicImage,
+    image_origin: (i32, i32),
+    options: &CursorOverlayOptions,
+) -> Result<(), String> {
+    let (mouse_x, mouse_y) = crate::get_mouse_position(app)?;
+    let position = (mouse_x - image_origin.0, mouse_y - image_origin.1);
+    if position.0 < 0
+        || position.1 < 0
+        || position.0 >= image.width() as i32
+        || position.1 >= image.height() as i32
+    {
+        return Ok(());
+    }
+
+    draw_cursor(image, &get_cursor_image(), position, options);
+
+    Ok(())
+}
+
+/// 在拼接后的截图上绘制鼠标指针，重新采样时指针按照所在显示器的比例缩放
+pub fn overlay_cursor_with_mapping(
+    app: &AppHandle,
+    image: &mut DynamicImage,
+    scale_mapping: &CaptureScaleMapping,
+    options: &CursorOverlayOptions,
+) -> Result<(), String> {
+    let (mouse_x, mouse_y) = crate::get_mouse_position(app)?;
+    let monitor_mapping = match scale_mapping.find_monitor(mouse_x, mouse_y) {
+        Some(monitor_mapping) => monitor_mapping,
+        None => return Ok(()),
+    };
+    let position = match scale_mapping.source_to_target(mouse_x, mouse_y) {
+        Some(position) => position,
+        None => return Ok(()),
+    };
+
+    let cursor = get_cursor_image().scaled(monitor_mapping.scale);
+    let options = CursorOverlayOptions {
+        radius: (options.radius as f32 * monitor_mapping.scale).round() as u32,
+        ..*options
+    };
+    draw_cursor(image, &cursor, position, &options);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn test_draw_cursor() {
+        // 预乘 alpha 的半透明白色和透明像素
+        let cursor = CursorImage::from_premultiplied_argb(
+            &[0x80808080, 0, 0xffffffff, 0xff000000],
+            2,
+            2,
+            1,
+            1,
+        )
+        .unwrap();
+        assert_eq!(cursor.image.get_pixel(0, 0).0, [255, 255, 255, 128]);
+        assert_eq!(cursor.image.get_pixel(1, 0).0, [0, 0, 0, 0]);
+        assert!(CursorImage::from_premultiplied_argb(&[0; 3], 2, 2, 0, 0).is_err());
+
+        let mut image = DynamicImage::ImageRgb8(RgbImage::new(40, 40));
+        draw_cursor(
+            &mut image,
+            &cursor,
+            (10, 10),
+            &CursorOverlayOptions::default(),
+        );
+        let rgb_image = image.to_rgb8();
+        assert_eq!(*rgb_image.get_pixel(9, 9), Rgb([128, 128, 128]));
+        assert_eq!(*rgb_image.get_pixel(10, 9), Rgb([0, 0, 0]));
+        assert_eq!(*rgb_image.get_pixel(9, 10), Rgb([255, 255, 255]));
+        assert_eq!(*rgb_image.get_pixel(10, 10), Rgb([0, 0, 0]));
+
+        // 高亮覆盖半径内的像素，点击圈中心保持不变
+        let options = CursorOverlayOptions {
+            style: CursorStyle::Highlight,
+            color: Some([255, 0, 0, 255]),
+            radius: 8,
+        };
+        let mut image = DynamicImage::ImageRgb8(RgbImage::new(40, 40));
+        draw_cursor(
+            &mut image,
+            &CursorImage::default_arrow(),
+            (20, 20),
+            &options,
+        );
+        let rgb_image = image.to_rgb8();
+        assert_eq!(*rgb_image.get_pixel(20, 20), Rgb([0, 0, 0]));
+        assert_eq!(*rgb_image.get_pixel(21, 22), Rgb([255, 255, 255]));
+        assert_eq!(*rgb_image.get_pixel(15, 20), Rgb([255, 0, 0]));
+        assert_eq!(*rgb_image.get_pixel(10, 20), Rgb([0, 0, 0]));
+
+        let options = CursorOverlayOptions {
+            style: CursorStyle::ClickRing,
+            ..options
+        };
+        let mut image = DynamicImage::ImageRgb8(RgbImage::new(40, 40));
+        draw_cursor(
+            &mut image,
+            &CursorImage::default_arrow(),
+            (20, 20),
+            &options,
+        );
+        let rgb_image = image.to_rgb8();
+        assert_eq!(*rgb_image.get_pixel(17, 20), Rgb([0, 0, 0]));
+        assert_eq!(*rgb_image.get_pixel(13, 20), Rgb([255, 0, 0]));
+
+        // 超出截图的部分被裁剪
+        let scaled_cursor = CursorImage::default_arrow().scaled(2.0);
+        assert_eq!(scaled_cursor.image.dimensions(), (24, 38));
+        draw_cursor(&mut image, &scaled_cursor, (35, 35), &options);
+    }
+}
@@ -0,0 +1,432 @@
+use image::codecs::avif::AvifEncoder;
+use image::codecs::bmp::BmpEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::codecs::qoi::QoiEncoder;
+use image::codecs::tiff::TiffEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, GenericImageView};
+use jpeg_encoder::{ColorType as JpegColorType, Encoder as JpegEncoder, SamplingFactor};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::io::Cursor;
+use std::path::Path;
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::EncoderOptions;
+use zune_jpegxl::JxlSimpleEncoder;
+
+use crate::image_format::ImageFileFormat;
+use crate::image_metadata::{ImageMetadata, write_image_metadata};
+use crate::png_optimizer::encode_optimized_png;
+
+/// PNG 的压缩等级
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PngCompressionLevel {
+    #[default]
+    Fast,
+    Default,
+    Best,
+}
+
+/// PNG 的行过滤方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PngFilter {
+    NoFilter,
+    Sub,
+    Up,
+    Avg,
+    #[default]
+    Paeth,
+    /// 逐行选择效果最好的过滤方式
+    Adaptive,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct PngEncoderOptions {
+    pub compression_level: PngCompressionLevel,
+    pub filter: PngFilter,
+    /// 尝试调色板和所有过滤方式，以最高压缩等级编码后选择最小的结果，忽略 compression_level 和 filter
+    pub optimize: bool,
+    /// 优化时颜色超过 256 种的图片也量化为调色板，有损
+    pub quantize: bool,
+}
+
+impl Default for PngEncoderOptions {
+    fn default() -> Self {
+        Self {
+            compression_level: PngCompressionLevel::Fast,
+            filter: PngFilter::Paeth,
+            optimize: false,
+            quantize: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct AvifEncoderOptions {
+    /// 编码速度，1-10，越快压缩率越低
+    pub speed: u8,
+    /// 质量，1-100
+    pub quality: u8,
+}
+
+impl Default for AvifEncoderOptions {
+    fn default() -> Self {
+        Self {
+            speed: 10,
+            quality: 80,
+        }
+    }
+}
+
+/// JPEG 的色度抽样方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum JpegChromaSubsampling {
+    /// 不抽样，文字边缘不会出现色彩溢出
+    #[default]
+    Yuv444,
+    /// 水平方向抽样
+    Yuv422,
+    /// 水平和垂直方向抽样，文件最小
+    Yuv420,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct JpegEncoderOptions {
+    /// 质量，1-100
+    pub quality: u8,
+    pub chroma_subsampling: JpegChromaSubsampling,
+}
+
+impl Default for JpegEncoderOptions {
+    fn default() -> Self {
+        Self {
+            quality: 80,
+            chroma_subsampling: JpegChromaSubsampling::Yuv444,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct WebpEncoderOptions {
+    pub lossless: bool,
+    /// 有损编码的质量，1-100，无损编码时忽略
+    pub quality: u8,
+}
+
+impl Default for WebpEncoderOptions {
+    fn default() -> Self {
+        Self {
+            lossless: true,
+            quality: 80,
+        }
+    }
+}
+
+/// 图片编码格式及编码参数，未传入的参数使用默认值
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub enum ImageEncoder {
+    Webp(WebpEncoderOptions),
+    Png(PngEncoderOptions),
+    Avif(AvifEncoderOptions),
+    Jpeg(JpegEncoderOptions),
+    /// 无损编码
+    JpegXl,
+    Qoi,
+    Tiff,
+    Bmp,
+}
+
+impl ImageEncoder {
+    pub fn format(&self) -> ImageFileFormat {
+        match self {
+            ImageEncoder::Webp(_) => ImageFileFormat::Webp,
+            ImageEncoder::Png(_) => ImageFileFormat::Png,
+            ImageEncoder::Avif(_) => ImageFileFormat::Avif,
+            ImageEncoder::Jpeg(_) => ImageFileFormat::Jpeg,
+            ImageEncoder::JpegXl => ImageFileFormat::JpegXl,
+            ImageEncoder::Qoi => ImageFileFormat::Qoi,
+            ImageEncoder::Tiff => ImageFileFormat::Tiff,
+            ImageEncoder::Bmp => ImageFileFormat::Bmp,
+        }
+    }
+
+    /// 编码后图片可以使用的文件扩展名，第一个为默认扩展名
+    pub fn extensions(&self) -> &'static [&'static str] {
+        self.format().info().extensions
+    }
+
+    pub fn extension(&self) -> &'static str {
+        self.extensions()[0]
+    }
+
+    /// 编码参数是否为无损编码
+    pub fn is_lossless(&self) -> bool {
+        match self {
+            ImageEncoder::Png(options) => !(options.optimize && options.quantize),
+            ImageEncoder::Webp(options) => options.lossless,
+            ImageEncoder::Avif(_) | ImageEncoder::Jpeg(_) => false,
+            ImageEncoder::JpegXl | ImageEncoder::Qoi | ImageEncoder::Tiff | ImageEncoder::Bmp => {
+                true
+            }
+        }
+    }
+}
+
+/// 转换为 8 位的 RGB 或 RGBA，已经是时不复制
+fn to_rgb8_or_rgba8(image: &DynamicImage) -> Cow<'_, DynamicImage> {
+    match image {
+        DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => Cow::Borrowed(image),
+        _ if image.color().has_alpha() => Cow::Owned(DynamicImage::ImageRgba8(image.to_rgba8())),
+        _ => Cow::Owned(DynamicImage::ImageRgb8(image.to_rgb8())),
+    }
+}
+
+pub fn encode_image(image: &DynamicImage, encoder: &ImageEncoder) -> Result<Vec<u8>, String> {
+    let (width, height) = image.dimensions();
+    encoder.format().info().check_size(width, height)?;
+
+    // 编码为指定格式
+    let mut buf = Vec::with_capacity(image.as_bytes().len() / 8);
+
+    let encode_result = match encoder {
+        ImageEncoder::Jpeg(options) => {
+            // jpg 是 8 位 RGB 格式，image 的 JPEG 编码器不支持色度抽样
+            let image = image.to_rgb8();
+            let mut encoder = JpegEncoder::new(&mut buf, options.quality.clamp(1, 100));
+            encoder.set_sampling_factor(match options.chroma_subsampling {
+                JpegChromaSubsampling::Yuv444 => SamplingFactor::R_4_4_4,
+                JpegChromaSubsampling::Yuv422 => SamplingFactor::R_4_2_2,
+                JpegChromaSubsampling::Yuv420 => SamplingFactor::R_4_2_0,
+            });
+
+            // 格式表中 JPEG 的最大尺寸为 65535，不会溢出
+            return match encoder.encode(
+                image.as_raw(),
+                width as u16,
+                height as u16,
+                JpegColorType::Rgb,
+            ) {
+                Ok(_) => Ok(buf),
+                Err(e) => Err(format!(
+                    "[encode_image] Failed to encode image as jpg: {}",
+                    e
+                )),
+            };
+        }
+        ImageEncoder::Webp(options) if options.lossless => {
+            // webp 只支持 8 位
+            to_rgb8_or_rgba8(image).write_with_encoder(WebPEncoder::new_lossless(&mut buf))
+        }
+        ImageEncoder::Webp(options) => {
+            // image 只提供无损的 WebP 编码器，有损编码使用 libwebp
+            let image = to_rgb8_or_rgba8(image);
+            let encoder = if image.color().has_alpha() {
+                webp::Encoder::from_rgba(image.as_bytes(), width, height)
+            } else {
+                webp::Encoder::from_rgb(image.as_bytes(), width, height)
+            };
+
+            return match encoder.encode_simple(false, options.quality.clamp(1, 100) as f32) {
+                Ok(data) => Ok(data.to_vec()),
+                Err(e) => Err(format!(
+                    "[encode_image] Failed to encode image as webp: {:?}",
+                    e
+                )),
+            };
+        }
+        ImageEncoder::Png(options) if options.optimize => {
+            return encode_optimized_png(image, options.quantize);
+        }
+        ImageEncoder::Png(options) => image.write_with_encoder(PngEncoder::new_with_quality(
+            &mut buf,
+            match options.compression_level {
+                PngCompressionLevel::Fast => CompressionType::Fast,
+                PngCompressionLevel::Default => CompressionType::Default,
+                PngCompressionLevel::Best => CompressionType::Best,
+            },
+            match options.filter {
+                PngFilter::NoFilter => FilterType::NoFilter,
+                PngFilter::Sub => FilterType::Sub,
+                PngFilter::Up => FilterType::Up,
+                PngFilter::Avg => FilterType::Avg,
+                PngFilter::Paeth => FilterType::Paeth,
+                PngFilter::Adaptive => FilterType::Adaptive,
+            },
+        )),
+        ImageEncoder::Avif(options) => {
+            image.write_with_encoder(AvifEncoder::new_with_speed_quality(
+                &mut buf,
+                options.speed.clamp(1, 10),
+                options.quality.clamp(1, 100),
+            ))
+        }
+        ImageEncoder::JpegXl => {
+            let image = to_rgb8_or_rgba8(image);
+            let has_alpha = image.color().has_alpha();
+            let encoder = JxlSimpleEncoder::new(
+                image.as_bytes(),
+                EncoderOptions::new(
+                    width as usize,
+                    height as usize,
+                    if has_alpha {
+                        ColorSpace::RGBA
+                    } else {
+                        ColorSpace::RGB
+                    },
+                    BitDepth::Eight,
+                ),
+            );
+
+            return match encoder.encode() {
+                Ok(encoder_result) => Ok(encoder_result),
+                Err(_) => Err(String::from("[encode_image] Failed to encode image as jxl")),
+            };
+        }
+        ImageEncoder::Qoi => to_rgb8_or_rgba8(image).write_with_encoder(QoiEncoder::new(&mut buf)),
+        ImageEncoder::Tiff => image.write_with_encoder(TiffEncoder::new(Cursor::new(&mut buf))),
+        ImageEncoder::Bmp => to_rgb8_or_rgba8(image).write_with_encoder(BmpEncoder::new(&mut buf)),
+    };
+
+    if let Err(e) = encode_result {
+        return Err(format!(
+            "[encode_image] Failed to encode image as {}: {}",
+            encoder.extension(),
+            e
+        ));
+    }
+
+    Ok(buf)
+}
+
+/// 确定保存路径使用的编码器，未指定编码器时根据文件扩展名在格式表中选择格式并使用默认参数
+///
+/// 指定的编码器和文件扩展名不一致时返回错误
+pub fn get_image_encoder_for_file(
+    file_path: &Path,
+    encoder: Option<ImageEncoder>,
+) -> Result<ImageEncoder, String> {
+    let image_format = match ImageFileFormat::from_path(file_path) {
+        Some(image_format) => image_format,
+        None => {
+            return Err(format!(
+                "[get_image_encoder_for_file] Unsupported image format: {}",
+                file_path.display()
+            ));
+        }
+    };
+
+    match encoder {
+        Some(encoder) if encoder.format() != image_format => Err(format!(
+            "[get_image_encoder_for_file] Extension does not match encoder {}: {}",
+            encoder.extension(),
+            file_path.display()
+        )),
+        Some(encoder) => Ok(encoder),
+        None => Ok(image_format.default_encoder()),
+    }
+}
+
+/// 按照保存路径编码图片，编码器的选择见 get_image_encoder_for_file
+///
+/// 未传入元数据时不写入元数据
+pub fn encode_image_for_file(
+    image: &DynamicImage,
+    file_path: &Path,
+    encoder: Option<ImageEncoder>,
+    metadata: Option<&ImageMetadata>,
+) -> Result<Vec<u8>, String> {
+    let encoder = get_image_encoder_for_file(file_path, encoder)?;
+
+    let mut image_buffer = encode_image(image, &encoder)?;
+    if let Some(metadata) = metadata {
+        image_buffer = write_image_metadata(image_buffer, encoder.format(), metadata)?;
+    }
+
+    Ok(image_buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_encode_image_options() {
+        let mut image = RgbaImage::new(32, 16);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            *pixel = Rgba([(x * 8) as u8, (y * 16) as u8, ((x + y) * 5) as u8, 255]);
+        }
+        let image = DynamicImage::ImageRgba8(image);
+
+        // 默认参数的 PNG 和 WebP 可以还原原图
+        for encoder in [
+            ImageEncoder::Png(PngEncoderOptions::default()),
+            ImageEncoder::Png(PngEncoderOptions {
+                compression_level: PngCompressionLevel::Best,
+                filter: PngFilter::Adaptive,
+                ..Default::default()
+            }),
+            ImageEncoder::Webp(WebpEncoderOptions::default()),
+        ] {
+            let buf = encode_image(&image, &encoder).unwrap();
+            assert_eq!(image::load_from_memory(&buf).unwrap(), image);
+        }
+
+        // 有损编码的结果尺寸不变
+        for encoder in [
+            ImageEncoder::Jpeg(JpegEncoderOptions {
+                quality: 50,
+                chroma_subsampling: JpegChromaSubsampling::Yuv444,
+            }),
+            ImageEncoder::Jpeg(JpegEncoderOptions {
+                quality: 50,
+                chroma_subsampling: JpegChromaSubsampling::Yuv422,
+            }),
+            ImageEncoder::Jpeg(JpegEncoderOptions {
+                quality: 50,
+                chroma_subsampling: JpegChromaSubsampling::Yuv420,
+            }),
+            ImageEncoder::Webp(WebpEncoderOptions {
+                lossless: false,
+                quality: 50,
+            }),
+            ImageEncoder::Avif(AvifEncoderOptions {
+                speed: 10,
+                quality: 50,
+            }),
+        ] {
+            let buf = encode_image(&image, &encoder).unwrap();
+            // 没有启用 AVIF 解码
+            if encoder.format() == ImageFileFormat::Avif {
+                assert!(!buf.is_empty());
+            } else {
+                assert_eq!(
+                    image::load_from_memory(&buf).unwrap().dimensions(),
+                    image.dimensions()
+                );
+            }
+        }
+
+        // 只传入格式时使用默认参数
+        let encoder: ImageEncoder = serde_json::from_str(r#"{"format":"jpeg"}"#).unwrap();
+        assert_eq!(encoder, ImageEncoder::Jpeg(JpegEncoderOptions::default()));
+        let encoder: ImageEncoder =
+            serde_json::from_str(r#"{"format":"webp","lossless":false}"#).unwrap();
+        assert_eq!(
+            encoder,
+            ImageEncoder::Webp(WebpEncoderOptions {
+                lossless: false,
+                ..Default::default()
+            })
+        );
+    }
+}
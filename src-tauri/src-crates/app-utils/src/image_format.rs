@@ -0,0 +1,295 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::image_encoder::{
+    AvifEncoderOptions, ImageEncoder, JpegEncoderOptions, PngEncoderOptions, WebpEncoderOptions,
+};
+
+/// 保存图片支持的格式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFileFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Avif,
+    JpegXl,
+    Qoi,
+    Tiff,
+    Bmp,
+}
+
+/// 图片格式的 MIME 类型、扩展名和支持的特性
+#[derive(Debug)]
+pub struct ImageFormatInfo {
+    pub format: ImageFileFormat,
+    /// MIME 类型，第一个为标准类型，其余为兼容的别名
+    pub mime_types: &'static [&'static str],
+    /// 文件扩展名，第一个为默认扩展名
+    pub extensions: &'static [&'static str],
+    /// 是否支持透明度，不支持时保存会丢弃透明度
+    pub alpha: bool,
+    /// 格式是否支持动画，目前只保存静态图片
+    pub animation: bool,
+    /// 编码器是否支持无损编码
+    pub lossless: bool,
+    /// 编码器是否支持有损编码
+    pub lossy: bool,
+    /// 支持的最大宽度
+    pub max_width: u32,
+    /// 支持的最大高度
+    pub max_height: u32,
+}
+
+/// 所有保存图片的路径都通过该表确定格式和编码器
+pub static IMAGE_FORMAT_REGISTRY: [ImageFormatInfo; 8] = [
+    ImageFormatInfo {
+        format: ImageFileFormat::Png,
+        mime_types: &["image/png"],
+        extensions: &["png"],
+        alpha: true,
+        animation: true,
+        lossless: true,
+        lossy: true,
+        max_width: i32::MAX as u32,
+        max_height: i32::MAX as u32,
+    },
+    ImageFormatInfo {
+        format: ImageFileFormat::Jpeg,
+        mime_types: &["image/jpeg"],
+        extensions: &["jpg", "jpeg"],
+        alpha: false,
+        animation: false,
+        lossless: false,
+        lossy: true,
+        max_width: u16::MAX as u32,
+        max_height: u16::MAX as u32,
+    },
+    ImageFormatInfo {
+        format: ImageFileFormat::Webp,
+        mime_types: &["image/webp"],
+        extensions: &["webp"],
+        alpha: true,
+        animation: true,
+        lossless: true,
+        lossy: true,
+        max_width: 16383,
+        max_height: 16383,
+    },
+    ImageFormatInfo {
+        format: ImageFileFormat::Avif,
+        mime_types: &["image/avif"],
+        extensions: &["avif"],
+        alpha: true,
+        animation: true,
+        lossless: false,
+        lossy: true,
+        max_width: u16::MAX as u32,
+        max_height: u16::MAX as u32,
+    },
+    ImageFormatInfo {
+        format: ImageFileFormat::JpegXl,
+        mime_types: &["image/jxl", "image/jpeg-xl"],
+        extensions: &["jxl"],
+        alpha: true,
+        animation: true,
+        lossless: true,
+        lossy: false,
+        max_width: 1 << 30,
+        max_height: 1 << 30,
+    },
+    ImageFormatInfo {
+        format: ImageFileFormat::Qoi,
+        mime_types: &["image/qoi", "image/x-qoi"],
+        extensions: &["qoi"],
+        alpha: true,
+        animation: false,
+        lossless: true,
+        lossy: false,
+        max_width: u32::MAX,
+        max_height: u32::MAX,
+    },
+    ImageFormatInfo {
+        format: ImageFileFormat::Tiff,
+        mime_types: &["image/tiff"],
+        extensions: &["tiff", "tif"],
+        alpha: true,
+        animation: false,
+        lossless: true,
+        lossy: false,
+        max_width: u32::MAX,
+        max_height: u32::MAX,
+    },
+    ImageFormatInfo {
+        format: ImageFileFormat::Bmp,
+        mime_types: &["image/bmp", "image/x-bmp"],
+        extensions: &["bmp"],
+        alpha: true,
+        animation: false,
+        lossless: true,
+        lossy: false,
+        max_width: i32::MAX as u32,
+        max_height: i32::MAX as u32,
+    },
+];
+
+impl ImageFileFormat {
+    pub fn info(self) -> &'static ImageFormatInfo {
+        IMAGE_FORMAT_REGISTRY
+            .iter()
+            .find(|info| info.format == self)
+            .expect("[ImageFileFormat::info] Format is not registered")
+    }
+
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        IMAGE_FORMAT_REGISTRY
+            .iter()
+            .find(|info| {
+                info.mime_types
+                    .iter()
+                    .any(|info_mime_type| info_mime_type.eq_ignore_ascii_case(mime_type))
+            })
+            .map(|info| info.format)
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        IMAGE_FORMAT_REGISTRY
+            .iter()
+            .find(|info| {
+                info.extensions
+                    .iter()
+                    .any(|info_extension| info_extension.eq_ignore_ascii_case(extension))
+            })
+            .map(|info| info.format)
+    }
+
+    pub fn from_path(file_path: &Path) -> Option<Self> {
+        Self::from_extension(file_path.extension()?.to_str()?)
+    }
+
+    /// 格式的默认编码器
+    pub fn default_encoder(self) -> ImageEncoder {
+        match self {
+            ImageFileFormat::Png => ImageEncoder::Png(PngEncoderOptions::default()),
+            ImageFileFormat::Jpeg => ImageEncoder::Jpeg(JpegEncoderOptions::default()),
+            ImageFileFormat::Webp => ImageEncoder::Webp(WebpEncoderOptions::default()),
+            ImageFileFormat::Avif => ImageEncoder::Avif(AvifEncoderOptions::default()),
+            ImageFileFormat::JpegXl => ImageEncoder::JpegXl,
+            ImageFileFormat::Qoi => ImageEncoder::Qoi,
+            ImageFileFormat::Tiff => ImageEncoder::Tiff,
+            ImageFileFormat::Bmp => ImageEncoder::Bmp,
+        }
+    }
+
+    /// 数据是否已经是该格式，image 无法识别 JPEG XL，总是返回 false
+    pub fn is_encoded_data(self, data: &[u8]) -> bool {
+        let image_format = match self {
+            ImageFileFormat::Png => image::ImageFormat::Png,
+            ImageFileFormat::Jpeg => image::ImageFormat::Jpeg,
+            ImageFileFormat::Webp => image::ImageFormat::WebP,
+            ImageFileFormat::Avif => image::ImageFormat::Avif,
+            ImageFileFormat::JpegXl => return false,
+            ImageFileFormat::Qoi => image::ImageFormat::Qoi,
+            ImageFileFormat::Tiff => image::ImageFormat::Tiff,
+            ImageFileFormat::Bmp => image::ImageFormat::Bmp,
+        };
+
+        image::guess_format(data).ok() == Some(image_format)
+    }
+}
+
+impl ImageFormatInfo {
+    pub fn check_size(&self, width: u32, height: u32) -> Result<(), String> {
+        if width > self.max_width || height > self.max_height {
+            return Err(format!(
+                "[ImageFormatInfo::check_size] Image size {}x{} exceeds the maximum size {}x{} of {}",
+                width, height, self.max_width, self.max_height, self.extensions[0]
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_encoder::{encode_image, get_image_encoder_for_file};
+    use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+
+    #[test]
+    fn test_image_format_registry() {
+        let mut image = RgbaImage::new(24, 12);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            *pixel = Rgba([(x * 10) as u8, (y * 20) as u8, 90, 128 + x as u8]);
+        }
+        let image = DynamicImage::ImageRgba8(image);
+
+        for info in IMAGE_FORMAT_REGISTRY.iter() {
+            let format = info.format;
+            assert_eq!(format.info().format, format);
+            for mime_type in info.mime_types {
+                assert_eq!(ImageFileFormat::from_mime_type(mime_type), Some(format));
+            }
+            for extension in info.extensions {
+                assert_eq!(
+                    ImageFileFormat::from_extension(&extension.to_uppercase()),
+                    Some(format)
+                );
+            }
+
+            let encoder = format.default_encoder();
+            assert_eq!(encoder.format(), format);
+            assert!(if encoder.is_lossless() {
+                info.lossless
+            } else {
+                info.lossy
+            });
+            let buf = encode_image(&image, &encoder).unwrap();
+            if format == ImageFileFormat::JpegXl || format == ImageFileFormat::Avif {
+                assert!(!buf.is_empty());
+                continue;
+            }
+            assert!(format.is_encoded_data(&buf));
+
+            // 无损编码可以还原原图，不支持透明度的格式丢弃透明度
+            let decoded_image = image::load_from_memory(&buf).unwrap();
+            assert_eq!(decoded_image.dimensions(), (24, 12));
+            assert_eq!(decoded_image.color().has_alpha(), info.alpha);
+            if encoder.is_lossless() {
+                assert_eq!(decoded_image.to_rgba8(), image.to_rgba8());
+            }
+        }
+
+        // 有损的 WebP 保留透明度
+        let encoder = ImageEncoder::Webp(WebpEncoderOptions {
+            lossless: false,
+            quality: 80,
+        });
+        assert!(ImageFileFormat::Webp.info().lossy && !encoder.is_lossless());
+        let buf = encode_image(&image, &encoder).unwrap();
+        assert!(ImageFileFormat::Webp.is_encoded_data(&buf));
+        let decoded_image = image::load_from_memory(&buf).unwrap();
+        assert_eq!(decoded_image.dimensions(), (24, 12));
+        assert!(decoded_image.color().has_alpha());
+
+        assert_eq!(
+            ImageFileFormat::from_path(Path::new("capture.tif")),
+            Some(ImageFileFormat::Tiff)
+        );
+        assert_eq!(ImageFileFormat::from_path(Path::new("capture")), None);
+        assert!(ImageFileFormat::Webp.info().check_size(16384, 100).is_err());
+
+        // 指定的编码器必须和扩展名一致
+        assert_eq!(
+            get_image_encoder_for_file(Path::new("capture.jpeg"), None),
+            Ok(ImageFileFormat::Jpeg.default_encoder())
+        );
+        assert_eq!(
+            get_image_encoder_for_file(Path::new("capture.PNG"), Some(ImageEncoder::Qoi))
+                .map_err(|e| e.contains("does not match")),
+            Err(true)
+        );
+        assert!(get_image_encoder_for_file(Path::new("capture"), Some(ImageEncoder::Qoi)).is_err());
+    }
+}
@@ -0,0 +1,1231 @@
+use serde::{Deserialize, Serialize};
+
+use crate::image_format::ImageFileFormat;
+
+const SOFTWARE_NAME: &str = "Snow Shot";
+const XMP_NAMESPACE: &str = "https://snowshot.top/ns/xmp/1.0/";
+const XMP_JPEG_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const JXL_SIGNATURE_BOX: &[u8] = b"\0\0\0\x0cJXL \r\n\x87\n";
+
+/// 保存图片时写入的元数据，未设置的字段不写入
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ImageMetadata {
+    /// 截图时间，Unix 时间戳（毫秒）
+    pub capture_time: Option<i64>,
+    /// 本地时间相对 UTC 的偏移（分钟），未设置时按 UTC 写入
+    pub utc_offset: Option<i32>,
+    pub monitor_name: Option<String>,
+    pub scale_factor: Option<f64>,
+    /// 截图时焦点窗口的应用名称
+    pub app_name: Option<String>,
+    pub window_title: Option<String>,
+    /// 用户备注
+    pub comment: Option<String>,
+    /// 隐私模式，不写入元数据，并移除图片中已有的元数据
+    pub strip: bool,
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|value| !value.is_empty())
+}
+
+impl ImageMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.capture_time.is_none()
+            && self.scale_factor.is_none()
+            && non_empty(&self.monitor_name).is_none()
+            && non_empty(&self.app_name).is_none()
+            && non_empty(&self.window_title).is_none()
+            && non_empty(&self.comment).is_none()
+    }
+
+    /// 截图时间的本地时间 (年, 月, 日, 时, 分, 秒)
+    fn local_date_time(&self) -> Option<(i64, i64, i64, i64, i64, i64)> {
+        let seconds =
+            self.capture_time?.div_euclid(1000) + self.utc_offset.unwrap_or(0) as i64 * 60;
+        let days = seconds.div_euclid(86400);
+        let day_seconds = seconds.rem_euclid(86400);
+
+        // 公历日期换算，见 https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Some((
+            year,
+            month,
+            day,
+            day_seconds / 3600,
+            day_seconds % 3600 / 60,
+            day_seconds % 60,
+        ))
+    }
+
+    fn utc_offset_text(&self) -> String {
+        let utc_offset = self.utc_offset.unwrap_or(0);
+        format!(
+            "{}{:02}:{:02}",
+            if utc_offset < 0 { '-' } else { '+' },
+            utc_offset.abs() / 60,
+            utc_offset.abs() % 60
+        )
+    }
+
+    /// EXIF 的时间格式，如 2025:01:02 03:04:05
+    fn exif_date_time(&self) -> Option<String> {
+        let (year, month, day, hour, minute, second) = self.local_date_time()?;
+        Some(format!(
+            "{:04}:{:02}:{:02} {:02}:{:02}:{:02}",
+            year, month, day, hour, minute, second
+        ))
+    }
+
+    /// ISO 8601 时间格式，如 2025-01-02T03:04:05+08:00
+    fn iso_date_time(&self) -> Option<String> {
+        let (year, month, day, hour, minute, second) = self.local_date_time()?;
+        Some(format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}",
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            self.utc_offset_text()
+        ))
+    }
+
+    /// PNG 文本块的关键字和内容
+    fn png_text_entries(&self) -> Vec<(&'static str, String)> {
+        let mut entries = vec![("Software", SOFTWARE_NAME.to_string())];
+        if let Some(date_time) = self.iso_date_time() {
+            entries.push(("Creation Time", date_time));
+        }
+        if let Some(window_title) = non_empty(&self.window_title) {
+            entries.push(("Title", window_title.to_string()));
+        }
+        if let Some(comment) = non_empty(&self.comment) {
+            entries.push(("Comment", comment.to_string()));
+        }
+        if let Some(app_name) = non_empty(&self.app_name) {
+            entries.push(("Application", app_name.to_string()));
+        }
+        if let Some(monitor_name) = non_empty(&self.monitor_name) {
+            entries.push(("Monitor", monitor_name.to_string()));
+        }
+        if let Some(scale_factor) = self.scale_factor {
+            entries.push(("Scale Factor", scale_factor.to_string()));
+        }
+
+        entries
+    }
+
+    /// 大端序的 TIFF 格式 EXIF 数据
+    ///
+    /// EXIF 没有应用名称、显示器和缩放比例的标签，这些字段只写入 XMP
+    fn exif_data(&self) -> Vec<u8> {
+        let mut exif_entries = vec![ExifEntry::undefined(0x9000, b"0232".to_vec())];
+        let mut ifd0_entries = vec![ExifEntry::ascii(0x0131, SOFTWARE_NAME)];
+
+        if let Some(date_time) = self.exif_date_time() {
+            ifd0_entries.push(ExifEntry::ascii(0x0132, &date_time));
+            exif_entries.push(ExifEntry::ascii(0x9003, &date_time));
+            exif_entries.push(ExifEntry::ascii(0x9011, &self.utc_offset_text()));
+        }
+        if let Some(window_title) = non_empty(&self.window_title) {
+            ifd0_entries.push(ExifEntry::ascii(0x010E, window_title));
+            // Windows 资源管理器读取的 UTF-16 标题
+            ifd0_entries.push(ExifEntry::byte(
+                0x9C9B,
+                window_title
+                    .encode_utf16()
+                    .chain([0])
+                    .flat_map(u16::to_le_bytes)
+                    .collect(),
+            ));
+        }
+        if let Some(comment) = non_empty(&self.comment) {
+            let mut value = b"UNICODE\0".to_vec();
+            value.extend(comment.encode_utf16().flat_map(u16::to_be_bytes));
+            exif_entries.push(ExifEntry::undefined(0x9286, value));
+        }
+
+        // Exif IFD 紧跟在 IFD0 之后
+        ifd0_entries.push(ExifEntry::long(0x8769, 0));
+        ifd0_entries.sort_by_key(|entry| entry.tag);
+        exif_entries.sort_by_key(|entry| entry.tag);
+        let exif_ifd_offset = 8 + ExifEntry::ifd_len(&ifd0_entries) as u32;
+        if let Some(entry) = ifd0_entries.iter_mut().find(|entry| entry.tag == 0x8769) {
+            entry.value = exif_ifd_offset.to_be_bytes().to_vec();
+        }
+
+        let mut data = b"MM\0\x2a\0\0\0\x08".to_vec();
+        ExifEntry::write_ifd(&mut data, &ifd0_entries);
+        ExifEntry::write_ifd(&mut data, &exif_entries);
+
+        data
+    }
+
+    fn xmp_packet(&self) -> String {
+        let mut properties = format!("<xmp:CreatorTool>{}</xmp:CreatorTool>", SOFTWARE_NAME);
+        if let Some(date_time) = self.iso_date_time() {
+            properties.push_str(&format!("<xmp:CreateDate>{}</xmp:CreateDate>", date_time));
+        }
+        if let Some(window_title) = non_empty(&self.window_title) {
+            properties.push_str(&format!(
+                "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>",
+                escape_xml(window_title)
+            ));
+        }
+        if let Some(comment) = non_empty(&self.comment) {
+            properties.push_str(&format!(
+                "<exif:UserComment><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></exif:UserComment>",
+                escape_xml(comment)
+            ));
+        }
+        if let Some(app_name) = non_empty(&self.app_name) {
+            properties.push_str(&format!(
+                "<snowshot:ApplicationName>{}</snowshot:ApplicationName>",
+                escape_xml(app_name)
+            ));
+        }
+        if let Some(monitor_name) = non_empty(&self.monitor_name) {
+            properties.push_str(&format!(
+                "<snowshot:MonitorName>{}</snowshot:MonitorName>",
+                escape_xml(monitor_name)
+            ));
+        }
+        if let Some(scale_factor) = self.scale_factor {
+            properties.push_str(&format!(
+                "<snowshot:ScaleFactor>{}</snowshot:ScaleFactor>",
+                scale_factor
+            ));
+        }
+
+        format!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\
+            <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\
+            <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\
+            <rdf:Description rdf:about=\"\" \
+            xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" \
+            xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+            xmlns:exif=\"http://ns.adobe.com/exif/1.0/\" \
+            xmlns:snowshot=\"{}\">{}</rdf:Description>\
+            </rdf:RDF>\
+            </x:xmpmeta>\
+            <?xpacket end=\"w\"?>",
+            XMP_NAMESPACE, properties
+        )
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// TIFF IFD 中的一项
+struct ExifEntry {
+    tag: u16,
+    field_type: u16,
+    count: u32,
+    value: Vec<u8>,
+}
+
+impl ExifEntry {
+    fn byte(tag: u16, value: Vec<u8>) -> Self {
+        Self {
+            tag,
+            field_type: 1,
+            count: value.len() as u32,
+            value,
+        }
+    }
+
+    /// 兼容常见读取方式，非 ASCII 字符按 UTF-8 写入
+    fn ascii(tag: u16, text: &str) -> Self {
+        let mut value = text.as_bytes().to_vec();
+        value.push(0);
+        Self {
+            tag,
+            field_type: 2,
+            count: value.len() as u32,
+            value,
+        }
+    }
+
+    fn long(tag: u16, value: u32) -> Self {
+        Self {
+            tag,
+            field_type: 4,
+            count: 1,
+            value: value.to_be_bytes().to_vec(),
+        }
+    }
+
+    fn undefined(tag: u16, value: Vec<u8>) -> Self {
+        Self {
+            tag,
+            field_type: 7,
+            count: value.len() as u32,
+            value,
+        }
+    }
+
+    /// 超过 4 字节的值存放在 IFD 之后，按 2 字节对齐
+    fn ifd_len(entries: &[ExifEntry]) -> usize {
+        2 + entries.len() * 12
+            + 4
+            + entries
+                .iter()
+                .filter(|entry| entry.value.len() > 4)
+                .map(|entry| entry.value.len() + entry.value.len() % 2)
+                .sum::<usize>()
+    }
+
+    /// 在 data 末尾写入 IFD，data 从 TIFF 头开始
+    fn write_ifd(data: &mut Vec<u8>, entries: &[ExifEntry]) {
+        let mut value_offset = data.len() + 2 + entries.len() * 12 + 4;
+        let mut values = Vec::new();
+
+        data.extend_from_slice(&(entries.len() as u16).to_be_bytes());
+        for entry in entries {
+            data.extend_from_slice(&entry.tag.to_be_bytes());
+            data.extend_from_slice(&entry.field_type.to_be_bytes());
+            data.extend_from_slice(&entry.count.to_be_bytes());
+
+            if entry.value.len() <= 4 {
+                let mut value = [0; 4];
+                value[..entry.value.len()].copy_from_slice(&entry.value);
+                data.extend_from_slice(&value);
+            } else {
+                data.extend_from_slice(&(value_offset as u32).to_be_bytes());
+                values.extend_from_slice(&entry.value);
+                if entry.value.len() % 2 == 1 {
+                    values.push(0);
+                }
+                value_offset += entry.value.len() + entry.value.len() % 2;
+            }
+        }
+        // 没有下一个 IFD
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&values);
+    }
+}
+
+/// 将元数据写入已编码的图片
+///
+/// PNG 写入文本块和 XMP，JPEG、WebP、AVIF 写入 EXIF 和 XMP，JPEG XL 转换为容器格式后写入 EXIF 和 XMP，
+/// QOI、TIFF、BMP 不写入元数据。写入前会移除图片中已有的元数据，避免重复
+///
+/// AVIF 中已有的 EXIF 和 XMP 存放在 mdat 中，无法安全移除，此时写入和移除都会返回错误
+pub fn write_image_metadata(
+    data: Vec<u8>,
+    format: ImageFileFormat,
+    metadata: &ImageMetadata,
+) -> Result<Vec<u8>, String> {
+    let write = !metadata.strip && !metadata.is_empty();
+    if !write && !metadata.strip {
+        return Ok(data);
+    }
+
+    let result = match format {
+        ImageFileFormat::Png => write_png_metadata(&data, metadata, write),
+        ImageFileFormat::Jpeg => write_jpeg_metadata(&data, metadata, write),
+        ImageFileFormat::Webp => write_webp_metadata(&data, metadata, write),
+        ImageFileFormat::Avif => match has_avif_metadata(&data) {
+            Some(true) => {
+                return Err(String::from(
+                    "[write_image_metadata] AVIF data already contains metadata",
+                ));
+            }
+            Some(false) if write => write_avif_metadata(&data, metadata),
+            Some(false) => return Ok(data),
+            None => None,
+        },
+        ImageFileFormat::JpegXl => write_jxl_metadata(&data, metadata, write),
+        _ => return Ok(data),
+    };
+
+    match result {
+        Some(data) => Ok(data),
+        None => Err(format!(
+            "[write_image_metadata] Failed to write metadata, invalid {} data",
+            format.info().extensions[0]
+        )),
+    }
+}
+
+fn push_png_chunk(data: &mut Vec<u8>, chunk_type: &[u8; 4], chunk_data: &[u8]) {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(chunk_type);
+    hasher.update(chunk_data);
+
+    data.extend_from_slice(&(chunk_data.len() as u32).to_be_bytes());
+    data.extend_from_slice(chunk_type);
+    data.extend_from_slice(chunk_data);
+    data.extend_from_slice(&hasher.finalize().to_be_bytes());
+}
+
+/// ASCII 内容写入 tEXt，其余写入未压缩的 UTF-8 iTXt
+fn push_png_text_chunk(data: &mut Vec<u8>, keyword: &str, text: &str) {
+    let mut chunk_data = keyword.as_bytes().to_vec();
+    chunk_data.push(0);
+    if text.is_ascii() {
+        chunk_data.extend_from_slice(text.as_bytes());
+        push_png_chunk(data, b"tEXt", &chunk_data);
+    } else {
+        // 压缩标志、压缩方式、语言标签、翻译后的关键字
+        chunk_data.extend_from_slice(&[0, 0, 0, 0]);
+        chunk_data.extend_from_slice(text.as_bytes());
+        push_png_chunk(data, b"iTXt", &chunk_data);
+    }
+}
+
+/// 在已编码的 PNG 的文件头之后写入一个文本块，不移除已有的文本块
+pub fn add_png_text_chunk(data: Vec<u8>, keyword: &str, text: &str) -> Result<Vec<u8>, String> {
+    // 签名之后的第一个块是固定长度的 IHDR
+    let header_end = PNG_SIGNATURE.len() + 12 + 13;
+    if !data.starts_with(PNG_SIGNATURE)
+        || data.get(PNG_SIGNATURE.len() + 4..PNG_SIGNATURE.len() + 8) != Some(b"IHDR".as_slice())
+        || data.len() < header_end
+    {
+        return Err(String::from(
+            "[add_png_text_chunk] Failed to add text chunk, invalid png data",
+        ));
+    }
+
+    let mut result = Vec::with_capacity(data.len() + keyword.len() + text.len() + 32);
+    result.extend_from_slice(&data[..header_end]);
+    push_png_text_chunk(&mut result, keyword, text);
+    result.extend_from_slice(&data[header_end..]);
+
+    Ok(result)
+}
+
+fn write_png_metadata(data: &[u8], metadata: &ImageMetadata, write: bool) -> Option<Vec<u8>> {
+    if !data.starts_with(PNG_SIGNATURE) {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(data.len() + 4096);
+    result.extend_from_slice(PNG_SIGNATURE);
+
+    let mut position = PNG_SIGNATURE.len();
+    while position < data.len() {
+        let length = u32::from_be_bytes(data.get(position..position + 4)?.try_into().ok()?);
+        let chunk_end = position.checked_add(12 + length as usize)?;
+        let chunk = data.get(position..chunk_end)?;
+        let chunk_type = &chunk[4..8];
+
+        if !matches!(chunk_type, b"tEXt" | b"zTXt" | b"iTXt" | b"eXIf" | b"tIME") {
+            result.extend_from_slice(chunk);
+        }
+
+        if chunk_type == b"IHDR" && write {
+            for (keyword, text) in metadata.png_text_entries() {
+                push_png_text_chunk(&mut result, keyword, &text);
+            }
+            push_png_text_chunk(&mut result, "XML:com.adobe.xmp", &metadata.xmp_packet());
+        }
+
+        position = chunk_end;
+    }
+
+    Some(result)
+}
+
+fn push_jpeg_segment(data: &mut Vec<u8>, marker: u8, segment_data: &[&[u8]]) -> Option<()> {
+    let length = segment_data.iter().map(|data| data.len()).sum::<usize>() + 2;
+    if length > u16::MAX as usize {
+        return None;
+    }
+
+    data.extend_from_slice(&[0xFF, marker]);
+    data.extend_from_slice(&(length as u16).to_be_bytes());
+    for segment_data in segment_data {
+        data.extend_from_slice(segment_data);
+    }
+
+    Some(())
+}
+
+/// 元数据写入 APP0（JFIF）之后，移除已有的 APP1（EXIF、XMP）、APP13（IPTC）和注释
+fn write_jpeg_metadata(data: &[u8], metadata: &ImageMetadata, write: bool) -> Option<Vec<u8>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let mut metadata_segments = Vec::new();
+    if write {
+        push_jpeg_segment(
+            &mut metadata_segments,
+            0xE1,
+            &[b"Exif\0\0", &metadata.exif_data()],
+        )?;
+        push_jpeg_segment(
+            &mut metadata_segments,
+            0xE1,
+            &[XMP_JPEG_HEADER, metadata.xmp_packet().as_bytes()],
+        )?;
+    }
+
+    let mut result = Vec::with_capacity(data.len() + metadata_segments.len());
+    result.extend_from_slice(&data[..2]);
+
+    let mut position = 2;
+    let mut inserted = false;
+    loop {
+        if *data.get(position)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(position + 1)?;
+
+        if !inserted && marker != 0xE0 {
+            result.extend_from_slice(&metadata_segments);
+            inserted = true;
+        }
+
+        // 扫描数据开始后不再有元数据
+        if marker == 0xDA {
+            result.extend_from_slice(&data[position..]);
+            break;
+        }
+
+        let length = u16::from_be_bytes(data.get(position + 2..position + 4)?.try_into().ok()?);
+        let segment_end = position + 2 + length as usize;
+        let segment = data.get(position..segment_end)?;
+        if !matches!(marker, 0xE1 | 0xED | 0xFE) {
+            result.extend_from_slice(segment);
+        }
+
+        position = segment_end;
+    }
+
+    Some(result)
+}
+
+/// EXIF 和 XMP 需要扩展格式（VP8X），简单格式的图片会转换为扩展格式
+fn write_webp_metadata(data: &[u8], metadata: &ImageMetadata, write: bool) -> Option<Vec<u8>> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return None;
+    }
+
+    let mut chunks: Vec<([u8; 4], Vec<u8>)> = Vec::new();
+    let mut position = 12;
+    while position + 8 <= data.len() {
+        let fourcc: [u8; 4] = data[position..position + 4].try_into().ok()?;
+        let size = u32::from_le_bytes(data[position + 4..position + 8].try_into().ok()?) as usize;
+        let chunk_data = data.get(position + 8..position + 8 + size)?;
+        if &fourcc != b"EXIF" && &fourcc != b"XMP " {
+            chunks.push((fourcc, chunk_data.to_vec()));
+        }
+        position += 8 + size + size % 2;
+    }
+
+    let (first_fourcc, first_data) = chunks.first()?;
+    if first_fourcc == b"VP8X" {
+        let flags = chunks[0].1.get_mut(0)?;
+        *flags &= !0x0C;
+        if write {
+            *flags |= 0x0C;
+        }
+    } else if write {
+        let (width, height, alpha) = match first_fourcc {
+            b"VP8L" => {
+                if *first_data.first()? != 0x2F {
+                    return None;
+                }
+                let bits = u32::from_le_bytes(first_data.get(1..5)?.try_into().ok()?);
+                (
+                    (bits & 0x3FFF) + 1,
+                    ((bits >> 14) & 0x3FFF) + 1,
+                    (bits >> 28) & 1 == 1,
+                )
+            }
+            b"VP8 " => {
+                let width = u16::from_le_bytes(first_data.get(6..8)?.try_into().ok()?) & 0x3FFF;
+                let height = u16::from_le_bytes(first_data.get(8..10)?.try_into().ok()?) & 0x3FFF;
+                (width as u32, height as u32, false)
+            }
+            _ => return None,
+        };
+
+        let mut vp8x_data = vec![if alpha { 0x10 | 0x0C } else { 0x0C }, 0, 0, 0];
+        vp8x_data.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+        vp8x_data.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+        chunks.insert(0, (*b"VP8X", vp8x_data));
+    }
+
+    if write {
+        chunks.push((*b"EXIF", metadata.exif_data()));
+        chunks.push((*b"XMP ", metadata.xmp_packet().into_bytes()));
+    }
+
+    let riff_size = 4 + chunks
+        .iter()
+        .map(|(_, chunk_data)| 8 + chunk_data.len() + chunk_data.len() % 2)
+        .sum::<usize>();
+    let mut result = Vec::with_capacity(riff_size + 8);
+    result.extend_from_slice(b"RIFF");
+    result.extend_from_slice(&u32::try_from(riff_size).ok()?.to_le_bytes());
+    result.extend_from_slice(b"WEBP");
+    for (fourcc, chunk_data) in chunks {
+        result.extend_from_slice(&fourcc);
+        result.extend_from_slice(&(chunk_data.len() as u32).to_le_bytes());
+        result.extend_from_slice(&chunk_data);
+        if chunk_data.len() % 2 == 1 {
+            result.push(0);
+        }
+    }
+
+    Some(result)
+}
+
+fn push_iso_box(data: &mut Vec<u8>, box_type: &[u8; 4], box_data: &[&[u8]]) {
+    let size = 8 + box_data.iter().map(|data| data.len()).sum::<usize>();
+    data.extend_from_slice(&(size as u32).to_be_bytes());
+    data.extend_from_slice(box_type);
+    for box_data in box_data {
+        data.extend_from_slice(box_data);
+    }
+}
+
+/// 修改内容后更新 box 头部的长度
+fn update_iso_box_size(data: &mut [u8]) {
+    let size = data.len() as u32;
+    data[..4].copy_from_slice(&size.to_be_bytes());
+}
+
+/// ISOBMFF 的 box，(类型, box 起始位置, 内容起始位置, box 结束位置)
+type IsoBox = ([u8; 4], usize, usize, usize);
+
+fn parse_iso_boxes(data: &[u8], start: usize, end: usize) -> Option<Vec<IsoBox>> {
+    let mut boxes = Vec::new();
+    let mut position = start;
+    while position < end {
+        let size = u32::from_be_bytes(data.get(position..position + 4)?.try_into().ok()?) as usize;
+        let box_type: [u8; 4] = data.get(position + 4..position + 8)?.try_into().ok()?;
+        let (content_start, box_end) = match size {
+            0 => (position + 8, end),
+            1 => {
+                let size =
+                    u64::from_be_bytes(data.get(position + 8..position + 16)?.try_into().ok()?);
+                (
+                    position + 16,
+                    position.checked_add(usize::try_from(size).ok()?)?,
+                )
+            }
+            _ => (position + 8, position + size),
+        };
+        if box_end > end || content_start > box_end {
+            return None;
+        }
+
+        boxes.push((box_type, position, content_start, box_end));
+        position = box_end;
+    }
+
+    Some(boxes)
+}
+
+fn read_uint(data: &[u8], position: usize, size: usize) -> Option<u64> {
+    Some(match size {
+        0 => 0,
+        4 => u32::from_be_bytes(data.get(position..position + 4)?.try_into().ok()?) as u64,
+        8 => u64::from_be_bytes(data.get(position..position + 8)?.try_into().ok()?),
+        _ => return None,
+    })
+}
+
+fn write_uint(data: &mut [u8], position: usize, size: usize, value: u64) -> Option<()> {
+    match size {
+        0 => {}
+        4 => data
+            .get_mut(position..position + 4)?
+            .copy_from_slice(&u32::try_from(value).ok()?.to_be_bytes()),
+        8 => data
+            .get_mut(position..position + 8)?
+            .copy_from_slice(&value.to_be_bytes()),
+        _ => return None,
+    }
+
+    Some(())
+}
+
+/// iloc box 的字段布局，位置相对于 box 起始位置
+struct IlocLayout {
+    version: u8,
+    offset_size: usize,
+    length_size: usize,
+    base_offset_size: usize,
+    index_size: usize,
+    /// 每项为 (item id, 构造方式, base_offset 位置, extent_offset 位置列表)
+    items: Vec<(u32, u16, usize, Vec<usize>)>,
+}
+
+fn parse_iloc(iloc: &[u8]) -> Option<IlocLayout> {
+    let version = *iloc.get(8)?;
+    let sizes = iloc.get(12..14)?;
+    let offset_size = (sizes[0] >> 4) as usize;
+    let length_size = (sizes[0] & 0x0F) as usize;
+    let base_offset_size = (sizes[1] >> 4) as usize;
+    let index_size = if version == 0 {
+        0
+    } else {
+        (sizes[1] & 0x0F) as usize
+    };
+
+    let read_id = |position: usize| -> Option<(u32, usize)> {
+        if version < 2 {
+            Some((
+                u16::from_be_bytes(iloc.get(position..position + 2)?.try_into().ok()?) as u32,
+                2,
+            ))
+        } else {
+            Some((
+                u32::from_be_bytes(iloc.get(position..position + 4)?.try_into().ok()?),
+                4,
+            ))
+        }
+    };
+
+    let (item_count, id_len) = read_id(14)?;
+    let mut position = 14 + id_len;
+    let mut items = Vec::new();
+    for _ in 0..item_count {
+        let (id, id_len) = read_id(position)?;
+        position += id_len;
+
+        let mut construction_method = 0;
+        if version > 0 {
+            construction_method =
+                u16::from_be_bytes(iloc.get(position..position + 2)?.try_into().ok()?) & 0x0F;
+            position += 2;
+        }
+        // data_reference_index
+        position += 2;
+        let base_offset_position = position;
+        position += base_offset_size;
+
+        let extent_count = u16::from_be_bytes(iloc.get(position..position + 2)?.try_into().ok()?);
+        position += 2;
+        let mut extent_offset_positions = Vec::new();
+        for _ in 0..extent_count {
+            position += index_size;
+            extent_offset_positions.push(position);
+            position += offset_size + length_size;
+        }
+
+        items.push((
+            id,
+            construction_method,
+            base_offset_position,
+            extent_offset_positions,
+        ));
+    }
+
+    if position > iloc.len() {
+        return None;
+    }
+
+    Some(IlocLayout {
+        version,
+        offset_size,
+        length_size,
+        base_offset_size,
+        index_size,
+        items,
+    })
+}
+
+/// 是否包含 Exif 或 mime（XMP）类型的 item
+fn has_avif_metadata(data: &[u8]) -> Option<bool> {
+    let boxes = parse_iso_boxes(data, 0, data.len())?;
+    let &(_, _, meta_content_start, meta_end) =
+        boxes.iter().find(|(box_type, ..)| box_type == b"meta")?;
+    let children = parse_iso_boxes(data, meta_content_start + 4, meta_end)?;
+    let &(_, _, iinf_content_start, iinf_end) =
+        children.iter().find(|(box_type, ..)| box_type == b"iinf")?;
+    let entries_start = if data[iinf_content_start] == 0 {
+        iinf_content_start + 6
+    } else {
+        iinf_content_start + 8
+    };
+
+    for (box_type, _, infe_content_start, infe_end) in
+        parse_iso_boxes(data, entries_start, iinf_end)?
+    {
+        // 版本 2 和 3 的 infe 才有 item_type
+        let item_type_start = match data.get(infe_content_start)? {
+            2 => infe_content_start + 8,
+            3 => infe_content_start + 10,
+            _ => continue,
+        };
+        if &box_type != b"infe" || item_type_start + 4 > infe_end {
+            continue;
+        }
+
+        if matches!(
+            &data[item_type_start..item_type_start + 4],
+            b"Exif" | b"mime"
+        ) {
+            return Some(true);
+        }
+    }
+
+    Some(false)
+}
+
+/// EXIF 和 XMP 作为新的 item 写入文件末尾的 mdat，通过 cdsc 关联到主图片
+///
+/// meta 变长后，原有 item 的文件偏移需要整体后移
+fn write_avif_metadata(data: &[u8], metadata: &ImageMetadata) -> Option<Vec<u8>> {
+    let boxes = parse_iso_boxes(data, 0, data.len())?;
+    let &(_, meta_start, meta_content_start, meta_end) =
+        boxes.iter().find(|(box_type, ..)| box_type == b"meta")?;
+    let children = parse_iso_boxes(data, meta_content_start + 4, meta_end)?;
+    // 修改后的 box 按 32 位长度写回
+    if children
+        .iter()
+        .any(|&(_, child_start, child_content_start, _)| child_content_start - child_start != 8)
+    {
+        return None;
+    }
+    let find_child = |box_type: &[u8; 4]| children.iter().find(|child| &child.0 == box_type);
+
+    let &(_, _, pitm_content_start, _) = find_child(b"pitm")?;
+    let primary_id = if data[pitm_content_start] == 0 {
+        u16::from_be_bytes(
+            data.get(pitm_content_start + 4..pitm_content_start + 6)?
+                .try_into()
+                .ok()?,
+        ) as u32
+    } else {
+        u32::from_be_bytes(
+            data.get(pitm_content_start + 4..pitm_content_start + 8)?
+                .try_into()
+                .ok()?,
+        )
+    };
+
+    let &(_, iloc_start, _, iloc_end) = find_child(b"iloc")?;
+    let iloc = &data[iloc_start..iloc_end];
+    let iloc_layout = parse_iloc(iloc)?;
+    if iloc_layout.offset_size < 4 || iloc_layout.length_size < 4 {
+        return None;
+    }
+
+    let exif_id = iloc_layout.items.iter().map(|item| item.0).max()? + 1;
+    let xmp_id = exif_id + 1;
+    if xmp_id > u16::MAX as u32 || primary_id > u16::MAX as u32 {
+        return None;
+    }
+
+    let mut exif_data = vec![0; 4];
+    exif_data.extend_from_slice(&metadata.exif_data());
+    let xmp_data = metadata.xmp_packet().into_bytes();
+
+    // iinf 追加 infe
+    let &(_, iinf_start, iinf_content_start, iinf_end) = find_child(b"iinf")?;
+    let mut iinf = data[iinf_start..iinf_end].to_vec();
+    let count_position = iinf_content_start - iinf_start + 4;
+    if data[iinf_content_start] == 0 {
+        let count = u16::from_be_bytes(
+            iinf.get(count_position..count_position + 2)?
+                .try_into()
+                .ok()?,
+        );
+        iinf[count_position..count_position + 2]
+            .copy_from_slice(&count.checked_add(2)?.to_be_bytes());
+    } else {
+        let count = u32::from_be_bytes(
+            iinf.get(count_position..count_position + 4)?
+                .try_into()
+                .ok()?,
+        );
+        iinf[count_position..count_position + 4].copy_from_slice(&(count + 2).to_be_bytes());
+    }
+    push_iso_box(
+        &mut iinf,
+        b"infe",
+        &[
+            &[2, 0, 0, 0],
+            &(exif_id as u16).to_be_bytes(),
+            &[0, 0],
+            b"Exif",
+            b"\0",
+        ],
+    );
+    push_iso_box(
+        &mut iinf,
+        b"infe",
+        &[
+            &[2, 0, 0, 0],
+            &(xmp_id as u16).to_be_bytes(),
+            &[0, 0],
+            b"mime",
+            b"\0application/rdf+xml\0",
+        ],
+    );
+    update_iso_box_size(&mut iinf);
+
+    // iref 追加 cdsc，没有时新建
+    let mut iref_references = Vec::new();
+    let iref_id_size = match find_child(b"iref") {
+        Some(&(_, _, iref_content_start, _)) if data[iref_content_start] != 0 => 4,
+        _ => 2,
+    };
+    for item_id in [exif_id, xmp_id] {
+        let mut reference = Vec::new();
+        if iref_id_size == 2 {
+            reference.extend_from_slice(&(item_id as u16).to_be_bytes());
+            reference.extend_from_slice(&1u16.to_be_bytes());
+            reference.extend_from_slice(&(primary_id as u16).to_be_bytes());
+        } else {
+            reference.extend_from_slice(&item_id.to_be_bytes());
+            reference.extend_from_slice(&1u16.to_be_bytes());
+            reference.extend_from_slice(&primary_id.to_be_bytes());
+        }
+        push_iso_box(&mut iref_references, b"cdsc", &[&reference]);
+    }
+    let iref = match find_child(b"iref") {
+        Some(&(_, iref_start, _, iref_end)) => {
+            let mut iref = data[iref_start..iref_end].to_vec();
+            iref.extend_from_slice(&iref_references);
+            update_iso_box_size(&mut iref);
+            iref
+        }
+        None => {
+            let mut iref = Vec::new();
+            push_iso_box(&mut iref, b"iref", &[&[0, 0, 0, 0], &iref_references]);
+            iref
+        }
+    };
+
+    // iloc 追加 item 并修正偏移
+    let build_iloc = |offset_delta: i64, exif_offset: u64| -> Option<Vec<u8>> {
+        let mut iloc = iloc.to_vec();
+        for (_, construction_method, base_offset_position, extent_offset_positions) in
+            iloc_layout.items.iter()
+        {
+            if *construction_method != 0 {
+                continue;
+            }
+
+            let base_offset =
+                read_uint(&iloc, *base_offset_position, iloc_layout.base_offset_size)?;
+            let mut positions = vec![(*base_offset_position, iloc_layout.base_offset_size)];
+            if base_offset == 0 {
+                positions = extent_offset_positions
+                    .iter()
+                    .map(|position| (*position, iloc_layout.offset_size))
+                    .collect();
+            }
+            for (position, size) in positions {
+                let offset = read_uint(&iloc, position, size)?;
+                // 只有 meta 之后的数据会移动
+                if offset >= meta_end as u64 {
+                    write_uint(
+                        &mut iloc,
+                        position,
+                        size,
+                        offset.checked_add_signed(offset_delta)?,
+                    )?;
+                }
+            }
+        }
+
+        let count_position = 14;
+        if iloc_layout.version < 2 {
+            let count =
+                u16::from_be_bytes(iloc[count_position..count_position + 2].try_into().ok()?);
+            iloc[count_position..count_position + 2]
+                .copy_from_slice(&count.checked_add(2)?.to_be_bytes());
+        } else {
+            let count =
+                u32::from_be_bytes(iloc[count_position..count_position + 4].try_into().ok()?);
+            iloc[count_position..count_position + 4].copy_from_slice(&(count + 2).to_be_bytes());
+        }
+
+        for (item_id, offset, length) in [
+            (exif_id, exif_offset, exif_data.len()),
+            (xmp_id, exif_offset + exif_data.len() as u64, xmp_data.len()),
+        ] {
+            if iloc_layout.version < 2 {
+                iloc.extend_from_slice(&(item_id as u16).to_be_bytes());
+            } else {
+                iloc.extend_from_slice(&item_id.to_be_bytes());
+            }
+            if iloc_layout.version > 0 {
+                iloc.extend_from_slice(&[0, 0]);
+            }
+            iloc.extend_from_slice(&[0, 0]);
+            iloc.resize(iloc.len() + iloc_layout.base_offset_size, 0);
+            iloc.extend_from_slice(&1u16.to_be_bytes());
+            iloc.resize(iloc.len() + iloc_layout.index_size, 0);
+            iloc.resize(iloc.len() + iloc_layout.offset_size, 0);
+            let position = iloc.len() - iloc_layout.offset_size;
+            write_uint(&mut iloc, position, iloc_layout.offset_size, offset)?;
+            iloc.resize(iloc.len() + iloc_layout.length_size, 0);
+            let position = iloc.len() - iloc_layout.length_size;
+            write_uint(&mut iloc, position, iloc_layout.length_size, length as u64)?;
+        }
+        update_iso_box_size(&mut iloc);
+
+        Some(iloc)
+    };
+
+    let build_meta = |iloc: &[u8]| -> Vec<u8> {
+        let mut meta_data = data[meta_content_start..meta_content_start + 4].to_vec();
+        for &(box_type, child_start, _, child_end) in children.iter() {
+            match &box_type {
+                b"iinf" => {
+                    meta_data.extend_from_slice(&iinf);
+                    if find_child(b"iref").is_none() {
+                        meta_data.extend_from_slice(&iref);
+                    }
+                }
+                b"iref" => meta_data.extend_from_slice(&iref),
+                b"iloc" => meta_data.extend_from_slice(iloc),
+                _ => meta_data.extend_from_slice(&data[child_start..child_end]),
+            }
+        }
+
+        let mut meta = Vec::with_capacity(meta_data.len() + 8);
+        push_iso_box(&mut meta, b"meta", &[&meta_data]);
+        meta
+    };
+
+    // iloc 的长度和偏移的值无关，先计算 meta 的长度
+    let meta_len = build_meta(&build_iloc(0, 0)?).len();
+    let offset_delta = meta_len as i64 - (meta_end - meta_start) as i64;
+    let metadata_offset = (data.len() as i64 + offset_delta) as u64 + 8;
+    let meta = build_meta(&build_iloc(offset_delta, metadata_offset)?);
+
+    let mut result = Vec::with_capacity(data.len() + meta.len() + exif_data.len() + xmp_data.len());
+    result.extend_from_slice(&data[..meta_start]);
+    result.extend_from_slice(&meta);
+    result.extend_from_slice(&data[meta_end..]);
+    push_iso_box(&mut result, b"mdat", &[&exif_data, &xmp_data]);
+
+    Some(result)
+}
+
+/// 编码器输出的是裸码流，转换为容器格式后写入 Exif 和 xml box
+///
+/// 已经是容器格式时，先移除已有的 Exif、xml 以及压缩后的 brob box
+fn write_jxl_metadata(data: &[u8], metadata: &ImageMetadata, write: bool) -> Option<Vec<u8>> {
+    let mut metadata_boxes = Vec::new();
+    if write {
+        push_iso_box(
+            &mut metadata_boxes,
+            b"Exif",
+            &[&[0, 0, 0, 0], &metadata.exif_data()],
+        );
+        push_iso_box(
+            &mut metadata_boxes,
+            b"xml ",
+            &[metadata.xmp_packet().as_bytes()],
+        );
+    }
+
+    if data.starts_with(&[0xFF, 0x0A]) {
+        // 裸码流中没有元数据
+        if !write {
+            return Some(data.to_vec());
+        }
+
+        let mut result = Vec::with_capacity(data.len() + metadata_boxes.len() + 64);
+        result.extend_from_slice(JXL_SIGNATURE_BOX);
+        push_iso_box(&mut result, b"ftyp", &[b"jxl ", &[0, 0, 0, 0], b"jxl "]);
+        result.extend_from_slice(&metadata_boxes);
+        push_iso_box(&mut result, b"jxlc", &[data]);
+
+        return Some(result);
+    }
+
+    if !data.starts_with(JXL_SIGNATURE_BOX) {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(data.len() + metadata_boxes.len());
+    result.extend_from_slice(JXL_SIGNATURE_BOX);
+    for (box_type, box_start, content_start, box_end) in
+        parse_iso_boxes(data, JXL_SIGNATURE_BOX.len(), data.len())?
+    {
+        let is_metadata = match &box_type {
+            b"Exif" | b"xml " => true,
+            b"brob" => matches!(
+                data.get(content_start..content_start + 4),
+                Some(b"Exif") | Some(b"xml ")
+            ),
+            _ => false,
+        };
+        if is_metadata {
+            continue;
+        }
+
+        // 元数据写在码流之前
+        if matches!(&box_type, b"jxlc" | b"jxlp") {
+            result.append(&mut metadata_boxes);
+        }
+        result.extend_from_slice(&data[box_start..box_end]);
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_encoder::encode_image;
+    use image::{DynamicImage, Rgba, RgbaImage};
+
+    fn contains(data: &[u8], pattern: &[u8]) -> bool {
+        data.windows(pattern.len()).any(|window| window == pattern)
+    }
+
+    #[test]
+    fn test_write_image_metadata() {
+        let mut image = RgbaImage::new(20, 10);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            *pixel = Rgba([(x * 12) as u8, (y * 25) as u8, 60, 200]);
+        }
+        let image = DynamicImage::ImageRgba8(image);
+
+        let metadata = ImageMetadata {
+            capture_time: Some(1_700_000_000_123),
+            utc_offset: Some(480),
+            monitor_name: Some(String::from("DISPLAY1")),
+            scale_factor: Some(1.5),
+            app_name: Some(String::from("Code.exe")),
+            window_title: Some(String::from("标题 <main.rs>")),
+            comment: Some(String::from("备注")),
+            strip: false,
+        };
+        assert_eq!(
+            metadata.exif_date_time().as_deref(),
+            Some("2023:11:15 06:13:20")
+        );
+        assert_eq!(
+            metadata.iso_date_time().as_deref(),
+            Some("2023-11-15T06:13:20+08:00")
+        );
+
+        let strip_metadata = ImageMetadata {
+            strip: true,
+            ..Default::default()
+        };
+
+        for format in [
+            ImageFileFormat::Png,
+            ImageFileFormat::Jpeg,
+            ImageFileFormat::Webp,
+        ] {
+            let buf = encode_image(&image, &format.default_encoder()).unwrap();
+            let decoded_image = image::load_from_memory(&buf).unwrap();
+
+            // 写入后仍然可以解码，且图片内容不变
+            let buf = write_image_metadata(buf, format, &metadata).unwrap();
+            assert!(contains(&buf, "DISPLAY1".as_bytes()));
+            assert!(contains(&buf, "标题 &lt;main.rs&gt;".as_bytes()));
+            assert_eq!(image::load_from_memory(&buf).unwrap(), decoded_image);
+
+            // 再次写入会先移除已有的元数据，不会重复
+            assert_eq!(
+                write_image_metadata(buf.clone(), format, &metadata).unwrap(),
+                buf
+            );
+
+            let buf = write_image_metadata(buf, format, &strip_metadata).unwrap();
+            assert!(!contains(&buf, b"DISPLAY1"));
+            assert!(!contains(&buf, b"Snow Shot"));
+            assert_eq!(image::load_from_memory(&buf).unwrap(), decoded_image);
+        }
+
+        // AVIF 原有 item 的偏移随 meta 变长后移，仍然指向原来的数据
+        let buf = encode_image(&image, &ImageFileFormat::Avif.default_encoder()).unwrap();
+        let read_items = |data: &[u8]| {
+            let boxes = parse_iso_boxes(data, 0, data.len()).unwrap();
+            let (_, _, meta_content_start, meta_end) = *boxes
+                .iter()
+                .find(|(box_type, ..)| box_type == b"meta")
+                .unwrap();
+            let children = parse_iso_boxes(data, meta_content_start + 4, meta_end).unwrap();
+            let (_, iloc_start, _, iloc_end) = *children
+                .iter()
+                .find(|(box_type, ..)| box_type == b"iloc")
+                .unwrap();
+            let iloc = &data[iloc_start..iloc_end];
+            let layout = parse_iloc(iloc).unwrap();
+            layout
+                .items
+                .iter()
+                .map(|(id, _, _, extent_offset_positions)| {
+                    let position = extent_offset_positions[0];
+                    let offset = read_uint(iloc, position, layout.offset_size).unwrap() as usize;
+                    let length = read_uint(iloc, position + layout.offset_size, layout.length_size)
+                        .unwrap() as usize;
+                    (*id, data[offset..offset + length].to_vec())
+                })
+                .collect::<Vec<_>>()
+        };
+        let items = read_items(&buf);
+        assert_eq!(
+            write_image_metadata(buf.clone(), ImageFileFormat::Avif, &strip_metadata).unwrap(),
+            buf
+        );
+        let buf = write_image_metadata(buf, ImageFileFormat::Avif, &metadata).unwrap();
+        let metadata_items = read_items(&buf);
+        assert_eq!(metadata_items.len(), items.len() + 2);
+        assert_eq!(metadata_items[..items.len()], items[..]);
+        assert!(metadata_items[items.len()].1[4..].starts_with(b"MM\0\x2a"));
+        assert!(contains(&metadata_items[items.len() + 1].1, b"DISPLAY1"));
+        assert!(contains(&buf, b"cdsc"));
+        assert_eq!(has_avif_metadata(&buf), Some(true));
+
+        // 已有的元数据无法移除，再次写入或移除都返回错误
+        assert!(write_image_metadata(buf.clone(), ImageFileFormat::Avif, &metadata).is_err());
+        assert!(write_image_metadata(buf, ImageFileFormat::Avif, &strip_metadata).is_err());
+
+        let codestream = encode_image(&image, &ImageFileFormat::JpegXl.default_encoder()).unwrap();
+        assert_eq!(
+            write_image_metadata(codestream.clone(), ImageFileFormat::JpegXl, &strip_metadata)
+                .unwrap(),
+            codestream
+        );
+        let buf =
+            write_image_metadata(codestream.clone(), ImageFileFormat::JpegXl, &metadata).unwrap();
+        assert!(buf.starts_with(JXL_SIGNATURE_BOX));
+        assert!(contains(&buf, b"jxlc"));
+        assert!(contains(&buf, b"DISPLAY1"));
+
+        // 容器格式再次写入会替换已有的 Exif 和 xml box
+        assert_eq!(
+            write_image_metadata(buf.clone(), ImageFileFormat::JpegXl, &metadata).unwrap(),
+            buf
+        );
+        let buf = write_image_metadata(buf, ImageFileFormat::JpegXl, &strip_metadata).unwrap();
+        assert!(!contains(&buf, b"DISPLAY1"));
+        assert!(!contains(&buf, b"Snow Shot"));
+        assert!(buf.ends_with(&codestream));
+
+        // 不支持元数据的格式保持不变
+        let buf = encode_image(&image, &ImageFileFormat::Bmp.default_encoder()).unwrap();
+        assert_eq!(
+            write_image_metadata(buf.clone(), ImageFileFormat::Bmp, &metadata).unwrap(),
+            buf
+        );
+    }
+}
@@ -1,26 +1,31 @@
-use std::ffi::OsStr;
 use std::path::PathBuf;
 use tokio::fs;
 
 use device_query::{DeviceQuery, DeviceState, MouseState};
-use image::codecs::avif::AvifEncoder;
-use image::codecs::jpeg::JpegEncoder;
-use image::codecs::png::{CompressionType, FilterType, PngEncoder};
-use image::codecs::webp::WebPEncoder;
-use image::{DynamicImage, GenericImageView};
 use rayon::iter::IntoParallelIterator;
 use rayon::iter::ParallelIterator;
 use snow_shot_app_shared::ElementRect;
 use tauri::AppHandle;
 use xcap::Monitor;
-use zune_core::bit_depth::BitDepth;
-use zune_core::colorspace::ColorSpace;
-use zune_core::options::EncoderOptions;
-use zune_jpegxl::JxlSimpleEncoder;
 
+#[cfg(target_os = "linux")]
+use crate::capture_backend::CaptureBackend;
+use crate::image_encoder::{ImageEncoder, encode_image_for_file};
+use crate::image_metadata::ImageMetadata;
 use crate::monitor_info::MonitorList;
-
+use crate::pixel_format::PixelFormat;
+
+pub mod batch_convert;
+pub mod capture_backend;
+pub mod capture_scale;
+pub mod capture_source;
+pub mod cursor_overlay;
+pub mod image_encoder;
+pub mod image_format;
+pub mod image_metadata;
 pub mod monitor_info;
+pub mod pixel_format;
+pub mod png_optimizer;
 
 pub fn get_device_state() -> Result<DeviceState, String> {
     #[cfg(target_os = "macos")]
@@ -50,28 +55,56 @@ pub fn get_target_monitor() -> Result<(i32, i32, Monitor), String> {
             ));
         }
     };
-    let monitor = Monitor::from_point(mouse_x, mouse_y).unwrap_or_else(|_| {
-        // 在 Wayland 中，获取不到鼠标位置，选用第一个显示器作为位置
+    let monitor = match Monitor::from_point(mouse_x, mouse_y) {
+        Ok(monitor) => monitor,
+        Err(_) => {
+            // 在 Wayland 中，获取不到鼠标位置，优先选用主显示器作为位置
 
-        log::warn!("[get_target_monitor] No monitor found, using first monitor");
+            log::warn!("[get_target_monitor] No monitor found, using primary monitor");
 
-        let monitor_list = xcap::Monitor::all().expect("[get_target_monitor] No monitor found");
-        let first_monitor = monitor_list
-            .first()
-            .expect("[get_target_monitor] No monitor found");
+            let monitor_list = match xcap::Monitor::all() {
+                Ok(monitor_list) => monitor_list,
+                Err(_) => return Err(String::from("[get_target_monitor] No monitor found")),
+            };
+            let first_monitor = match monitor_list
+                .iter()
+                .find(|monitor| monitor.is_primary().unwrap_or(false))
+                .or(monitor_list.first())
+            {
+                Some(first_monitor) => first_monitor,
+                None => return Err(String::from("[get_target_monitor] No monitor found")),
+            };
 
-        mouse_x = first_monitor.x().unwrap_or(0) + first_monitor.width().unwrap_or(0) as i32 / 2;
-        mouse_y = first_monitor.y().unwrap_or(0) + first_monitor.height().unwrap_or(0) as i32 / 2;
+            mouse_x =
+                first_monitor.x().unwrap_or(0) + first_monitor.width().unwrap_or(0) as i32 / 2;
+            mouse_y =
+                first_monitor.y().unwrap_or(0) + first_monitor.height().unwrap_or(0) as i32 / 2;
 
-        first_monitor.clone()
-    });
+            first_monitor.clone()
+        }
+    };
 
     Ok((mouse_x, mouse_y, monitor))
 }
 
+/// 保存图片到文件，未指定编码器时根据文件扩展名在格式表中选择格式并使用默认参数
+///
+/// 未传入元数据时不写入元数据
 pub async fn save_image_to_file(
     image: &image::DynamicImage,
     file_path: PathBuf,
+    encoder: Option<ImageEncoder>,
+    metadata: Option<&ImageMetadata>,
+) -> Result<(), String> {
+    let image_buffer = encode_image_for_file(image, &file_path, encoder, metadata)?;
+
+    write_image_buffer_to_file(image_buffer, file_path).await
+}
+
+/// 将已编码的图片写入文件，父目录不存在时创建
+pub async fn write_image_buffer_to_file(
+    image_buffer: Vec<u8>,
+    file_path: PathBuf,
 ) -> Result<(), String> {
     // 确保文件路径的父目录存在
     if let Some(parent_dir) = file_path.parent() {
@@ -79,13 +112,13 @@ pub async fn save_image_to_file(
             match fs::create_dir_all(parent_dir).await {
                 Ok(_) => {
                     log::info!(
-                        "[save_image_to_file] Created directory: {}",
+                        "[write_image_buffer_to_file] Created directory: {}",
                         parent_dir.display()
                     );
                 }
                 Err(e) => {
                     return Err(format!(
-                        "[save_image_to_file] Failed to create directory {}: {}",
+                        "[write_image_buffer_to_file] Failed to create directory {}: {}",
                         parent_dir.display(),
                         e
                     ));
@@ -94,75 +127,14 @@ pub async fn save_image_to_file(
         }
     }
 
-    let extension = match file_path.extension() {
-        Some(extension) => extension,
-        None => {
-            log::warn!("[save_image_to_file] No extension found, using default extension");
-
-            OsStr::new("")
-        }
-    };
-
-    if extension == "jxl" {
-        let has_alpha = image.color().has_alpha();
-        let (width, height) = image.dimensions();
-        let image_data = if has_alpha {
-            DynamicImage::ImageRgba8(image.to_rgba8())
-        } else {
-            DynamicImage::ImageRgb8(image.to_rgb8())
-        };
-        let encoder = JxlSimpleEncoder::new(
-            image_data.as_bytes(),
-            EncoderOptions::new(
-                width as usize,
-                height as usize,
-                if has_alpha {
-                    ColorSpace::RGBA
-                } else {
-                    ColorSpace::RGB
-                },
-                BitDepth::Eight,
-            ),
-        );
-        let encoder_result = match encoder.encode() {
-            Ok(encoder_result) => encoder_result,
-            Err(_) => {
-                return Err(format!(
-                    "[save_image_to_file] Failed to encode image: {}",
-                    file_path.display()
-                ));
-            }
-        };
-
-        return match fs::write(file_path.clone(), encoder_result).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!(
-                "[save_image_to_file] Failed to save image to file: {} {}",
-                e,
-                file_path.display(),
-            )),
-        };
-    } else {
-        // jpg 是 RGB 格式，所以需要转换为 RGB 格式
-        let image = if image.color().has_alpha() && extension == "jpg" {
-            &DynamicImage::ImageRgb8(image.to_rgb8())
-        } else {
-            image
-        };
-
-        match image.save(file_path.clone()) {
-            Ok(_) => (),
-            Err(e) => {
-                return Err(format!(
-                    "[save_image_to_file] Failed to save image to file: {} {}",
-                    e,
-                    file_path.display(),
-                ));
-            }
-        }
+    match fs::write(file_path.clone(), image_buffer).await {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!(
+            "[write_image_buffer_to_file] Failed to save image to file: {} {}",
+            e,
+            file_path.display(),
+        )),
     }
-
-    return Ok(());
 }
 
 pub fn get_mouse_position(
@@ -273,6 +245,35 @@ pub fn capture_target_monitor(
     crop_area: Option<ElementRect>,
     #[allow(unused_variables)] exclude_window: Option<&tauri::Window>,
 ) -> Option<image::DynamicImage> {
+    #[cfg(target_os = "linux")]
+    {
+        if CaptureBackend::detect() == CaptureBackend::X11 {
+            let (monitor_x, monitor_y) = (monitor.x().unwrap_or(0), monitor.y().unwrap_or(0));
+            let crop_area = crop_area.unwrap_or(ElementRect {
+                min_x: 0,
+                min_y: 0,
+                max_x: monitor.width().unwrap_or(0) as i32,
+                max_y: monitor.height().unwrap_or(0) as i32,
+            });
+
+            match capture_backend::x11::capture_region(
+                monitor_x + crop_area.min_x,
+                monitor_y + crop_area.min_y,
+                (crop_area.max_x - crop_area.min_x) as u32,
+                (crop_area.max_y - crop_area.min_y) as u32,
+            ) {
+                Ok(image) => return Some(image::DynamicImage::ImageRgb8(image)),
+                Err(error) => {
+                    log::warn!(
+                        "[capture_target_monitor] failed to capture image with X11: {}",
+                        error
+                    );
+                }
+            }
+        }
+    }
+
+    // Linux 下 X11 截图失败或在 Wayland 中时，由 xcap 通过 portal 或 PipeWire 截图
     #[cfg(not(target_os = "macos"))]
     {
         let image = if let Some(crop_area) = crop_area {
@@ -458,66 +459,29 @@ pub fn bgra_to_rgb(bgra_data: &[u8]) -> Vec<u8> {
     rgb_data
 }
 
-pub enum ImageEncoder {
-    Webp,
-    Png,
-    Avif,
-    Jpeg,
-}
-
-pub fn encode_image(image: &image::DynamicImage, encoder: ImageEncoder) -> Vec<u8> {
-    // 编码为指定格式
-    let mut buf = Vec::with_capacity(image.as_bytes().len() / 8);
-
-    match encoder {
-        ImageEncoder::Jpeg => {
-            image
-                .write_with_encoder(JpegEncoder::new_with_quality(&mut buf, 80))
-                .unwrap();
-        }
-        ImageEncoder::Webp => {
-            image
-                .write_with_encoder(WebPEncoder::new_lossless(&mut buf))
-                .unwrap();
-        }
-        ImageEncoder::Png => {
-            image
-                .write_with_encoder(PngEncoder::new_with_quality(
-                    &mut buf,
-                    CompressionType::Fast,
-                    FilterType::Paeth,
-                ))
-                .unwrap();
-        }
-        ImageEncoder::Avif => {
-            image
-                .write_with_encoder(AvifEncoder::new_with_speed_quality(&mut buf, 10, 80))
-                .unwrap();
-        }
-    }
-
-    return buf;
-}
-
 /// 将一个图像绘制到另一个图像上
 ///
+/// image_pixels 按照 pixel_format 存储，target_image 格式不一致时先转换
+///
 /// # Arguments
 ///
 /// - `image_pixels` (`&mut [u8]`) - 合并后的图像像素数据
 /// - `target_pixels` (`&[u8]`) - 待合并的图像的像素数组
 /// - `offset_x` (`i64`) - 待合并的图像在合并后的图像上的偏移量
 /// - `offset_y` (`i64`) - 待合并的图像在合并后的图像上的偏移量
-///
-/// ```
+/// - `pixel_format` (`PixelFormat`) - 合并后的图像的像素格式
 pub fn overlay_image_ptr(
     image_pixels: *mut u8,
     image_width: usize,
     target_image: &image::DynamicImage,
     offset_x: usize,
     offset_y: usize,
-    channel_count: usize,
+    pixel_format: PixelFormat,
 ) {
     let image_pixels_ptr = image_pixels as usize;
+    let channel_count = pixel_format.bytes_per_pixel();
+
+    let target_image = pixel_format.convert_ref(target_image);
 
     let target_image_width = target_image.width() as usize;
     let target_image_height = target_image.height() as usize;
@@ -550,7 +514,7 @@ pub fn overlay_image(
     target_image: &image::DynamicImage,
     offset_x: usize,
     offset_y: usize,
-    channel_count: usize,
+    pixel_format: PixelFormat,
 ) {
     overlay_image_ptr(
         image_pixels.as_mut_ptr(),
@@ -558,6 +522,6 @@ pub fn overlay_image(
         target_image,
         offset_x,
         offset_y,
-        channel_count,
+        pixel_format,
     );
 }
@@ -1,20 +1,29 @@
+use image::imageops::FilterType;
 use image::{DynamicImage, ImageBuffer, Rgb, RgbImage};
-use rayon::iter::{
-    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
-};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use serde::Serialize;
 use snow_shot_app_shared::ElementRect;
+use std::sync::Arc;
 use xcap::Monitor;
 
+use crate::capture_scale::{CaptureScaleMapping, CaptureScaleMode};
+use crate::capture_source::{CaptureSource, XcapCaptureSource};
+use crate::pixel_format::PixelFormat;
+
 #[derive(Debug)]
 pub struct MonitorInfo {
-    pub monitor: Monitor,
+    /// 通过 xcap 获取的显示器，伪造的显示器为 None
+    pub monitor: Option<Monitor>,
+    pub name: String,
     pub rect: ElementRect,
     pub scale_factor: f32,
+    /// 显示器顺时针旋转的角度，为 0、90、180 或 270，rect 已经是旋转后的区域
+    pub rotation: f32,
 }
 
 #[derive(Serialize, Clone)]
 pub struct MonitorRect {
+    pub name: String,
     pub rect: ElementRect,
     pub scale_factor: f32,
 }
@@ -51,10 +60,27 @@ impl MonitorInfo {
             scale_factor = 0.0;
         }
 
+        // X11 没有逻辑坐标，显示器的位置和大小都是物理像素
+        #[cfg(target_os = "linux")]
+        {
+            let x = monitor.x().unwrap_or(0);
+            let y = monitor.y().unwrap_or(0);
+            monitor_rect = ElementRect {
+                min_x: x,
+                min_y: y,
+                max_x: x + monitor.width().unwrap_or(0) as i32,
+                max_y: y + monitor.height().unwrap_or(0) as i32,
+            };
+            scale_factor = monitor.scale_factor().unwrap_or(1.0);
+        }
+
         MonitorInfo {
-            monitor: monitor.clone(),
+            monitor: Some(monitor.clone()),
+            name: monitor.name().unwrap_or_default(),
             rect: monitor_rect,
             scale_factor,
+            // Linux 下 xcap 从 RandR 读取 CRTC 的旋转
+            rotation: monitor.rotation().unwrap_or(0.0),
         }
     }
 
@@ -75,13 +101,19 @@ impl MonitorInfo {
     }
 }
 
-#[derive(Debug)]
-pub struct MonitorList(Vec<MonitorInfo>);
+pub struct MonitorList {
+    monitors: Vec<MonitorInfo>,
+    source: Arc<dyn CaptureSource>,
+}
 
-impl MonitorList {
-    fn get_monitors(region: Option<ElementRect>) -> MonitorList {
-        let monitors = Monitor::all().unwrap_or_default();
+impl std::fmt::Debug for MonitorList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MonitorList").field(&self.monitors).finish()
+    }
+}
 
+impl MonitorList {
+    fn get_monitors(source: Arc<dyn CaptureSource>, region: Option<ElementRect>) -> MonitorList {
         let region = region.unwrap_or(ElementRect {
             min_x: i32::MIN,
             min_y: i32::MIN,
@@ -89,26 +121,34 @@ impl MonitorList {
             max_y: i32::MAX,
         });
 
-        let monitor_info_list = monitors
-            .par_iter()
-            .map(|monitor| MonitorInfo::new(monitor))
+        let monitor_info_list = source
+            .monitors()
+            .into_iter()
             .filter(|monitor| monitor.rect.overlaps(&region))
             .collect::<Vec<MonitorInfo>>();
 
-        MonitorList(monitor_info_list)
+        MonitorList {
+            monitors: monitor_info_list,
+            source,
+        }
     }
 
     pub fn all() -> MonitorList {
-        Self::get_monitors(None)
+        Self::get_monitors(Arc::new(XcapCaptureSource), None)
     }
 
     pub fn get_by_region(region: ElementRect) -> MonitorList {
-        Self::get_monitors(Some(region))
+        Self::get_monitors(Arc::new(XcapCaptureSource), Some(region))
+    }
+
+    /// 从指定的来源获取显示器，如测试中使用的 FakeCaptureSource
+    pub fn from_source(source: Arc<dyn CaptureSource>, region: Option<ElementRect>) -> MonitorList {
+        Self::get_monitors(source, region)
     }
 
     /// 获取所有显示器的最小矩形
     pub fn get_monitors_bounding_box(&self) -> ElementRect {
-        let monitors = &self.0;
+        let monitors = &self.monitors;
 
         if monitors.is_empty() {
             return ElementRect {
@@ -155,13 +195,13 @@ impl MonitorList {
         crop_region: Option<ElementRect>,
         exclude_window: Option<&tauri::Window>,
     ) -> Result<image::DynamicImage, String> {
-        let monitors = &self.0;
+        let monitors = &self.monitors;
 
         // 特殊情况，只有一个显示器，直接返回
         if monitors.len() == 1 {
             let first_monitor = monitors.first().unwrap();
-            let capture_image = super::capture_target_monitor(
-                &first_monitor.monitor,
+            let capture_image = self.source.capture_monitor(
+                first_monitor,
                 if let Some(crop_region) = crop_region {
                     Some(first_monitor.get_monitor_crop_region(crop_region))
                 } else {
@@ -201,17 +241,17 @@ impl MonitorList {
                 max_x: i32::MAX,
                 max_y: i32::MAX,
             })))
-            .map(|monitor| {
+            .filter_map(|monitor| {
                 let monitor_crop_region = if let Some(crop_region) = crop_region {
                     Some(monitor.get_monitor_crop_region(crop_region))
                 } else {
                     None
                 };
 
-                let capture_image = super::capture_target_monitor(&monitor.monitor, monitor_crop_region, exclude_window);
+                let capture_image = self.source.capture_monitor(monitor, monitor_crop_region, exclude_window);
 
                 match capture_image {
-                    Some(image) => Some((image, monitor_crop_region)),
+                    Some(image) => Some((monitor, image, monitor_crop_region)),
                     None => {
                         log::warn!(
                             "[MonitorInfoList::capture] Failed to capture monitor image, monitor rect: {:?}",
@@ -222,11 +262,7 @@ impl MonitorList {
                     }
                 }
             })
-            .filter_map(|result| match result {
-                Some((image, monitor_crop_region)) => Some((image, monitor_crop_region)),
-                None => None,
-            })
-            .collect::<Vec<(image::DynamicImage, Option<ElementRect>)>>();
+            .collect::<Vec<(&MonitorInfo, image::DynamicImage, Option<ElementRect>)>>();
 
         if monitor_image_list.is_empty() {
             return Err(format!(
@@ -251,20 +287,15 @@ impl MonitorList {
             )
         };
 
+        // 截图失败的显示器和显示器之间的空隙保持黑色
         const RGB_CHANNEL_COUNT: usize = 3;
-        let mut capture_image_pixels: Vec<u8> = unsafe {
-            let mut vec =
-                Vec::with_capacity(capture_image_width * capture_image_height * RGB_CHANNEL_COUNT);
-            vec.set_len(capture_image_width * capture_image_height * RGB_CHANNEL_COUNT);
-            vec
-        };
+        let mut capture_image_pixels: Vec<u8> =
+            vec![0; capture_image_width * capture_image_height * RGB_CHANNEL_COUNT];
 
         let capture_image_pixels_ptr = capture_image_pixels.as_mut_ptr() as usize;
 
-        monitor_image_list.par_iter().enumerate().for_each(
-            |(index, (monitor_image, monitor_crop_region))| {
-                let monitor = &monitors[index];
-
+        monitor_image_list.par_iter().for_each(
+            |(monitor, monitor_image, monitor_crop_region)| {
                 // 计算显示器在合并图像中的位置
                 let offset_x: i64;
                 let offset_y: i64;
@@ -283,6 +314,21 @@ impl MonitorList {
                     offset_y = (monitor.rect.min_y - monitors_bounding_box.min_y) as i64;
                 }
 
+                // 显示器返回的图像和区域大小不一致时，避免越界写入
+                if offset_x < 0
+                    || offset_y < 0
+                    || offset_x as usize + monitor_image.width() as usize > capture_image_width
+                    || offset_y as usize + monitor_image.height() as usize > capture_image_height
+                {
+                    log::warn!(
+                        "[MonitorInfoList::capture] Monitor image is out of bounds, monitor rect: {:?}, image size: {}x{}",
+                        monitor.rect,
+                        monitor_image.width(),
+                        monitor_image.height()
+                    );
+                    return;
+                }
+
                 // 将显示器图像绘制到合并图像上
                 super::overlay_image_ptr(
                     capture_image_pixels_ptr as *mut u8,
@@ -290,7 +336,7 @@ impl MonitorList {
                     monitor_image,
                     offset_x as usize,
                     offset_y as usize,
-                    RGB_CHANNEL_COUNT,
+                    PixelFormat::Rgb8,
                 );
             },
         );
@@ -371,73 +417,90 @@ impl MonitorList {
         RgbImage::from_raw(width, height, output_data).unwrap()
     }
 
-    /**
-     * 获取 Windows 下放大镜的颜色变换举证
-     */
-    async fn get_mag_color_effect() -> Result<Option<[f32; 25]>, String> {
-        #[cfg(not(target_os = "windows"))]
-        {
-            return Ok(None);
-        }
-
-        #[cfg(target_os = "windows")]
-        {
-            let init_result = unsafe { windows::Win32::UI::Magnification::MagInitialize() };
-            if !init_result.as_bool() {
-                log::warn!(
-                    "[MonitorInfoList::get_mag_color_effect] Failed to initialize magnification library"
+    /// 按照映射截取每个显示器，缩放到映射中的大小后拼接
+    fn capture_resampled(
+        &self,
+        scale_mapping: &CaptureScaleMapping,
+        exclude_window: Option<&tauri::Window>,
+    ) -> Result<image::DynamicImage, String> {
+        let monitor_image_list = scale_mapping
+            .monitors
+            .par_iter()
+            .filter_map(|monitor_mapping| {
+                let monitor = &self.monitors[monitor_mapping.monitor_index];
+                let capture_image = self.source.capture_monitor(
+                    monitor,
+                    Some(monitor.get_monitor_crop_region(monitor_mapping.source_rect)),
+                    exclude_window,
                 );
-                return Ok(None);
-            }
 
-            let mut current_effect = windows::Win32::UI::Magnification::MAGCOLOREFFECT::default();
-            let get_effect_result = unsafe {
-                windows::Win32::UI::Magnification::MagGetFullscreenColorEffect(&mut current_effect)
-            };
+                let capture_image = match capture_image {
+                    Some(image) => image,
+                    None => {
+                        log::warn!(
+                            "[MonitorInfoList::capture_resampled] Failed to capture monitor image, monitor rect: {:?}",
+                            monitor.rect
+                        );
 
-            // 释放 Mag
-            let uninit_result = unsafe { windows::Win32::UI::Magnification::MagUninitialize() };
-            if !uninit_result.as_bool() {
-                log::warn!(
-                    "[MonitorInfoList::get_mag_color_effect] Failed to uninitialize magnification library"
-                );
-            }
+                        return None;
+                    }
+                };
 
-            if !get_effect_result.as_bool() {
-                log::warn!(
-                    "[MonitorInfoList::get_mag_color_effect] Failed to get magnification color effect"
+                let target_rect = monitor_mapping.target_rect;
+                let (target_width, target_height) = (
+                    (target_rect.max_x - target_rect.min_x) as u32,
+                    (target_rect.max_y - target_rect.min_y) as u32,
                 );
-                return Ok(None);
-            }
+                let capture_image = if capture_image.width() == target_width
+                    && capture_image.height() == target_height
+                {
+                    capture_image.to_rgb8()
+                } else {
+                    capture_image
+                        .resize_exact(target_width, target_height, FilterType::CatmullRom)
+                        .to_rgb8()
+                };
 
-            let matrix = current_effect.transform;
-            // 无任何效果的默认矩阵
-            const NORMAL_MATRIX: [f32; 25] = [
-                1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0,
-                0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
-            ];
-            // 判断 matrix 是否等于 NORMAL_MATRIX
-            if matrix.eq(&NORMAL_MATRIX) {
-                return Ok(None);
-            }
+                Some((target_rect, capture_image))
+            })
+            .collect::<Vec<(ElementRect, RgbImage)>>();
 
-            Ok(Some(matrix))
+        if monitor_image_list.is_empty() {
+            return Err(String::from(
+                "[MonitorInfoList::capture_resampled] Failed to capture monitor image, monitor_image_list is empty",
+            ));
         }
+
+        // 截图失败的显示器和显示器之间的空隙保持黑色
+        let mut capture_image = RgbImage::new(scale_mapping.width, scale_mapping.height);
+        for (target_rect, monitor_image) in monitor_image_list.iter() {
+            image::imageops::replace(
+                &mut capture_image,
+                monitor_image,
+                target_rect.min_x as i64,
+                target_rect.min_y as i64,
+            );
+        }
+
+        Ok(DynamicImage::ImageRgb8(capture_image))
     }
 
     async fn capture_core(
         &self,
         crop_region: Option<ElementRect>,
+        scale_mapping: Option<&CaptureScaleMapping>,
         exclude_window: Option<&tauri::Window>,
     ) -> Result<image::DynamicImage, String> {
-        let result = tokio::try_join!(
-            self.capture_future(crop_region, exclude_window),
-            Self::get_mag_color_effect()
-        );
+        let result = match scale_mapping {
+            Some(scale_mapping) if scale_mapping.target_scale_factor.is_some() => {
+                self.capture_resampled(scale_mapping, exclude_window)
+            }
+            _ => self.capture_future(crop_region, exclude_window).await,
+        };
 
         match result {
-            Ok((image, color_effect)) => {
-                let image = match color_effect {
+            Ok(image) => {
+                let image = match self.source.color_effect() {
                     Some(matrix) => DynamicImage::ImageRgb8(Self::apply_color_effect_to_image(
                         &image.as_rgb8().unwrap(),
                         &matrix,
@@ -458,7 +521,7 @@ impl MonitorList {
         &self,
         exclude_window: Option<&tauri::Window>,
     ) -> Result<image::DynamicImage, String> {
-        self.capture_core(None, exclude_window).await
+        self.capture_core(None, None, exclude_window).await
     }
 
     pub async fn capture_region(
@@ -466,13 +529,46 @@ impl MonitorList {
         region: ElementRect,
         exclude_window: Option<&tauri::Window>,
     ) -> Result<image::DynamicImage, String> {
-        self.capture_core(Some(region), exclude_window).await
+        self.capture_core(Some(region), None, exclude_window).await
+    }
+
+    /// 计算截图时桌面物理像素坐标和输出图像坐标的映射
+    pub fn get_scale_mapping(
+        &self,
+        region: Option<ElementRect>,
+        scale_mode: CaptureScaleMode,
+    ) -> CaptureScaleMapping {
+        let monitors = self
+            .monitors
+            .iter()
+            .map(|monitor| (monitor.rect, monitor.scale_factor))
+            .collect::<Vec<_>>();
+
+        CaptureScaleMapping::new(&monitors, region, scale_mode)
+    }
+
+    /// 按照缩放方式截图，显示器缩放比例不一致时可以缩放到相同的比例后拼接
+    ///
+    /// @param region 截取的区域，未指定时截取所有显示器
+    pub async fn capture_with_scale_mode(
+        &self,
+        region: Option<ElementRect>,
+        scale_mode: CaptureScaleMode,
+        exclude_window: Option<&tauri::Window>,
+    ) -> Result<(image::DynamicImage, CaptureScaleMapping), String> {
+        let scale_mapping = self.get_scale_mapping(region, scale_mode);
+        let image = self
+            .capture_core(region, Some(&scale_mapping), exclude_window)
+            .await?;
+
+        Ok((image, scale_mapping))
     }
 
     pub fn monitor_rect_list(&self) -> Vec<MonitorRect> {
-        self.0
+        self.monitors
             .iter()
             .map(|monitor| MonitorRect {
+                name: monitor.name.clone(),
                 rect: monitor.rect,
                 scale_factor: monitor.scale_factor,
             })
@@ -480,7 +576,7 @@ impl MonitorList {
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &MonitorInfo> {
-        self.0.iter()
+        self.monitors.iter()
     }
 }
 
@@ -489,6 +585,167 @@ mod tests {
     use std::env;
 
     use super::*;
+    use crate::capture_scale::CaptureScaleMode;
+    use crate::capture_source::FakeCaptureSource;
+
+    fn rect(min_x: i32, min_y: i32, max_x: i32, max_y: i32) -> ElementRect {
+        ElementRect {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
+
+    fn assert_fake_pixels(image: &DynamicImage, origin_x: i32, origin_y: i32, black: ElementRect) {
+        let image = image.to_rgb8();
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let (x, y) = (origin_x + x as i32, origin_y + y as i32);
+            let expected =
+                if black.min_x <= x && x < black.max_x && black.min_y <= y && y < black.max_y {
+                    Rgb([0, 0, 0])
+                } else {
+                    FakeCaptureSource::pixel(x, y)
+                };
+            assert_eq!(*pixel, expected, "pixel at ({}, {})", x, y);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_capture_fake_monitors() {
+        // 左侧显示器在负坐标，右侧显示器截图失败
+        let source = Arc::new(
+            FakeCaptureSource::new()
+                .with_monitor("left", rect(-300, -40, 0, 160), 1.0)
+                .with_monitor("main", rect(0, 0, 320, 200), 1.5)
+                .with_failed_monitor("right", rect(320, 0, 400, 100), 2.0),
+        );
+
+        let monitors = MonitorList::from_source(source.clone(), None);
+        assert_eq!(
+            monitors.get_monitors_bounding_box(),
+            rect(-300, -40, 400, 200)
+        );
+        assert_eq!(
+            monitors
+                .monitor_rect_list()
+                .iter()
+                .map(|monitor_rect| monitor_rect.scale_factor)
+                .collect::<Vec<_>>(),
+            vec![1.0, 1.5, 2.0]
+        );
+
+        // 显示器之间的空隙和失败的显示器为黑色
+        let image = monitors.capture(None).await.unwrap();
+        assert_eq!((image.width(), image.height()), (700, 240));
+        let image = image.to_rgb8();
+        assert_eq!(*image.get_pixel(0, 0), FakeCaptureSource::pixel(-300, -40));
+        assert_eq!(*image.get_pixel(350, 100), FakeCaptureSource::pixel(50, 60));
+        assert_eq!(*image.get_pixel(200, 220), Rgb([0, 0, 0]));
+        assert_eq!(*image.get_pixel(650, 40), Rgb([0, 0, 0]));
+
+        // 跨越负坐标的区域
+        let crop_region = rect(-50, -30, 30, 20);
+        let monitors = MonitorList::from_source(source.clone(), Some(crop_region));
+        assert_eq!(monitors.iter().count(), 2);
+        let image = monitors.capture_region(crop_region, None).await.unwrap();
+        assert_eq!((image.width(), image.height()), (80, 50));
+        assert_fake_pixels(&image, -50, -30, rect(0, -30, 30, 0));
+
+        // 单个显示器的区域
+        let crop_region = rect(10, 20, 60, 50);
+        let monitors = MonitorList::from_source(source.clone(), Some(crop_region));
+        let image = monitors.capture_region(crop_region, None).await.unwrap();
+        assert_fake_pixels(&image, 10, 20, rect(0, 0, 0, 0));
+
+        // 所有显示器都失败
+        let crop_region = rect(330, 10, 380, 60);
+        let monitors = MonitorList::from_source(source, Some(crop_region));
+        assert!(monitors.capture_region(crop_region, None).await.is_err());
+
+        // 反色
+        let mut invert_matrix = [0.0; 25];
+        for i in 0..3 {
+            invert_matrix[i * 5 + i] = -1.0;
+            invert_matrix[20 + i] = 1.0;
+        }
+        invert_matrix[18] = 1.0;
+        invert_matrix[24] = 1.0;
+        let source = Arc::new(
+            FakeCaptureSource::new()
+                .with_monitor("main", rect(0, 0, 64, 32), 1.0)
+                .with_color_effect(invert_matrix),
+        );
+        let image = MonitorList::from_source(source, None)
+            .capture(None)
+            .await
+            .unwrap()
+            .to_rgb8();
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let expected_pixel = FakeCaptureSource::pixel(x as i32, y as i32);
+            for channel in 0..3 {
+                assert!((255 - expected_pixel[channel]).abs_diff(pixel[channel]) <= 1);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_capture_fake_monitors_resample() {
+        // 100% 显示器右侧是 150% 显示器，中间的截图失败
+        let source = Arc::new(
+            FakeCaptureSource::new()
+                .with_monitor("left", rect(0, 0, 40, 30), 1.0)
+                .with_monitor("right", rect(40, 0, 100, 45), 1.5)
+                .with_failed_monitor("bottom", rect(0, 45, 100, 60), 1.0),
+        );
+        let monitors = MonitorList::from_source(source, None);
+
+        let (image, scale_mapping) = monitors
+            .capture_with_scale_mode(
+                None,
+                CaptureScaleMode::Resample {
+                    target_scale_factor: None,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(scale_mapping.target_scale_factor, Some(1.5));
+        assert_eq!(scale_mapping.monitors[0].target_rect, rect(0, 0, 60, 45));
+        assert_eq!(scale_mapping.monitors[1].target_rect, rect(60, 0, 120, 45));
+        assert_eq!(scale_mapping.monitors[2].target_rect, rect(0, 60, 150, 83));
+        assert_eq!((image.width(), image.height()), (150, 83));
+
+        // 150% 的显示器不需要缩放，保持原始像素
+        let image = image.to_rgb8();
+        for y in 0..45 {
+            for x in 0..60 {
+                assert_eq!(
+                    *image.get_pixel(60 + x, y),
+                    FakeCaptureSource::pixel(40 + x as i32, y as i32)
+                );
+            }
+        }
+        assert_eq!(*image.get_pixel(130, 20), Rgb([0, 0, 0]));
+        assert_eq!(*image.get_pixel(10, 50), Rgb([0, 0, 0]));
+
+        // 保留物理像素时和直接截图一致
+        let region = rect(20, 10, 70, 40);
+        let (image, scale_mapping) = monitors
+            .capture_with_scale_mode(Some(region), CaptureScaleMode::Native, None)
+            .await
+            .unwrap();
+        assert_eq!(scale_mapping.target_scale_factor, None);
+        assert_eq!(scale_mapping.source_to_target(45, 12), Some((25, 2)));
+        assert_eq!(
+            image.to_rgb8(),
+            monitors
+                .capture_region(region, None)
+                .await
+                .unwrap()
+                .to_rgb8()
+        );
+    }
 
     #[test]
     fn test_get_all_monitors() {
@@ -0,0 +1,142 @@
+use image::{ColorType, DynamicImage, ImageBuffer, Rgb};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// 拼接、叠加图片时使用的像素格式，像素按行连续存储
+///
+/// 16 位格式按照平台字节序存储，与 `DynamicImage::as_bytes` 一致
+#[derive(PartialEq, Eq, Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub enum PixelFormat {
+    #[default]
+    Rgb8,
+    /// 保留透明通道
+    Rgba8,
+    /// 保留高位深（如 HDR 显示器的截图）
+    Rgb16,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgb8 => 3,
+            PixelFormat::Rgba8 => 4,
+            PixelFormat::Rgb16 => 6,
+        }
+    }
+
+    pub fn has_alpha(self) -> bool {
+        self == PixelFormat::Rgba8
+    }
+
+    pub fn color_type(self) -> ColorType {
+        match self {
+            PixelFormat::Rgb8 => ColorType::Rgb8,
+            PixelFormat::Rgba8 => ColorType::Rgba8,
+            PixelFormat::Rgb16 => ColorType::Rgb16,
+        }
+    }
+
+    /// 选择能保留图片内容的像素格式
+    ///
+    /// 高位深的图片优先保留位深，同时带有透明通道的高位深图片会丢失透明通道
+    pub fn from_image(image: &DynamicImage) -> Self {
+        match image.color() {
+            ColorType::Rgb8 | ColorType::L8 => PixelFormat::Rgb8,
+            ColorType::Rgba8 | ColorType::La8 => PixelFormat::Rgba8,
+            _ => {
+                if image.color().bytes_per_pixel() / image.color().channel_count() > 1 {
+                    PixelFormat::Rgb16
+                } else if image.color().has_alpha() {
+                    PixelFormat::Rgba8
+                } else {
+                    PixelFormat::Rgb8
+                }
+            }
+        }
+    }
+
+    /// 转换为当前像素格式，格式一致时不做处理
+    pub fn convert(self, image: DynamicImage) -> DynamicImage {
+        match (self, image) {
+            (PixelFormat::Rgb8, image @ DynamicImage::ImageRgb8(_)) => image,
+            (PixelFormat::Rgba8, image @ DynamicImage::ImageRgba8(_)) => image,
+            (PixelFormat::Rgb16, image @ DynamicImage::ImageRgb16(_)) => image,
+            (PixelFormat::Rgb8, image) => DynamicImage::ImageRgb8(image.to_rgb8()),
+            (PixelFormat::Rgba8, image) => DynamicImage::ImageRgba8(image.to_rgba8()),
+            (PixelFormat::Rgb16, image) => DynamicImage::ImageRgb16(image.to_rgb16()),
+        }
+    }
+
+    /// 转换为当前像素格式，格式一致时直接借用，不复制像素数据
+    pub fn convert_ref(self, image: &DynamicImage) -> Cow<'_, DynamicImage> {
+        if image.color() == self.color_type() {
+            return Cow::Borrowed(image);
+        }
+
+        Cow::Owned(match self {
+            PixelFormat::Rgb8 => DynamicImage::ImageRgb8(image.to_rgb8()),
+            PixelFormat::Rgba8 => DynamicImage::ImageRgba8(image.to_rgba8()),
+            PixelFormat::Rgb16 => DynamicImage::ImageRgb16(image.to_rgb16()),
+        })
+    }
+
+    /// 从按行存储的像素数据构建图片，数据长度不匹配时返回 None
+    pub fn image_from_bytes(self, width: u32, height: u32, bytes: Vec<u8>) -> Option<DynamicImage> {
+        match self {
+            PixelFormat::Rgb8 => {
+                image::RgbImage::from_raw(width, height, bytes).map(DynamicImage::ImageRgb8)
+            }
+            PixelFormat::Rgba8 => {
+                image::RgbaImage::from_raw(width, height, bytes).map(DynamicImage::ImageRgba8)
+            }
+            PixelFormat::Rgb16 => {
+                let pixels = bytes
+                    .chunks_exact(2)
+                    .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+                    .collect::<Vec<u16>>();
+
+                ImageBuffer::<Rgb<u16>, Vec<u16>>::from_raw(width, height, pixels)
+                    .map(DynamicImage::ImageRgb16)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let source = DynamicImage::ImageRgba16(ImageBuffer::from_fn(4, 3, |x, y| {
+            image::Rgba([x as u16 * 4000, y as u16 * 9000, 513, 30000])
+        }));
+
+        for pixel_format in [PixelFormat::Rgb8, PixelFormat::Rgba8, PixelFormat::Rgb16] {
+            let image = pixel_format.convert(source.clone());
+            assert_eq!(image.color(), pixel_format.color_type());
+            assert_eq!(
+                image.as_bytes().len(),
+                4 * 3 * pixel_format.bytes_per_pixel()
+            );
+
+            assert!(matches!(pixel_format.convert_ref(&image), Cow::Borrowed(_)));
+            assert_eq!(pixel_format.convert_ref(&source).as_ref(), &image);
+
+            let restored = pixel_format
+                .image_from_bytes(4, 3, image.as_bytes().to_vec())
+                .unwrap();
+            assert_eq!(restored, image);
+        }
+
+        assert_eq!(PixelFormat::from_image(&source), PixelFormat::Rgb16);
+        assert_eq!(
+            PixelFormat::from_image(&DynamicImage::new_rgba8(1, 1)),
+            PixelFormat::Rgba8
+        );
+        assert_eq!(
+            PixelFormat::from_image(&DynamicImage::new_luma8(1, 1)),
+            PixelFormat::Rgb8
+        );
+    }
+}
@@ -0,0 +1,429 @@
+use image::DynamicImage;
+use png::{AdaptiveFilterType, BitDepth, ColorType, Compression, FilterType};
+use rayon::iter::IndexedParallelIterator;
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use rayon::slice::{ParallelSlice, ParallelSliceMut};
+use std::collections::HashMap;
+
+/// 调色板最多的颜色数
+const MAX_PALETTE_SIZE: usize = 256;
+
+/// 尝试的过滤方式，None 为逐行自适应
+const FILTER_STRATEGIES: [Option<FilterType>; 6] = [
+    Some(FilterType::NoFilter),
+    Some(FilterType::Sub),
+    Some(FilterType::Up),
+    Some(FilterType::Avg),
+    Some(FilterType::Paeth),
+    None,
+];
+
+/// 一种候选的 PNG 像素格式
+struct PngCandidate {
+    color_type: ColorType,
+    bit_depth: BitDepth,
+    palette: Option<Vec<u8>>,
+    trns: Option<Vec<u8>>,
+    /// 按行打包后的像素数据
+    data: Vec<u8>,
+}
+
+/// 优化编码 PNG
+///
+/// 根据图片内容生成灰度、去除透明度、调色板等候选格式，每种格式尝试所有过滤方式并以最高压缩等级编码，
+/// 选择最小的结果。颜色不超过 256 种时使用无损的调色板，quantize 为 true 时颜色更多的图片也会量化为调色板（有损）。
+/// 高位深的图片按 8 位编码
+pub fn encode_optimized_png(image: &DynamicImage, quantize: bool) -> Result<Vec<u8>, String> {
+    let width = image.width();
+    let height = image.height();
+    let rgba_image = image.to_rgba8();
+    let pixels: &[u8] = rgba_image.as_raw();
+
+    let opaque = pixels.par_chunks_exact(4).all(|pixel| pixel[3] == 255);
+    let grayscale = pixels
+        .par_chunks_exact(4)
+        .all(|pixel| pixel[0] == pixel[1] && pixel[1] == pixel[2]);
+
+    let mut candidates = vec![truecolor_candidate(pixels, opaque, grayscale)];
+
+    match collect_colors(
+        pixels,
+        if quantize {
+            None
+        } else {
+            Some(MAX_PALETTE_SIZE)
+        },
+    ) {
+        Some(histogram) if histogram.len() <= MAX_PALETTE_SIZE => {
+            let mut palette = histogram.into_keys().collect::<Vec<_>>();
+            // 透明的颜色排在前面，tRNS 只需要覆盖这部分
+            palette.sort_by_key(|color| (color[3] == 255, *color));
+
+            let color_indices = palette
+                .iter()
+                .enumerate()
+                .map(|(index, color)| (*color, index as u8))
+                .collect::<HashMap<_, _>>();
+            candidates.push(palette_candidate(
+                pixels,
+                width as usize,
+                &palette,
+                &color_indices,
+            ));
+        }
+        Some(histogram) => {
+            let histogram = histogram.into_iter().collect::<Vec<_>>();
+            let mut palette = median_cut_palette(histogram.clone(), MAX_PALETTE_SIZE);
+            palette.sort_by_key(|color| (color[3] == 255, *color));
+
+            // 每种颜色映射到调色板中最接近的颜色
+            let color_indices = histogram
+                .par_iter()
+                .map(|(color, _)| (*color, nearest_palette_index(&palette, color)))
+                .collect::<HashMap<_, _>>();
+            candidates.push(palette_candidate(
+                pixels,
+                width as usize,
+                &palette,
+                &color_indices,
+            ));
+        }
+        None => {}
+    }
+
+    let results = candidates
+        .iter()
+        .flat_map(|candidate| FILTER_STRATEGIES.map(|filter| (candidate, filter)))
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|(candidate, filter)| encode_candidate(candidate, width, height, filter))
+        .collect::<Vec<_>>();
+
+    let mut smallest: Option<Vec<u8>> = None;
+    for result in results {
+        let buf = match result {
+            Ok(buf) => buf,
+            Err(e) => {
+                return Err(format!(
+                    "[encode_optimized_png] Failed to encode image as png: {}",
+                    e
+                ));
+            }
+        };
+
+        if smallest
+            .as_ref()
+            .is_none_or(|smallest| buf.len() < smallest.len())
+        {
+            smallest = Some(buf);
+        }
+    }
+
+    match smallest {
+        Some(buf) => Ok(buf),
+        None => Err(String::from(
+            "[encode_optimized_png] Failed to encode image as png",
+        )),
+    }
+}
+
+/// 统计颜色数量，超过 limit 种时返回 None
+fn collect_colors(pixels: &[u8], limit: Option<usize>) -> Option<HashMap<[u8; 4], u32>> {
+    let mut histogram: HashMap<[u8; 4], u32> = HashMap::new();
+    for pixel in pixels.chunks_exact(4) {
+        let mut color = [pixel[0], pixel[1], pixel[2], pixel[3]];
+        // 完全透明的像素颜色没有意义，合并为一种
+        if color[3] == 0 {
+            color = [0, 0, 0, 0];
+        }
+
+        *histogram.entry(color).or_insert(0) += 1;
+        if limit.is_some_and(|limit| histogram.len() > limit) {
+            return None;
+        }
+    }
+
+    Some(histogram)
+}
+
+/// 去除不需要的通道后的真彩色或灰度格式
+fn truecolor_candidate(pixels: &[u8], opaque: bool, grayscale: bool) -> PngCandidate {
+    let (color_type, channels): (ColorType, &[usize]) = match (grayscale, opaque) {
+        (true, true) => (ColorType::Grayscale, &[0]),
+        (true, false) => (ColorType::GrayscaleAlpha, &[0, 3]),
+        (false, true) => (ColorType::Rgb, &[0, 1, 2]),
+        (false, false) => (ColorType::Rgba, &[0, 1, 2, 3]),
+    };
+
+    let data = if channels.len() == 4 {
+        pixels.to_vec()
+    } else {
+        pixels
+            .chunks_exact(4)
+            .flat_map(|pixel| channels.iter().map(|&channel| pixel[channel]))
+            .collect()
+    };
+
+    PngCandidate {
+        color_type,
+        bit_depth: BitDepth::Eight,
+        palette: None,
+        trns: None,
+        data,
+    }
+}
+
+/// 调色板格式，按颜色数选择最小的位深
+fn palette_candidate(
+    pixels: &[u8],
+    width: usize,
+    palette: &[[u8; 4]],
+    color_indices: &HashMap<[u8; 4], u8>,
+) -> PngCandidate {
+    let bit_depth = match palette.len() {
+        0..=2 => BitDepth::One,
+        3..=4 => BitDepth::Two,
+        5..=16 => BitDepth::Four,
+        _ => BitDepth::Eight,
+    };
+    let bits = bit_depth as usize;
+    let pixels_per_byte = 8 / bits;
+    let row_size = width.div_ceil(pixels_per_byte);
+
+    let mut data = vec![0u8; row_size * (pixels.len() / 4 / width.max(1))];
+    if width > 0 {
+        data.par_chunks_exact_mut(row_size)
+            .zip(pixels.par_chunks_exact(width * 4))
+            .for_each(|(row, row_pixels)| {
+                for (x, pixel) in row_pixels.chunks_exact(4).enumerate() {
+                    let mut color = [pixel[0], pixel[1], pixel[2], pixel[3]];
+                    if color[3] == 0 {
+                        color = [0, 0, 0, 0];
+                    }
+                    // 高位在前
+                    let shift = 8 - bits * (x % pixels_per_byte + 1);
+                    row[x / pixels_per_byte] |= color_indices[&color] << shift;
+                }
+            });
+    }
+
+    let mut rgb_palette = Vec::with_capacity(palette.len() * 3);
+    for color in palette {
+        rgb_palette.extend_from_slice(&color[..3]);
+    }
+    let trns = palette
+        .iter()
+        .take_while(|color| color[3] != 255)
+        .map(|color| color[3])
+        .collect::<Vec<_>>();
+
+    PngCandidate {
+        color_type: ColorType::Indexed,
+        bit_depth,
+        palette: Some(rgb_palette),
+        trns: if trns.is_empty() { None } else { Some(trns) },
+        data,
+    }
+}
+
+fn encode_candidate(
+    candidate: &PngCandidate,
+    width: u32,
+    height: u32,
+    filter: Option<FilterType>,
+) -> Result<Vec<u8>, png::EncodingError> {
+    let mut buf = Vec::with_capacity(candidate.data.len() / 4);
+
+    let mut encoder = png::Encoder::new(&mut buf, width, height);
+    encoder.set_color(candidate.color_type);
+    encoder.set_depth(candidate.bit_depth);
+    encoder.set_compression(Compression::Best);
+    match filter {
+        Some(filter) => {
+            encoder.set_filter(filter);
+            encoder.set_adaptive_filter(AdaptiveFilterType::NonAdaptive);
+        }
+        None => encoder.set_adaptive_filter(AdaptiveFilterType::Adaptive),
+    }
+    if let Some(palette) = &candidate.palette {
+        encoder.set_palette(palette.as_slice());
+    }
+    if let Some(trns) = &candidate.trns {
+        encoder.set_trns(trns.as_slice());
+    }
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&candidate.data)?;
+    writer.finish()?;
+
+    Ok(buf)
+}
+
+/// 中位切分量化
+///
+/// 每次选择通道范围最大的颜色盒，在该通道按像素数的中位数切分，直到颜色盒数量达到 max_colors，
+/// 调色板为每个颜色盒内颜色按像素数加权的平均值
+fn median_cut_palette(mut histogram: Vec<([u8; 4], u32)>, max_colors: usize) -> Vec<[u8; 4]> {
+    let channel_range = |colors: &[([u8; 4], u32)]| -> (usize, u8) {
+        (0..4)
+            .map(|channel| {
+                let (min, max) = colors.iter().fold((255u8, 0u8), |(min, max), (color, _)| {
+                    (min.min(color[channel]), max.max(color[channel]))
+                });
+                (channel, max.saturating_sub(min))
+            })
+            .max_by_key(|&(channel, range)| (range, std::cmp::Reverse(channel)))
+            .unwrap_or((0, 0))
+    };
+
+    // 颜色盒为 histogram 中的区间
+    let mut boxes = vec![(0, histogram.len())];
+    while boxes.len() < max_colors {
+        let Some((box_index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, (start, end))| end - start > 1)
+            .map(|(box_index, (start, end))| {
+                let (channel, range) = channel_range(&histogram[*start..*end]);
+                (box_index, channel, range)
+            })
+            .filter(|&(_, _, range)| range > 0)
+            .max_by_key(|&(box_index, _, range)| (range, std::cmp::Reverse(box_index)))
+            .map(|(box_index, channel, _)| (box_index, channel))
+        else {
+            break;
+        };
+
+        let (start, end) = boxes[box_index];
+        let colors = &mut histogram[start..end];
+        colors.sort_unstable_by_key(|(color, _)| color[channel]);
+
+        let total = colors.iter().map(|(_, count)| *count as u64).sum::<u64>();
+        let mut accumulated = 0;
+        let mut split = colors.len() / 2;
+        for (index, (_, count)) in colors.iter().enumerate() {
+            accumulated += *count as u64;
+            if accumulated * 2 >= total {
+                split = index + 1;
+                break;
+            }
+        }
+        let split = start + split.clamp(1, colors.len() - 1);
+
+        boxes[box_index] = (start, split);
+        boxes.push((split, end));
+    }
+
+    boxes
+        .iter()
+        .map(|&(start, end)| {
+            let mut sum = [0u64; 4];
+            let mut total = 0u64;
+            for (color, count) in &histogram[start..end] {
+                for channel in 0..4 {
+                    sum[channel] += color[channel] as u64 * *count as u64;
+                }
+                total += *count as u64;
+            }
+
+            let total = total.max(1);
+            sum.map(|value| ((value + total / 2) / total) as u8)
+        })
+        .collect()
+}
+
+fn nearest_palette_index(palette: &[[u8; 4]], color: &[u8; 4]) -> u8 {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, entry)| {
+            (0..4)
+                .map(|channel| {
+                    let diff = entry[channel] as i32 - color[channel] as i32;
+                    diff * diff
+                })
+                .sum::<i32>()
+        })
+        .map(|(index, _)| index as u8)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_encoder::{ImageEncoder, PngEncoderOptions, encode_image};
+    use image::{Rgba, RgbaImage};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_encode_optimized_png() {
+        // 类似界面截图的纯色块，包含透明区域
+        let colors = [
+            Rgba([255, 255, 255, 255]),
+            Rgba([30, 120, 220, 255]),
+            Rgba([40, 40, 40, 255]),
+            Rgba([240, 240, 240, 255]),
+            Rgba([0, 0, 0, 0]),
+        ];
+        let mut image = RgbaImage::new(97, 61);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            *pixel = colors[((x / 13 + y / 7) % 5) as usize];
+        }
+        let image = DynamicImage::ImageRgba8(image);
+
+        let buf = encode_optimized_png(&image, false).unwrap();
+        // IHDR 的颜色类型为调色板，位深为 4
+        assert_eq!((buf[24], buf[25]), (4, 3));
+        assert_eq!(
+            image::load_from_memory(&buf).unwrap().to_rgba8(),
+            image.to_rgba8()
+        );
+        let default_buf =
+            encode_image(&image, &ImageEncoder::Png(PngEncoderOptions::default())).unwrap();
+        assert!(buf.len() < default_buf.len());
+
+        // 颜色超过 256 种时无损编码
+        let mut image = RgbaImage::new(64, 64);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            *pixel = Rgba([(x * 4) as u8, (y * 4) as u8, ((x + y) * 2) as u8, 255]);
+        }
+        let image = DynamicImage::ImageRgba8(image);
+
+        let buf = encode_optimized_png(&image, false).unwrap();
+        assert_eq!(buf[25], 2);
+        assert_eq!(
+            image::load_from_memory(&buf).unwrap().to_rgba8(),
+            image.to_rgba8()
+        );
+
+        // 带噪点的色块颜色超过 256 种，量化为调色板后更小，误差有限
+        let mut image = RgbaImage::new(64, 64);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let color = colors[((x / 16 + y / 16) % 4) as usize];
+            let noise =
+                (x.wrapping_mul(73856093) ^ y.wrapping_mul(19349663)).wrapping_mul(83492791);
+            *pixel = Rgba([
+                color[0].saturating_sub((noise >> 8) as u8 % 6),
+                color[1].saturating_sub((noise >> 16) as u8 % 6),
+                color[2].saturating_sub((noise >> 24) as u8 % 6),
+                255,
+            ]);
+        }
+        let image = DynamicImage::ImageRgba8(image);
+        assert!(collect_colors(image.to_rgba8().as_raw(), Some(MAX_PALETTE_SIZE)).is_none());
+
+        let lossless_buf = encode_optimized_png(&image, false).unwrap();
+        let buf = encode_optimized_png(&image, true).unwrap();
+        assert_eq!(buf[25], 3);
+        assert!(buf.len() < lossless_buf.len());
+        let decoded_image = image::load_from_memory(&buf).unwrap().to_rgba8();
+        assert!(decoded_image.pixels().collect::<HashSet<_>>().len() <= MAX_PALETTE_SIZE);
+        let total_diff = decoded_image
+            .as_raw()
+            .iter()
+            .zip(image.to_rgba8().as_raw())
+            .map(|(a, b)| a.abs_diff(*b) as u64)
+            .sum::<u64>();
+        assert!(total_diff / (64 * 64 * 4) < 4);
+    }
+}
@@ -1,11 +1,13 @@
 use base64::prelude::*;
-use image::DynamicImage;
+use snow_shot_app_utils::batch_convert::{self, BatchConvertOptions, BatchConvertResult};
+use snow_shot_app_utils::image_encoder::ImageEncoder;
+use snow_shot_app_utils::image_format::ImageFileFormat;
+use snow_shot_app_utils::image_metadata::{ImageMetadata, write_image_metadata};
+use snow_shot_app_utils::save_image_to_file;
+use std::borrow::Cow;
 use std::path::PathBuf;
+use tauri::Emitter;
 use tokio::fs;
-use zune_core::bit_depth::BitDepth;
-use zune_core::colorspace::ColorSpace;
-use zune_core::options::EncoderOptions;
-use zune_jpegxl::JxlSimpleEncoder;
 
 pub async fn save_file(request: tauri::ipc::Request<'_>) -> Result<(), String> {
     let file_data = match request.body() {
@@ -40,64 +42,59 @@ pub async fn save_file(request: tauri::ipc::Request<'_>) -> Result<(), String> {
         None => return Err(String::from("[save_file] Missing file type")),
     };
 
-    // 如果是 avif 则重写解码写入
-    if file_type == "image/avif" {
-        let image = match image::load_from_memory_with_format(
-            file_data.as_slice(),
-            image::ImageFormat::WebP,
-        ) {
-            Ok(image) => image,
-            Err(_) => return Err(String::from("[save_file] Invalid image")),
-        };
-
-        return match image.save_with_format(file_path, image::ImageFormat::Avif) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!(
-                "[save_file] Failed to save image to file: {}",
-                e.to_string()
-            )),
-        };
-    } else if file_type == "image/jpeg-xl" {
-        let image = match image::load_from_memory_with_format(
-            file_data.as_slice(),
-            image::ImageFormat::WebP,
-        ) {
-            Ok(image) => image,
-            Err(_) => return Err(String::from("[save_file] Invalid image")),
-        };
-        let has_alpha = image.color().has_alpha();
-        let image_data = if has_alpha {
-            DynamicImage::ImageRgba8(image.to_rgba8())
-        } else {
-            DynamicImage::ImageRgb8(image.to_rgb8())
-        };
-        let encoder = JxlSimpleEncoder::new(
-            image_data.as_bytes(),
-            EncoderOptions::new(
-                image.width() as usize,
-                image.height() as usize,
-                if has_alpha {
-                    ColorSpace::RGBA
-                } else {
-                    ColorSpace::RGB
-                },
-                BitDepth::Eight,
-            ),
-        );
-        let encoder_result = match encoder.encode() {
-            Ok(encoder_result) => encoder_result,
-            Err(_) => return Err(String::from("[save_file] Failed to encode image")),
-        };
-        return match fs::write(file_path, encoder_result).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!(
-                "[save_file] Failed to save image to file: {}",
-                e.to_string()
-            )),
-        };
+    // 未传入元数据时保持图片原有的元数据
+    let metadata: Option<ImageMetadata> = match request.headers().get("x-image-metadata") {
+        Some(header) => match BASE64_STANDARD.decode(header.to_str().unwrap()) {
+            Ok(metadata) => match serde_json::from_slice(&metadata) {
+                Ok(metadata) => Some(metadata),
+                Err(_) => return Err(String::from("[save_file] Invalid image metadata")),
+            },
+            Err(_) => return Err(String::from("[save_file] Invalid image metadata")),
+        },
+        None => None,
+    };
+
+    // 指定编码器时（如优化 PNG）总是由后端重新编码
+    let encoder: Option<ImageEncoder> = match request.headers().get("x-image-encoder") {
+        Some(header) => match BASE64_STANDARD.decode(header.to_str().unwrap()) {
+            Ok(encoder) => match serde_json::from_slice(&encoder) {
+                Ok(encoder) => Some(encoder),
+                Err(_) => return Err(String::from("[save_file] Invalid image encoder")),
+            },
+            Err(_) => return Err(String::from("[save_file] Invalid image encoder")),
+        },
+        None => None,
+    };
+
+    let mut file_data = Cow::Borrowed(file_data);
+
+    // 图片按照格式表保存，前端已经编码为目标格式时直接写入，否则（如浏览器不支持编码的格式）解码后重新编码
+    if let Some(image_format) = ImageFileFormat::from_mime_type(&file_type) {
+        if encoder.is_some() || !image_format.is_encoded_data(&file_data) {
+            let image = match image::load_from_memory(file_data.as_slice()) {
+                Ok(image) => image,
+                Err(_) => return Err(String::from("[save_file] Invalid image")),
+            };
+
+            return save_image_to_file(
+                &image,
+                file_path,
+                Some(encoder.unwrap_or_else(|| image_format.default_encoder())),
+                metadata.as_ref(),
+            )
+            .await;
+        }
+
+        if let Some(metadata) = &metadata {
+            file_data = Cow::Owned(write_image_metadata(
+                file_data.into_owned(),
+                image_format,
+                metadata,
+            )?);
+        }
     }
 
-    match fs::write(file_path, file_data).await {
+    match fs::write(file_path, file_data.as_slice()).await {
         Ok(_) => Ok(()),
         Err(e) => Err(format!(
             "[save_file] Failed to save image to file: {}",
@@ -159,3 +156,31 @@ pub async fn remove_file(file_path: PathBuf) -> Result<(), String> {
         )),
     }
 }
+
+pub const BATCH_CONVERT_IMAGES_PROGRESS_EMIT_KEY: &str = "batch-convert-images:progress";
+
+/**
+ * 批量转换图片格式
+ *
+ * 每处理完一个文件会向窗口发送 BatchConvertProgress，预演模式只返回转换计划
+ */
+pub async fn batch_convert_images(
+    window: tauri::Window,
+    options: BatchConvertOptions,
+) -> Result<BatchConvertResult, String> {
+    let convert_task = tokio::task::spawn_blocking(move || {
+        batch_convert::batch_convert_images(&options, |progress| {
+            if let Err(e) = window.emit(BATCH_CONVERT_IMAGES_PROGRESS_EMIT_KEY, progress) {
+                log::error!("[batch_convert_images] Failed to emit progress: {}", e);
+            }
+        })
+    });
+
+    match convert_task.await {
+        Ok(result) => result,
+        Err(e) => Err(format!(
+            "[batch_convert_images] Failed to join convert task: {}",
+            e
+        )),
+    }
+}
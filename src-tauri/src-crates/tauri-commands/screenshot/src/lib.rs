@@ -1,6 +1,16 @@
 use serde::Serialize;
 use snow_shot_app_os::ui_automation::UIElements;
+use snow_shot_app_services::capture_scheduler_service::{
+    CaptureScheduleOptions, CaptureScheduleResult, CaptureSchedulerService, run_capture_schedule,
+};
 use snow_shot_app_shared::ElementRect;
+use snow_shot_app_utils::capture_scale::{CaptureScaleMapping, CaptureScaleMode};
+use snow_shot_app_utils::cursor_overlay::{
+    CursorOverlayOptions, overlay_cursor, overlay_cursor_with_mapping,
+};
+use snow_shot_app_utils::image_encoder::{ImageEncoder, encode_image};
+use snow_shot_app_utils::image_metadata::ImageMetadata;
+use snow_shot_app_utils::monitor_info::MonitorInfo;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -11,12 +21,13 @@ use xcap::Window;
 
 pub async fn capture_current_monitor(
     #[allow(unused_variables)] window: tauri::Window,
-    encoder: String,
+    encoder: ImageEncoder,
+    cursor: Option<CursorOverlayOptions>,
 ) -> Result<Response, String> {
     // 获取当前鼠标的位置
     let (_, _, monitor) = snow_shot_app_utils::get_target_monitor()?;
 
-    let image_buffer =
+    let mut image_buffer =
         match snow_shot_app_utils::capture_target_monitor(&monitor, None, Some(&window)) {
             Some(image) => image,
             None => {
@@ -25,14 +36,19 @@ pub async fn capture_current_monitor(
             }
         };
 
-    let image_buffer = snow_shot_app_utils::encode_image(
-        &image_buffer,
-        match encoder.as_str() {
-            "webp" => snow_shot_app_utils::ImageEncoder::Webp,
-            "png" => snow_shot_app_utils::ImageEncoder::Png,
-            _ => snow_shot_app_utils::ImageEncoder::Webp,
-        },
-    );
+    if let Some(cursor) = cursor {
+        let monitor_rect = MonitorInfo::new(&monitor).rect;
+        if let Err(e) = overlay_cursor(
+            window.app_handle(),
+            &mut image_buffer,
+            (monitor_rect.min_x, monitor_rect.min_y),
+            &cursor,
+        ) {
+            log::warn!("[capture_current_monitor] Failed to overlay cursor: {}", e);
+        }
+    }
+
+    let image_buffer = encode_image(&image_buffer, &encoder)?;
 
     Ok(Response::new(image_buffer))
 }
@@ -40,34 +56,75 @@ pub async fn capture_current_monitor(
 pub async fn capture_all_monitors(
     window: tauri::Window,
     enable_multiple_monitor: bool,
+    encoder: ImageEncoder,
+    scale_mode: Option<CaptureScaleMode>,
+    cursor: Option<CursorOverlayOptions>,
 ) -> Result<Response, String> {
-    let image = snow_shot_app_utils::get_capture_monitor_list(
+    let monitor_list = snow_shot_app_utils::get_capture_monitor_list(
         &window.app_handle(),
         None,
         enable_multiple_monitor,
-    )?
-    .capture(Some(&window))
-    .await?;
+    )?;
+    let scale_mode = scale_mode.unwrap_or_default();
+    let (mut image, scale_mapping) = match scale_mode {
+        CaptureScaleMode::Native => (monitor_list.capture(Some(&window)).await?, None),
+        scale_mode => {
+            let (image, scale_mapping) = monitor_list
+                .capture_with_scale_mode(None, scale_mode, Some(&window))
+                .await?;
+            (image, Some(scale_mapping))
+        }
+    };
 
-    let image_buffer =
-        snow_shot_app_utils::encode_image(&image, snow_shot_app_utils::ImageEncoder::Png);
+    if let Some(cursor) = cursor {
+        let scale_mapping =
+            scale_mapping.unwrap_or_else(|| monitor_list.get_scale_mapping(None, scale_mode));
+        if let Err(e) =
+            overlay_cursor_with_mapping(window.app_handle(), &mut image, &scale_mapping, &cursor)
+        {
+            log::warn!("[capture_all_monitors] Failed to overlay cursor: {}", e);
+        }
+    }
+
+    let image_buffer = encode_image(&image, &encoder)?;
 
     Ok(Response::new(image_buffer))
 }
 
-pub async fn capture_focused_window<F>(
-    write_image_to_clipboard: F,
-    file_path: String,
-    copy_to_clipboard: bool,
-    focus_window_app_name_variable_name: String,
-) -> Result<(), String>
-where
-    F: Fn(&image::DynamicImage) -> Result<(), String> + Send + 'static,
-{
+/// 获取 capture_all_monitors 截图的坐标映射，用于将桌面物理像素坐标转为截图中的坐标
+pub async fn get_capture_scale_mapping(
+    window: tauri::Window,
+    enable_multiple_monitor: bool,
+    scale_mode: Option<CaptureScaleMode>,
+) -> Result<CaptureScaleMapping, String> {
+    let monitor_list = snow_shot_app_utils::get_capture_monitor_list(
+        &window.app_handle(),
+        None,
+        enable_multiple_monitor,
+    )?;
+
+    Ok(monitor_list.get_scale_mapping(None, scale_mode.unwrap_or_default()))
+}
+
+/// 截取的焦点窗口，获取不到焦点窗口时为鼠标所在的显示器
+pub struct FocusedWindowImage {
+    pub image: image::RgbaImage,
+    /// 截取窗口的应用名称
+    pub app_name: String,
+    pub title: String,
+    /// 截取窗口所在的显示器
+    pub monitor: Option<xcap::Monitor>,
+}
+
+/// 截取当前的焦点窗口
+pub fn capture_focused_window_image() -> Result<FocusedWindowImage, String> {
     let image;
 
     // 截取窗口的应用名称
     let focused_window_app_name;
+    // 截取窗口的标题和所在的显示器，写入图片元数据
+    let focused_window_title;
+    let capture_monitor: Option<xcap::Monitor>;
 
     #[cfg(target_os = "windows")]
     {
@@ -76,20 +133,21 @@ where
         let focused_window = xcap::Window::new(xcap::ImplWindow::new(hwnd));
 
         focused_window_app_name = focused_window.app_name().unwrap_or_default();
+        focused_window_title = focused_window.title().unwrap_or_default();
 
-        image = match focused_window.capture_image() {
-            Ok(image) => image,
+        (image, capture_monitor) = match focused_window.capture_image() {
+            Ok(image) => (image, focused_window.current_monitor().ok()),
             Err(_) => {
-                log::warn!("[capture_focused_window] Failed to capture focused window");
+                log::warn!("[capture_focused_window_image] Failed to capture focused window");
                 // 改成捕获当前显示器
 
                 let (_, _, monitor) = snow_shot_app_utils::get_target_monitor()?;
 
                 match monitor.capture_image() {
-                    Ok(image) => image,
+                    Ok(image) => (image, Some(monitor)),
                     Err(_) => {
                         return Err(String::from(
-                            "[capture_focused_window] Failed to capture image",
+                            "[capture_focused_window_image] Failed to capture image",
                         ));
                     }
                 }
@@ -99,14 +157,45 @@ where
 
     #[cfg(target_os = "linux")]
     {
-        let (_, _, monitor) = snow_shot_app_utils::get_target_monitor();
+        // X11 下通过 _NET_ACTIVE_WINDOW 获取焦点窗口，Wayland 下获取不到时截取当前显示器
+        let window_list = xcap::Window::all().unwrap_or_default();
+        let window = window_list
+            .iter()
+            .find(|w| w.is_focused().unwrap_or(false) && !w.is_minimized().unwrap_or(false));
 
-        image = match monitor.capture_image() {
-            Ok(image) => image,
-            Err(_) => {
-                return Err(String::from(
-                    "[capture_focused_window] Failed to capture image",
-                ));
+        focused_window_app_name = match window {
+            Some(window) => window.app_name().unwrap_or_default(),
+            None => "".to_string(),
+        };
+        focused_window_title = match window {
+            Some(window) => window.title().unwrap_or_default(),
+            None => "".to_string(),
+        };
+
+        let window_image = match window {
+            Some(window) => match window.capture_image() {
+                Ok(image) => Some((image, window.current_monitor().ok())),
+                Err(_) => None,
+            },
+            None => None,
+        };
+
+        (image, capture_monitor) = match window_image {
+            Some(window_image) => window_image,
+            None => {
+                log::warn!("[capture_focused_window_image] Failed to capture focused window");
+                // 改成捕获当前显示器
+
+                let (_, _, monitor) = snow_shot_app_utils::get_target_monitor()?;
+
+                match snow_shot_app_utils::capture_target_monitor(&monitor, None, None) {
+                    Some(image) => (image.to_rgba8(), Some(monitor)),
+                    None => {
+                        return Err(String::from(
+                            "[capture_focused_window_image] Failed to capture image",
+                        ));
+                    }
+                }
             }
         };
     }
@@ -125,28 +214,32 @@ where
             Some(window) => window.app_name().unwrap_or_default(),
             None => "".to_string(),
         };
+        focused_window_title = match window {
+            Some(window) => window.title().unwrap_or_default(),
+            None => "".to_string(),
+        };
 
         let window_image = match window {
             Some(window) => match window.capture_image() {
-                Ok(image) => Some(image),
+                Ok(image) => Some((image, window.current_monitor().ok())),
                 Err(_) => None,
             },
             None => None,
         };
 
-        image = match window_image {
-            Some(image) => image,
+        (image, capture_monitor) = match window_image {
+            Some(window_image) => window_image,
             None => {
-                log::warn!("[capture_focused_window] Failed to capture focused window");
+                log::warn!("[capture_focused_window_image] Failed to capture focused window");
                 // 改成捕获当前显示器
 
                 let (_, _, monitor) = snow_shot_app_utils::get_target_monitor()?;
 
                 match monitor.capture_image() {
-                    Ok(image) => image,
+                    Ok(image) => (image, Some(monitor)),
                     Err(_) => {
                         return Err(String::from(
-                            "[capture_focused_window] Failed to capture image",
+                            "[capture_focused_window_image] Failed to capture image",
                         ));
                     }
                 }
@@ -154,6 +247,46 @@ where
         };
     }
 
+    Ok(FocusedWindowImage {
+        image,
+        app_name: focused_window_app_name,
+        title: focused_window_title,
+        monitor: capture_monitor,
+    })
+}
+
+pub async fn capture_focused_window<F>(
+    write_image_to_clipboard: F,
+    file_path: String,
+    copy_to_clipboard: bool,
+    focus_window_app_name_variable_name: String,
+    metadata: ImageMetadata,
+) -> Result<(), String>
+where
+    F: Fn(&image::DynamicImage) -> Result<(), String> + Send + 'static,
+{
+    let capture_time = SystemTime::now();
+    let FocusedWindowImage {
+        image,
+        app_name: focused_window_app_name,
+        title: focused_window_title,
+        monitor: capture_monitor,
+    } = capture_focused_window_image()?;
+
+    let mut metadata = metadata;
+    if !metadata.strip {
+        metadata.capture_time = capture_time
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|duration| duration.as_millis() as i64);
+        metadata.app_name = Some(focused_window_app_name.clone());
+        metadata.window_title = Some(focused_window_title);
+        if let Some(monitor) = &capture_monitor {
+            metadata.monitor_name = monitor.name().ok();
+            metadata.scale_factor = monitor.scale_factor().ok().map(|scale| scale as f64);
+        }
+    }
+
     let focused_window_app_name = if focused_window_app_name == "" {
         "unknown".to_string()
     } else {
@@ -169,8 +302,12 @@ where
     let image = Arc::new(image::DynamicImage::ImageRgba8(image));
 
     // 并行执行保存文件和写入剪贴板
-    let save_file_future =
-        snow_shot_app_utils::save_image_to_file(&image, PathBuf::from(file_path));
+    let save_file_future = snow_shot_app_utils::save_image_to_file(
+        &image,
+        PathBuf::from(file_path),
+        None,
+        Some(&metadata),
+    );
     let clipboard_future = if copy_to_clipboard {
         let image_clone = Arc::clone(&image);
         Some(tokio::task::spawn_blocking(
@@ -201,6 +338,46 @@ where
     Ok(())
 }
 
+/// 定时截图，倒计时和每次截图的进度通过事件发送，任务结束或取消后返回结果
+pub async fn capture_schedule_start(
+    app_handle: tauri::AppHandle,
+    capture_scheduler_service: tauri::State<'_, Mutex<CaptureSchedulerService>>,
+    options: CaptureScheduleOptions,
+) -> Result<CaptureScheduleResult, String> {
+    let control = capture_scheduler_service.lock().await.start()?;
+
+    let result = run_capture_schedule(&app_handle, &control, &options, || {
+        capture_focused_window_image()
+            .map(|focused_window| image::DynamicImage::ImageRgba8(focused_window.image))
+    })
+    .await;
+
+    capture_scheduler_service.lock().await.finish();
+
+    Ok(result)
+}
+
+pub async fn capture_schedule_pause(
+    capture_scheduler_service: tauri::State<'_, Mutex<CaptureSchedulerService>>,
+) -> Result<(), String> {
+    capture_scheduler_service.lock().await.pause();
+    Ok(())
+}
+
+pub async fn capture_schedule_resume(
+    capture_scheduler_service: tauri::State<'_, Mutex<CaptureSchedulerService>>,
+) -> Result<(), String> {
+    capture_scheduler_service.lock().await.resume();
+    Ok(())
+}
+
+pub async fn capture_schedule_cancel(
+    capture_scheduler_service: tauri::State<'_, Mutex<CaptureSchedulerService>>,
+) -> Result<(), String> {
+    capture_scheduler_service.lock().await.cancel();
+    Ok(())
+}
+
 pub async fn init_ui_elements(ui_elements: tauri::State<'_, Mutex<UIElements>>) -> Result<(), ()> {
     let mut ui_elements = ui_elements.lock().await;
 
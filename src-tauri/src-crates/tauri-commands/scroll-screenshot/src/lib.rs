@@ -1,45 +1,73 @@
+use device_query::Keycode;
+use enigo::{Axis, Coordinate, Mouse};
 use image::codecs::png::{self, CompressionType, PngEncoder};
 use image::imageops::FilterType;
 use serde::Serialize;
 use snow_shot_app_scroll_screenshot_service::scroll_screenshot_capture_service::ScrollScreenshotCaptureService;
-use snow_shot_app_shared::ElementRect;
+use snow_shot_app_shared::{ElementRect, EnigoManager};
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tauri::ipc::Response;
+use tauri::{Emitter, Listener};
 use tokio::sync::Mutex;
 
-use snow_shot_app_scroll_screenshot_service::scroll_screenshot_image_service::ScrollScreenshotImageService;
+use snow_shot_app_scroll_screenshot_service::scroll_screenshot_auto_scroll_service::{
+    AUTO_SCROLL_MAX_FRAME_COUNT, AUTO_SCROLL_SETTLE_INTERVAL, AUTO_SCROLL_SETTLE_TIMEOUT,
+    ScrollAutoScrollProgress, ScrollAutoScrollStopReason, ScrollScreenshotAutoScrollService,
+    ScrollSettleDetector,
+};
+use snow_shot_app_services::listen_key_service::{
+    LISTEN_KEY_SERVICE_KEY_DOWN_EMIT_KEY, ListenKeyDownEvent, ListenKeyService,
+};
+
+use snow_shot_app_scroll_screenshot_service::scroll_screenshot_image_service::{
+    ScrollFrameChange, ScrollScreenshotImageService,
+};
+use snow_shot_app_scroll_screenshot_service::scroll_screenshot_metadata::{
+    ScrollFrameCaptureInfo, embed_png_metadata, write_metadata_file,
+};
+use snow_shot_app_scroll_screenshot_service::scroll_screenshot_page_export::{
+    ScrollPageExportFormat, ScrollPageExportOptions, ScrollPageManifest, create_file,
+    export_pages_to_file,
+};
 use snow_shot_app_scroll_screenshot_service::scroll_screenshot_service::{
-    ScrollDirection, ScrollImageList, ScrollScreenshotService,
+    ScrollDirection, ScrollImageList, ScrollScreenshotParams, ScrollScreenshotService,
+};
+use snow_shot_app_scroll_screenshot_service::scroll_screenshot_session::{
+    ScrollSessionInfo, autosave_session, get_autosave_session_directory, has_autosave_session,
+    load_autosave_session, load_session_from_file, remove_autosave_session, save_session_to_file,
 };
-use snow_shot_app_utils::{self, save_image_to_file};
+use snow_shot_app_utils::image_encoder::{
+    ImageEncoder, encode_image_for_file, get_image_encoder_for_file,
+};
+use snow_shot_app_utils::monitor_info::MonitorList;
+use snow_shot_app_utils::{self, save_image_to_file, write_image_buffer_to_file};
 
 pub async fn scroll_screenshot_init(
     scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
     direction: ScrollDirection,
-    sample_rate: f32,
-    min_sample_size: u32,
-    max_sample_size: u32,
-    corner_threshold: u8,
-    descriptor_patch_size: usize,
-    min_size_delta: i32,
-    try_rollback: bool,
+    params: ScrollScreenshotParams,
 ) -> Result<(), ()> {
     let mut scroll_screenshot_service = scroll_screenshot_service.lock().await;
 
-    scroll_screenshot_service.init(
-        direction,
-        sample_rate,
-        min_sample_size,
-        max_sample_size,
-        corner_threshold,
-        descriptor_patch_size,
-        min_size_delta,
-        try_rollback,
-    );
+    scroll_screenshot_service.init_with_params(direction, params);
 
     Ok(())
 }
 
+#[derive(Serialize)]
+pub struct ScrollScreenshotCaptureStatus {
+    /// 截图相对上一次加入队列的截图的变化，只有滚动的截图会加入队列
+    pub frame_change: ScrollFrameChange,
+    /// 最近截图的变化率，范围为 0 到 1
+    pub change_rate: f32,
+    /// 建议的下一次截图间隔（毫秒）
+    pub capture_interval: u64,
+}
+
 pub async fn scroll_screenshot_capture(
     window: tauri::Window,
     scroll_screenshot_image_service: tauri::State<'_, Mutex<ScrollScreenshotImageService>>,
@@ -49,45 +77,97 @@ pub async fn scroll_screenshot_capture(
     min_y: i32,
     max_x: i32,
     max_y: i32,
-) -> Result<(), String> {
+) -> Result<ScrollScreenshotCaptureStatus, String> {
     // 区域截图
-    let image = {
-        #[cfg(target_os = "macos")]
-        let rect_scale;
-        #[cfg(not(target_os = "macos"))]
-        let rect_scale = 1.0f64;
-
-        // macOS 下截图区域是基于逻辑像素
-        #[cfg(target_os = "macos")]
-        {
-            rect_scale = (1.0 / window.scale_factor().unwrap_or(1.0)) as f64;
-        }
-
-        let min_x = min_x as f64 * rect_scale;
-        let min_y = min_y as f64 * rect_scale;
-        let max_x = max_x as f64 * rect_scale;
-        let max_y = max_y as f64 * rect_scale;
-
-        let crop_region = ElementRect {
-            min_x: min_x.round() as i32,
-            min_y: min_y.round() as i32,
-            max_x: max_x.round() as i32,
-            max_y: max_y.round() as i32,
-        };
+    let (image, capture_info) = {
+        let crop_region = get_capture_region(&window, min_x, min_y, max_x, max_y);
         let mut monitor_list_service = scroll_screenshot_capture_service.lock().await;
         monitor_list_service.init(crop_region);
 
         let monitor_list = monitor_list_service.get();
 
-        monitor_list.capture_region(crop_region, Some(&window)).await?
+        let capture_time = SystemTime::now();
+        let image = monitor_list.capture_region(crop_region, Some(&window)).await?;
+
+        (
+            image,
+            get_frame_capture_info(monitor_list, crop_region, capture_time),
+        )
     };
 
-    scroll_screenshot_image_service
-        .lock()
-        .await
-        .push_image(image, scroll_image_list);
+    let mut scroll_screenshot_image_service = scroll_screenshot_image_service.lock().await;
+    let frame_change =
+        scroll_screenshot_image_service.push_image(image, scroll_image_list, Some(capture_info));
+
+    Ok(ScrollScreenshotCaptureStatus {
+        frame_change,
+        change_rate: scroll_screenshot_image_service.change_rate(),
+        capture_interval: scroll_screenshot_image_service
+            .capture_interval()
+            .as_millis() as u64,
+    })
+}
 
-    Ok(())
+/**
+ * 记录截图的时间、显示器和区域，导出时写入元数据
+ */
+fn get_frame_capture_info(
+    monitor_list: &MonitorList,
+    crop_region: ElementRect,
+    capture_time: SystemTime,
+) -> ScrollFrameCaptureInfo {
+    // 截图区域跨越多个显示器时，取重叠面积最大的显示器
+    let monitor_name = monitor_list
+        .iter()
+        .max_by_key(|monitor_info| {
+            let overlap_rect = monitor_info.rect.clip_rect(&crop_region);
+            (overlap_rect.max_x - overlap_rect.min_x).max(0) as i64
+                * (overlap_rect.max_y - overlap_rect.min_y).max(0) as i64
+        })
+        .map(|monitor_info| monitor_info.name.clone())
+        .filter(|monitor_name| !monitor_name.is_empty());
+
+    ScrollFrameCaptureInfo {
+        timestamp: capture_time
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0),
+        monitor_name,
+        crop_region,
+    }
+}
+
+/**
+ * 将选区转换为截图区域，macOS 下截图区域是基于逻辑像素
+ */
+fn get_capture_region(
+    #[allow(unused_variables)] window: &tauri::Window,
+    min_x: i32,
+    min_y: i32,
+    max_x: i32,
+    max_y: i32,
+) -> ElementRect {
+    #[cfg(target_os = "macos")]
+    let rect_scale;
+    #[cfg(not(target_os = "macos"))]
+    let rect_scale = 1.0f64;
+
+    #[cfg(target_os = "macos")]
+    {
+        rect_scale = (1.0 / window.scale_factor().unwrap_or(1.0)) as f64;
+    }
+
+    let min_x = min_x as f64 * rect_scale;
+    let min_y = min_y as f64 * rect_scale;
+    let max_x = max_x as f64 * rect_scale;
+    let max_y = max_y as f64 * rect_scale;
+
+    ElementRect {
+        min_x: min_x.round() as i32,
+        min_y: min_y.round() as i32,
+        max_x: max_x.round() as i32,
+        max_y: max_y.round() as i32,
+    }
 }
 
 /**
@@ -114,8 +194,12 @@ pub async fn scroll_screenshot_handle_image(
         }
     };
 
-    let (handle_result, is_origin, result_scroll_image_list) =
-        scroll_screenshot_service.handle_image(scroll_image.image, scroll_image.direction);
+    let (handle_result, is_origin, result_scroll_image_list) = scroll_screenshot_service
+        .handle_image_with_capture_info(
+            scroll_image.image,
+            scroll_image.direction,
+            scroll_image.capture_info,
+        );
 
     if is_origin {
         return Ok(Response::new(vec![1])); // 特殊标记，表示是未变化
@@ -132,48 +216,399 @@ pub async fn scroll_screenshot_handle_image(
         }
     };
 
-    let crop_image = match handle_result {
+    let image_list = match handle_result {
         (edge_position, None) => {
             return Ok(Response::new(edge_position.to_le_bytes().to_vec()));
         }
-        (_, Some(ScrollImageList::Top)) => scroll_screenshot_service.top_image_list.last().unwrap(),
-        (_, Some(ScrollImageList::Bottom)) => {
-            scroll_screenshot_service.bottom_image_list.last().unwrap()
+        (_, Some(image_list)) => image_list,
+    };
+
+    autosave_scroll_session(&mut scroll_screenshot_service);
+
+    Ok(Response::new(encode_scroll_image_thumbnail(
+        &scroll_screenshot_service,
+        image_list,
+        handle_result.0,
+        result_scroll_image_list,
+        thumbnail_size,
+    )))
+}
+
+pub const SCROLL_SCREENSHOT_AUTO_SCROLL_FRAME_EMIT_KEY: &str =
+    "scroll-screenshot:auto-scroll-frame";
+/// 自动滚动时监听按键使用的标识
+const SCROLL_SCREENSHOT_AUTO_SCROLL_LISTEN_KEY_LABEL: &str = "scroll-screenshot-auto-scroll";
+
+/**
+ * 自动滚动截图
+ *
+ * 每次滚动固定的距离，等待画面稳定后截图并拼接，连续没有新内容时认为到达页面末端。
+ * 每拼接一帧会向窗口发送缩略图，格式和 scroll_screenshot_handle_image 一致。
+ * 按下 Esc 或调用 scroll_screenshot_auto_scroll_stop 可以取消
+ */
+pub async fn scroll_screenshot_auto_scroll(
+    app_handle: tauri::AppHandle,
+    window: tauri::Window,
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    scroll_screenshot_capture_service: tauri::State<'_, Mutex<ScrollScreenshotCaptureService>>,
+    scroll_screenshot_auto_scroll_service: tauri::State<
+        '_,
+        Mutex<ScrollScreenshotAutoScrollService>,
+    >,
+    listen_key_service: tauri::State<'_, Mutex<ListenKeyService>>,
+    enigo_manager: tauri::State<'_, Mutex<EnigoManager>>,
+    min_x: i32,
+    min_y: i32,
+    max_x: i32,
+    max_y: i32,
+    scroll_step: i32,
+    thumbnail_size: u32,
+) -> Result<ScrollAutoScrollStopReason, String> {
+    let cancelled = scroll_screenshot_auto_scroll_service.lock().await.start()?;
+
+    // 通过 ListenKeyService 的按键事件取消
+    if let Err(e) = listen_key_service.lock().await.start_by_window_label(
+        app_handle.clone(),
+        SCROLL_SCREENSHOT_AUTO_SCROLL_LISTEN_KEY_LABEL,
+    ) {
+        log::warn!(
+            "[scroll_screenshot_auto_scroll] Failed to start listen key service: {}",
+            e
+        );
+    }
+    let listen_key_cancelled = cancelled.clone();
+    let listen_key_event_id =
+        app_handle.listen(LISTEN_KEY_SERVICE_KEY_DOWN_EMIT_KEY, move |event| {
+            if let Ok(key_down_event) = serde_json::from_str::<ListenKeyDownEvent>(event.payload())
+            {
+                if key_down_event.is_key(Keycode::Escape) {
+                    listen_key_cancelled.store(true, Ordering::Relaxed);
+                }
+            }
+        });
+
+    // 滚轮事件需要穿透到下方的窗口
+    let result = match window.set_ignore_cursor_events(true) {
+        Ok(_) => {
+            auto_scroll_core(
+                &window,
+                &scroll_screenshot_service,
+                &scroll_screenshot_capture_service,
+                &enigo_manager,
+                &cancelled,
+                get_capture_region(&window, min_x, min_y, max_x, max_y),
+                scroll_step,
+                thumbnail_size,
+            )
+            .await
         }
+        Err(e) => Err(format!(
+            "[scroll_screenshot_auto_scroll] Failed to set ignore cursor events: {}",
+            e
+        )),
     };
 
-    let mut buf = Vec::new();
+    let _ = window.set_ignore_cursor_events(false);
+    app_handle.unlisten(listen_key_event_id);
+    if let Err(e) = listen_key_service
+        .lock()
+        .await
+        .stop_by_window_label(SCROLL_SCREENSHOT_AUTO_SCROLL_LISTEN_KEY_LABEL)
+    {
+        log::warn!(
+            "[scroll_screenshot_auto_scroll] Failed to stop listen key service: {}",
+            e
+        );
+    }
+    scroll_screenshot_auto_scroll_service.lock().await.finish();
 
-    let image_width = crop_image.image.width();
-    let image_height = crop_image.image.height();
-    let scale = if scroll_screenshot_service.current_direction == ScrollDirection::Vertical {
-        thumbnail_size as f32 / image_width as f32
-    } else {
-        thumbnail_size as f32 / image_height as f32
+    result
+}
+
+async fn auto_scroll_core(
+    window: &tauri::Window,
+    scroll_screenshot_service: &Mutex<ScrollScreenshotService>,
+    scroll_screenshot_capture_service: &Mutex<ScrollScreenshotCaptureService>,
+    enigo_manager: &Mutex<EnigoManager>,
+    cancelled: &Arc<AtomicBool>,
+    crop_region: ElementRect,
+    scroll_step: i32,
+    thumbnail_size: u32,
+) -> Result<ScrollAutoScrollStopReason, String> {
+    let scroll_axis = match scroll_screenshot_service.lock().await.current_direction {
+        ScrollDirection::Vertical => Axis::Vertical,
+        ScrollDirection::Horizontal => Axis::Horizontal,
+        ScrollDirection::Free => {
+            return Err(String::from(
+                "[scroll_screenshot_auto_scroll] Free direction is not supported",
+            ));
+        }
     };
 
-    let thumbnail = crop_image.image.resize(
-        ((image_width as f32 * scale) as u32).max(1), // 防止图片某一边为 0
-        ((image_height as f32 * scale) as u32).max(1),
-        FilterType::Triangle,
-    );
+    scroll_screenshot_capture_service
+        .lock()
+        .await
+        .init(crop_region);
+
+    // 滚轮作用于鼠标所在的窗口，先移动到截图区域中心
+    {
+        let mut enigo = enigo_manager.lock().await;
+        if let Err(e) = enigo.get_enigo()?.move_mouse(
+            (crop_region.min_x + crop_region.max_x) / 2,
+            (crop_region.min_y + crop_region.max_y) / 2,
+            Coordinate::Abs,
+        ) {
+            return Err(format!(
+                "[scroll_screenshot_auto_scroll] Failed to move mouse: {}",
+                e
+            ));
+        }
+    }
+
+    let mut settle_detector = ScrollSettleDetector::new();
+    let mut progress = ScrollAutoScrollProgress::new(AUTO_SCROLL_MAX_FRAME_COUNT);
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return Ok(ScrollAutoScrollStopReason::Cancelled);
+        }
+
+        {
+            let mut enigo = enigo_manager.lock().await;
+            if let Err(e) = enigo.get_enigo()?.scroll(scroll_step, scroll_axis) {
+                return Err(format!(
+                    "[scroll_screenshot_auto_scroll] Failed to scroll: {}",
+                    e
+                ));
+            }
+        }
 
-    thumbnail
-        .write_with_encoder(PngEncoder::new_with_quality(
-            &mut buf,
-            CompressionType::Fast,
-            png::FilterType::Paeth,
-        ))
-        .unwrap();
+        // 等待画面稳定，超时后使用最后一次截图
+        settle_detector.reset();
+        let settle_start = Instant::now();
+        let (image, capture_info) = loop {
+            tokio::time::sleep(AUTO_SCROLL_SETTLE_INTERVAL).await;
+            if cancelled.load(Ordering::Relaxed) {
+                return Ok(ScrollAutoScrollStopReason::Cancelled);
+            }
+
+            let capture_service = scroll_screenshot_capture_service.lock().await;
+            let monitor_list = capture_service.get();
+            let capture_time = SystemTime::now();
+            let image = monitor_list
+                .capture_region(crop_region, Some(window))
+                .await?;
+
+            if settle_detector.push(&image) || settle_start.elapsed() >= AUTO_SCROLL_SETTLE_TIMEOUT
+            {
+                break (
+                    image,
+                    get_frame_capture_info(monitor_list, crop_region, capture_time),
+                );
+            }
+        };
+
+        let mut scroll_screenshot_service = scroll_screenshot_service.lock().await;
+        let handle_result = scroll_screenshot_service.handle_image_with_capture_info(
+            image,
+            ScrollImageList::Bottom,
+            Some(capture_info),
+        );
+        let has_new_content = ScrollAutoScrollProgress::has_new_content(&handle_result);
+
+        if let (Some((edge_position, Some(image_list))), _, result_scroll_image_list) =
+            handle_result
+        {
+            autosave_scroll_session(&mut scroll_screenshot_service);
+
+            let thumbnail = encode_scroll_image_thumbnail(
+                &scroll_screenshot_service,
+                image_list,
+                edge_position,
+                result_scroll_image_list,
+                thumbnail_size,
+            );
+            if let Err(e) = window.emit(SCROLL_SCREENSHOT_AUTO_SCROLL_FRAME_EMIT_KEY, thumbnail) {
+                log::error!(
+                    "[scroll_screenshot_auto_scroll] Failed to emit frame: {}",
+                    e
+                );
+            }
+        }
+
+        if let Some(stop_reason) = progress.push(has_new_content) {
+            return Ok(stop_reason);
+        }
+    }
+}
+
+/**
+ * 取消正在进行的自动滚动
+ */
+pub async fn scroll_screenshot_auto_scroll_stop(
+    scroll_screenshot_auto_scroll_service: tauri::State<
+        '_,
+        Mutex<ScrollScreenshotAutoScrollService>,
+    >,
+) -> Result<(), ()> {
+    scroll_screenshot_auto_scroll_service.lock().await.cancel();
+
+    Ok(())
+}
+
+/**
+ * 拼接成功后自动保存会话，失败时只记录日志，不影响截图
+ */
+fn autosave_scroll_session(scroll_screenshot_service: &mut ScrollScreenshotService) {
+    if let Err(e) = autosave_session(scroll_screenshot_service, &get_autosave_session_directory()) {
+        log::warn!(
+            "[autosave_scroll_session] Failed to autosave session: {}",
+            e
+        );
+    }
+}
+
+/**
+ * 编码图片列表中最后一张图片的缩略图，并在末尾附加拼接信息
+ *
+ * 图片列表为空时只返回拼接信息
+ */
+fn encode_scroll_image_thumbnail(
+    scroll_screenshot_service: &ScrollScreenshotService,
+    image_list: ScrollImageList,
+    edge_position: i32,
+    result_scroll_image_list: ScrollImageList,
+    thumbnail_size: u32,
+) -> Vec<u8> {
+    let frame_count = scroll_screenshot_service.frame_store.len(image_list);
+
+    encode_scroll_frame_thumbnail(
+        scroll_screenshot_service,
+        image_list,
+        frame_count.checked_sub(1),
+        edge_position,
+        result_scroll_image_list,
+        thumbnail_size,
+    )
+}
+
+/**
+ * 编码图片列表中指定帧的缩略图，并在末尾附加拼接信息
+ *
+ * 未指定帧时只返回拼接信息
+ */
+fn encode_scroll_frame_thumbnail(
+    scroll_screenshot_service: &ScrollScreenshotService,
+    image_list: ScrollImageList,
+    frame_index: Option<usize>,
+    edge_position: i32,
+    result_scroll_image_list: ScrollImageList,
+    thumbnail_size: u32,
+) -> Vec<u8> {
+    let mut buf = Vec::new();
+
+    let mut overlay_size = 0;
+    if let Some(frame_index) = frame_index {
+        let crop_image = scroll_screenshot_service
+            .frame_store
+            .read_image(image_list, frame_index)
+            .unwrap();
+
+        let image_width = crop_image.width();
+        let image_height = crop_image.height();
+        let scale = if scroll_screenshot_service.current_direction == ScrollDirection::Vertical {
+            thumbnail_size as f32 / image_width as f32
+        } else {
+            thumbnail_size as f32 / image_height as f32
+        };
+
+        let thumbnail = crop_image.resize(
+            ((image_width as f32 * scale) as u32).max(1), // 防止图片某一边为 0
+            ((image_height as f32 * scale) as u32).max(1),
+            FilterType::Triangle,
+        );
+
+        thumbnail
+            .write_with_encoder(PngEncoder::new_with_quality(
+                &mut buf,
+                CompressionType::Fast,
+                png::FilterType::Paeth,
+            ))
+            .unwrap();
+
+        overlay_size = (scroll_screenshot_service
+            .frame_store
+            .overlay_size(image_list, frame_index) as f32
+            * scale) as i32;
+    }
 
     // 添加边缘位置信息到缓冲区末尾
-    buf.extend_from_slice(&handle_result.0.to_le_bytes());
-    buf.extend_from_slice(&((crop_image.overlay_size as f32 * scale) as i32).to_le_bytes());
+    buf.extend_from_slice(&edge_position.to_le_bytes());
+    buf.extend_from_slice(&overlay_size.to_le_bytes());
     buf.extend_from_slice(&scroll_screenshot_service.top_image_size.to_le_bytes());
     buf.extend_from_slice(&scroll_screenshot_service.bottom_image_size.to_le_bytes());
     buf.extend_from_slice(&(result_scroll_image_list as i32).to_le_bytes());
 
-    Ok(Response::new(buf))
+    // 固定区域尺寸
+    let sticky_bands = scroll_screenshot_service.sticky_bands.unwrap_or_default();
+    buf.extend_from_slice(&(sticky_bands.start_size as i32).to_le_bytes());
+    buf.extend_from_slice(&(sticky_bands.end_size as i32).to_le_bytes());
+
+    // 匹配的置信度
+    buf.extend_from_slice(&scroll_screenshot_service.match_confidence.to_le_bytes());
+
+    buf
+}
+
+/**
+ * 撤销最近拼接的一帧
+ *
+ * 返回该图片列表中新的最后一帧的缩略图和撤销后的尺寸信息，格式和 scroll_screenshot_handle_image 一致，
+ * 列表中没有剩余的帧时不包含缩略图，没有可撤销的帧时返回空数据
+ */
+pub async fn scroll_screenshot_undo(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    thumbnail_size: u32,
+) -> Result<Response, String> {
+    let mut scroll_screenshot_service = scroll_screenshot_service.lock().await;
+
+    let image_list = match scroll_screenshot_service.undo()? {
+        Some(image_list) => image_list,
+        None => return Ok(Response::new(vec![])),
+    };
+
+    Ok(Response::new(encode_scroll_image_thumbnail(
+        &scroll_screenshot_service,
+        image_list,
+        0,
+        image_list,
+        thumbnail_size,
+    )))
+}
+
+/**
+ * 重新拼接最近撤销的一帧
+ *
+ * 返回该帧的缩略图和拼接信息，格式和 scroll_screenshot_handle_image 一致，
+ * 没有可重做的帧时返回空数据
+ */
+pub async fn scroll_screenshot_redo(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    thumbnail_size: u32,
+) -> Result<Response, String> {
+    let mut scroll_screenshot_service = scroll_screenshot_service.lock().await;
+
+    let image_list = match scroll_screenshot_service.redo()? {
+        Some(image_list) => image_list,
+        None => return Ok(Response::new(vec![])),
+    };
+
+    Ok(Response::new(encode_scroll_image_thumbnail(
+        &scroll_screenshot_service,
+        image_list,
+        0,
+        image_list,
+        thumbnail_size,
+    )))
 }
 
 #[derive(Serialize)]
@@ -193,27 +628,164 @@ pub async fn scroll_screenshot_get_size(
     })
 }
 
+/**
+ * 保存拼接结果
+ *
+ * 按照格式表编码，未指定编码器时根据文件扩展名选择格式并使用默认参数；
+ * write_metadata 为 true 时在图片旁写入 <文件名>.json，记录每一帧的截图信息和在图片中的位置；
+ * embed_metadata 为 true 时将相同的数据嵌入 PNG 的文本块，其他格式忽略
+ */
 pub async fn scroll_screenshot_save_to_file(
     scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
     file_path: String,
+    encoder: Option<ImageEncoder>,
+    write_metadata: bool,
+    embed_metadata: bool,
 ) -> Result<(), String> {
     let mut scroll_screenshot_service = scroll_screenshot_service.lock().await;
 
-    let image = scroll_screenshot_service.export();
-    let image = match image {
-        Some(image) => image,
-        None => {
-            return Err(format!(
-                "[scroll_screenshot_save_to_file] Failed to export image"
-            ));
-        }
+    let file_path = PathBuf::from(file_path);
+    let encoder = get_image_encoder_for_file(&file_path, encoder)?;
+
+    let metadata = if write_metadata || embed_metadata {
+        scroll_screenshot_service.get_export_metadata()
+    } else {
+        None
     };
 
-    save_image_to_file(&image, PathBuf::from(file_path)).await?;
+    let embedded_metadata = metadata.as_ref().filter(|_| embed_metadata);
+    match (encoder, embedded_metadata) {
+        // PNG 按行写出，不需要在内存中构建完整的图片；优化需要完整的图片
+        (ImageEncoder::Png(options), _) if !options.optimize => {
+            let mut writer = create_file(&file_path)?;
+            scroll_screenshot_service.export_png_with_options(
+                &mut writer,
+                embedded_metadata,
+                &options,
+            )?;
+            writer
+                .flush()
+                .map_err(|e| format!("[scroll_screenshot_save_to_file] Failed to flush: {}", e))?;
+        }
+        (ImageEncoder::Png(_), Some(embedded_metadata)) => {
+            let image = scroll_screenshot_service.export()?;
+            let image_buffer = embed_png_metadata(
+                encode_image_for_file(&image, &file_path, Some(encoder), None)?,
+                embedded_metadata,
+            )?;
+            write_image_buffer_to_file(image_buffer, file_path.clone()).await?;
+        }
+        _ => {
+            let image = scroll_screenshot_service.export()?;
+            save_image_to_file(&image, file_path.clone(), Some(encoder), None).await?;
+        }
+    }
+
+    if write_metadata {
+        if let Some(metadata) = &metadata {
+            write_metadata_file(metadata, &file_path)?;
+        }
+    }
 
     Ok(())
 }
 
+/**
+ * 将拼接结果分页导出，返回每页在完整图片中的位置
+ */
+pub async fn scroll_screenshot_export_pages(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    file_path: String,
+    format: ScrollPageExportFormat,
+    page_size: u32,
+    overlap: u32,
+) -> Result<ScrollPageManifest, String> {
+    let mut scroll_screenshot_service = scroll_screenshot_service.lock().await;
+
+    export_pages_to_file(
+        &mut scroll_screenshot_service,
+        ScrollPageExportOptions {
+            format,
+            page_size,
+            overlap,
+        },
+        &PathBuf::from(file_path),
+    )
+}
+
+/**
+ * 保存当前的拼接状态，之后可以恢复截图或重新导出
+ */
+pub async fn scroll_screenshot_save_session(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    file_path: String,
+) -> Result<(), String> {
+    let scroll_screenshot_service = scroll_screenshot_service.lock().await;
+
+    save_session_to_file(&scroll_screenshot_service, &PathBuf::from(file_path))
+}
+
+/**
+ * 加载保存的拼接状态，替换当前的拼接状态
+ */
+pub async fn scroll_screenshot_load_session(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    file_path: String,
+) -> Result<ScrollSessionInfo, String> {
+    let mut scroll_screenshot_service = scroll_screenshot_service.lock().await;
+
+    load_session_from_file(&mut scroll_screenshot_service, &PathBuf::from(file_path))
+}
+
+/**
+ * 是否有截图过程中自动保存的会话，截图正常结束后会删除
+ */
+pub async fn scroll_screenshot_has_autosave_session() -> Result<bool, ()> {
+    Ok(has_autosave_session(&get_autosave_session_directory()))
+}
+
+/**
+ * 恢复截图过程中自动保存的会话，替换当前的拼接状态
+ */
+pub async fn scroll_screenshot_restore_autosave_session(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+) -> Result<ScrollSessionInfo, String> {
+    let mut scroll_screenshot_service = scroll_screenshot_service.lock().await;
+
+    load_autosave_session(
+        &mut scroll_screenshot_service,
+        &get_autosave_session_directory(),
+    )
+}
+
+/**
+ * 编码图片列表中指定帧的缩略图，格式和 scroll_screenshot_handle_image 一致，用于恢复会话后显示已拼接的帧
+ */
+pub async fn scroll_screenshot_get_frame_thumbnail(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    image_list: ScrollImageList,
+    index: usize,
+    thumbnail_size: u32,
+) -> Result<Response, String> {
+    let scroll_screenshot_service = scroll_screenshot_service.lock().await;
+
+    if index >= scroll_screenshot_service.frame_store.len(image_list) {
+        return Err(format!(
+            "[scroll_screenshot_get_frame_thumbnail] Frame index out of range: {}",
+            index
+        ));
+    }
+
+    Ok(Response::new(encode_scroll_frame_thumbnail(
+        &scroll_screenshot_service,
+        image_list,
+        Some(index),
+        0,
+        image_list,
+        thumbnail_size,
+    )))
+}
+
 pub async fn scroll_screenshot_save_to_clipboard<F>(
     write_image_to_clipboard: F,
     scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
@@ -223,22 +795,9 @@ where
 {
     let mut scroll_screenshot_service = scroll_screenshot_service.lock().await;
 
-    let image = scroll_screenshot_service.export();
-    match image {
-        Some(image) => match write_image_to_clipboard(&image) {
-            Ok(_) => (),
-            Err(e) => {
-                return Err(e);
-            }
-        },
-        None => {
-            return Err(String::from(
-                "[scroll_screenshot_save_to_clipboard] Failed to export image",
-            ));
-        }
-    }
+    let image = scroll_screenshot_service.export()?;
 
-    Ok(())
+    write_image_to_clipboard(&image)
 }
 
 pub async fn scroll_screenshot_clear(
@@ -254,6 +813,13 @@ pub async fn scroll_screenshot_clear(
     scroll_screenshot_image_service.clear();
     scroll_screenshot_capture_service.clear();
 
+    if let Err(e) = remove_autosave_session(&get_autosave_session_directory()) {
+        log::warn!(
+            "[scroll_screenshot_clear] Failed to remove autosave session: {}",
+            e
+        );
+    }
+
     Ok(())
 }
 
@@ -262,21 +828,11 @@ pub async fn scroll_screenshot_get_image_data(
 ) -> Result<Response, ()> {
     let mut scroll_screenshot_service = scroll_screenshot_service.lock().await;
 
-    let image = scroll_screenshot_service.export();
-    let image_data = match image {
-        Some(image) => image,
-        None => return Err(()),
-    };
-
-    let mut buf = Vec::with_capacity((image_data.height() * image_data.width() * 3 / 8) as usize);
-
-    image_data
-        .write_with_encoder(PngEncoder::new_with_quality(
-            &mut buf,
-            CompressionType::Fast,
-            png::FilterType::Adaptive,
-        ))
-        .unwrap();
+    // 按行编码，不需要在内存中构建完整的图片
+    let mut buf = Vec::new();
+    if scroll_screenshot_service.export_png(&mut buf).is_err() {
+        return Err(());
+    }
 
     Ok(Response::new(buf))
 }
@@ -1,4 +1,5 @@
 use snow_shot_app_services::file_cache_service::FileCacheService;
+use snow_shot_app_utils::batch_convert::{BatchConvertOptions, BatchConvertResult};
 use std::{path::PathBuf, sync::Arc};
 use tauri::command;
 
@@ -22,6 +23,14 @@ pub async fn copy_file(from: PathBuf, to: PathBuf) -> Result<(), String> {
     snow_shot_tauri_commands_file::copy_file(from, to).await
 }
 
+#[command]
+pub async fn batch_convert_images(
+    window: tauri::Window,
+    options: BatchConvertOptions,
+) -> Result<BatchConvertResult, String> {
+    snow_shot_tauri_commands_file::batch_convert_images(window, options).await
+}
+
 #[command]
 pub async fn text_file_read(
     text_file_cache_service: tauri::State<'_, Arc<FileCacheService>>,
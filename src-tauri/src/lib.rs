@@ -13,9 +13,11 @@ use tokio::sync::Mutex;
 use tauri::Manager;
 
 use snow_shot_app_os::ui_automation::UIElements;
+use snow_shot_app_scroll_screenshot_service::scroll_screenshot_auto_scroll_service;
 use snow_shot_app_scroll_screenshot_service::scroll_screenshot_capture_service;
 use snow_shot_app_scroll_screenshot_service::scroll_screenshot_image_service;
 use snow_shot_app_scroll_screenshot_service::scroll_screenshot_service;
+use snow_shot_app_services::capture_scheduler_service;
 use snow_shot_app_services::file_cache_service;
 use snow_shot_app_services::free_drag_window_service;
 use snow_shot_app_services::listen_key_service;
@@ -42,6 +44,8 @@ pub fn run() {
         Mutex::new(scroll_screenshot_image_service::ScrollScreenshotImageService::new());
     let scroll_screenshot_capture_service =
         Mutex::new(scroll_screenshot_capture_service::ScrollScreenshotCaptureService::new());
+    let scroll_screenshot_auto_scroll_service =
+        Mutex::new(scroll_screenshot_auto_scroll_service::ScrollScreenshotAutoScrollService::new());
 
     let free_drag_window_service =
         Mutex::new(free_drag_window_service::FreeDragWindowService::new());
@@ -50,6 +54,9 @@ pub fn run() {
 
     let file_cache_service = Arc::new(file_cache_service::FileCacheService::new());
 
+    let capture_scheduler_service =
+        Mutex::new(capture_scheduler_service::CaptureSchedulerService::new());
+
     tauri::Builder::default()
         .plugin(
             tauri_plugin_window_state::Builder::new()
@@ -158,19 +165,26 @@ pub fn run() {
         .manage(scroll_screenshot_service)
         .manage(scroll_screenshot_image_service)
         .manage(scroll_screenshot_capture_service)
+        .manage(scroll_screenshot_auto_scroll_service)
         .manage(video_record_service)
         .manage(free_drag_window_service)
         .manage(listen_key_service)
         .manage(file_cache_service)
+        .manage(capture_scheduler_service)
         .invoke_handler(tauri::generate_handler![
             screenshot::capture_current_monitor,
             screenshot::capture_all_monitors,
+            screenshot::get_capture_scale_mapping,
             screenshot::capture_focused_window,
             screenshot::get_window_elements,
             screenshot::init_ui_elements,
             screenshot::get_element_from_position,
             screenshot::init_ui_elements_cache,
             screenshot::get_mouse_position,
+            screenshot::capture_schedule_start,
+            screenshot::capture_schedule_pause,
+            screenshot::capture_schedule_resume,
+            screenshot::capture_schedule_cancel,
             screenshot::create_draw_window,
             screenshot::switch_always_on_top,
             screenshot::set_draw_window_style,
@@ -178,6 +192,7 @@ pub fn run() {
             file::write_file,
             file::copy_file,
             file::remove_file,
+            file::batch_convert_images,
             file::create_dir,
             file::remove_dir,
             file::get_app_config_dir,
@@ -209,10 +224,20 @@ pub fn run() {
             scroll_screenshot::scroll_screenshot_init,
             scroll_screenshot::scroll_screenshot_capture,
             scroll_screenshot::scroll_screenshot_handle_image,
+            scroll_screenshot::scroll_screenshot_auto_scroll,
+            scroll_screenshot::scroll_screenshot_auto_scroll_stop,
             scroll_screenshot::scroll_screenshot_save_to_file,
+            scroll_screenshot::scroll_screenshot_export_pages,
+            scroll_screenshot::scroll_screenshot_save_session,
+            scroll_screenshot::scroll_screenshot_load_session,
+            scroll_screenshot::scroll_screenshot_has_autosave_session,
+            scroll_screenshot::scroll_screenshot_restore_autosave_session,
+            scroll_screenshot::scroll_screenshot_get_frame_thumbnail,
             scroll_screenshot::scroll_screenshot_save_to_clipboard,
             scroll_screenshot::scroll_screenshot_get_size,
             scroll_screenshot::scroll_screenshot_clear,
+            scroll_screenshot::scroll_screenshot_undo,
+            scroll_screenshot::scroll_screenshot_redo,
             video_record::video_record_start,
             video_record::video_record_stop,
             video_record::video_record_pause,
@@ -4,23 +4,55 @@ use tauri_plugin_clipboard_manager::ClipboardExt;
 use tokio::sync::Mutex;
 
 use snow_shot_app_os::ui_automation::UIElements;
+use snow_shot_app_services::capture_scheduler_service::{
+    CaptureScheduleOptions, CaptureScheduleResult, CaptureSchedulerService,
+};
 use snow_shot_app_shared::ElementRect;
+use snow_shot_app_utils::capture_scale::{CaptureScaleMapping, CaptureScaleMode};
+use snow_shot_app_utils::cursor_overlay::CursorOverlayOptions;
+use snow_shot_app_utils::image_encoder::ImageEncoder;
+use snow_shot_app_utils::image_metadata::ImageMetadata;
 use snow_shot_tauri_commands_screenshot::WindowElement;
 
 #[command]
 pub async fn capture_current_monitor(
     window: tauri::Window,
-    encoder: String,
+    encoder: ImageEncoder,
+    cursor: Option<CursorOverlayOptions>,
 ) -> Result<Response, String> {
-    snow_shot_tauri_commands_screenshot::capture_current_monitor(window, encoder).await
+    snow_shot_tauri_commands_screenshot::capture_current_monitor(window, encoder, cursor).await
 }
 
 #[command]
 pub async fn capture_all_monitors(
     window: tauri::Window,
     enable_multiple_monitor: bool,
+    encoder: ImageEncoder,
+    scale_mode: Option<CaptureScaleMode>,
+    cursor: Option<CursorOverlayOptions>,
 ) -> Result<Response, String> {
-    snow_shot_tauri_commands_screenshot::capture_all_monitors(window, enable_multiple_monitor).await
+    snow_shot_tauri_commands_screenshot::capture_all_monitors(
+        window,
+        enable_multiple_monitor,
+        encoder,
+        scale_mode,
+        cursor,
+    )
+    .await
+}
+
+#[command]
+pub async fn get_capture_scale_mapping(
+    window: tauri::Window,
+    enable_multiple_monitor: bool,
+    scale_mode: Option<CaptureScaleMode>,
+) -> Result<CaptureScaleMapping, String> {
+    snow_shot_tauri_commands_screenshot::get_capture_scale_mapping(
+        window,
+        enable_multiple_monitor,
+        scale_mode,
+    )
+    .await
 }
 
 /**
@@ -32,6 +64,7 @@ pub async fn capture_focused_window(
     file_path: String,
     copy_to_clipboard: bool,
     focus_window_app_name_variable_name: String,
+    metadata: ImageMetadata,
 ) -> Result<(), String> {
     snow_shot_tauri_commands_screenshot::capture_focused_window(
         move |image| match app.clipboard().write_image(&tauri::image::Image::new(
@@ -48,6 +81,7 @@ pub async fn capture_focused_window(
         file_path,
         copy_to_clipboard,
         focus_window_app_name_variable_name,
+        metadata,
     )
     .await
 }
@@ -84,6 +118,41 @@ pub async fn get_element_from_position(
         .await
 }
 
+#[command]
+pub async fn capture_schedule_start(
+    app_handle: tauri::AppHandle,
+    capture_scheduler_service: tauri::State<'_, Mutex<CaptureSchedulerService>>,
+    options: CaptureScheduleOptions,
+) -> Result<CaptureScheduleResult, String> {
+    snow_shot_tauri_commands_screenshot::capture_schedule_start(
+        app_handle,
+        capture_scheduler_service,
+        options,
+    )
+    .await
+}
+
+#[command]
+pub async fn capture_schedule_pause(
+    capture_scheduler_service: tauri::State<'_, Mutex<CaptureSchedulerService>>,
+) -> Result<(), String> {
+    snow_shot_tauri_commands_screenshot::capture_schedule_pause(capture_scheduler_service).await
+}
+
+#[command]
+pub async fn capture_schedule_resume(
+    capture_scheduler_service: tauri::State<'_, Mutex<CaptureSchedulerService>>,
+) -> Result<(), String> {
+    snow_shot_tauri_commands_screenshot::capture_schedule_resume(capture_scheduler_service).await
+}
+
+#[command]
+pub async fn capture_schedule_cancel(
+    capture_scheduler_service: tauri::State<'_, Mutex<CaptureSchedulerService>>,
+) -> Result<(), String> {
+    snow_shot_tauri_commands_screenshot::capture_schedule_cancel(capture_scheduler_service).await
+}
+
 #[command]
 pub async fn get_mouse_position(app: tauri::AppHandle) -> Result<(i32, i32), String> {
     snow_shot_tauri_commands_screenshot::get_mouse_position(app).await
@@ -3,34 +3,33 @@ use tauri::ipc::Response;
 use tauri_plugin_clipboard_manager::ClipboardExt;
 use tokio::sync::Mutex;
 
+use snow_shot_app_services::listen_key_service::ListenKeyService;
+use snow_shot_app_shared::EnigoManager;
+
+use snow_shot_app_scroll_screenshot_service::scroll_screenshot_auto_scroll_service::{
+    ScrollAutoScrollStopReason, ScrollScreenshotAutoScrollService,
+};
 use snow_shot_app_scroll_screenshot_service::scroll_screenshot_capture_service::ScrollScreenshotCaptureService;
 use snow_shot_app_scroll_screenshot_service::scroll_screenshot_image_service::ScrollScreenshotImageService;
+use snow_shot_app_scroll_screenshot_service::scroll_screenshot_page_export::{
+    ScrollPageExportFormat, ScrollPageManifest,
+};
 use snow_shot_app_scroll_screenshot_service::scroll_screenshot_service::{
-    ScrollDirection, ScrollImageList, ScrollScreenshotService,
+    ScrollDirection, ScrollImageList, ScrollScreenshotParams, ScrollScreenshotService,
 };
+use snow_shot_app_scroll_screenshot_service::scroll_screenshot_session::ScrollSessionInfo;
+use snow_shot_app_utils::image_encoder::ImageEncoder;
 
 #[command]
 pub async fn scroll_screenshot_init(
     scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
     direction: ScrollDirection,
-    sample_rate: f32,
-    min_sample_size: u32,
-    max_sample_size: u32,
-    corner_threshold: u8,
-    descriptor_patch_size: usize,
-    min_size_delta: i32,
-    try_rollback: bool,
+    params: ScrollScreenshotParams,
 ) -> Result<(), ()> {
     snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_init(
         scroll_screenshot_service,
         direction,
-        sample_rate,
-        min_sample_size,
-        max_sample_size,
-        corner_threshold,
-        descriptor_patch_size,
-        min_size_delta,
-        try_rollback,
+        params,
     )
     .await
 }
@@ -45,7 +44,7 @@ pub async fn scroll_screenshot_capture(
     min_y: i32,
     max_x: i32,
     max_y: i32,
-) -> Result<(), String> {
+) -> Result<snow_shot_tauri_commands_scroll_screenshot::ScrollScreenshotCaptureStatus, String> {
     snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_capture(
         window,
         scroll_screenshot_image_service,
@@ -76,6 +75,59 @@ pub async fn scroll_screenshot_handle_image(
     .await
 }
 
+/**
+ * 自动滚动截图，按下 Esc 或调用 scroll_screenshot_auto_scroll_stop 取消
+ */
+#[command]
+pub async fn scroll_screenshot_auto_scroll(
+    app_handle: tauri::AppHandle,
+    window: tauri::Window,
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    scroll_screenshot_capture_service: tauri::State<'_, Mutex<ScrollScreenshotCaptureService>>,
+    scroll_screenshot_auto_scroll_service: tauri::State<
+        '_,
+        Mutex<ScrollScreenshotAutoScrollService>,
+    >,
+    listen_key_service: tauri::State<'_, Mutex<ListenKeyService>>,
+    enigo_manager: tauri::State<'_, Mutex<EnigoManager>>,
+    min_x: i32,
+    min_y: i32,
+    max_x: i32,
+    max_y: i32,
+    scroll_step: i32,
+    thumbnail_size: u32,
+) -> Result<ScrollAutoScrollStopReason, String> {
+    snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_auto_scroll(
+        app_handle,
+        window,
+        scroll_screenshot_service,
+        scroll_screenshot_capture_service,
+        scroll_screenshot_auto_scroll_service,
+        listen_key_service,
+        enigo_manager,
+        min_x,
+        min_y,
+        max_x,
+        max_y,
+        scroll_step,
+        thumbnail_size,
+    )
+    .await
+}
+
+#[command]
+pub async fn scroll_screenshot_auto_scroll_stop(
+    scroll_screenshot_auto_scroll_service: tauri::State<
+        '_,
+        Mutex<ScrollScreenshotAutoScrollService>,
+    >,
+) -> Result<(), ()> {
+    snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_auto_scroll_stop(
+        scroll_screenshot_auto_scroll_service,
+    )
+    .await
+}
+
 #[command]
 pub async fn scroll_screenshot_get_size(
     scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
@@ -90,10 +142,89 @@ pub async fn scroll_screenshot_get_size(
 pub async fn scroll_screenshot_save_to_file(
     scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
     file_path: String,
+    encoder: Option<ImageEncoder>,
+    write_metadata: bool,
+    embed_metadata: bool,
 ) -> Result<(), String> {
     snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_save_to_file(
         scroll_screenshot_service,
         file_path,
+        encoder,
+        write_metadata,
+        embed_metadata,
+    )
+    .await
+}
+
+#[command]
+pub async fn scroll_screenshot_export_pages(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    file_path: String,
+    format: ScrollPageExportFormat,
+    page_size: u32,
+    overlap: u32,
+) -> Result<ScrollPageManifest, String> {
+    snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_export_pages(
+        scroll_screenshot_service,
+        file_path,
+        format,
+        page_size,
+        overlap,
+    )
+    .await
+}
+
+#[command]
+pub async fn scroll_screenshot_save_session(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    file_path: String,
+) -> Result<(), String> {
+    snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_save_session(
+        scroll_screenshot_service,
+        file_path,
+    )
+    .await
+}
+
+#[command]
+pub async fn scroll_screenshot_load_session(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    file_path: String,
+) -> Result<ScrollSessionInfo, String> {
+    snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_load_session(
+        scroll_screenshot_service,
+        file_path,
+    )
+    .await
+}
+
+#[command]
+pub async fn scroll_screenshot_has_autosave_session() -> Result<bool, ()> {
+    snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_has_autosave_session().await
+}
+
+#[command]
+pub async fn scroll_screenshot_restore_autosave_session(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+) -> Result<ScrollSessionInfo, String> {
+    snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_restore_autosave_session(
+        scroll_screenshot_service,
+    )
+    .await
+}
+
+#[command]
+pub async fn scroll_screenshot_get_frame_thumbnail(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    image_list: ScrollImageList,
+    index: usize,
+    thumbnail_size: u32,
+) -> Result<Response, String> {
+    snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_get_frame_thumbnail(
+        scroll_screenshot_service,
+        image_list,
+        index,
+        thumbnail_size,
     )
     .await
 }
@@ -143,3 +274,27 @@ pub async fn scroll_screenshot_get_image_data(
     )
     .await
 }
+
+#[command]
+pub async fn scroll_screenshot_undo(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    thumbnail_size: u32,
+) -> Result<Response, String> {
+    snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_undo(
+        scroll_screenshot_service,
+        thumbnail_size,
+    )
+    .await
+}
+
+#[command]
+pub async fn scroll_screenshot_redo(
+    scroll_screenshot_service: tauri::State<'_, Mutex<ScrollScreenshotService>>,
+    thumbnail_size: u32,
+) -> Result<Response, String> {
+    snow_shot_tauri_commands_scroll_screenshot::scroll_screenshot_redo(
+        scroll_screenshot_service,
+        thumbnail_size,
+    )
+    .await
+}
@@ -34,6 +34,7 @@ pub async fn video_record_start(
     encoder_preset: String,
     video_max_width: i32,
     video_max_height: i32,
+    draw_cursor: Option<bool>,
 ) -> Result<(), String> {
     println!(
         "Starting video recording: area=({},{}) to ({},{}), output={}",
@@ -58,6 +59,7 @@ pub async fn video_record_start(
         encoder_preset,
         video_max_width,
         video_max_height,
+        draw_cursor.unwrap_or(true),
     ) {
         Ok(_) => {
             println!("Video recording started successfully");
@@ -30,13 +30,18 @@ import {
 } from './settings/functionSettings/extra';
 import { defaultTranslationPrompt } from './tools/translation/extra';
 import { ColorPickerShowMode } from './draw/components/colorPicker';
-import { ImageFormat } from '@/utils/file';
+import { ImageFormat, PngOptimization } from '@/utils/file';
 import { DrawState } from './fullScreenDraw/components/drawCore/extra';
 import { OcrDetectAfterAction } from './fixedContent/components/ocrResult';
 import { OcrModel } from '@/commands/ocr';
 import { HistoryValidDuration } from '@/utils/captureHistory';
 import { getPlatformValue } from '@/utils';
 import { GifFormat, VideoMaxSize } from '@/commands/videoRecord';
+import {
+    ScrollFeatureBackend,
+    ScrollPageExportFormat,
+    ScrollSeamMode,
+} from '@/commands/scrollScreenshot';
 import * as tauriLog from '@tauri-apps/plugin-log';
 import { appError, appWarn } from '@/utils/log';
 import {
@@ -235,6 +240,12 @@ export type AppSettingsData = {
         saveFileDirectory: string;
         /** 保存文件格式 */
         saveFileFormat: ImageFormat;
+        /** 保存文件时写入截图时间、应用名称等元数据，关闭时移除所有元数据 */
+        saveImageMetadata: boolean;
+        /** 写入元数据的备注 */
+        imageMetadataComment: string;
+        /** 保存 PNG 时的优化方式 */
+        pngOptimization: PngOptimization;
         /** OCR 后自动执行 */
         ocrAfterAction: OcrDetectAfterAction;
         /** OCR 复制时复制文本 */
@@ -301,11 +312,34 @@ export type AppSettingsData = {
     };
     [AppSettingsGroup.SystemScrollScreenshot]: {
         tryRollback: boolean;
+        /** 检测并去除滚动时保持不动的顶部、底部区域 */
+        detectStickyBands: boolean;
         minSide: number;
         maxSide: number;
         sampleRate: number;
         imageFeatureDescriptionLength: number;
         imageFeatureThreshold: number;
+        /** 拼接帧占用内存的上限（MB），超出后写入临时文件 */
+        memoryBudget: number;
+        /** 相邻两帧重叠区域的接缝处理方式 */
+        seamMode: ScrollSeamMode;
+        /** 接缝两侧混合的像素数，为 0 时不混合 */
+        seamFeatherSize: number;
+        /** 特征提取和匹配方式 */
+        featureBackend: ScrollFeatureBackend;
+        /** 自动滚动时每次滚动的滚轮格数 */
+        autoScrollStep: number;
+        /** 保存时分页导出 */
+        enablePageExport: boolean;
+        pageExportFormat: ScrollPageExportFormat;
+        /** 每页在滚动方向上的尺寸 */
+        pageSize: number;
+        /** 相邻两页重叠的尺寸 */
+        pageOverlap: number;
+        /** 保存时在图片旁写入记录每一帧信息的 JSON 文件 */
+        saveMetadataFile: boolean;
+        /** 保存为 PNG 时将每一帧的信息嵌入文本块 */
+        embedMetadata: boolean;
     };
     [AppSettingsGroup.FunctionTrayIcon]: {
         /** 托盘点击后 */
@@ -446,16 +480,31 @@ export const defaultAppSettingsData: AppSettingsData = {
         fastSave: false,
         saveFileDirectory: '',
         saveFileFormat: ImageFormat.PNG,
+        saveImageMetadata: false,
+        imageMetadataComment: '',
+        pngOptimization: PngOptimization.None,
         ocrAfterAction: OcrDetectAfterAction.None,
         ocrCopyText: true,
     },
     [AppSettingsGroup.SystemScrollScreenshot]: {
         tryRollback: true,
+        detectStickyBands: false,
         imageFeatureThreshold: 24,
         minSide: 128,
         maxSide: 128,
         sampleRate: 1,
         imageFeatureDescriptionLength: 28,
+        memoryBudget: 1024,
+        seamMode: ScrollSeamMode.Overlay,
+        seamFeatherSize: 0,
+        featureBackend: ScrollFeatureBackend.FastPatch,
+        autoScrollStep: 3,
+        enablePageExport: false,
+        pageExportFormat: ScrollPageExportFormat.Pdf,
+        pageSize: 4096,
+        pageOverlap: 0,
+        saveMetadataFile: false,
+        embedMetadata: false,
     },
     [AppSettingsGroup.FunctionFixedContent]: {
         zoomWithMouse: true,
@@ -1227,6 +1276,18 @@ const ContextWrapCore: React.FC<{ children: React.ReactNode }> = ({ children })
                         typeof newSettings?.saveFileFormat === 'string'
                             ? newSettings.saveFileFormat
                             : (prevSettings?.saveFileFormat ?? ImageFormat.PNG),
+                    saveImageMetadata:
+                        typeof newSettings?.saveImageMetadata === 'boolean'
+                            ? newSettings.saveImageMetadata
+                            : (prevSettings?.saveImageMetadata ?? false),
+                    imageMetadataComment:
+                        typeof newSettings?.imageMetadataComment === 'string'
+                            ? newSettings.imageMetadataComment
+                            : (prevSettings?.imageMetadataComment ?? ''),
+                    pngOptimization:
+                        typeof newSettings?.pngOptimization === 'string'
+                            ? (newSettings.pngOptimization as PngOptimization)
+                            : (prevSettings?.pngOptimization ?? PngOptimization.None),
                     ocrAfterAction:
                         typeof newSettings?.ocrAfterAction === 'string'
                             ? (newSettings.ocrAfterAction as OcrDetectAfterAction)
@@ -1320,6 +1381,64 @@ const ContextWrapCore: React.FC<{ children: React.ReactNode }> = ({ children })
                             ? newSettings.tryRollback
                             : (prevSettings?.tryRollback ??
                               defaultAppSettingsData[group].tryRollback),
+                    detectStickyBands:
+                        typeof newSettings?.detectStickyBands === 'boolean'
+                            ? newSettings.detectStickyBands
+                            : (prevSettings?.detectStickyBands ??
+                              defaultAppSettingsData[group].detectStickyBands),
+                    memoryBudget:
+                        typeof newSettings?.memoryBudget === 'number'
+                            ? Math.min(Math.max(newSettings.memoryBudget, 128), 8192)
+                            : (prevSettings?.memoryBudget ??
+                              defaultAppSettingsData[group].memoryBudget),
+                    seamMode:
+                        typeof newSettings?.seamMode === 'string'
+                            ? (newSettings.seamMode as ScrollSeamMode)
+                            : (prevSettings?.seamMode ?? defaultAppSettingsData[group].seamMode),
+                    seamFeatherSize:
+                        typeof newSettings?.seamFeatherSize === 'number'
+                            ? Math.min(Math.max(newSettings.seamFeatherSize, 0), 32)
+                            : (prevSettings?.seamFeatherSize ??
+                              defaultAppSettingsData[group].seamFeatherSize),
+                    featureBackend:
+                        typeof newSettings?.featureBackend === 'string'
+                            ? (newSettings.featureBackend as ScrollFeatureBackend)
+                            : (prevSettings?.featureBackend ??
+                              defaultAppSettingsData[group].featureBackend),
+                    autoScrollStep:
+                        typeof newSettings?.autoScrollStep === 'number'
+                            ? Math.min(Math.max(newSettings.autoScrollStep, 1), 20)
+                            : (prevSettings?.autoScrollStep ??
+                              defaultAppSettingsData[group].autoScrollStep),
+                    enablePageExport:
+                        typeof newSettings?.enablePageExport === 'boolean'
+                            ? newSettings.enablePageExport
+                            : (prevSettings?.enablePageExport ??
+                              defaultAppSettingsData[group].enablePageExport),
+                    pageExportFormat:
+                        typeof newSettings?.pageExportFormat === 'string'
+                            ? (newSettings.pageExportFormat as ScrollPageExportFormat)
+                            : (prevSettings?.pageExportFormat ??
+                              defaultAppSettingsData[group].pageExportFormat),
+                    pageSize:
+                        typeof newSettings?.pageSize === 'number'
+                            ? Math.min(Math.max(newSettings.pageSize, 256), 65535)
+                            : (prevSettings?.pageSize ?? defaultAppSettingsData[group].pageSize),
+                    pageOverlap:
+                        typeof newSettings?.pageOverlap === 'number'
+                            ? Math.min(Math.max(newSettings.pageOverlap, 0), 1024)
+                            : (prevSettings?.pageOverlap ??
+                              defaultAppSettingsData[group].pageOverlap),
+                    saveMetadataFile:
+                        typeof newSettings?.saveMetadataFile === 'boolean'
+                            ? newSettings.saveMetadataFile
+                            : (prevSettings?.saveMetadataFile ??
+                              defaultAppSettingsData[group].saveMetadataFile),
+                    embedMetadata:
+                        typeof newSettings?.embedMetadata === 'boolean'
+                            ? newSettings.embedMetadata
+                            : (prevSettings?.embedMetadata ??
+                              defaultAppSettingsData[group].embedMetadata),
                 };
             } else if (group === AppSettingsGroup.FunctionTrayIcon) {
                 newSettings = newSettings as AppSettingsData[typeof group];
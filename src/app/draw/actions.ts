@@ -6,7 +6,14 @@ import { Window as AppWindow } from '@tauri-apps/api/window';
 import { CaptureStep } from './types';
 import { FixedContentActionType } from '../fixedContent/components/fixedContentCore';
 import { OcrBlocksActionType } from './components/ocrBlocks';
-import { showImageDialog, ImageFormat, ImagePath } from '@/utils/file';
+import {
+    showImageDialog,
+    ImageFormat,
+    ImagePath,
+    getImageEncoderFromSettings,
+    getImageMetadataFromSettings,
+    ImageCaptureInfo,
+} from '@/utils/file';
 import { AppSettingsData, AppSettingsGroup } from '../contextWrap';
 import { writeImageToClipboard } from '@/utils/clipboard';
 import { AppOcrResult } from '../fixedContent/components/ocrResult';
@@ -120,6 +127,8 @@ export const saveToFile = async (
     beforeSaveFile?: (filePath: string) => Promise<void>,
     prevImageFormat?: ImageFormat,
     fastSavePath?: ImagePath,
+    /** 截图时的信息，写入图片元数据 */
+    captureInfo?: ImageCaptureInfo,
 ) => {
     const imagePath = fastSavePath ?? (await showImageDialog(appSettings, prevImageFormat));
 
@@ -135,10 +144,12 @@ export const saveToFile = async (
                 return;
             }
 
+            // 浏览器只能编码 PNG、JPEG 和 WebP，其余格式由后端解码后重新编码
             let blobType: string = imagePath.imageFormat;
             if (
-                imagePath.imageFormat === ImageFormat.AVIF ||
-                imagePath.imageFormat === ImageFormat.JPEG_XL
+                imagePath.imageFormat !== ImageFormat.PNG &&
+                imagePath.imageFormat !== ImageFormat.JPEG &&
+                imagePath.imageFormat !== ImageFormat.WEBP
             ) {
                 blobType = 'image/webp';
             }
@@ -170,7 +181,13 @@ export const saveToFile = async (
         return;
     }
 
-    await saveFile(imagePath.filePath, imageData, imagePath.imageFormat);
+    await saveFile(
+        imagePath.filePath,
+        imageData,
+        imagePath.imageFormat,
+        getImageMetadataFromSettings(appSettings, captureInfo),
+        getImageEncoderFromSettings(appSettings, imagePath.imageFormat),
+    );
 };
 
 export const fixedToScreen = async (
@@ -184,6 +201,8 @@ export const fixedToScreen = async (
     setCaptureStep: (step: CaptureStep) => void,
     /** 已有的 OCR 结果 */
     ocrResult: AppOcrResult | undefined,
+    /** 截图时间，固定的图片保存时写入元数据 */
+    captureTime?: number,
 ) => {
     const selectRectParams = selectLayerAction.getSelectRectParams();
     if (!selectRectParams) {
@@ -228,6 +247,13 @@ export const fixedToScreen = async (
             captureBoundingBoxInfo,
             ocrResult,
             selectRectParams,
+            captureInfo:
+                captureTime === undefined
+                    ? undefined
+                    : captureBoundingBoxInfo.getImageCaptureInfo(
+                          selectRectParams.rect,
+                          captureTime,
+                      ),
         }),
     ]);
 
@@ -3,15 +3,26 @@ import { DrawContext } from '@/app/draw/types';
 import { ElementRect } from '@/commands';
 import { clickThrough, scrollThrough } from '@/commands/core';
 import {
+    parseScrollScreenshotAutoScrollFrame,
+    SCROLL_SCREENSHOT_AUTO_SCROLL_FRAME_EMIT_KEY,
     SCROLL_SCREENSHOT_CAPTURE_RESULT_EXTRA_DATA_SIZE,
+    ScrollAutoScrollStopReason,
+    ScrollFrameChange,
     ScrollImageList,
     ScrollDirection,
+    scrollScreenshotAutoScroll,
+    scrollScreenshotAutoScrollStop,
     scrollScreenshotCapture,
     ScrollScreenshotCaptureResult,
+    ScrollScreenshotCaptureStatus,
     scrollScreenshotClear,
+    scrollScreenshotGetFrameThumbnail,
     scrollScreenshotHandleImage,
+    scrollScreenshotHasAutosaveSession,
     scrollScreenshotInit,
+    scrollScreenshotRestoreAutosaveSession,
 } from '@/commands/scrollScreenshot';
+import { PauseOutlined, VerticalAlignBottomOutlined } from '@ant-design/icons';
 import { useStateRef } from '@/hooks/useStateRef';
 import { useStateSubscriber } from '@/hooks/useStateSubscriber';
 import { zIndexs } from '@/utils/zIndex';
@@ -39,6 +50,8 @@ import { getCurrentWindow } from '@tauri-apps/api/window';
 import { appError } from '@/utils/log';
 
 const THUMBNAIL_WIDTH = 128;
+/** 匹配置信度低于该值时提示用户 */
+const SCROLL_SCREENSHOT_LOW_CONFIDENCE = 0.3;
 
 export type ScrollScreenshotActionType = {
     getScrollScreenshotSubToolContainer: () => HTMLDivElement | null | undefined;
@@ -52,7 +65,7 @@ type ImageUrl = {
 export const ScrollScreenshot: React.FC<{
     actionRef: React.RefObject<ScrollScreenshotActionType | undefined>;
 }> = ({ actionRef }) => {
-    const { message } = useContext(AntdContext);
+    const { message, modal } = useContext(AntdContext);
     const intl = useIntl();
     const { token } = theme.useToken();
 
@@ -199,6 +212,25 @@ export const ScrollScreenshot: React.FC<{
         );
     }, [intl, message]);
 
+    const lastLowConfidenceHideRef = useRef<MessageType | undefined>(undefined);
+
+    const showLowConfidenceMessage = useMemo(() => {
+        return throttle(
+            () => {
+                if (lastLowConfidenceHideRef.current) {
+                    try {
+                        lastLowConfidenceHideRef.current();
+                    } catch {}
+                }
+                lastLowConfidenceHideRef.current = message.warning(
+                    intl.formatMessage({ id: 'draw.scrollScreenshot.lowConfidence' }),
+                );
+            },
+            3000,
+            { edges: ['leading'] },
+        );
+    }, [intl, message]);
+
     /**
      * @returns 是否需要继续处理
      */
@@ -234,10 +266,24 @@ export const ScrollScreenshot: React.FC<{
             return needContinue;
         }
 
+        if (
+            captureResult.match_confidence !== undefined &&
+            captureResult.match_confidence < SCROLL_SCREENSHOT_LOW_CONFIDENCE
+        ) {
+            showLowConfidenceMessage();
+        }
+
         updateImageUrlList(captureResult);
 
         return needContinue;
-    }, [setLoading, updateImageUrlList, message, intl, showCaptureMissMessage]);
+    }, [
+        setLoading,
+        updateImageUrlList,
+        message,
+        intl,
+        showCaptureMissMessage,
+        showLowConfidenceMessage,
+    ]);
 
     const pendingCaptureImageListRef = useRef<boolean>(false);
     const handleCaptureImageList = useCallback(async () => {
@@ -265,7 +311,9 @@ export const ScrollScreenshot: React.FC<{
 
     const pendingCaptureRef = useRef<boolean>(false);
     const captureImageCore = useCallback(
-        async (scrollImageList: ScrollImageList) => {
+        async (
+            scrollImageList: ScrollImageList,
+        ): Promise<ScrollScreenshotCaptureStatus | undefined> => {
             const rect = captureBoundingBoxInfoRef.current!.transformWindowRect(
                 selectLayerActionRef.current!.getSelectRect()!,
             );
@@ -285,17 +333,22 @@ export const ScrollScreenshot: React.FC<{
 
             pendingCaptureRef.current = true;
 
-            await scrollScreenshotCapture(
+            const captureStatus = await scrollScreenshotCapture(
                 scrollImageList,
                 rect.min_x,
                 rect.min_y,
                 rect.max_x,
                 rect.max_y,
-            );
+            ).finally(() => {
+                pendingCaptureRef.current = false;
+            });
 
-            pendingCaptureRef.current = false;
+            // 重复帧和局部动画不会加入队列，不需要处理
+            if (captureStatus.frame_change === ScrollFrameChange.Scroll) {
+                handleCaptureImageListDebounce();
+            }
 
-            handleCaptureImageListDebounce();
+            return captureStatus;
         },
         [
             captureBoundingBoxInfoRef,
@@ -305,21 +358,72 @@ export const ScrollScreenshot: React.FC<{
         ],
     );
 
-    const captureImageDebounce = useMemo(() => {
-        return debounce(captureImageCore, 128);
-    }, [captureImageCore]);
+    // 滚动停止后再截取一次，间隔根据画面的变化率调整
+    const trailingCaptureTimerRef = useRef<ReturnType<typeof setTimeout> | undefined>(undefined);
+    const captureIntervalRef = useRef(128);
+    useEffect(() => {
+        return () => {
+            clearTimeout(trailingCaptureTimerRef.current);
+        };
+    }, []);
 
     const captureImage = useCallback(
         async (scrollImageList: ScrollImageList) => {
-            await captureImageCore(scrollImageList);
-            captureImageDebounce(scrollImageList);
+            const captureStatus = await captureImageCore(scrollImageList);
+            if (captureStatus) {
+                captureIntervalRef.current = captureStatus.capture_interval;
+            }
+
+            clearTimeout(trailingCaptureTimerRef.current);
+            trailingCaptureTimerRef.current = setTimeout(() => {
+                captureImageCore(scrollImageList);
+            }, captureIntervalRef.current);
         },
-        [captureImageCore, captureImageDebounce],
+        [captureImageCore],
     );
 
+    const [autoScrolling, setAutoScrolling, autoScrollingRef] = useStateRef(false);
+
+    useEffect(() => {
+        const unlisten = getCurrentWindow().listen<number[]>(
+            SCROLL_SCREENSHOT_AUTO_SCROLL_FRAME_EMIT_KEY,
+            (event) => {
+                const captureResult = parseScrollScreenshotAutoScrollFrame(event.payload);
+                if (!captureResult) {
+                    return;
+                }
+
+                updateImageUrlList(captureResult);
+            },
+        );
+
+        return () => {
+            unlisten.then((unlistenFn) => unlistenFn());
+        };
+    }, [updateImageUrlList]);
+
+    // 恢复自动保存的会话，并按拼接顺序显示已拼接的帧
+    const restoreAutosaveSession = useCallback(async () => {
+        const sessionInfo = await scrollScreenshotRestoreAutosaveSession();
+        setScrollDirection(sessionInfo.direction);
+
+        const thumbnailSize = Math.round(THUMBNAIL_WIDTH * window.devicePixelRatio);
+        const frameCountList = [
+            [ScrollImageList.Top, sessionInfo.top_frame_count],
+            [ScrollImageList.Bottom, sessionInfo.bottom_frame_count],
+        ] as const;
+        for (const [imageList, frameCount] of frameCountList) {
+            for (let index = 0; index < frameCount; index++) {
+                updateImageUrlList(
+                    await scrollScreenshotGetFrameThumbnail(imageList, index, thumbnailSize),
+                );
+            }
+        }
+    }, [setScrollDirection, updateImageUrlList]);
+
     const [showTip, setShowTip] = useState(false);
     const init = useCallback(
-        async (rect: ElementRect, direction: ScrollDirection) => {
+        async (rect: ElementRect, direction: ScrollDirection, checkAutosaveSession: boolean) => {
             const scale = 1 / window.devicePixelRatio;
             setPositionRect({
                 min_x: rect.min_x * scale,
@@ -333,32 +437,63 @@ export const ScrollScreenshot: React.FC<{
             const maxSide = Math.max(scrollSettings.maxSide, scrollSettings.minSide);
 
             try {
-                await scrollScreenshotInit(
-                    direction,
-                    rect.max_x - rect.min_x,
-                    rect.max_y - rect.min_y,
-                    scrollSettings.sampleRate,
-                    scrollSettings.minSide,
-                    maxSide,
-                    scrollSettings.imageFeatureThreshold,
-                    scrollSettings.imageFeatureDescriptionLength,
-                    scrollDirectionRef.current === ScrollDirection.Horizontal
-                        ? Math.ceil((rect.max_x - rect.min_x) * 0.8)
-                        : Math.ceil((rect.max_y - rect.min_y) * 0.8),
-                    scrollSettings.tryRollback,
-                );
+                await scrollScreenshotInit(direction, {
+                    sample_rate: scrollSettings.sampleRate,
+                    min_sample_size: scrollSettings.minSide,
+                    max_sample_size: maxSide,
+                    corner_threshold: scrollSettings.imageFeatureThreshold,
+                    descriptor_patch_size: scrollSettings.imageFeatureDescriptionLength,
+                    min_size_delta:
+                        scrollDirectionRef.current === ScrollDirection.Horizontal
+                            ? Math.ceil((rect.max_x - rect.min_x) * 0.8)
+                            : Math.ceil((rect.max_y - rect.min_y) * 0.8),
+                    try_rollback: scrollSettings.tryRollback,
+                    detect_sticky_bands: scrollSettings.detectStickyBands,
+                    memory_budget: scrollSettings.memoryBudget * 1024 * 1024,
+                    seam_mode: scrollSettings.seamMode,
+                    seam_feather_size: scrollSettings.seamFeatherSize,
+                    feature_backend: scrollSettings.featureBackend,
+                });
             } catch (error) {
                 appError('[init] scrollScreenshotInit error', error);
                 message.error(intl.formatMessage({ id: 'draw.scrollScreenshot.initError' }));
                 return;
             }
 
+            // 上次截图没有正常结束时，询问是否恢复自动保存的会话
+            if (checkAutosaveSession && (await scrollScreenshotHasAutosaveSession())) {
+                const restore = await modal.confirm({
+                    content: intl.formatMessage({ id: 'draw.scrollScreenshot.restoreSession' }),
+                    centered: true,
+                });
+
+                if (restore) {
+                    try {
+                        await restoreAutosaveSession();
+                    } catch (error) {
+                        appError('[init] restoreAutosaveSession error', error);
+                        message.error(
+                            intl.formatMessage({ id: 'draw.scrollScreenshot.restoreSessionError' }),
+                        );
+                    }
+                }
+            }
+
             enableScrollThroughRef.current = true;
 
             // 初始化成功后，自动截取第一个片段
             captureImage(ScrollImageList.Bottom);
         },
-        [setPositionRect, getAppSettings, captureImage, scrollDirectionRef, message, intl],
+        [
+            setPositionRect,
+            getAppSettings,
+            captureImage,
+            scrollDirectionRef,
+            message,
+            modal,
+            intl,
+            restoreAutosaveSession,
+        ],
     );
 
     const pendingScrollThroughRef = useRef<boolean>(false);
@@ -375,7 +510,7 @@ export const ScrollScreenshot: React.FC<{
 
     const onWheel = useCallback<WheelEventHandler<HTMLDivElement>>(
         (event) => {
-            if (!enableScrollThroughRef.current) {
+            if (!enableScrollThroughRef.current || autoScrollingRef.current) {
                 return;
             }
 
@@ -402,7 +537,7 @@ export const ScrollScreenshot: React.FC<{
                     });
             }
         },
-        [captureImage, enableCursorEventsDebounce, message, scrollDirectionRef],
+        [captureImage, enableCursorEventsDebounce, message, scrollDirectionRef, autoScrollingRef],
     );
 
     const enableIgnoreCursorEventsRef = useRef(false);
@@ -416,19 +551,74 @@ export const ScrollScreenshot: React.FC<{
         enableIgnoreCursorEventsRef.current = false;
     }, []);
 
-    const startCapture = useCallback(() => {
-        setCaptuerEdgePosition(undefined);
-        enableScrollThroughRef.current = false;
-        releaseImageUrlList();
-        setPositionRect(undefined);
-
-        const selectRect = selectLayerActionRef.current?.getSelectRect();
-        if (!selectRect) {
+    const toggleAutoScroll = useCallback(async () => {
+        if (autoScrollingRef.current) {
+            scrollScreenshotAutoScrollStop().catch((error) => {
+                appError('[toggleAutoScroll] scrollScreenshotAutoScrollStop error', error);
+            });
             return;
         }
 
-        init(selectRect, scrollDirectionRef.current);
-    }, [releaseImageUrlList, selectLayerActionRef, init, scrollDirectionRef, setPositionRect]);
+        const rect = captureBoundingBoxInfoRef.current!.transformWindowRect(
+            selectLayerActionRef.current!.getSelectRect()!,
+        );
+        const scrollSettings = getAppSettings()[AppSettingsGroup.SystemScrollScreenshot];
+
+        setShowTip(false);
+        setAutoScrolling(true);
+        setDrawEvent({
+            event: DrawEvent.ScrollScreenshot,
+            params: undefined,
+        });
+        setDrawEvent(undefined);
+
+        let stopReason: ScrollAutoScrollStopReason | undefined;
+        try {
+            stopReason = await scrollScreenshotAutoScroll(
+                rect.min_x,
+                rect.min_y,
+                rect.max_x,
+                rect.max_y,
+                scrollSettings.autoScrollStep,
+                Math.round(THUMBNAIL_WIDTH * window.devicePixelRatio),
+            );
+        } catch (error) {
+            appError('[toggleAutoScroll] scrollScreenshotAutoScroll error', error);
+            message.error(intl.formatMessage({ id: 'draw.scrollScreenshot.autoScrollError' }));
+        }
+
+        setAutoScrolling(false);
+
+        if (stopReason === ScrollAutoScrollStopReason.EndOfPage) {
+            message.success(intl.formatMessage({ id: 'draw.scrollScreenshot.autoScrollFinished' }));
+        }
+    }, [
+        autoScrollingRef,
+        captureBoundingBoxInfoRef,
+        selectLayerActionRef,
+        getAppSettings,
+        setAutoScrolling,
+        setDrawEvent,
+        message,
+        intl,
+    ]);
+
+    const startCapture = useCallback(
+        (checkAutosaveSession: boolean = false) => {
+            setCaptuerEdgePosition(undefined);
+            enableScrollThroughRef.current = false;
+            releaseImageUrlList();
+            setPositionRect(undefined);
+
+            const selectRect = selectLayerActionRef.current?.getSelectRect();
+            if (!selectRect) {
+                return;
+            }
+
+            init(selectRect, scrollDirectionRef.current, checkAutosaveSession);
+        },
+        [releaseImageUrlList, selectLayerActionRef, init, scrollDirectionRef, setPositionRect],
+    );
     useStateSubscriber(
         DrawStatePublisher,
         useCallback(
@@ -438,7 +628,7 @@ export const ScrollScreenshot: React.FC<{
                     return;
                 }
 
-                startCapture();
+                startCapture(true);
             },
             [setPositionRect, startCapture],
         ),
@@ -446,6 +636,7 @@ export const ScrollScreenshot: React.FC<{
 
     useEffect(() => {
         return () => {
+            scrollScreenshotAutoScrollStop();
             scrollScreenshotClear();
         };
     }, []);
@@ -483,7 +674,19 @@ export const ScrollScreenshot: React.FC<{
                 actionRef={subToolsActionRef}
                 buttons={[
                     <Button
-                        disabled={loading}
+                        disabled={loading && !autoScrolling}
+                        onClick={toggleAutoScroll}
+                        icon={autoScrolling ? <PauseOutlined /> : <VerticalAlignBottomOutlined />}
+                        title={intl.formatMessage({
+                            id: autoScrolling
+                                ? 'draw.scrollScreenshot.autoScrollStop'
+                                : 'draw.scrollScreenshot.autoScroll',
+                        })}
+                        type={'text'}
+                        key="autoScroll"
+                    />,
+                    <Button
+                        disabled={loading || autoScrolling}
                         onClick={() => {
                             if (scrollDirectionRef.current === ScrollDirection.Horizontal) {
                                 setScrollDirection(ScrollDirection.Vertical);
@@ -740,6 +740,7 @@ const SelectLayerCore: React.FC<SelectLayerProps> = ({ actionRef }) => {
                 event: DrawEvent.ChangeMonitor,
                 params: {
                     rect: {
+                        name: activeMonitor.name,
                         rect: currentActiveMonitorRectRef.current,
                         scale_factor: activeMonitor.scale_factor,
                     },
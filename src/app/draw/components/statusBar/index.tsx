@@ -56,6 +56,7 @@ const KeyLabel: React.FC<{
 // 新实例化的组件可能收不到 DrawEvent.ChangeMonitor 消息
 // 用个全局变量存一下然后初始化
 let useMonitorRectData: MonitorRect = {
+    name: '',
     rect: {
         min_x: 0,
         min_y: 0,
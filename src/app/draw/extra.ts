@@ -7,6 +7,7 @@ import { ScreenshotType } from '@/functions/screenshot';
 import { OcrDetectResult } from '@/commands/ocr';
 import { MonitorInfo } from '@/commands/core';
 import { ElementRect, ImageBuffer } from '@/commands';
+import { ImageCaptureInfo } from '@/utils/file';
 import { MousePosition } from '@/utils/mousePosition';
 import Flatbush from 'flatbush';
 import { last } from 'es-toolkit';
@@ -135,6 +136,8 @@ export const DrawEventPublisher = createPublisher<DrawEventParams>(undefined, tr
  * 显示器范围
  */
 export type MonitorRect = {
+    /** 显示器名称 */
+    name: string;
     /** 显示器范围 */
     rect: ElementRect;
     /** 显示器缩放 */
@@ -297,4 +300,20 @@ export class CaptureBoundingBoxInfo {
 
         return this.monitorRectList[lastIndex];
     }
+
+    /**
+     * 获取选区的截图信息，保存图片时写入元数据
+     * @param selectedRect 相对于截图窗口的选区
+     * @param captureTime 截图时间
+     */
+    getImageCaptureInfo(selectedRect: ElementRect, captureTime: number): ImageCaptureInfo {
+        const monitor = this.getActiveMonitor(this.transformWindowRect(selectedRect), true);
+
+        return {
+            captureTime,
+            monitorName: monitor?.name || undefined,
+            // macOS 下显示器的缩放比例为 0，不写入
+            scaleFactor: monitor?.scale_factor || undefined,
+        };
+    }
 }
@@ -52,8 +52,17 @@ import {
     scrollScreenshotClear,
     scrollScreenshotSaveToClipboard,
 } from '@/commands/scrollScreenshot';
-import { getImageFormat, getImagePathFromSettings, showImageDialog } from '@/utils/file';
-import { scrollScreenshotSaveToFile } from '@/commands/scrollScreenshot';
+import {
+    getImageEncoderFromSettings,
+    getImageFormat,
+    getImagePathFromSettings,
+    showImageDialog,
+} from '@/utils/file';
+import {
+    scrollScreenshotExportPages,
+    scrollScreenshotSaveToFile,
+    ScrollPageExportFormat,
+} from '@/commands/scrollScreenshot';
 import { AppSettingsActionContext, AppSettingsGroup } from '../contextWrap';
 import { AppSettingsPublisher } from '../contextWrap';
 import {} from './components/drawToolbar/components/tools/extraTool';
@@ -122,6 +131,8 @@ const DrawPageCore: React.FC<{
     // 截图原始数据
     const imageBufferRef = useRef<ImageBuffer | undefined>(undefined);
     const captureBoundingBoxInfoRef = useRef<CaptureBoundingBoxInfo | undefined>(undefined);
+    // 截图时间，保存图片时写入元数据
+    const captureTimeRef = useRef<number | undefined>(undefined);
     const imageBlobUrlRef = useRef<string | undefined>(undefined);
     const { addListener, removeListener } = useContext(EventListenerContext);
 
@@ -440,6 +451,7 @@ const DrawPageCore: React.FC<{
                 });
             }
 
+            captureTimeRef.current = Date.now();
             const result = await captureAllMonitors(
                 getAppSettings()[AppSettingsGroup.SystemScreenshot].enableMultipleMonitor,
             ).catch((error) => {
@@ -527,6 +539,20 @@ const DrawPageCore: React.FC<{
         ],
     );
 
+    /** 当前选区的截图信息，保存图片时写入元数据 */
+    const getImageCaptureInfo = useCallback(() => {
+        const captureBoundingBoxInfo = captureBoundingBoxInfoRef.current;
+        const selectRectParams = selectLayerActionRef.current?.getSelectRectParams();
+        if (!captureBoundingBoxInfo || !selectRectParams || captureTimeRef.current === undefined) {
+            return undefined;
+        }
+
+        return captureBoundingBoxInfo.getImageCaptureInfo(
+            selectRectParams.rect,
+            captureTimeRef.current,
+        );
+    }, []);
+
     const saveCaptureHistory = useCallback(async () => {
         const imageBuffer = imageBufferRef.current;
 
@@ -574,7 +600,33 @@ const DrawPageCore: React.FC<{
                     );
                 }
 
-                scrollScreenshotSaveToFile(imagePath.filePath).then(() => {
+                const scrollSettings = getAppSettings()[AppSettingsGroup.SystemScrollScreenshot];
+                let savePromise: Promise<unknown>;
+                if (scrollSettings.enablePageExport) {
+                    // PDF 和 ZIP 使用所选路径的文件名，图片序列以所选路径为前缀
+                    let filePath = imagePath.filePath;
+                    if (scrollSettings.pageExportFormat === ScrollPageExportFormat.Pdf) {
+                        filePath = filePath.replace(/\.[^./\\]+$/, '') + '.pdf';
+                    } else if (scrollSettings.pageExportFormat === ScrollPageExportFormat.Zip) {
+                        filePath = filePath.replace(/\.[^./\\]+$/, '') + '.zip';
+                    }
+
+                    savePromise = scrollScreenshotExportPages(
+                        filePath,
+                        scrollSettings.pageExportFormat,
+                        scrollSettings.pageSize,
+                        Math.min(scrollSettings.pageOverlap, scrollSettings.pageSize - 1),
+                    );
+                } else {
+                    savePromise = scrollScreenshotSaveToFile(
+                        imagePath.filePath,
+                        getImageEncoderFromSettings(getAppSettings(), imagePath.imageFormat),
+                        scrollSettings.saveMetadataFile,
+                        scrollSettings.embedMetadata,
+                    );
+                }
+
+                savePromise.then(() => {
                     scrollScreenshotClear();
                 });
                 finishCapture();
@@ -617,9 +669,17 @@ const DrawPageCore: React.FC<{
                 },
                 getAppSettings()[AppSettingsGroup.Cache].prevImageFormat,
                 fastSave ? await getImagePathFromSettings(getAppSettings(), 'fast') : undefined,
+                getImageCaptureInfo(),
             );
         },
-        [finishCapture, getAppSettings, getDrawState, saveCaptureHistory, updateAppSettings],
+        [
+            finishCapture,
+            getAppSettings,
+            getDrawState,
+            getImageCaptureInfo,
+            saveCaptureHistory,
+            updateAppSettings,
+        ],
     );
 
     const onFixed = useCallback(async () => {
@@ -662,6 +722,7 @@ const DrawPageCore: React.FC<{
             isOcrTool(getDrawState())
                 ? ocrBlocksActionRef.current?.getOcrResultAction()?.getOcrResult()
                 : undefined,
+            captureTimeRef.current,
         );
 
         switchLayer(undefined, drawLayerActionRef.current, selectLayerActionRef.current);
@@ -706,11 +767,17 @@ const DrawPageCore: React.FC<{
         const enableAutoSave = getAppSettings()[AppSettingsGroup.FunctionScreenshot].autoSaveOnCopy;
 
         if (getDrawState() === DrawState.ScrollScreenshot) {
-            const filePath = (await getImagePathFromSettings(getAppSettings(), 'auto'))?.filePath;
+            const imagePath = await getImagePathFromSettings(getAppSettings(), 'auto');
+            const scrollSettings = getAppSettings()[AppSettingsGroup.SystemScrollScreenshot];
             Promise.all([
                 scrollScreenshotSaveToClipboard(),
-                enableAutoSave && filePath
-                    ? scrollScreenshotSaveToFile(filePath)
+                enableAutoSave && imagePath
+                    ? scrollScreenshotSaveToFile(
+                          imagePath.filePath,
+                          getImageEncoderFromSettings(getAppSettings(), imagePath.imageFormat),
+                          scrollSettings.saveMetadataFile,
+                          scrollSettings.embedMetadata,
+                      )
                     : Promise.resolve(),
             ]).then(() => {
                 scrollScreenshotClear();
@@ -750,6 +817,8 @@ const DrawPageCore: React.FC<{
             layerContainerRef.current!.style.opacity = '0';
 
             const selectRectParams = selectLayerActionRef.current.getSelectRectParams();
+            // 结束截图后选区会重置，提前获取截图信息
+            const captureInfo = getImageCaptureInfo();
             const imageCanvas = await getCanvas(
                 selectRectParams,
                 drawLayerActionRef.current,
@@ -792,10 +861,11 @@ const DrawPageCore: React.FC<{
                     undefined,
                     undefined,
                     await getImagePathFromSettings(getAppSettings(), 'auto'),
+                    captureInfo,
                 );
             }
         }
-    }, [finishCapture, getAppSettings, getDrawState, saveCaptureHistory]);
+    }, [finishCapture, getAppSettings, getDrawState, getImageCaptureInfo, saveCaptureHistory]);
 
     const releaseExecuteScreenshotTimerRef = useRef<
         | {
@@ -17,7 +17,12 @@ import {
 } from 'react';
 import { FormattedMessage, useIntl } from 'react-intl';
 import * as dialog from '@tauri-apps/plugin-dialog';
-import { generateImageFileName, ImageFormat } from '@/utils/file';
+import {
+    generateImageFileName,
+    getImageMetadataFromSettings,
+    ImageCaptureInfo,
+    ImageFormat,
+} from '@/utils/file';
 import { closeWindowComplete } from '@/utils/window';
 import { useCallbackRender } from '@/hooks/useCallbackRender';
 import { zIndexs } from '@/utils/zIndex';
@@ -71,6 +76,8 @@ export type FixedContentInitDrawParams = {
     ocrResult: AppOcrResult | undefined;
     /** 选择区域参数 */
     selectRectParams: SelectRectParams;
+    /** 截图时的信息，保存时写入图片元数据 */
+    captureInfo?: ImageCaptureInfo;
 };
 
 export type FixedContentInitHtmlParams = {
@@ -184,6 +191,8 @@ export const FixedContentCore: React.FC<{
         ignoreTextScaleFactor: false,
     });
     const blobRef = useRef<Blob | undefined>(undefined);
+    // 固定截图时的信息，其它内容没有截图信息
+    const captureInfoRef = useRef<ImageCaptureInfo | undefined>(undefined);
     const [canvasImageUrl, setCanvasImageUrl] = useState<string | undefined>(undefined);
     const [imageUrl, setImageUrl] = useState<string | undefined>(undefined);
     const imageBlobRef = useRef<Blob | undefined>(undefined);
@@ -507,6 +516,7 @@ export const FixedContentCore: React.FC<{
             setFixedContentType(FixedContentType.DrawCanvas);
 
             const { canvas, captureBoundingBoxInfo, selectRectParams } = params;
+            captureInfoRef.current = params.captureInfo;
 
             if (selectRectParams.shadowWidth > 0) {
                 setShowBorder(false);
@@ -888,7 +898,12 @@ export const FixedContentCore: React.FC<{
             return;
         }
 
-        await saveFile(filePath, await canvasBlob.arrayBuffer(), ImageFormat.PNG);
+        await saveFile(
+            filePath,
+            await canvasBlob.arrayBuffer(),
+            ImageFormat.PNG,
+            getImageMetadataFromSettings(getAppSettings(), captureInfoRef.current),
+        );
     }, [getAppSettings, renderToBlob]);
 
     const switchSelectTextCore = useCallback(async () => {
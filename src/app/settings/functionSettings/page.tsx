@@ -46,6 +46,7 @@ import {
     getImageSaveDirectory,
     getVideoRecordSaveDirectory,
     ImageFormat,
+    PngOptimization,
 } from '@/utils/file';
 import { FOCUS_WINDOW_APP_NAME_ENV_VARIABLE, TranslationApiType } from './extra';
 import { TestChat } from './components/testChat';
@@ -545,11 +546,90 @@ export default function SystemSettings() {
                                             label: 'JPEG XL(*.jxl)',
                                             value: ImageFormat.JPEG_XL,
                                         },
+                                        {
+                                            label: 'QOI(*.qoi)',
+                                            value: ImageFormat.QOI,
+                                        },
+                                        {
+                                            label: 'TIFF(*.tiff)',
+                                            value: ImageFormat.TIFF,
+                                        },
+                                        {
+                                            label: 'BMP(*.bmp)',
+                                            value: ImageFormat.BMP,
+                                        },
                                     ]}
                                 />
                             </ProForm.Item>
                         </Col>
                     </Row>
+
+                    <Row gutter={token.marginLG}>
+                        <Col span={12}>
+                            <ProFormSwitch
+                                name="saveImageMetadata"
+                                layout="horizontal"
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.functionSettings.screenshotSettings.autoSaveFileMode.saveImageMetadata" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.functionSettings.screenshotSettings.autoSaveFileMode.saveImageMetadata.tip" />
+                                        }
+                                    />
+                                }
+                            />
+                        </Col>
+
+                        <Col span={12}>
+                            <ProFormText
+                                name="imageMetadataComment"
+                                label={
+                                    <FormattedMessage id="settings.functionSettings.screenshotSettings.autoSaveFileMode.imageMetadataComment" />
+                                }
+                            />
+                        </Col>
+                    </Row>
+
+                    <Row gutter={token.marginLG}>
+                        <Col span={12}>
+                            <ProFormSelect
+                                name="pngOptimization"
+                                layout="horizontal"
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.functionSettings.screenshotSettings.autoSaveFileMode.pngOptimization" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.functionSettings.screenshotSettings.autoSaveFileMode.pngOptimization.tip" />
+                                        }
+                                    />
+                                }
+                                options={[
+                                    {
+                                        label: (
+                                            <FormattedMessage id="settings.functionSettings.screenshotSettings.autoSaveFileMode.pngOptimization.none" />
+                                        ),
+                                        value: PngOptimization.None,
+                                    },
+                                    {
+                                        label: (
+                                            <FormattedMessage id="settings.functionSettings.screenshotSettings.autoSaveFileMode.pngOptimization.lossless" />
+                                        ),
+                                        value: PngOptimization.Lossless,
+                                    },
+                                    {
+                                        label: (
+                                            <FormattedMessage id="settings.functionSettings.screenshotSettings.autoSaveFileMode.pngOptimization.lossy" />
+                                        ),
+                                        value: PngOptimization.Lossy,
+                                    },
+                                ]}
+                            />
+                        </Col>
+                    </Row>
                 </ProForm>
             </Spin>
 
@@ -41,6 +41,11 @@ import { createLocalConfigDir, getAppConfigBaseDir } from '@/commands/file';
 import { appError } from '@/utils/log';
 import * as dialog from '@tauri-apps/plugin-dialog';
 import { restartWithAdmin } from '@/commands/core';
+import {
+    ScrollFeatureBackend,
+    ScrollPageExportFormat,
+    ScrollSeamMode,
+} from '@/commands/scrollScreenshot';
 
 export default function SystemSettings() {
     const intl = useIntl();
@@ -555,6 +560,21 @@ export default function SystemSettings() {
                                 name="tryRollback"
                             />
                         </Col>
+                        <Col span={12}>
+                            <ProFormSwitch
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.detectStickyBands" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.detectStickyBands.tip" />
+                                        }
+                                    />
+                                }
+                                name="detectStickyBands"
+                            />
+                        </Col>
                     </Row>
 
                     <Row gutter={token.marginLG}>
@@ -673,6 +693,271 @@ export default function SystemSettings() {
                                 layout="vertical"
                             />
                         </Col>
+                        <Col span={12}>
+                            <ProFormSlider
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.memoryBudget" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.memoryBudget.tip" />
+                                        }
+                                    />
+                                }
+                                name="memoryBudget"
+                                min={128}
+                                max={8192}
+                                step={128}
+                                marks={{
+                                    128: '128MB',
+                                    8192: '8GB',
+                                }}
+                                layout="vertical"
+                            />
+                        </Col>
+                    </Row>
+
+                    <Row gutter={token.marginLG}>
+                        <Col span={12}>
+                            <ProFormSelect
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.seamMode" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.seamMode.tip" />
+                                        }
+                                    />
+                                }
+                                name="seamMode"
+                                options={[
+                                    {
+                                        label: (
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.seamMode.overlay" />
+                                        ),
+                                        value: ScrollSeamMode.Overlay,
+                                    },
+                                    {
+                                        label: (
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.seamMode.minDifference" />
+                                        ),
+                                        value: ScrollSeamMode.MinDifference,
+                                    },
+                                ]}
+                            />
+                        </Col>
+                        <Col span={12}>
+                            <ProFormSlider
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.seamFeatherSize" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.seamFeatherSize.tip" />
+                                        }
+                                    />
+                                }
+                                name="seamFeatherSize"
+                                min={0}
+                                max={32}
+                                step={1}
+                                marks={{
+                                    0: '0',
+                                    32: '32',
+                                }}
+                                layout="vertical"
+                            />
+                        </Col>
+                        <Col span={12}>
+                            <ProFormSelect
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.featureBackend" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.featureBackend.tip" />
+                                        }
+                                    />
+                                }
+                                name="featureBackend"
+                                options={[
+                                    {
+                                        label: (
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.featureBackend.fastPatch" />
+                                        ),
+                                        value: ScrollFeatureBackend.FastPatch,
+                                    },
+                                    {
+                                        label: (
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.featureBackend.fastBrief" />
+                                        ),
+                                        value: ScrollFeatureBackend.FastBrief,
+                                    },
+                                    {
+                                        label: (
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.featureBackend.lineHash" />
+                                        ),
+                                        value: ScrollFeatureBackend.LineHash,
+                                    },
+                                ]}
+                            />
+                        </Col>
+                        <Col span={12}>
+                            <ProFormSlider
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.autoScrollStep" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.autoScrollStep.tip" />
+                                        }
+                                    />
+                                }
+                                name="autoScrollStep"
+                                min={1}
+                                max={20}
+                                step={1}
+                                marks={{
+                                    1: '1',
+                                    20: '20',
+                                }}
+                                layout="vertical"
+                            />
+                        </Col>
+                    </Row>
+
+                    <Row gutter={token.marginLG}>
+                        <Col span={12}>
+                            <ProFormSwitch
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.enablePageExport" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.enablePageExport.tip" />
+                                        }
+                                    />
+                                }
+                                name="enablePageExport"
+                            />
+                        </Col>
+                        <Col span={12}>
+                            <ProFormSelect
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.pageExportFormat" />
+                                        }
+                                    />
+                                }
+                                name="pageExportFormat"
+                                options={[
+                                    {
+                                        label: (
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.pageExportFormat.pdf" />
+                                        ),
+                                        value: ScrollPageExportFormat.Pdf,
+                                    },
+                                    {
+                                        label: (
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.pageExportFormat.imageSequence" />
+                                        ),
+                                        value: ScrollPageExportFormat.ImageSequence,
+                                    },
+                                    {
+                                        label: (
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.pageExportFormat.zip" />
+                                        ),
+                                        value: ScrollPageExportFormat.Zip,
+                                    },
+                                ]}
+                            />
+                        </Col>
+                        <Col span={12}>
+                            <ProFormSlider
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.pageSize" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.pageSize.tip" />
+                                        }
+                                    />
+                                }
+                                name="pageSize"
+                                min={256}
+                                max={65535}
+                                step={256}
+                                marks={{
+                                    256: '256',
+                                    65535: '65535',
+                                }}
+                                layout="vertical"
+                            />
+                        </Col>
+                        <Col span={12}>
+                            <ProFormSlider
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.pageOverlap" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.pageOverlap.tip" />
+                                        }
+                                    />
+                                }
+                                name="pageOverlap"
+                                min={0}
+                                max={1024}
+                                step={1}
+                                marks={{
+                                    0: '0',
+                                    1024: '1024',
+                                }}
+                                layout="vertical"
+                            />
+                        </Col>
+                    </Row>
+
+                    <Row gutter={token.marginLG}>
+                        <Col span={12}>
+                            <ProFormSwitch
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.saveMetadataFile" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.saveMetadataFile.tip" />
+                                        }
+                                    />
+                                }
+                                name="saveMetadataFile"
+                            />
+                        </Col>
+                        <Col span={12}>
+                            <ProFormSwitch
+                                label={
+                                    <IconLabel
+                                        label={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.embedMetadata" />
+                                        }
+                                        tooltipTitle={
+                                            <FormattedMessage id="settings.systemSettings.scrollScreenshotSettings.embedMetadata.tip" />
+                                        }
+                                    />
+                                }
+                                name="embedMetadata"
+                            />
+                        </Col>
                     </Row>
                 </ProForm>
             </Spin>
@@ -1,5 +1,6 @@
 import { invoke } from '@tauri-apps/api/core';
 import { Base64 } from 'js-base64';
+import { ImageEncoderOptions } from '.';
 
 export const writeFile = async (filePath: string, data: ArrayBuffer | Uint8Array) => {
     const result = await invoke<void>('write_file', data, {
@@ -61,3 +62,62 @@ export const getAppConfigBaseDir = async () => {
     const result = await invoke<string>('get_app_config_base_dir');
     return result;
 };
+
+export type BatchConvertOptions = {
+    /** 输入的目录或通配符路径，通配符支持 `*`、`?` 和匹配任意层目录的 `**` */
+    input: string;
+    /** 输出目录，未指定时输出到源文件所在目录 */
+    output_dir?: string;
+    encoder: ImageEncoderOptions;
+    /** 图片的最大边长，超过时按比例缩小 */
+    max_dimension?: number;
+    /** 输入为目录时是否包含子目录 */
+    recursive?: boolean;
+    /** 是否覆盖已经存在的文件 */
+    overwrite?: boolean;
+    /** 只返回转换计划，不写入文件 */
+    dry_run?: boolean;
+    /** 同时转换的图片数量 */
+    parallelism?: number;
+};
+
+export enum BatchConvertStatus {
+    Converted = 'converted',
+    Skipped = 'skipped',
+    Failed = 'failed',
+    Planned = 'planned',
+}
+
+export type BatchConvertItem = {
+    input_path: string;
+    output_path: string;
+    status: BatchConvertStatus;
+    source_width: number;
+    source_height: number;
+    target_width: number;
+    target_height: number;
+    input_file_size: number;
+    output_file_size: number;
+    error: string | null;
+};
+
+export type BatchConvertProgress = {
+    completed: number;
+    total: number;
+    item: BatchConvertItem;
+};
+
+export type BatchConvertResult = {
+    converted: number;
+    skipped: number;
+    failed: number;
+    items: BatchConvertItem[];
+};
+
+/** 批量转换每处理完一个文件发送的事件，内容为 BatchConvertProgress */
+export const BATCH_CONVERT_IMAGES_PROGRESS_EMIT_KEY = 'batch-convert-images:progress';
+
+export const batchConvertImages = async (options: BatchConvertOptions) => {
+    const result = await invoke<BatchConvertResult>('batch_convert_images', { options });
+    return result;
+};
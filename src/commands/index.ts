@@ -5,22 +5,112 @@ import { ImageFormat } from '@/utils/file';
 export enum ImageEncoder {
     Png = 'png',
     WebP = 'webp',
+    Avif = 'avif',
+    Jpeg = 'jpeg',
+    JpegXl = 'jpeg_xl',
+    Qoi = 'qoi',
+    Tiff = 'tiff',
+    Bmp = 'bmp',
 }
 
+export type PngEncoderOptions = {
+    compression_level?: 'fast' | 'default' | 'best';
+    filter?: 'no_filter' | 'sub' | 'up' | 'avg' | 'paeth' | 'adaptive';
+    /** 尝试调色板和所有过滤方式，以最高压缩等级编码后选择最小的结果 */
+    optimize?: boolean;
+    /** 优化时颜色超过 256 种的图片也量化为调色板，有损 */
+    quantize?: boolean;
+};
+
+export type AvifEncoderOptions = {
+    /** 编码速度，1-10，越快压缩率越低 */
+    speed?: number;
+    /** 质量，1-100 */
+    quality?: number;
+};
+
+export type JpegEncoderOptions = {
+    /** 质量，1-100 */
+    quality?: number;
+    /** 色度抽样方式，默认不抽样 */
+    chroma_subsampling?: 'yuv444' | 'yuv422' | 'yuv420';
+};
+
+export type WebpEncoderOptions = {
+    /** 默认无损编码 */
+    lossless?: boolean;
+    /** 有损编码的质量，1-100，无损编码时忽略 */
+    quality?: number;
+};
+
+/**
+ * 图片编码格式及编码参数，未传入的参数使用默认值
+ */
+export type ImageEncoderOptions =
+    | ({ format: ImageEncoder.Png } & PngEncoderOptions)
+    | ({ format: ImageEncoder.WebP } & WebpEncoderOptions)
+    | ({ format: ImageEncoder.Avif } & AvifEncoderOptions)
+    | ({ format: ImageEncoder.Jpeg } & JpegEncoderOptions)
+    | { format: ImageEncoder.JpegXl }
+    | { format: ImageEncoder.Qoi }
+    | { format: ImageEncoder.Tiff }
+    | { format: ImageEncoder.Bmp };
+
+/**
+ * 保存图片时写入的元数据，未设置的字段不写入
+ */
+export type ImageMetadata = {
+    /** 截图时间，Unix 时间戳（毫秒） */
+    capture_time?: number;
+    /** 本地时间相对 UTC 的偏移（分钟） */
+    utc_offset?: number;
+    monitor_name?: string;
+    scale_factor?: number;
+    app_name?: string;
+    window_title?: string;
+    comment?: string;
+    /** 隐私模式，不写入元数据，并移除图片中已有的元数据 */
+    strip?: boolean;
+};
+
 export type ImageBuffer = {
     encoder: ImageEncoder;
     data: Blob;
     buffer: ArrayBuffer;
 };
 
+export enum CursorStyle {
+    /** 只绘制指针 */
+    Plain = 'plain',
+    /** 在指针下方绘制半透明的圆形高亮 */
+    Highlight = 'highlight',
+    /** 在指针周围绘制点击圈 */
+    ClickRing = 'click_ring',
+}
+
+/**
+ * 在截图中绘制鼠标指针的参数
+ */
+export type CursorOverlayOptions = {
+    style?: CursorStyle;
+    /** 高亮或点击圈的颜色，RGBA，未指定时使用样式的默认颜色 */
+    color?: [number, number, number, number];
+    /** 高亮或点击圈的半径，物理像素 */
+    radius?: number;
+};
+
 /**
  * 捕获鼠标所在位置的屏幕图像
+ * @param encoder 编码格式及编码参数
+ * @param cursor 绘制鼠标指针的参数，未指定时不绘制
  */
 export const captureCurrentMonitor = async (
-    encoder: ImageEncoder,
+    encoder: ImageEncoderOptions,
+    cursor?: CursorOverlayOptions,
 ): Promise<ImageBuffer | undefined> => {
     const result = await invoke<ArrayBuffer>('capture_current_monitor', {
         encoder,
+        cursor,
     });
 
     if (result.byteLength === 0) {
@@ -28,7 +118,7 @@ export const captureCurrentMonitor = async (
     }
 
     return {
-        encoder,
+        encoder: encoder.format,
         data: new Blob([result]),
         buffer: result,
     };
@@ -79,16 +169,31 @@ export const getMousePosition = async () => {
     return result;
 };
 
+/**
+ * 保存文件
+ * @param metadata 图片元数据，未传入时保持图片原有的元数据
+ * @param encoder 图片编码器，传入时由后端重新编码
+ */
 export const saveFile = async (
     filePath: string,
     data: ArrayBuffer | Uint8Array,
     fileType: ImageFormat,
+    metadata?: ImageMetadata,
+    encoder?: ImageEncoderOptions,
 ) => {
+    const headers: Record<string, string> = {
+        'x-file-path': Base64.encode(filePath),
+        'x-file-type': Base64.encode(fileType),
+    };
+    if (metadata) {
+        headers['x-image-metadata'] = Base64.encode(JSON.stringify(metadata));
+    }
+    if (encoder) {
+        headers['x-image-encoder'] = Base64.encode(JSON.stringify(encoder));
+    }
+
     const result = await invoke<void>('save_file', data, {
-        headers: {
-            'x-file-path': Base64.encode(filePath),
-            'x-file-type': Base64.encode(fileType),
-        },
+        headers,
     });
     return result;
 };
@@ -1,5 +1,12 @@
 import { invoke } from '@tauri-apps/api/core';
-import { ImageBuffer, ImageEncoder } from '.';
+import {
+    CursorOverlayOptions,
+    ElementRect,
+    ImageBuffer,
+    ImageEncoder,
+    ImageEncoderOptions,
+    ImageMetadata,
+} from '.';
 
 export const switchAlwaysOnTop = async (windowId: number) => {
     const result = await invoke<string>('switch_always_on_top', {
@@ -17,25 +24,67 @@ export const setDrawWindowStyle = async () => {
  * 捕获焦点窗口
  * @param filePath 文件路径
  * @param copyToClipboard 是否复制到剪贴板
+ * @param metadata 图片元数据，截图时间、应用名称、窗口标题和显示器由后端填写
  */
 export const captureFocusedWindow = async (
     filePath: string,
     copyToClipboard: boolean,
     focusWindowAppNameVariableName: string,
+    metadata: ImageMetadata,
 ) => {
     const result = await invoke('capture_focused_window', {
         filePath,
         copyToClipboard,
         focusWindowAppNameVariableName,
+        metadata,
     });
     return result;
 };
 
+/**
+ * 多个显示器拼接时的缩放方式
+ * native 保留每个显示器的物理像素
+ * resample 将每个显示器缩放到相同的缩放比例，未指定时使用显示器中最大的缩放比例
+ */
+export type CaptureScaleMode =
+    | { mode: 'native' }
+    | { mode: 'resample'; target_scale_factor?: number };
+
+export type MonitorScaleMapping = {
+    monitor_index: number;
+    /** 被截取的区域，桌面的物理像素坐标 */
+    source_rect: ElementRect;
+    /** 在输出图像中的区域 */
+    target_rect: ElementRect;
+    scale: number;
+};
+
+export type CaptureScaleMapping = {
+    /** 输出图像的缩放比例，保留物理像素时为 null */
+    target_scale_factor: number | null;
+    width: number;
+    height: number;
+    monitors: MonitorScaleMapping[];
+};
+
+/**
+ * 捕获所有显示器
+ * @param enableMultipleMonitor 是否捕获多个显示器
+ * @param encoder 编码格式及编码参数
+ * @param scaleMode 多个显示器拼接时的缩放方式
+ * @param cursor 绘制鼠标指针的参数，未指定时不绘制
+ */
 export const captureAllMonitors = async (
     enableMultipleMonitor: boolean,
+    encoder: ImageEncoderOptions = { format: ImageEncoder.Png },
+    scaleMode: CaptureScaleMode = { mode: 'native' },
+    cursor?: CursorOverlayOptions,
 ): Promise<ImageBuffer | undefined> => {
     const result = await invoke<ArrayBuffer>('capture_all_monitors', {
         enableMultipleMonitor,
+        encoder,
+        scaleMode,
+        cursor,
     });
 
     if (result.byteLength === 0) {
@@ -43,8 +92,105 @@ export const captureAllMonitors = async (
     }
 
     return {
-        encoder: ImageEncoder.Png,
+        encoder: encoder.format,
         data: new Blob([result]),
         buffer: result,
     };
 };
+
+/**
+ * 获取捕获所有显示器时桌面物理像素坐标和截图坐标的映射
+ * @param enableMultipleMonitor 是否捕获多个显示器
+ * @param scaleMode 多个显示器拼接时的缩放方式
+ */
+export const getCaptureScaleMapping = async (
+    enableMultipleMonitor: boolean,
+    scaleMode: CaptureScaleMode = { mode: 'native' },
+): Promise<CaptureScaleMapping> => {
+    const result = await invoke<CaptureScaleMapping>('get_capture_scale_mapping', {
+        enableMultipleMonitor,
+        scaleMode,
+    });
+    return result;
+};
+
+export const CAPTURE_SCHEDULER_COUNTDOWN_EMIT_KEY = 'capture-scheduler:countdown';
+export const CAPTURE_SCHEDULER_PROGRESS_EMIT_KEY = 'capture-scheduler:progress';
+
+/**
+ * 定时截图的对象
+ * region 截取桌面的区域，物理像素坐标
+ * focused_window 截取当前的焦点窗口
+ */
+export type CaptureScheduleTarget =
+    | { type: 'region'; region: ElementRect }
+    | { type: 'focused_window' };
+
+export type CaptureScheduleOptions = {
+    target: CaptureScheduleTarget;
+    /** 第一次截图前的倒计时，秒 */
+    delay_seconds?: number;
+    /** 重复截图的间隔，秒，为 0 时只截图一次 */
+    interval_seconds?: number;
+    /** 重复截图的总时长，秒，和 max_count 都未指定时一直截图直到取消 */
+    duration_seconds?: number;
+    /** 最多截图的次数 */
+    max_count?: number;
+    /** 保存的文件路径，和截图文件名一样使用 dayjs 格式的时间变量，如 {{YYYY-MM-DD_HH-mm-ss}} */
+    file_path_template: string;
+    /** 未指定时根据文件扩展名选择格式 */
+    encoder?: ImageEncoderOptions;
+    /** 和上一次保存的截图相同时跳过 */
+    skip_unchanged?: boolean;
+    /** 每个颜色通道的平均差异不超过该值时认为相同，0 表示完全相同 */
+    unchanged_threshold?: number;
+};
+
+export type CaptureScheduleCountdown = {
+    remaining_seconds: number;
+};
+
+export type CaptureScheduleProgress = {
+    /** 第几次截图，从 1 开始 */
+    index: number;
+    total: number | null;
+    status: 'saved' | 'skipped' | 'failed';
+    file_path: string | null;
+    error: string | null;
+};
+
+export type CaptureScheduleResult = {
+    stop_reason: 'completed' | 'cancelled';
+    saved_count: number;
+    skipped_count: number;
+    failed_count: number;
+    file_paths: string[];
+};
+
+/**
+ * 定时截图，倒计时和每次截图的进度通过事件发送，任务结束或取消后返回结果
+ * @param options 定时截图的参数
+ */
+export const captureScheduleStart = async (
+    options: CaptureScheduleOptions,
+): Promise<CaptureScheduleResult> => {
+    const result = await invoke<CaptureScheduleResult>('capture_schedule_start', {
+        options,
+    });
+    return result;
+};
+
+export const captureSchedulePause = async () => {
+    const result = await invoke('capture_schedule_pause');
+    return result;
+};
+
+export const captureScheduleResume = async () => {
+    const result = await invoke('capture_schedule_resume');
+    return result;
+};
+
+export const captureScheduleCancel = async () => {
+    const result = await invoke('capture_schedule_cancel');
+    return result;
+};
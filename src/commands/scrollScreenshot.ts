@@ -1,11 +1,14 @@
 import { appError } from '@/utils/log';
 import { invoke } from '@tauri-apps/api/core';
+import { ImageEncoderOptions } from '.';
 
 export enum ScrollDirection {
     /// 垂直滚动
     Vertical = 'Vertical',
     /// 水平滚动
     Horizontal = 'Horizontal',
+    /// 自由方向
+    Free = 'Free',
 }
 
 export enum ScrollImageList {
@@ -15,29 +18,56 @@ export enum ScrollImageList {
     Bottom = 'Bottom',
 }
 
+export enum ScrollSeamMode {
+    /// 后绘制的帧直接覆盖重叠区域
+    Overlay = 'Overlay',
+    /// 在重叠区域中选择差异最小的位置作为接缝
+    MinDifference = 'MinDifference',
+}
+
+export enum ScrollFeatureBackend {
+    /// FAST 角点、块均值描述符、HNSW 近邻搜索
+    FastPatch = 'FastPatch',
+    /// FAST 角点、BRIEF 二进制描述符、汉明距离匹配
+    FastBrief = 'FastBrief',
+    /// 每一行（列）作为一个特征按哈希匹配，适合文字较多的页面
+    LineHash = 'LineHash',
+}
+
+export type ScrollScreenshotParams = {
+    /// 采样率
+    sample_rate: number;
+    /// 最小采样尺寸
+    min_sample_size: number;
+    /// 最大采样尺寸
+    max_sample_size: number;
+    /// 特征点阈值
+    corner_threshold: number;
+    /// 描述符块大小
+    descriptor_patch_size: number;
+    /// 最小变化量（高于该值才会建立索引）
+    min_size_delta: number;
+    /// 是否尝试回滚
+    try_rollback: boolean;
+    /// 是否检测固定的标题栏和底栏
+    detect_sticky_bands: boolean;
+    /// 帧占用的内存上限（字节），超出后较早的帧会写入磁盘
+    memory_budget: number;
+    /// 重叠区域的接缝处理方式
+    seam_mode: ScrollSeamMode;
+    /// 接缝两侧混合的尺寸，为 0 时不混合
+    seam_feather_size: number;
+    /// 特征提取和匹配方式
+    feature_backend: ScrollFeatureBackend;
+};
+
 export const scrollScreenshotInit = async (
     direction: ScrollDirection,
-    imageWidth: number,
-    imageHeight: number,
-    sampleRate: number,
-    minSampleSize: number,
-    maxSampleSize: number,
-    cornerThreshold: number,
-    descriptorPatchSize: number,
-    minSizeDelta: number,
-    tryRollback: boolean,
+    params: ScrollScreenshotParams,
 ) => {
     const result = await invoke('scroll_screenshot_init', {
         direction,
-        imageWidth,
-        imageHeight,
-        sampleRate,
-        minSampleSize,
-        maxSampleSize,
-        cornerThreshold,
-        descriptorPatchSize,
-        minSizeDelta,
-        tryRollback,
+        params,
     });
     return result;
 };
@@ -50,9 +80,33 @@ export type ScrollScreenshotCaptureResult = {
     top_image_size?: number | undefined;
     bottom_image_size?: number | undefined;
     current_direction?: ScrollImageList | undefined;
+    /** 顶部（左侧）固定区域尺寸 */
+    sticky_start_size?: number | undefined;
+    /** 底部（右侧）固定区域尺寸 */
+    sticky_end_size?: number | undefined;
+    /** 匹配的置信度，范围为 0 到 1 */
+    match_confidence?: number | undefined;
 };
 
-export const SCROLL_SCREENSHOT_CAPTURE_RESULT_EXTRA_DATA_SIZE = 4 + 4 + 4 + 4 + 4;
+export const SCROLL_SCREENSHOT_CAPTURE_RESULT_EXTRA_DATA_SIZE = 4 + 4 + 4 + 4 + 4 + 4 + 4 + 4;
+
+export enum ScrollFrameChange {
+    /// 和上一帧基本一致
+    Duplicate = 'Duplicate',
+    /// 只有局部区域变化，画面没有滚动
+    Animation = 'Animation',
+    /// 画面发生了滚动，需要拼接
+    Scroll = 'Scroll',
+}
+
+export type ScrollScreenshotCaptureStatus = {
+    /** 只有滚动的截图会加入待处理队列 */
+    frame_change: ScrollFrameChange;
+    /** 最近截图的变化率，范围为 0 到 1 */
+    change_rate: number;
+    /** 建议的下一次截图间隔（毫秒） */
+    capture_interval: number;
+};
 
 export const scrollScreenshotCapture = async (
     scrollImageList: ScrollImageList,
@@ -61,7 +115,7 @@ export const scrollScreenshotCapture = async (
     maxX: number,
     maxY: number,
 ) => {
-    const result = await invoke<ArrayBuffer>('scroll_screenshot_capture', {
+    const result = await invoke<ScrollScreenshotCaptureStatus>('scroll_screenshot_capture', {
         scrollImageList,
         minX,
         minY,
@@ -123,6 +177,66 @@ export const scrollScreenshotHandleImage = async (
         }
     }
 
+    return parseScrollScreenshotCaptureResult(result);
+};
+
+export enum ScrollAutoScrollStopReason {
+    /// 滚动后没有新的内容
+    EndOfPage = 'EndOfPage',
+    /// 用户取消
+    Cancelled = 'Cancelled',
+    /// 达到最大帧数
+    MaxFrameCount = 'MaxFrameCount',
+}
+
+/** 自动滚动每拼接一帧发送的事件，内容和 scroll_screenshot_handle_image 的返回值一致 */
+export const SCROLL_SCREENSHOT_AUTO_SCROLL_FRAME_EMIT_KEY = 'scroll-screenshot:auto-scroll-frame';
+
+/**
+ * 自动滚动截图，按下 Esc 或调用 scrollScreenshotAutoScrollStop 取消
+ * @returns 停止的原因
+ */
+export const scrollScreenshotAutoScroll = async (
+    minX: number,
+    minY: number,
+    maxX: number,
+    maxY: number,
+    scrollStep: number,
+    thumbnailSize: number,
+) => {
+    const result = await invoke<ScrollAutoScrollStopReason>('scroll_screenshot_auto_scroll', {
+        minX,
+        minY,
+        maxX,
+        maxY,
+        scrollStep,
+        thumbnailSize,
+    });
+    return result;
+};
+
+export const scrollScreenshotAutoScrollStop = async () => {
+    const result = await invoke<void>('scroll_screenshot_auto_scroll_stop');
+    return result;
+};
+
+/**
+ * 解析自动滚动发送的帧
+ */
+export const parseScrollScreenshotAutoScrollFrame = (
+    payload: number[],
+): ScrollScreenshotCaptureResult | undefined => {
+    if (payload.length < SCROLL_SCREENSHOT_CAPTURE_RESULT_EXTRA_DATA_SIZE) {
+        return undefined;
+    }
+
+    return parseScrollScreenshotCaptureResult(new Uint8Array(payload).buffer);
+};
+
+/**
+ * 解析缩略图和末尾附加的拼接信息，缩略图可能为空
+ */
+const parseScrollScreenshotCaptureResult = (result: ArrayBuffer): ScrollScreenshotCaptureResult => {
     // 将屏幕信息和图像数据分离
     const imageDataLength = result.byteLength - SCROLL_SCREENSHOT_CAPTURE_RESULT_EXTRA_DATA_SIZE;
 
@@ -138,18 +252,73 @@ export const scrollScreenshotHandleImage = async (
     const bottomImageSize = screenInfoView.getInt32(12, true); // i32
     const currentDirection =
         screenInfoView.getInt32(16, true) === 0 ? ScrollImageList.Top : ScrollImageList.Bottom; // i32
+    const stickyStartSize = screenInfoView.getInt32(20, true); // i32
+    const stickyEndSize = screenInfoView.getInt32(24, true); // i32
+    const matchConfidence = screenInfoView.getFloat32(28, true); // f32
 
     return {
         type: 'success',
-        thumbnail_buffer: result,
+        thumbnail_buffer: imageDataLength > 0 ? result : undefined,
         edge_position: edgePosition,
         overlay_size: overlaySize,
         top_image_size: topImageSize,
         bottom_image_size: bottomImageSize,
         current_direction: currentDirection,
+        sticky_start_size: stickyStartSize,
+        sticky_end_size: stickyEndSize,
+        match_confidence: matchConfidence,
     };
 };
 
+/**
+ * 撤销最近拼接的一帧
+ * @returns 该图片列表中新的最后一帧的缩略图和撤销后的尺寸信息，列表为空时没有缩略图，
+ * 没有可撤销的帧时返回 undefined
+ */
+export const scrollScreenshotUndo = async (
+    thumbnailSize: number,
+): Promise<ScrollScreenshotCaptureResult | undefined> => {
+    let result: ArrayBuffer | undefined;
+    try {
+        result = await invoke<ArrayBuffer>('scroll_screenshot_undo', {
+            thumbnailSize,
+        });
+    } catch (error) {
+        appError('[scrollScreenshotUndo] error', error);
+        return undefined;
+    }
+
+    if (result.byteLength < SCROLL_SCREENSHOT_CAPTURE_RESULT_EXTRA_DATA_SIZE) {
+        return undefined;
+    }
+
+    return parseScrollScreenshotCaptureResult(result);
+};
+
+/**
+ * 重新拼接最近撤销的一帧
+ * @returns 该帧的缩略图和尺寸信息，没有可重做的帧时返回 undefined
+ */
+export const scrollScreenshotRedo = async (
+    thumbnailSize: number,
+): Promise<ScrollScreenshotCaptureResult | undefined> => {
+    let result: ArrayBuffer | undefined;
+    try {
+        result = await invoke<ArrayBuffer>('scroll_screenshot_redo', {
+            thumbnailSize,
+        });
+    } catch (error) {
+        appError('[scrollScreenshotRedo] error', error);
+        return undefined;
+    }
+
+    if (result.byteLength < SCROLL_SCREENSHOT_CAPTURE_RESULT_EXTRA_DATA_SIZE) {
+        return undefined;
+    }
+
+    return parseScrollScreenshotCaptureResult(result);
+};
+
 export type ScrollScreenshotCaptureSize = {
     top_image_size: number;
     bottom_image_size: number;
@@ -160,13 +329,137 @@ export const scrollScreenshotGetSize = async () => {
     return result;
 };
 
-export const scrollScreenshotSaveToFile = async (filePath: string) => {
+/**
+ * 保存拼接结果
+ * @param encoder 编码格式及编码参数，需要和文件扩展名一致，未指定时根据文件扩展名使用默认参数
+ * @param writeMetadata 在图片旁写入 <文件名>.json，记录每一帧的截图信息和在图片中的位置
+ * @param embedMetadata 保存为 PNG 时将相同的信息嵌入文本块
+ */
+export const scrollScreenshotSaveToFile = async (
+    filePath: string,
+    encoder: ImageEncoderOptions | undefined = undefined,
+    writeMetadata: boolean = false,
+    embedMetadata: boolean = false,
+) => {
     const result = await invoke('scroll_screenshot_save_to_file', {
         filePath,
+        encoder,
+        writeMetadata,
+        embedMetadata,
+    });
+    return result;
+};
+
+export enum ScrollPageExportFormat {
+    /// 多页 PDF
+    Pdf = 'Pdf',
+    /// 按序号命名的图片文件
+    ImageSequence = 'ImageSequence',
+    /// 打包为 ZIP
+    Zip = 'Zip',
+}
+
+export type ScrollPageInfo = {
+    index: number;
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+    file_name: string | null;
+};
+
+export type ScrollPageManifest = {
+    direction: ScrollDirection;
+    width: number;
+    height: number;
+    page_size: number;
+    overlap: number;
+    pages: ScrollPageInfo[];
+};
+
+/**
+ * 将拼接结果按 pageSize 分页导出，相邻两页重叠 overlap
+ * @returns 每页在完整图片中的位置
+ */
+export const scrollScreenshotExportPages = async (
+    filePath: string,
+    format: ScrollPageExportFormat,
+    pageSize: number,
+    overlap: number,
+) => {
+    const result = await invoke<ScrollPageManifest>('scroll_screenshot_export_pages', {
+        filePath,
+        format,
+        pageSize,
+        overlap,
     });
     return result;
 };
 
+/**
+ * 保存当前的拼接状态，之后可以恢复截图或重新导出
+ */
+export const scrollScreenshotSaveSession = async (filePath: string) => {
+    const result = await invoke('scroll_screenshot_save_session', {
+        filePath,
+    });
+    return result;
+};
+
+export type ScrollSessionInfo = {
+    direction: ScrollDirection;
+    frame_count: number;
+    top_frame_count: number;
+    bottom_frame_count: number;
+    /** 导出图片的宽度，没有帧时为 0 */
+    width: number;
+    /** 导出图片的高度，没有帧时为 0 */
+    height: number;
+};
+
+/**
+ * 加载保存的拼接状态，替换当前的拼接状态
+ */
+export const scrollScreenshotLoadSession = async (filePath: string) => {
+    const result = await invoke<ScrollSessionInfo>('scroll_screenshot_load_session', {
+        filePath,
+    });
+    return result;
+};
+
+/**
+ * 是否有截图过程中自动保存的会话，截图正常结束后会删除
+ */
+export const scrollScreenshotHasAutosaveSession = async () => {
+    const result = await invoke<boolean>('scroll_screenshot_has_autosave_session');
+    return result;
+};
+
+/**
+ * 恢复截图过程中自动保存的会话，替换当前的拼接状态
+ */
+export const scrollScreenshotRestoreAutosaveSession = async () => {
+    const result = await invoke<ScrollSessionInfo>('scroll_screenshot_restore_autosave_session');
+    return result;
+};
+
+/**
+ * 获取图片列表中指定帧的缩略图，用于恢复会话后显示已拼接的帧
+ */
+export const scrollScreenshotGetFrameThumbnail = async (
+    imageList: ScrollImageList,
+    index: number,
+    thumbnailSize: number,
+): Promise<ScrollScreenshotCaptureResult> => {
+    const result = await invoke<ArrayBuffer>('scroll_screenshot_get_frame_thumbnail', {
+        imageList,
+        index,
+        thumbnailSize,
+    });
+
+    return parseScrollScreenshotCaptureResult(result);
+};
+
 export const scrollScreenshotSaveToClipboard = async () => {
     const result = await invoke('scroll_screenshot_save_to_clipboard');
     return result;
@@ -35,6 +35,7 @@ export const videoRecordStart = async (
     encoderPreset: string,
     videoMaxWidth: number,
     videoMaxHeight: number,
+    drawCursor: boolean = true,
 ) => {
     const result = await invoke('video_record_start', {
         minX,
@@ -52,6 +53,7 @@ export const videoRecordStart = async (
         encoderPreset,
         videoMaxWidth,
         videoMaxHeight,
+        drawCursor,
     });
     return result;
 };
@@ -1,6 +1,6 @@
 import { AppSettingsData, AppSettingsGroup } from '@/app/contextWrap';
 import { captureFocusedWindow } from '@/commands/screenshot';
-import { getImagePathFromSettings } from '@/utils/file';
+import { getImageMetadataFromSettings, getImagePathFromSettings } from '@/utils/file';
 import { playSound } from '@/utils/audio';
 import { emit } from '@tauri-apps/api/event';
 import * as tauriLog from '@tauri-apps/plugin-log';
@@ -39,6 +39,7 @@ export const executeScreenshotFocusedWindow = async (appSettings: AppSettingsDat
         imagePath.filePath,
         appSettings[AppSettingsGroup.FunctionScreenshot].focusedWindowCopyToClipboard,
         FOCUS_WINDOW_APP_NAME_ENV_VARIABLE,
+        getImageMetadataFromSettings(appSettings),
     );
     // 播放相机快门音效
     playSound('/audios/camera_shutter.mp3');
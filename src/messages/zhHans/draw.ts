@@ -99,8 +99,15 @@ export const draw = {
     'draw.scrollScreenshot.initError': '初始化失败，请向开发者反馈 [1]',
     'draw.scrollScreenshot.captureError': '滚动截图失败，请向开发者反馈 [2]',
     'draw.scrollScreenshot.captureMiss': '滚动过快，请滚动到已截取的边缘再次尝试',
+    'draw.scrollScreenshot.lowConfidence': '画面存在重复内容，拼接可能不准确，请放慢滚动速度',
     'draw.scrollScreenshot.changeDirection': '切换滚动方向',
+    'draw.scrollScreenshot.autoScroll': '自动滚动（按 Esc 停止）',
+    'draw.scrollScreenshot.autoScrollStop': '停止自动滚动',
+    'draw.scrollScreenshot.autoScrollFinished': '已滚动到页面末端',
+    'draw.scrollScreenshot.autoScrollError': '自动滚动失败，请检查应用权限',
     'draw.scrollScreenshot.scrollError': '部分滚动失效，请检查应用权限',
+    'draw.scrollScreenshot.restoreSession': '上次的滚动截图没有正常结束，是否恢复已拼接的内容？',
+    'draw.scrollScreenshot.restoreSessionError': '恢复滚动截图失败',
     'draw.ocrDetect.translate': '翻译',
     'draw.ocrDetect.translate.autoReplace': '自动替换识别结果',
     'draw.ocrDetect.translate.keepLayout': '保留排版',
@@ -140,6 +140,20 @@ export const settings = {
         '工具栏新增一个快速保存按钮，点击后自动保存到指定路径',
     'settings.functionSettings.screenshotSettings.autoSaveFileMode.directory': '文件保存目录',
     'settings.functionSettings.screenshotSettings.autoSaveFileMode.saveFileFormat': '保存文件格式',
+    'settings.functionSettings.screenshotSettings.autoSaveFileMode.saveImageMetadata':
+        '写入图片元数据',
+    'settings.functionSettings.screenshotSettings.autoSaveFileMode.saveImageMetadata.tip':
+        '在图片中写入截图时间、应用名称、窗口标题等信息，关闭时会移除图片中的所有元数据',
+    'settings.functionSettings.screenshotSettings.autoSaveFileMode.imageMetadataComment':
+        '元数据备注',
+    'settings.functionSettings.screenshotSettings.autoSaveFileMode.pngOptimization': 'PNG 优化',
+    'settings.functionSettings.screenshotSettings.autoSaveFileMode.pngOptimization.tip':
+        '尝试调色板和多种压缩方式，保存更小的 PNG 文件，保存耗时会增加',
+    'settings.functionSettings.screenshotSettings.autoSaveFileMode.pngOptimization.none': '不优化',
+    'settings.functionSettings.screenshotSettings.autoSaveFileMode.pngOptimization.lossless':
+        '无损',
+    'settings.functionSettings.screenshotSettings.autoSaveFileMode.pngOptimization.lossy':
+        '有损（量化为 256 色）',
     'settings.functionSettings.outputSettings': '输出',
     'settings.functionSettings.outputSettings.variables': '支持的变量',
     'settings.functionSettings.outputSettings.variables.focusedWindowAppName':
@@ -200,6 +214,9 @@ export const settings = {
     'settings.systemSettings.scrollScreenshotSettings.tryRollback': '匹配两侧图片',
     'settings.systemSettings.scrollScreenshotSettings.tryRollback.tip':
         '滚动截图存在上下、左右两种情况，滚动时会根据滚动方向匹配一侧图片，开启后会在一侧匹配失败时尝试匹配另一侧（常适用于匹配失败重新滚动到匹配失败位置时，如果关闭，必须再次向匹配方向进行滚动操作，但可能存在错误匹配导致拼接错误的情况）',
+    'settings.systemSettings.scrollScreenshotSettings.detectStickyBands': '去除固定区域',
+    'settings.systemSettings.scrollScreenshotSettings.detectStickyBands.tip':
+        '检测滚动时保持不动的顶部、底部区域（如导航栏、工具栏），拼接时只保留一份。需要连续多帧保持不变才会被识别',
     'settings.systemSettings.scrollScreenshotSettings.imageFeatureThreshold': '图片特征阈值',
     'settings.systemSettings.scrollScreenshotSettings.imageFeatureThreshold.tip':
         '值越大，选取特征点的要求越高，采用 FAST 算法（https://en.wikipedia.org/wiki/Features_from_accelerated_segment_test）',
@@ -216,6 +233,47 @@ export const settings = {
         '图片特征描述大小',
     'settings.systemSettings.scrollScreenshotSettings.imageFeatureDescriptionLength.tip':
         '如果是 8 则以特征点为中心选取 8x8 的区域进行比较',
+    'settings.systemSettings.scrollScreenshotSettings.memoryBudget': '内存占用上限',
+    'settings.systemSettings.scrollScreenshotSettings.memoryBudget.tip':
+        '拼接的图片超出该大小后，较早的图片会写入临时文件，以降低长截图的内存占用',
+    'settings.systemSettings.scrollScreenshotSettings.seamMode': '接缝处理',
+    'settings.systemSettings.scrollScreenshotSettings.seamMode.tip':
+        '相邻两张图片重叠时的拼接方式，选择差异最小的位置可以避开动画、光标等变化的内容',
+    'settings.systemSettings.scrollScreenshotSettings.seamMode.overlay': '直接覆盖',
+    'settings.systemSettings.scrollScreenshotSettings.seamMode.minDifference': '差异最小处拼接',
+    'settings.systemSettings.scrollScreenshotSettings.seamFeatherSize': '接缝过渡',
+    'settings.systemSettings.scrollScreenshotSettings.seamFeatherSize.tip':
+        '在接缝两侧混合的像素数，为 0 时不混合，仅在差异最小处拼接时生效',
+    'settings.systemSettings.scrollScreenshotSettings.featureBackend': '特征匹配方式',
+    'settings.systemSettings.scrollScreenshotSettings.featureBackend.tip':
+        '拼接时寻找相邻两张图片重叠位置的方式，文字较多的页面可以尝试按行匹配，自由方向不支持按行匹配',
+    'settings.systemSettings.scrollScreenshotSettings.featureBackend.fastPatch': '角点 + 区域均值',
+    'settings.systemSettings.scrollScreenshotSettings.featureBackend.fastBrief':
+        '角点 + 二进制描述',
+    'settings.systemSettings.scrollScreenshotSettings.featureBackend.lineHash': '按行匹配',
+    'settings.systemSettings.scrollScreenshotSettings.autoScrollStep': '自动滚动距离',
+    'settings.systemSettings.scrollScreenshotSettings.autoScrollStep.tip':
+        '自动滚动时每次滚动的滚轮格数，页面滚动过快导致拼接失败时可以调小',
+    'settings.systemSettings.scrollScreenshotSettings.enablePageExport': '分页导出',
+    'settings.systemSettings.scrollScreenshotSettings.enablePageExport.tip':
+        '保存时将长截图切分为多页，避免超出图片格式的尺寸限制或查看器无法打开，同时生成记录每页位置的清单文件',
+    'settings.systemSettings.scrollScreenshotSettings.pageExportFormat': '分页格式',
+    'settings.systemSettings.scrollScreenshotSettings.pageExportFormat.pdf': 'PDF',
+    'settings.systemSettings.scrollScreenshotSettings.pageExportFormat.imageSequence':
+        '图片序列',
+    'settings.systemSettings.scrollScreenshotSettings.pageExportFormat.zip': 'ZIP',
+    'settings.systemSettings.scrollScreenshotSettings.pageSize': '每页尺寸',
+    'settings.systemSettings.scrollScreenshotSettings.pageSize.tip':
+        '每页在滚动方向上的像素数，水平滚动时为宽度',
+    'settings.systemSettings.scrollScreenshotSettings.pageOverlap': '页面重叠',
+    'settings.systemSettings.scrollScreenshotSettings.pageOverlap.tip':
+        '相邻两页重复的像素数，需要小于每页尺寸',
+    'settings.systemSettings.scrollScreenshotSettings.saveMetadataFile': '保存拼接信息',
+    'settings.systemSettings.scrollScreenshotSettings.saveMetadataFile.tip':
+        '在图片旁生成同名的 .json 文件，记录每一帧的截图时间、显示器、截图区域、在图片中的位置和匹配置信度',
+    'settings.systemSettings.scrollScreenshotSettings.embedMetadata': '嵌入拼接信息',
+    'settings.systemSettings.scrollScreenshotSettings.embedMetadata.tip':
+        '保存为 PNG 时将相同的信息写入图片的文本块',
     'settings.commonSettings.trayIconSettings': '托盘',
     'settings.commonSettings.trayIconSettings.enableTrayIcon': '启用托盘',
     'settings.commonSettings.trayIconSettings.defaultIcons': '默认图标',
@@ -61,6 +61,24 @@ export class CaptureHistory {
                 case ImageEncoder.Png:
                     fileExtension = '.png';
                     break;
+                case ImageEncoder.Avif:
+                    fileExtension = '.avif';
+                    break;
+                case ImageEncoder.Jpeg:
+                    fileExtension = '.jpg';
+                    break;
+                case ImageEncoder.JpegXl:
+                    fileExtension = '.jxl';
+                    break;
+                case ImageEncoder.Qoi:
+                    fileExtension = '.qoi';
+                    break;
+                case ImageEncoder.Tiff:
+                    fileExtension = '.tiff';
+                    break;
+                case ImageEncoder.Bmp:
+                    fileExtension = '.bmp';
+                    break;
             }
         } else {
             fileExtension = path.extname(imageBuffer.file_name);
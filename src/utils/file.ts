@@ -3,6 +3,7 @@ import * as dialog from '@tauri-apps/plugin-dialog';
 import { AppSettingsData, AppSettingsGroup } from '@/app/contextWrap';
 import { homeDir, join as joinPath } from '@tauri-apps/api/path';
 import { createDir } from '@/commands/file';
+import { ImageEncoder, ImageEncoderOptions, ImageMetadata } from '@/commands';
 import { platform } from '@tauri-apps/plugin-os';
 
 const parseTemplate = (template: string): string => {
@@ -35,6 +36,18 @@ export enum ImageFormat {
     WEBP = 'image/webp',
     AVIF = 'image/avif',
     JPEG_XL = 'image/jpeg-xl',
+    QOI = 'image/qoi',
+    TIFF = 'image/tiff',
+    BMP = 'image/bmp',
+}
+
+/** PNG 的优化方式 */
+export enum PngOptimization {
+    None = 'none',
+    /** 无损，颜色不超过 256 种时使用调色板 */
+    Lossless = 'lossless',
+    /** 颜色超过 256 种时量化为调色板 */
+    Lossy = 'lossy',
 }
 
 export type ImagePath = {
@@ -57,6 +70,15 @@ export const joinImagePath = (filePath: string, imageFormat: ImageFormat) => {
         case ImageFormat.JPEG_XL:
             fileExtension = 'jxl';
             break;
+        case ImageFormat.QOI:
+            fileExtension = 'qoi';
+            break;
+        case ImageFormat.TIFF:
+            fileExtension = 'tiff';
+            break;
+        case ImageFormat.BMP:
+            fileExtension = 'bmp';
+            break;
         case ImageFormat.PNG:
         default:
             fileExtension = 'png';
@@ -76,6 +98,12 @@ export const getImageFormat = (filePath: string) => {
         imageFormat = ImageFormat.AVIF;
     } else if (filePath.endsWith('.jxl')) {
         imageFormat = ImageFormat.JPEG_XL;
+    } else if (filePath.endsWith('.qoi')) {
+        imageFormat = ImageFormat.QOI;
+    } else if (filePath.endsWith('.tiff') || filePath.endsWith('.tif')) {
+        imageFormat = ImageFormat.TIFF;
+    } else if (filePath.endsWith('.bmp')) {
+        imageFormat = ImageFormat.BMP;
     }
 
     return imageFormat;
@@ -138,6 +166,57 @@ export const getImagePathFromSettings = async (
     };
 };
 
+/**
+ * 截图时的信息，保存图片时写入元数据
+ */
+export type ImageCaptureInfo = {
+    /** 截图时间，Unix 时间戳（毫秒） */
+    captureTime: number;
+    monitorName?: string;
+    scaleFactor?: number;
+};
+
+/**
+ * 根据设置生成保存图片时写入的元数据
+ * @param captureInfo 截图时的信息，未传入时不写入截图时间和显示器（如由后端填写）
+ */
+export const getImageMetadataFromSettings = (
+    appSettings: AppSettingsData,
+    captureInfo?: ImageCaptureInfo,
+): ImageMetadata => {
+    const screenshotSettings = appSettings[AppSettingsGroup.FunctionScreenshot];
+    if (!screenshotSettings.saveImageMetadata) {
+        return { strip: true };
+    }
+
+    return {
+        capture_time: captureInfo?.captureTime,
+        utc_offset: -new Date(captureInfo?.captureTime ?? Date.now()).getTimezoneOffset(),
+        monitor_name: captureInfo?.monitorName,
+        scale_factor: captureInfo?.scaleFactor,
+        comment: screenshotSettings.imageMetadataComment || undefined,
+    };
+};
+
+/**
+ * 根据设置选择保存图片的编码器，返回 undefined 时使用前端编码的数据
+ */
+export const getImageEncoderFromSettings = (
+    appSettings: AppSettingsData,
+    imageFormat: ImageFormat,
+): ImageEncoderOptions | undefined => {
+    const pngOptimization = appSettings[AppSettingsGroup.FunctionScreenshot].pngOptimization;
+    if (imageFormat !== ImageFormat.PNG || pngOptimization === PngOptimization.None) {
+        return undefined;
+    }
+
+    return {
+        format: ImageEncoder.Png,
+        optimize: true,
+        quantize: pngOptimization === PngOptimization.Lossy,
+    };
+};
+
 export const showImageDialog = async (
     appSettings: AppSettingsData,
     prevFormat?: ImageFormat,
@@ -168,6 +247,24 @@ export const showImageDialog = async (
                 extensions: ['jxl'],
             };
             break;
+        case ImageFormat.QOI:
+            firstFilter = {
+                name: 'QOI(*.qoi)',
+                extensions: ['qoi'],
+            };
+            break;
+        case ImageFormat.TIFF:
+            firstFilter = {
+                name: 'TIFF(*.tiff)',
+                extensions: ['tiff', 'tif'],
+            };
+            break;
+        case ImageFormat.BMP:
+            firstFilter = {
+                name: 'BMP(*.bmp)',
+                extensions: ['bmp'],
+            };
+            break;
         case ImageFormat.PNG:
         default:
             firstFilter = {
@@ -200,6 +297,18 @@ export const showImageDialog = async (
                 name: 'JPEG XL(*.jxl)',
                 extensions: ['jxl'],
             },
+            {
+                name: 'QOI(*.qoi)',
+                extensions: ['qoi'],
+            },
+            {
+                name: 'TIFF(*.tiff)',
+                extensions: ['tiff', 'tif'],
+            },
+            {
+                name: 'BMP(*.bmp)',
+                extensions: ['bmp'],
+            },
         ],
         defaultPath: generateImageFileName(
             appSettings[AppSettingsGroup.FunctionOutput].manualSaveFileNameFormat,